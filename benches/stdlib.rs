@@ -109,6 +109,47 @@ fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
+    let map = indexset::BTreeMap::from_iter(input.iter().map(|item| (*item, [0u8; 256])));
+    let soa_map = indexset::soa::BTreeMap::from_iter(input.iter().map(|item| (*item, [0u8; 256])));
+    c.bench_function("indexset map get 100k with 256-byte values", |b| {
+        b.iter(|| {
+            input.iter().for_each(|item| {
+                black_box(map.get(black_box(item)));
+            })
+        })
+    });
+    c.bench_function("soa indexset map get 100k with 256-byte values", |b| {
+        b.iter(|| {
+            input.iter().for_each(|item| {
+                black_box(soa_map.get(black_box(item)));
+            })
+        })
+    });
+
+    let mut vec_node_set: indexset::BTreeSet<usize> = indexset::BTreeSet::new();
+    let mut search_node_set: indexset::BTreeSet<usize, indexset::search::SearchNode<usize>> =
+        indexset::BTreeSet::default();
+    for i in &input {
+        vec_node_set.insert(*i);
+        search_node_set.insert(*i);
+    }
+    c.bench_function("indexset contains and rank 100k", |b| {
+        b.iter(|| {
+            input.iter().for_each(|item| {
+                black_box(vec_node_set.contains(black_box(item)));
+                black_box(vec_node_set.rank(black_box(item)));
+            })
+        })
+    });
+    c.bench_function("search node indexset contains and rank 100k", |b| {
+        b.iter(|| {
+            input.iter().for_each(|item| {
+                black_box(search_node_set.contains(black_box(item)));
+                black_box(search_node_set.rank(black_box(item)));
+            })
+        })
+    });
+
     c.bench_function("stdlib collect 100k into vec", |b| {
         b.iter(|| std::hint::black_box(stdlib.iter().collect::<Vec<&&usize>>()))
     });
@@ -0,0 +1,765 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::iter::{Flatten, FusedIterator};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::constants::DEFAULT_INNER_SIZE;
+use crate::core::layout::Layout;
+use crate::core::node::SplitPolicy;
+use crate::core::pair::Pair;
+use crate::Node;
+
+/// An ordered bag (counted multiset) based on a two-level B-Tree.
+///
+/// Each distinct element is stored exactly once, alongside the number of copies of it that the bag
+/// holds. Every copy weighs in the index over the nodes, which thus sums these multiplicities
+/// rather than the number of stored elements, so position-based queries, such as [`BTreeBag::select`] and [`BTreeBag::rank`],
+/// behave as if every copy were present, while memory usage only depends on the number of distinct
+/// elements.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::bag::BTreeBag;
+///
+/// let mut histogram = BTreeBag::new();
+/// histogram.insert_n(10, 3);
+/// histogram.insert_n(20, 2);
+/// histogram.insert(30);
+///
+/// assert_eq!(histogram.len(), 6);
+/// assert_eq!(histogram.distinct_len(), 3);
+/// assert_eq!(histogram.count(&10), 3);
+/// assert_eq!(histogram.select(3), Some(&20));
+/// assert_eq!(histogram.rank(&30), 5);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+pub struct BTreeBag<T>
+where
+    T: Ord,
+{
+    layout: Layout<Node<Pair<T, usize>>>,
+    node_capacity: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    split_policy: SplitPolicy,
+    distinct_len: usize,
+}
+
+impl<T: Ord> Default for BTreeBag<T> {
+    fn default() -> Self {
+        Self {
+            layout: Layout::new(Node::with_capacity(DEFAULT_INNER_SIZE)),
+            node_capacity: DEFAULT_INNER_SIZE,
+            split_policy: SplitPolicy::default(),
+            distinct_len: 0,
+        }
+    }
+}
+
+impl<T: Ord> BTreeBag<T> {
+    /// Makes a new, empty `BTreeBag` with maximum node size 1024.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag: BTreeBag<i32> = BTreeBag::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `BTreeBag` with the given maximum node size, that is, the maximum
+    /// number of distinct elements per node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag: BTreeBag<i32> = BTreeBag::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            layout: Layout::new(Node::with_capacity(maximum_node_size)),
+            node_capacity: maximum_node_size,
+            ..Default::default()
+        }
+    }
+    /// Clears the bag, removing all elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag = BTreeBag::new();
+    /// bag.insert(1);
+    /// bag.clear();
+    /// assert!(bag.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.layout = Layout::new(Node::with_capacity(self.node_capacity));
+        self.distinct_len = 0;
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    /// use indexset::SplitPolicy;
+    ///
+    /// let mut bag: BTreeBag<u64> = BTreeBag::new();
+    /// bag.set_split_policy(SplitPolicy::RightBiased);
+    /// assert!(matches!(bag.split_policy(), SplitPolicy::RightBiased));
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.split_policy = split_policy;
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.split_policy
+    }
+    /// Returns the number of elements in the bag, counting every copy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let bag = BTreeBag::from_iter([1, 1, 2]);
+    /// assert_eq!(bag.len(), 3);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.layout.len()
+    }
+    /// Returns the number of distinct elements in the bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let bag = BTreeBag::from_iter([1, 1, 2]);
+    /// assert_eq!(bag.distinct_len(), 2);
+    /// ```
+    pub fn distinct_len(&self) -> usize {
+        self.distinct_len
+    }
+    /// Returns `true` if the bag contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn locate_node<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.layout.locate_node(|node| {
+            if let Some(max) = node.last() {
+                return max.key.borrow() < value;
+            };
+
+            false
+        })
+    }
+    fn locate_value<Q>(&self, value: &Q) -> (usize, usize)
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node_idx = self.locate_node(value);
+        let position_within_node =
+            self.layout.nodes()[node_idx].partition_point(|entry| entry.key.borrow() < value);
+
+        (node_idx, position_within_node)
+    }
+    fn get_entry<Q>(&self, value: &Q) -> Option<&Pair<T, usize>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (node_idx, position_within_node) = self.locate_value(value);
+
+        self.layout.nodes()[node_idx]
+            .get(position_within_node)
+            .filter(|entry| entry.key.borrow() == value)
+    }
+    /// Adds a single copy of the value to the bag. Returns the number of copies that were
+    /// present before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag = BTreeBag::new();
+    /// assert_eq!(bag.insert(2), 0);
+    /// assert_eq!(bag.insert(2), 1);
+    /// assert_eq!(bag.len(), 2);
+    /// ```
+    pub fn insert(&mut self, value: T) -> usize {
+        self.insert_n(value, 1)
+    }
+    /// Adds `n` copies of the value to the bag. Returns the number of copies that were present
+    /// before.
+    ///
+    /// Inserting zero copies leaves the bag untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the total number of copies in the bag would overflow a `usize`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag = BTreeBag::new();
+    /// assert_eq!(bag.insert_n("a", 3), 0);
+    /// assert_eq!(bag.insert_n("a", 2), 3);
+    /// assert_eq!(bag.count("a"), 5);
+    /// ```
+    pub fn insert_n(&mut self, value: T, n: usize) -> usize {
+        // Every count is at most the total, so checking the latter covers both
+        self.len().checked_add(n).expect("bag length overflow");
+        let (node_idx, position_within_node) = self.locate_value(&value);
+        let node = &self.layout.nodes()[node_idx];
+        let node_len = node.len();
+        if node
+            .get(position_within_node)
+            .is_some_and(|entry| entry.key == value)
+        {
+            return self.layout.insert_weighted_with(node_idx, n, |node| {
+                let entry = &mut node[position_within_node];
+                let previous_count = entry.value;
+                entry.value += n;
+
+                previous_count
+            });
+        }
+
+        if n == 0 {
+            return 0;
+        }
+
+        let full = node_len >= self.node_capacity && node_len > 1;
+        let (node_idx, position_within_node) = self.layout.make_weighted_room(
+            node_idx,
+            position_within_node,
+            node_len,
+            self.split_policy,
+            full,
+            |node, at| {
+                let new_node = node.split_off(at);
+                let new_node_weight = new_node.iter().map(|entry| entry.value).sum();
+
+                (new_node, new_node_weight)
+            },
+        );
+        self.layout.insert_weighted_with(node_idx, n, |node| {
+            node.insert(
+                position_within_node,
+                Pair {
+                    key: value,
+                    value: n,
+                },
+            )
+        });
+        self.distinct_len += 1;
+
+        0
+    }
+    /// Removes a single copy of the value from the bag. Returns whether a copy was present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag = BTreeBag::from_iter([2, 2]);
+    /// assert_eq!(bag.remove(&2), true);
+    /// assert_eq!(bag.remove(&2), true);
+    /// assert_eq!(bag.remove(&2), false);
+    /// ```
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_n(value, 1) == 1
+    }
+    /// Removes up to `n` copies of the value from the bag. Returns the number of copies that were
+    /// actually removed.
+    ///
+    /// The element is dropped from the bag once no copies of it remain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag = BTreeBag::new();
+    /// bag.insert_n(7, 5);
+    /// assert_eq!(bag.remove_n(&7, 2), 2);
+    /// assert_eq!(bag.remove_n(&7, 10), 3);
+    /// assert!(!bag.contains(&7));
+    /// ```
+    pub fn remove_n<Q>(&mut self, value: &Q, n: usize) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (node_idx, position_within_node) = self.locate_value(value);
+        let count = match self.layout.nodes()[node_idx].get(position_within_node) {
+            Some(entry) if entry.key.borrow() == value => entry.value,
+            _ => return 0,
+        };
+
+        // The node is dropped along with its last copy, unless it is the sole remaining one
+        let removed = n.min(count);
+        self.layout.remove_weighted_with(node_idx, removed, |node| {
+            if removed == count {
+                node.remove(position_within_node);
+            } else {
+                node[position_within_node].value -= removed;
+            }
+        });
+        if removed == count {
+            self.distinct_len -= 1;
+        }
+
+        removed
+    }
+    /// Removes every copy of the value from the bag. Returns the number of copies removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag = BTreeBag::from_iter([1, 2, 2, 2]);
+    /// assert_eq!(bag.remove_all(&2), 3);
+    /// assert_eq!(bag.len(), 1);
+    /// ```
+    pub fn remove_all<Q>(&mut self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_n(value, usize::MAX)
+    }
+    /// Returns the number of copies of the value in the bag.
+    ///
+    /// The value may be any borrowed form of the bag's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let bag = BTreeBag::from_iter([1, 2, 2]);
+    /// assert_eq!(bag.count(&2), 2);
+    /// assert_eq!(bag.count(&3), 0);
+    /// ```
+    pub fn count<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_entry(value).map_or(0, |entry| entry.value)
+    }
+    /// Returns `true` if the bag contains at least one copy of the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let bag = BTreeBag::from_iter([1, 2, 2]);
+    /// assert!(bag.contains(&1));
+    /// assert!(!bag.contains(&3));
+    /// ```
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_entry(value).is_some()
+    }
+    /// Returns the element covering the i-th position of the bag, as if every copy of every
+    /// element were laid out in order, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag = BTreeBag::new();
+    /// bag.insert_n('a', 2);
+    /// bag.insert_n('b', 3);
+    /// assert_eq!(bag.select(0), Some(&'a'));
+    /// assert_eq!(bag.select(1), Some(&'a'));
+    /// assert_eq!(bag.select(2), Some(&'b'));
+    /// assert_eq!(bag.select(4), Some(&'b'));
+    /// assert_eq!(bag.select(5), None);
+    /// ```
+    pub fn select(&self, idx: usize) -> Option<&T> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let (node_idx, mut position) = self.layout.locate_ith(idx);
+        for entry in &self.layout.nodes()[node_idx] {
+            if position < entry.value {
+                return Some(&entry.key);
+            }
+            position -= entry.value;
+        }
+
+        None
+    }
+    /// Returns the number of copies of elements in the bag that are strictly less than the value,
+    /// which is also the position of the first copy of the value, had it been present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let mut bag = BTreeBag::new();
+    /// bag.insert_n(1, 2);
+    /// bag.insert_n(3, 3);
+    /// assert_eq!(bag.rank(&1), 0);
+    /// assert_eq!(bag.rank(&2), 2);
+    /// assert_eq!(bag.rank(&3), 2);
+    /// assert_eq!(bag.rank(&4), 5);
+    /// ```
+    pub fn rank<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (node_idx, position_within_node) = self.locate_value(value);
+
+        self.layout.offset(node_idx)
+            + self.layout.nodes()[node_idx][..position_within_node]
+                .iter()
+                .map(|entry| entry.value)
+                .sum::<usize>()
+    }
+    /// Returns the smallest element in the bag, and how many copies of it are present, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let bag = BTreeBag::from_iter([2, 1, 1]);
+    /// assert_eq!(bag.first(), Some((&1, 2)));
+    /// ```
+    pub fn first(&self) -> Option<(&T, usize)> {
+        self.layout
+            .nodes()
+            .first()?
+            .first()
+            .map(|entry| (&entry.key, entry.value))
+    }
+    /// Returns the largest element in the bag, and how many copies of it are present, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let bag = BTreeBag::from_iter([2, 1, 1]);
+    /// assert_eq!(bag.last(), Some((&2, 1)));
+    /// ```
+    pub fn last(&self) -> Option<(&T, usize)> {
+        self.layout
+            .nodes()
+            .last()?
+            .last()
+            .map(|entry| (&entry.key, entry.value))
+    }
+    /// Gets an iterator that visits each distinct element of the bag in ascending order, alongside
+    /// its number of copies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bag::BTreeBag;
+    ///
+    /// let bag = BTreeBag::from_iter([3, 1, 3]);
+    /// assert_eq!(bag.iter().collect::<Vec<_>>(), [(&1, 1), (&3, 2)]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.layout.nodes().iter().flatten(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for BTreeBag<T>
+where
+    T: Ord + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BTreeBag<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut bag = BTreeBag::new();
+        iter.into_iter().for_each(|item| {
+            bag.insert(item);
+        });
+
+        bag
+    }
+}
+
+impl<T: Ord> Extend<T> for BTreeBag<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|item| {
+            self.insert(item);
+        });
+    }
+}
+
+impl<T: Ord> IntoIterator for BTreeBag<T> {
+    type Item = (T, usize);
+
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.layout.into_nodes().into_iter().flatten(),
+        }
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a BTreeBag<T> {
+    type Item = (&'a T, usize);
+
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the distinct elements of a `BTreeBag`, and their number of copies.
+///
+/// This `struct` is created by the [`iter`] method on [`BTreeBag`].
+/// See its documentation for more.
+///
+/// [`iter`]: BTreeBag::iter
+pub struct Iter<'a, T>
+where
+    T: Ord,
+{
+    inner: Flatten<std::slice::Iter<'a, Node<Pair<T, usize>>>>,
+}
+
+impl<'a, T: Ord> Iterator for Iter<'a, T> {
+    type Item = (&'a T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (&entry.key, entry.value))
+    }
+}
+
+impl<'a, T: Ord> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| (&entry.key, entry.value))
+    }
+}
+
+impl<'a, T: Ord> FusedIterator for Iter<'a, T> {}
+
+/// An owning iterator over the distinct elements of a `BTreeBag`, and their number of copies.
+///
+/// This `struct` is created by the [`into_iter`] method on [`BTreeBag`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+///
+/// [`into_iter`]: BTreeBag#method.into_iter
+pub struct IntoIter<T>
+where
+    T: Ord,
+{
+    inner: Flatten<std::vec::IntoIter<Node<Pair<T, usize>>>>,
+}
+
+impl<T: Ord> Iterator for IntoIter<T> {
+    type Item = (T, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|entry| (entry.key, entry.value))
+    }
+}
+
+impl<T: Ord> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|entry| (entry.key, entry.value))
+    }
+}
+
+impl<T: Ord> FusedIterator for IntoIter<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::BTreeBag;
+    use crate::SplitPolicy;
+    use rand::{Rng, SeedableRng};
+
+    // Checks that the weight indexed for every node is the sum of the counts in it
+    fn assert_consistent<T: Ord>(bag: &BTreeBag<T>) {
+        let mut len = 0;
+        for (node_idx, node) in bag.layout.nodes().iter().enumerate() {
+            assert!(!node.is_empty() || bag.is_empty());
+            assert!(node.len() <= bag.node_capacity);
+            assert_eq!(bag.layout.offset(node_idx), len);
+            len += node.iter().map(|entry| entry.value).sum::<usize>();
+        }
+        assert_eq!(bag.len(), len);
+    }
+
+    #[test]
+    fn test_select_and_rank_count_multiplicities() {
+        let mut bag = BTreeBag::with_maximum_node_size(4);
+        for value in 0..100 {
+            bag.insert_n(value, value % 3 + 1);
+        }
+
+        let expanded: Vec<usize> = (0..100)
+            .flat_map(|value| std::iter::repeat(value).take(value % 3 + 1))
+            .collect();
+        assert_eq!(bag.len(), expanded.len());
+        assert_eq!(bag.distinct_len(), 100);
+        for (idx, value) in expanded.iter().enumerate() {
+            assert_eq!(bag.select(idx), Some(value));
+        }
+        assert_eq!(bag.select(expanded.len()), None);
+        for value in 0..101 {
+            assert_eq!(bag.rank(&value), expanded.partition_point(|v| *v < value));
+        }
+    }
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([29; 32]);
+        let mut bag = BTreeBag::with_maximum_node_size(8);
+        let mut model: std::collections::BTreeMap<u16, usize> = std::collections::BTreeMap::new();
+        for _ in 0..20_000 {
+            let value = rng.gen_range(0..512);
+            let n = rng.gen_range(0..4);
+            if rng.gen_bool(0.55) {
+                let previous = model.get(&value).copied().unwrap_or_default();
+                assert_eq!(bag.insert_n(value, n), previous);
+                if previous + n > 0 {
+                    model.insert(value, previous + n);
+                }
+            } else {
+                let previous = model.get(&value).copied().unwrap_or_default();
+                let removed = n.min(previous);
+                assert_eq!(bag.remove_n(&value, n), removed);
+                if previous - removed == 0 {
+                    model.remove(&value);
+                } else {
+                    model.insert(value, previous - removed);
+                }
+            }
+        }
+
+        assert_consistent(&bag);
+        assert_eq!(bag.len(), model.values().sum::<usize>());
+        assert_eq!(bag.distinct_len(), model.len());
+        assert!(bag.iter().map(|(v, c)| (*v, c)).eq(model.clone()));
+        let mut position = 0;
+        for (value, count) in &model {
+            assert_eq!(bag.rank(value), position);
+            assert_eq!(bag.count(value), *count);
+            assert_eq!(bag.select(position), Some(value));
+            assert_eq!(bag.select(position + count - 1), Some(value));
+            position += count;
+        }
+        assert!(bag.into_iter().rev().eq(model.into_iter().rev()));
+    }
+
+    #[test]
+    fn test_splits_follow_the_policy() {
+        let mut bag = BTreeBag::with_maximum_node_size(8);
+        bag.set_split_policy(SplitPolicy::RightBiased);
+        for value in 0..1000u32 {
+            bag.insert_n(value, 3);
+        }
+
+        assert_consistent(&bag);
+        assert_eq!(bag.layout.nodes().len(), 1000 / 8);
+        assert_eq!(bag.select(2999), Some(&999));
+
+        // Splitting right before the insertion point leaves one entry to the node after it
+        let mut bag = BTreeBag::with_maximum_node_size(8);
+        bag.set_split_policy(SplitPolicy::Custom(|len, _| len - 1));
+        let mut rng = rand::rngs::StdRng::from_seed([30; 32]);
+        for _ in 0..2000 {
+            let value = rng.gen_range(0..300u32);
+            if rng.gen_bool(0.7) {
+                bag.insert_n(value, rng.gen_range(1..4));
+            } else {
+                bag.remove_n(&value, rng.gen_range(1..4));
+            }
+            assert_consistent(&bag);
+        }
+    }
+
+    #[test]
+    fn test_debug_lists_counts() {
+        let bag = BTreeBag::from_iter([3, 1, 3, 3]);
+        assert_eq!(format!("{:?}", bag), "{1: 1, 3: 3}");
+        assert_eq!(format!("{:?}", BTreeBag::<u8>::new()), "{}");
+    }
+
+    #[test]
+    fn test_drain_to_empty() {
+        let mut bag = BTreeBag::with_maximum_node_size(2);
+        bag.extend([5, 1, 3, 3, 4, 2, 2, 2]);
+        for value in 1..=5 {
+            bag.remove_all(&value);
+        }
+        assert!(bag.is_empty());
+        assert_consistent(&bag);
+        assert_eq!(bag.select(0), None);
+        assert_eq!(bag.first(), None);
+        bag.insert(9);
+        assert_eq!(bag.select(0), Some(&9));
+        assert_eq!(bag.rank(&10), 1);
+    }
+
+    #[test]
+    fn test_insert_n_overflow_panics_before_changing_the_bag() {
+        let mut bag = BTreeBag::new();
+        bag.insert_n(1, usize::MAX - 1);
+        let overflow = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            bag.insert_n(2, 2);
+        }));
+        assert!(overflow.is_err());
+        assert_eq!(bag.len(), usize::MAX - 1);
+        assert_eq!(bag.distinct_len(), 1);
+        assert_eq!(bag.count(&2), 0);
+        assert_eq!(bag.insert_n(1, 1), usize::MAX - 1);
+        assert_eq!(bag.count(&1), usize::MAX);
+    }
+}
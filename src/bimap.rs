@@ -0,0 +1,748 @@
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::ops::RangeBounds;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{BTreeMap, IntoIterMap, IterMap, RangeMap};
+
+/// An ordered bidirectional map, made of two [`BTreeMap`]s that are kept consistent with each
+/// other.
+///
+/// Every left value is associated to exactly one right value, and vice-versa. Both sides are kept
+/// sorted, so lookups, ranges, ranks and positional queries can be made over either of them.
+///
+/// Since each value is stored on both sides, inserting requires both `L` and `R` to be [`Clone`].
+///
+/// # Examples
+///
+/// ```
+/// use indexset::bimap::{BiBTreeMap, Overwritten};
+///
+/// let mut users = BiBTreeMap::new();
+/// users.insert(3, "carol");
+/// users.insert(1, "bob");
+/// users.insert(2, "alice");
+///
+/// assert_eq!(users.get_by_left(&1), Some(&"bob"));
+/// assert_eq!(users.get_by_right("alice"), Some(&2));
+/// assert_eq!(users.get_index_by_left(0), Some((&1, &"bob")));
+/// assert_eq!(users.get_index_by_right(0), Some((&2, &"alice")));
+///
+/// // Re-assigning a name to another id drops its previous association
+/// assert_eq!(users.insert(4, "bob"), Overwritten::Right(1, "bob"));
+/// assert!(!users.contains_left(&1));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct BiBTreeMap<L, R>
+where
+    L: Ord,
+    R: Ord,
+{
+    left: BTreeMap<L, R>,
+    right: BTreeMap<R, L>,
+}
+
+/// The pairs that were removed from a [`BiBTreeMap`] to make room for a newly inserted one.
+///
+/// This `enum` is returned by the [`insert`] method on [`BiBTreeMap`].
+///
+/// [`insert`]: BiBTreeMap::insert
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Overwritten<L, R> {
+    /// Neither value was present.
+    Neither,
+    /// The left value was present, associated to a different right value.
+    Left(L, R),
+    /// The right value was present, associated to a different left value.
+    Right(L, R),
+    /// The exact same pair was present.
+    Pair(L, R),
+    /// Both values were present, in two different pairs. The pair holding the left value comes
+    /// first.
+    Both((L, R), (L, R)),
+}
+
+impl<L: Ord, R: Ord> Default for BiBTreeMap<L, R> {
+    fn default() -> Self {
+        Self {
+            left: BTreeMap::default(),
+            right: BTreeMap::default(),
+        }
+    }
+}
+
+impl<L: Ord, R: Ord> BiBTreeMap<L, R> {
+    /// Makes a new, empty `BiBTreeMap` with maximum node size 1024.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let mut map: BiBTreeMap<u32, String> = BiBTreeMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `BiBTreeMap` whose two sides have the given maximum node size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let mut map: BiBTreeMap<u32, String> = BiBTreeMap::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            left: BTreeMap::with_maximum_node_size(maximum_node_size),
+            right: BTreeMap::with_maximum_node_size(maximum_node_size),
+        }
+    }
+    /// Clears the map, removing all pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let mut map = BiBTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.clear();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.left.clear();
+        self.right.clear();
+    }
+    /// Returns the number of pairs in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.left.len()
+    }
+    /// Returns `true` if the map contains no pairs.
+    pub fn is_empty(&self) -> bool {
+        self.left.is_empty()
+    }
+    /// Inserts a pair into the map, first removing any pair that holds either of its values, so
+    /// that both sides stay one-to-one. Returns the pairs that were removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::{BiBTreeMap, Overwritten};
+    ///
+    /// let mut map = BiBTreeMap::new();
+    /// assert_eq!(map.insert(1, 'a'), Overwritten::Neither);
+    /// assert_eq!(map.insert(2, 'b'), Overwritten::Neither);
+    /// assert_eq!(map.insert(1, 'a'), Overwritten::Pair(1, 'a'));
+    /// assert_eq!(map.insert(1, 'c'), Overwritten::Left(1, 'a'));
+    /// assert_eq!(map.insert(1, 'b'), Overwritten::Both((1, 'c'), (2, 'b')));
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn insert(&mut self, left: L, right: R) -> Overwritten<L, R>
+    where
+        L: Clone,
+        R: Clone,
+    {
+        let overwritten = match (self.remove_by_left(&left), self.remove_by_right(&right)) {
+            (None, None) => Overwritten::Neither,
+            (Some((old_left, old_right)), None) if old_right == right => {
+                Overwritten::Pair(old_left, old_right)
+            }
+            (Some((old_left, old_right)), None) => Overwritten::Left(old_left, old_right),
+            (None, Some((old_left, old_right))) => Overwritten::Right(old_left, old_right),
+            (Some(left_pair), Some(right_pair)) => Overwritten::Both(left_pair, right_pair),
+        };
+
+        self.left.insert(left.clone(), right.clone());
+        self.right.insert(right, left);
+
+        overwritten
+    }
+    /// Inserts a pair into the map only if neither of its values is already present. Otherwise,
+    /// the map is left untouched and the pair is given back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let mut map = BiBTreeMap::new();
+    /// assert_eq!(map.insert_no_overwrite(1, 'a'), Ok(()));
+    /// assert_eq!(map.insert_no_overwrite(2, 'a'), Err((2, 'a')));
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn insert_no_overwrite(&mut self, left: L, right: R) -> Result<(), (L, R)>
+    where
+        L: Clone,
+        R: Clone,
+    {
+        if self.left.contains_key(&left) || self.right.contains_key(&right) {
+            return Err((left, right));
+        }
+
+        self.left.insert(left.clone(), right.clone());
+        self.right.insert(right, left);
+
+        Ok(())
+    }
+    /// Returns a reference to the right value associated to the left value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(1, "a")]);
+    /// assert_eq!(map.get_by_left(&1), Some(&"a"));
+    /// assert_eq!(map.get_by_left(&2), None);
+    /// ```
+    pub fn get_by_left<Q>(&self, left: &Q) -> Option<&R>
+    where
+        L: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.left.get(left)
+    }
+    /// Returns a reference to the left value associated to the right value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(1, "a")]);
+    /// assert_eq!(map.get_by_right("a"), Some(&1));
+    /// assert_eq!(map.get_by_right("b"), None);
+    /// ```
+    pub fn get_by_right<Q>(&self, right: &Q) -> Option<&L>
+    where
+        R: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.right.get(right)
+    }
+    /// Returns `true` if the map contains the left value.
+    pub fn contains_left<Q>(&self, left: &Q) -> bool
+    where
+        L: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.left.contains_key(left)
+    }
+    /// Returns `true` if the map contains the right value.
+    pub fn contains_right<Q>(&self, right: &Q) -> bool
+    where
+        R: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.right.contains_key(right)
+    }
+    /// Removes the pair holding the left value from both sides of the map, and returns it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let mut map = BiBTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// assert_eq!(map.remove_by_left(&1), Some((1, "a")));
+    /// assert_eq!(map.remove_by_left(&1), None);
+    /// assert!(!map.contains_right("a"));
+    ///
+    /// let mut map = BiBTreeMap::from_iter([(String::from("a"), 1)]);
+    /// assert_eq!(map.remove_by_left("a"), Some((String::from("a"), 1)));
+    /// ```
+    pub fn remove_by_left<Q>(&mut self, left: &Q) -> Option<(L, R)>
+    where
+        L: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (left, right) = self.left.remove_entry(left)?;
+        self.right.remove(&right);
+
+        Some((left, right))
+    }
+    /// Removes the pair holding the right value from both sides of the map, and returns it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let mut map = BiBTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// assert_eq!(map.remove_by_right(&"b"), Some((2, "b")));
+    /// assert_eq!(map.remove_by_right(&"b"), None);
+    /// assert!(!map.contains_left(&2));
+    ///
+    /// let mut map = BiBTreeMap::from_iter([(1, String::from("a"))]);
+    /// assert_eq!(map.remove_by_right("a"), Some((1, String::from("a"))));
+    /// ```
+    pub fn remove_by_right<Q>(&mut self, right: &Q) -> Option<(L, R)>
+    where
+        R: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (right, left) = self.right.remove_entry(right)?;
+        self.left.remove(&left);
+
+        Some((left, right))
+    }
+    /// Returns the pair in the i-th position of the map, ordered by left values, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(2, "a"), (1, "b")]);
+    /// assert_eq!(map.get_index_by_left(0), Some((&1, &"b")));
+    /// assert_eq!(map.get_index_by_left(2), None);
+    /// ```
+    pub fn get_index_by_left(&self, idx: usize) -> Option<(&L, &R)> {
+        self.left.get_index(idx)
+    }
+    /// Returns the pair in the i-th position of the map, ordered by right values, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(2, "a"), (1, "b")]);
+    /// assert_eq!(map.get_index_by_right(0), Some((&2, &"a")));
+    /// assert_eq!(map.get_index_by_right(2), None);
+    /// ```
+    pub fn get_index_by_right(&self, idx: usize) -> Option<(&L, &R)> {
+        self.right.get_index(idx).map(|(right, left)| (left, right))
+    }
+    /// Returns the number of left values that are strictly less than the given one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(1, "c"), (3, "a")]);
+    /// assert_eq!(map.rank_by_left(&2), 1);
+    /// ```
+    pub fn rank_by_left<Q>(&self, left: &Q) -> usize
+    where
+        L: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.left.rank(left)
+    }
+    /// Returns the number of right values that are strictly less than the given one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(1, "c"), (3, "a")]);
+    /// assert_eq!(map.rank_by_right("b"), 1);
+    /// ```
+    pub fn rank_by_right<Q>(&self, right: &Q) -> usize
+    where
+        R: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.right.rank(right)
+    }
+    /// Gets an iterator over the pairs of the map, sorted by left values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(2, "a"), (1, "b")]);
+    /// assert_eq!(map.iter_by_left().collect::<Vec<_>>(), [(&1, &"b"), (&2, &"a")]);
+    /// ```
+    pub fn iter_by_left(&self) -> LeftIter<'_, L, R> {
+        LeftIter {
+            inner: self.left.iter(),
+        }
+    }
+    /// Gets an iterator over the pairs of the map, sorted by right values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(2, "a"), (1, "b")]);
+    /// assert_eq!(map.iter_by_right().collect::<Vec<_>>(), [(&2, &"a"), (&1, &"b")]);
+    /// ```
+    pub fn iter_by_right(&self) -> RightIter<'_, L, R> {
+        RightIter {
+            inner: self.right.iter(),
+        }
+    }
+    /// Constructs a double-ended iterator over the pairs whose left value falls within the
+    /// range, sorted by left values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(1, "d"), (2, "c"), (3, "b"), (4, "a")]);
+    /// assert_eq!(
+    ///     map.range_by_left(2..4).collect::<Vec<_>>(),
+    ///     [(&2, &"c"), (&3, &"b")]
+    /// );
+    /// ```
+    pub fn range_by_left<Q, T>(&self, range: T) -> LeftRange<'_, L, R>
+    where
+        L: Borrow<Q>,
+        Q: Ord + ?Sized,
+        T: RangeBounds<Q>,
+    {
+        let (start_idx, end_idx) = self.left.key_range_to_idx(range);
+        if start_idx < end_idx {
+            return LeftRange {
+                inner: Some(RangeMap {
+                    inner: self.left.set.range_idx(start_idx..end_idx),
+                }),
+            };
+        }
+
+        LeftRange { inner: None }
+    }
+    /// Constructs a double-ended iterator over the pairs whose right value falls within the
+    /// range, sorted by right values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::bimap::BiBTreeMap;
+    ///
+    /// let map = BiBTreeMap::from_iter([(1, "d"), (2, "c"), (3, "b"), (4, "a")]);
+    /// assert_eq!(
+    ///     map.range_by_right("b"..="c").collect::<Vec<_>>(),
+    ///     [(&3, &"b"), (&2, &"c")]
+    /// );
+    /// ```
+    pub fn range_by_right<Q, T>(&self, range: T) -> RightRange<'_, L, R>
+    where
+        R: Borrow<Q>,
+        Q: Ord + ?Sized,
+        T: RangeBounds<Q>,
+    {
+        let (start_idx, end_idx) = self.right.key_range_to_idx(range);
+        if start_idx < end_idx {
+            return RightRange {
+                inner: Some(RangeMap {
+                    inner: self.right.set.range_idx(start_idx..end_idx),
+                }),
+            };
+        }
+
+        RightRange { inner: None }
+    }
+}
+
+impl<L: Ord + Clone, R: Ord + Clone> FromIterator<(L, R)> for BiBTreeMap<L, R> {
+    fn from_iter<I: IntoIterator<Item = (L, R)>>(iter: I) -> Self {
+        let mut map = BiBTreeMap::new();
+        iter.into_iter().for_each(|(left, right)| {
+            map.insert(left, right);
+        });
+
+        map
+    }
+}
+
+impl<L: Ord, R: Ord> IntoIterator for BiBTreeMap<L, R> {
+    type Item = (L, R);
+
+    type IntoIter = IntoIter<L, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.left.into_iter(),
+        }
+    }
+}
+
+impl<'a, L: Ord, R: Ord> IntoIterator for &'a BiBTreeMap<L, R> {
+    type Item = (&'a L, &'a R);
+
+    type IntoIter = LeftIter<'a, L, R>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_by_left()
+    }
+}
+
+/// An iterator over the pairs of a `BiBTreeMap`, sorted by left values.
+///
+/// This `struct` is created by the [`iter_by_left`] method on [`BiBTreeMap`].
+/// See its documentation for more.
+///
+/// [`iter_by_left`]: BiBTreeMap::iter_by_left
+pub struct LeftIter<'a, L, R>
+where
+    L: Ord,
+{
+    inner: IterMap<'a, L, R>,
+}
+
+impl<'a, L: Ord, R> Iterator for LeftIter<'a, L, R> {
+    type Item = (&'a L, &'a R);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a, L: Ord, R> DoubleEndedIterator for LeftIter<'a, L, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<'a, L: Ord, R> FusedIterator for LeftIter<'a, L, R> {}
+
+/// An iterator over the pairs of a `BiBTreeMap`, sorted by right values.
+///
+/// This `struct` is created by the [`iter_by_right`] method on [`BiBTreeMap`].
+/// See its documentation for more.
+///
+/// [`iter_by_right`]: BiBTreeMap::iter_by_right
+pub struct RightIter<'a, L, R>
+where
+    R: Ord,
+{
+    inner: IterMap<'a, R, L>,
+}
+
+impl<'a, L, R: Ord> Iterator for RightIter<'a, L, R> {
+    type Item = (&'a L, &'a R);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(right, left)| (left, right))
+    }
+}
+
+impl<'a, L, R: Ord> DoubleEndedIterator for RightIter<'a, L, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(right, left)| (left, right))
+    }
+}
+
+impl<'a, L, R: Ord> FusedIterator for RightIter<'a, L, R> {}
+
+/// An iterator over a sub-range of pairs in a `BiBTreeMap`, sorted by left values.
+///
+/// This `struct` is created by the [`range_by_left`] method on [`BiBTreeMap`].
+/// See its documentation for more.
+///
+/// [`range_by_left`]: BiBTreeMap::range_by_left
+pub struct LeftRange<'a, L, R>
+where
+    L: Ord,
+{
+    inner: Option<RangeMap<'a, L, R>>,
+}
+
+impl<'a, L: Ord, R> Iterator for LeftRange<'a, L, R> {
+    type Item = (&'a L, &'a R);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.as_mut()?.next()
+    }
+}
+
+impl<'a, L: Ord, R> DoubleEndedIterator for LeftRange<'a, L, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.as_mut()?.next_back()
+    }
+}
+
+impl<'a, L: Ord, R> FusedIterator for LeftRange<'a, L, R> {}
+
+/// An iterator over a sub-range of pairs in a `BiBTreeMap`, sorted by right values.
+///
+/// This `struct` is created by the [`range_by_right`] method on [`BiBTreeMap`].
+/// See its documentation for more.
+///
+/// [`range_by_right`]: BiBTreeMap::range_by_right
+pub struct RightRange<'a, L, R>
+where
+    R: Ord,
+{
+    inner: Option<RangeMap<'a, R, L>>,
+}
+
+impl<'a, L, R: Ord> Iterator for RightRange<'a, L, R> {
+    type Item = (&'a L, &'a R);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .as_mut()?
+            .next()
+            .map(|(right, left)| (left, right))
+    }
+}
+
+impl<'a, L, R: Ord> DoubleEndedIterator for RightRange<'a, L, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .as_mut()?
+            .next_back()
+            .map(|(right, left)| (left, right))
+    }
+}
+
+impl<'a, L, R: Ord> FusedIterator for RightRange<'a, L, R> {}
+
+/// An owning iterator over the pairs of a `BiBTreeMap`, sorted by left values.
+///
+/// This `struct` is created by the [`into_iter`] method on [`BiBTreeMap`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+///
+/// [`into_iter`]: BiBTreeMap#method.into_iter
+pub struct IntoIter<L, R>
+where
+    L: Ord,
+{
+    inner: IntoIterMap<L, R>,
+}
+
+impl<L: Ord, R> Iterator for IntoIter<L, R> {
+    type Item = (L, R);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<L: Ord, R> DoubleEndedIterator for IntoIter<L, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<L: Ord, R> FusedIterator for IntoIter<L, R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{BiBTreeMap, Overwritten};
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_insert_keeps_both_sides_consistent() {
+        let mut map = BiBTreeMap::with_maximum_node_size(4);
+        for id in 0..100u32 {
+            assert_eq!(
+                map.insert(id, format!("user-{id:03}")),
+                Overwritten::Neither
+            );
+        }
+        assert_eq!(
+            map.insert(5, "user-007".to_string()),
+            Overwritten::Both((5, "user-005".to_string()), (7, "user-007".to_string()))
+        );
+        assert_eq!(map.len(), 99);
+        assert_eq!(map.get_by_right("user-007"), Some(&5));
+        assert_eq!(map.get_by_left(&7), None);
+        assert_eq!(map.get_by_right("user-005"), None);
+        assert_eq!(map.rank_by_left(&8), 7);
+        assert_eq!(map.rank_by_right("user-008"), 7);
+        assert_eq!(
+            map.get_index_by_right(6),
+            Some((&5, &"user-007".to_string()))
+        );
+        assert_eq!(map.range_by_left(6..=8).count(), 2);
+        assert_eq!(map.range_by_left(..0).count(), 0);
+        assert_eq!(map.range_by_right("user-090".to_string()..).count(), 10);
+        assert_eq!(
+            map.range_by_right("user-0995".to_string().."user-1".to_string())
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([30; 32]);
+        let mut map = BiBTreeMap::with_maximum_node_size(8);
+        let mut model: Vec<(u8, u16)> = vec![];
+        for _ in 0..10_000 {
+            let left = rng.gen_range(0..128u8);
+            let right = rng.gen_range(0..256u16);
+            match rng.gen_range(0..4) {
+                0 => {
+                    let expected = model
+                        .iter()
+                        .position(|(l, _)| *l == left)
+                        .map(|idx| model.remove(idx));
+                    assert_eq!(map.remove_by_left(&left), expected);
+                }
+                1 => {
+                    let expected = model
+                        .iter()
+                        .position(|(_, r)| *r == right)
+                        .map(|idx| model.remove(idx));
+                    assert_eq!(map.remove_by_right(&right), expected);
+                }
+                _ => {
+                    model.retain(|(l, r)| *l != left && *r != right);
+                    model.push((left, right));
+                    map.insert(left, right);
+                }
+            }
+        }
+
+        model.sort();
+        assert_eq!(map.len(), model.len());
+        for (idx, (left, right)) in model.iter().enumerate() {
+            assert_eq!(map.get_index_by_left(idx), Some((left, right)));
+            assert_eq!(map.get_by_right(right), Some(left));
+        }
+        let mut by_right = model.clone();
+        by_right.sort_by_key(|(_, right)| *right);
+        for (idx, (left, right)) in by_right.iter().enumerate() {
+            assert_eq!(map.get_index_by_right(idx), Some((left, right)));
+        }
+        for bound in 0..128 {
+            assert_eq!(
+                map.range_by_left(bound..bound + 16).count(),
+                model
+                    .iter()
+                    .filter(|(l, _)| (bound..bound + 16).contains(l))
+                    .count()
+            );
+            assert_eq!(
+                map.range_by_right(..=bound as u16 * 2)
+                    .rev()
+                    .map(|(l, r)| (*l, *r))
+                    .collect::<Vec<_>>(),
+                by_right
+                    .iter()
+                    .filter(|(_, r)| *r <= bound as u16 * 2)
+                    .rev()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            );
+        }
+        assert!(map.into_iter().eq(model));
+    }
+}
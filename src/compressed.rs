@@ -0,0 +1,1961 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::iter::FusedIterator;
+use std::ops::{Bound, RangeBounds};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::constants::DEFAULT_INNER_SIZE;
+use crate::core::layout::{IntoNodes, Layout, LayoutNode, Nodes, Spine};
+use crate::core::node::SplitPolicy;
+
+// Length of the longest common prefix of both strings that ends on a character boundary
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    let mut len = a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count();
+    while !a.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    len
+}
+
+// Every key of a node is `prefix` followed by one of the suffixes, which are laid out one after
+// the other in `buffer`, the i-th one ending at `ends[i]`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default)]
+struct Node {
+    prefix: String,
+    buffer: String,
+    ends: Vec<usize>,
+}
+
+impl Node {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            ends: Vec::with_capacity(capacity),
+            ..Default::default()
+        }
+    }
+    fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+    fn start(&self, position: usize) -> usize {
+        match position {
+            0 => 0,
+            _ => self.ends[position - 1],
+        }
+    }
+    fn suffix(&self, position: usize) -> &str {
+        &self.buffer[self.start(position)..self.ends[position]]
+    }
+    fn key(&self, position: usize) -> Cow<'_, str> {
+        if self.prefix.is_empty() {
+            return Cow::Borrowed(self.suffix(position));
+        }
+
+        Cow::Owned([self.prefix.as_str(), self.suffix(position)].concat())
+    }
+    fn compare(&self, position: usize, needle: &str) -> Ordering {
+        self.prefix
+            .bytes()
+            .chain(self.suffix(position).bytes())
+            .cmp(needle.bytes())
+    }
+    // Number of keys that are less than `needle`, or not greater than it when `inclusive` is set
+    fn rank(&self, needle: &str, inclusive: bool) -> usize {
+        // Keys sharing the prefix, they all compare to a needle that does not as the prefix does
+        let Some(rest) = needle.strip_prefix(self.prefix.as_str()) else {
+            return if self.prefix.as_str() < needle {
+                self.len()
+            } else {
+                0
+            };
+        };
+
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let suffix = self.suffix(mid);
+            if suffix < rest || (inclusive && suffix == rest) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        low
+    }
+    // Rebuilds the buffer, with every suffix losing its first `strip` bytes and gaining `extra`
+    fn rewrite_suffixes(&mut self, strip: usize, extra: &str) {
+        let mut buffer = String::with_capacity(self.buffer.len() + self.len() * extra.len());
+        let mut ends = Vec::with_capacity(self.ends.capacity());
+        for position in 0..self.len() {
+            buffer.push_str(extra);
+            buffer.push_str(&self.suffix(position)[strip..]);
+            ends.push(buffer.len());
+        }
+
+        self.buffer = buffer;
+        self.ends = ends;
+    }
+    // Lengthens the prefix as much as the keys allow, which only depends on the first and last one
+    fn compress(&mut self) {
+        if self.is_empty() {
+            self.prefix.clear();
+            self.buffer.clear();
+            return;
+        }
+
+        let shared = common_prefix_len(self.suffix(0), self.suffix(self.len() - 1));
+        if shared > 0 {
+            let extra = self.suffix(0)[..shared].to_string();
+            self.rewrite_suffixes(shared, "");
+            self.prefix.push_str(&extra);
+        }
+    }
+    fn insert(&mut self, position: usize, key: &str) {
+        if self.is_empty() {
+            self.prefix = key.to_string();
+            self.buffer.clear();
+            self.ends.push(0);
+            return;
+        }
+
+        if !key.starts_with(self.prefix.as_str()) {
+            let shared = common_prefix_len(&self.prefix, key);
+            let extra = self.prefix.split_off(shared);
+            self.rewrite_suffixes(0, &extra);
+        }
+
+        let suffix = &key[self.prefix.len()..];
+        let start = self.start(position);
+        self.buffer.insert_str(start, suffix);
+        self.ends[position..]
+            .iter_mut()
+            .for_each(|end| *end += suffix.len());
+        self.ends.insert(position, start + suffix.len());
+    }
+    fn remove(&mut self, position: usize) -> String {
+        let key = self.key(position).into_owned();
+        let (start, end) = (self.start(position), self.ends[position]);
+        self.buffer.replace_range(start..end, "");
+        self.ends.remove(position);
+        self.ends[position..]
+            .iter_mut()
+            .for_each(|offset| *offset -= end - start);
+
+        // Only the first and the last key bound the prefix, so it can only grow when one of them
+        // goes away
+        if position == 0 || position == self.len() {
+            self.compress();
+        }
+
+        key
+    }
+}
+
+impl LayoutNode for Node {
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+    fn split_off(&mut self, at: usize) -> Self {
+        let offset = self.start(at);
+        let mut other = Self {
+            prefix: self.prefix.clone(),
+            buffer: self.buffer.split_off(offset),
+            ends: self.ends.split_off(at),
+        };
+        other.ends.iter_mut().for_each(|end| *end -= offset);
+
+        self.compress();
+        other.compress();
+
+        other
+    }
+}
+
+// Walks over the keys of a node, rebuilding each of them as it is reached
+struct NodeKeys<'a> {
+    node: &'a Node,
+    positions: std::ops::Range<usize>,
+}
+
+impl<'a> NodeKeys<'a> {
+    fn new(node: &'a Node) -> Self {
+        Self::part(node, 0..node.len())
+    }
+    fn part(node: &'a Node, positions: std::ops::Range<usize>) -> Self {
+        Self { node, positions }
+    }
+    // Rebuilds every key at once, as the node is about to go away
+    fn into_owned(node: Node) -> std::vec::IntoIter<String> {
+        let keys: Vec<String> = NodeKeys::new(&node).map(Cow::into_owned).collect();
+
+        keys.into_iter()
+    }
+}
+
+impl<'a> Iterator for NodeKeys<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.positions
+            .next()
+            .map(|position| self.node.key(position))
+    }
+}
+
+impl<'a> DoubleEndedIterator for NodeKeys<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.positions
+            .next_back()
+            .map(|position| self.node.key(position))
+    }
+}
+
+/// An ordered set of strings based on a two-level B-Tree, whose nodes are prefix-compressed.
+///
+/// Sorted strings that sit next to each other tend to share long prefixes, as URLs, paths or
+/// composite identifiers do. Each node of this set stores the prefix shared by all of its strings
+/// once, and the remaining suffixes back to back in a single buffer, so that it takes far less
+/// memory than a [`BTreeSet<String>`](crate::BTreeSet) holding the same strings. Lookups compare
+/// the needle against the prefix once per node and then only against suffixes.
+///
+/// As strings are not stored whole, they are handed out as [`Cow`]s, which borrow from the node
+/// when its prefix is empty and are rebuilt otherwise. Apart from that, the set behaves like a
+/// [`BTreeSet<String>`](crate::BTreeSet), including positional access and ranks.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::compressed::StringSet;
+///
+/// let mut urls = StringSet::new();
+/// urls.insert("https://example.com/b");
+/// urls.insert("https://example.com/a");
+/// urls.insert("https://example.org/");
+///
+/// assert!(urls.contains("https://example.com/a"));
+/// assert_eq!(urls.rank("https://example.com/c"), 2);
+/// assert_eq!(urls.get_index(2).as_deref(), Some("https://example.org/"));
+/// assert!(urls
+///     .range("https://example.com/"..)
+///     .eq(["https://example.com/a", "https://example.com/b", "https://example.org/"]));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct StringSet {
+    layout: Layout<Node>,
+    node_capacity: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    split_policy: SplitPolicy,
+}
+
+impl Default for StringSet {
+    fn default() -> Self {
+        Self {
+            layout: Layout::new(Node::with_capacity(DEFAULT_INNER_SIZE)),
+            node_capacity: DEFAULT_INNER_SIZE,
+            split_policy: SplitPolicy::default(),
+        }
+    }
+}
+
+impl StringSet {
+    /// Makes a new, empty `StringSet` with maximum node size 1024.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `StringSet` with the given maximum node size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            layout: Layout::new(Node::with_capacity(maximum_node_size)),
+            node_capacity: maximum_node_size,
+            ..Default::default()
+        }
+    }
+    /// Clears the set, removing all strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::new();
+    /// set.insert("a");
+    /// set.clear();
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.layout = Layout::new(Node::with_capacity(self.node_capacity));
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    /// use indexset::SplitPolicy;
+    ///
+    /// let mut set = StringSet::new();
+    /// set.set_split_policy(SplitPolicy::RightBiased);
+    /// assert!(matches!(set.split_policy(), SplitPolicy::RightBiased));
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.split_policy = split_policy;
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.split_policy
+    }
+    /// Returns the number of strings in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::new();
+    /// assert_eq!(set.len(), 0);
+    /// set.insert("a");
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.layout.len()
+    }
+    /// Returns `true` if the set contains no strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::new();
+    /// assert!(set.is_empty());
+    /// set.insert("a");
+    /// assert!(!set.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    // Position of the first string that is greater than or equal to `needle`, or greater than it
+    // when `inclusive` is set, as a node and an offset within it
+    fn locate(&self, needle: &str, inclusive: bool) -> (usize, usize) {
+        let node_idx = self.layout.locate_node(|node| {
+            if node.is_empty() {
+                return false;
+            }
+
+            match node.compare(node.len() - 1, needle) {
+                Ordering::Less => true,
+                Ordering::Equal => inclusive,
+                Ordering::Greater => false,
+            }
+        });
+
+        (
+            node_idx,
+            self.layout.nodes()[node_idx].rank(needle, inclusive),
+        )
+    }
+    fn rank_bound(&self, needle: &str, inclusive: bool) -> usize {
+        let (node_idx, position_within_node) = self.locate(needle, inclusive);
+
+        self.layout.offset(node_idx) + position_within_node
+    }
+    fn find(&self, value: &str) -> Option<(usize, usize)> {
+        let (node_idx, position_within_node) = self.locate(value, false);
+        let node = &self.layout.nodes()[node_idx];
+        if position_within_node < node.len()
+            && node.compare(position_within_node, value) == Ordering::Equal
+        {
+            return Some((node_idx, position_within_node));
+        }
+
+        None
+    }
+    /// Returns `true` if the set contains the string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let set = StringSet::from_iter(["apple", "apricot"]);
+    /// assert!(set.contains("apple"));
+    /// assert!(!set.contains("ap"));
+    /// ```
+    pub fn contains(&self, value: &str) -> bool {
+        self.find(value).is_some()
+    }
+    /// Returns the string in the set that is equal to the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let set = StringSet::from_iter(["apple", "apricot"]);
+    /// assert_eq!(set.get("apple").as_deref(), Some("apple"));
+    /// assert_eq!(set.get("banana"), None);
+    /// ```
+    pub fn get(&self, value: &str) -> Option<Cow<'_, str>> {
+        let (node_idx, position_within_node) = self.find(value)?;
+
+        Some(self.layout.nodes()[node_idx].key(position_within_node))
+    }
+    /// Returns the i-th string of the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let set = StringSet::from_iter(["c", "a", "b"]);
+    /// assert_eq!(set.get_index(0).as_deref(), Some("a"));
+    /// assert_eq!(set.get_index(2).as_deref(), Some("c"));
+    /// assert_eq!(set.get_index(3), None);
+    /// ```
+    pub fn get_index(&self, idx: usize) -> Option<Cow<'_, str>> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let (node_idx, position_within_node) = self.layout.locate_ith(idx);
+
+        Some(self.layout.nodes()[node_idx].key(position_within_node))
+    }
+    /// Returns the position in which the given string would fall in the already-existing sorted
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let set = StringSet::from_iter(["a", "b", "d"]);
+    /// assert_eq!(set.rank("a"), 0);
+    /// assert_eq!(set.rank("c"), 2);
+    /// assert_eq!(set.rank("e"), 3);
+    /// ```
+    pub fn rank(&self, value: &str) -> usize {
+        self.rank_bound(value, false)
+    }
+    /// Returns the first string of the set, which is the minimum one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::new();
+    /// assert_eq!(set.first(), None);
+    /// set.insert("b");
+    /// set.insert("a");
+    /// assert_eq!(set.first().as_deref(), Some("a"));
+    /// ```
+    pub fn first(&self) -> Option<Cow<'_, str>> {
+        self.get_index(0)
+    }
+    /// Returns the last string of the set, which is the maximum one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::new();
+    /// assert_eq!(set.last(), None);
+    /// set.insert("a");
+    /// set.insert("b");
+    /// assert_eq!(set.last().as_deref(), Some("b"));
+    /// ```
+    pub fn last(&self) -> Option<Cow<'_, str>> {
+        self.get_index(self.len().checked_sub(1)?)
+    }
+    /// Adds a string to the set.
+    ///
+    /// Returns whether the string was newly inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::new();
+    /// assert_eq!(set.insert("a"), true);
+    /// assert_eq!(set.insert("a"), false);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn insert(&mut self, value: &str) -> bool {
+        let (node_idx, position_within_node) = self.locate(value, false);
+        let node = &self.layout.nodes()[node_idx];
+        if position_within_node < node.len()
+            && node.compare(position_within_node, value) == Ordering::Equal
+        {
+            return false;
+        }
+
+        self.layout.insert(
+            node_idx,
+            position_within_node,
+            self.node_capacity,
+            self.split_policy,
+            |node, position| node.insert(position, value),
+        );
+
+        true
+    }
+    fn delete_at(&mut self, node_idx: usize, position_within_node: usize) -> String {
+        self.layout
+            .remove_with(node_idx, |node| node.remove(position_within_node))
+    }
+    /// Removes a string from the set. Returns whether the string was present in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::from_iter(["a"]);
+    /// assert_eq!(set.remove("a"), true);
+    /// assert_eq!(set.remove("a"), false);
+    /// ```
+    pub fn remove(&mut self, value: &str) -> bool {
+        self.take(value).is_some()
+    }
+    /// Removes and returns the string in the set that is equal to the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::from_iter(["a"]);
+    /// assert_eq!(set.take("a"), Some(String::from("a")));
+    /// assert_eq!(set.take("a"), None);
+    /// ```
+    pub fn take(&mut self, value: &str) -> Option<String> {
+        let (node_idx, position_within_node) = self.find(value)?;
+
+        Some(self.delete_at(node_idx, position_within_node))
+    }
+    /// Removes the i-th string from the set and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::from_iter(["a", "b"]);
+    /// assert_eq!(set.pop_index(1), "b");
+    /// assert_eq!(set.pop_index(0), "a");
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn pop_index(&mut self, idx: usize) -> String {
+        assert!(idx < self.len(), "index out of bounds");
+        let (node_idx, position_within_node) = self.layout.locate_ith(idx);
+
+        self.delete_at(node_idx, position_within_node)
+    }
+    /// Removes and returns the first string of the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::from_iter(["a", "b"]);
+    /// assert_eq!(set.pop_first().as_deref(), Some("a"));
+    /// assert_eq!(set.pop_first().as_deref(), Some("b"));
+    /// assert_eq!(set.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.delete_at(0, 0))
+    }
+    /// Removes and returns the last string of the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let mut set = StringSet::from_iter(["a", "b"]);
+    /// assert_eq!(set.pop_last().as_deref(), Some("b"));
+    /// assert_eq!(set.pop_last().as_deref(), Some("a"));
+    /// assert_eq!(set.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let node_idx = self.layout.nodes().len() - 1;
+        let position_within_node = self.layout.nodes()[node_idx].len() - 1;
+
+        Some(self.delete_at(node_idx, position_within_node))
+    }
+    fn spine(&self, start: usize, end: usize) -> Spine<Nodes<'_, Node, NodeKeys<'_>>> {
+        self.layout.spine(start, end, NodeKeys::new, NodeKeys::part)
+    }
+    /// Gets an iterator that visits the strings of the set in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let set = StringSet::from_iter(["c", "b", "a"]);
+    /// let mut iter = set.iter();
+    /// assert_eq!(iter.next().as_deref(), Some("a"));
+    /// assert_eq!(iter.next_back().as_deref(), Some("c"));
+    /// assert_eq!(iter.next().as_deref(), Some("b"));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            spine: self.spine(0, self.len()),
+        }
+    }
+    /// Constructs a double-ended iterator over a sub-range of strings in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::StringSet;
+    ///
+    /// let set = StringSet::from_iter(["ab", "b", "ba", "c"]);
+    /// assert!(set.range("b".."c").eq(["b", "ba"]));
+    /// assert!(set.range(..="b").eq(["ab", "b"]));
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> Range<'_>
+    where
+        Q: AsRef<str> + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.rank_bound(start.as_ref(), false),
+            Bound::Excluded(start) => self.rank_bound(start.as_ref(), true),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.rank_bound(end.as_ref(), true),
+            Bound::Excluded(end) => self.rank_bound(end.as_ref(), false),
+            Bound::Unbounded => self.len(),
+        };
+
+        Range {
+            iter: Iter {
+                spine: self.spine(start, end),
+            },
+        }
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for StringSet {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        let mut set = StringSet::new();
+        iter.into_iter().for_each(|value| {
+            set.insert(value.as_ref());
+        });
+
+        set
+    }
+}
+
+impl<S: AsRef<str>, const N: usize> From<[S; N]> for StringSet {
+    fn from(value: [S; N]) -> Self {
+        StringSet::from_iter(value)
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for StringSet {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|value| {
+            self.insert(value.as_ref());
+        });
+    }
+}
+
+impl IntoIterator for StringSet {
+    type Item = String;
+
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            spine: self.layout.into_spine(NodeKeys::into_owned),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a StringSet {
+    type Item = Cow<'a, str>;
+
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the strings of a `StringSet`.
+///
+/// This `struct` is created by the [`iter`] method on [`StringSet`].
+/// See its documentation for more.
+///
+/// [`iter`]: StringSet::iter
+pub struct Iter<'a> {
+    spine: Spine<Nodes<'a, Node, NodeKeys<'a>>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.spine.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spine.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.spine.next_back()
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+impl<'a> FusedIterator for Iter<'a> {}
+
+/// An owning iterator over the strings of a `StringSet`.
+///
+/// This `struct` is created by the [`into_iter`] method on [`StringSet`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+///
+/// [`into_iter`]: StringSet#method.into_iter
+pub struct IntoIter {
+    spine: Spine<IntoNodes<Node, std::vec::IntoIter<String>>>,
+}
+
+impl Iterator for IntoIter {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.spine.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spine.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.spine.next_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl FusedIterator for IntoIter {}
+
+/// An iterator over a sub-range of strings in a `StringSet`.
+///
+/// This `struct` is created by the [`range`] method on [`StringSet`].
+/// See its documentation for more.
+///
+/// [`range`]: StringSet::range
+pub struct Range<'a> {
+    iter: Iter<'a>,
+}
+
+impl<'a> Iterator for Range<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Range<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl<'a> ExactSizeIterator for Range<'a> {}
+
+impl<'a> FusedIterator for Range<'a> {}
+
+// Deltas from the base of a node, each taking `width` bits, or one bit per value of its span
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Packed { width: u32 },
+    Bitmap,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+struct CompactNode {
+    base: u64,
+    max: u64,
+    len: usize,
+    encoding: Encoding,
+    words: Vec<u64>,
+}
+
+impl CompactNode {
+    fn empty() -> Self {
+        Self {
+            base: 0,
+            max: 0,
+            len: 0,
+            encoding: Encoding::Packed { width: 0 },
+            words: vec![],
+        }
+    }
+    // Picks whichever of the two encodings takes fewer words for the sorted `values`
+    fn encode(values: &[u64]) -> Self {
+        let (Some(&base), Some(&max)) = (values.first(), values.last()) else {
+            return Self::empty();
+        };
+
+        let span = max - base;
+        let width = u64::BITS - span.leading_zeros();
+        let packed_words = (values.len() * width as usize).div_ceil(64);
+        let bitmap_words = (span / 64 + 1) as usize;
+        if bitmap_words < packed_words {
+            let mut words = vec![0; bitmap_words];
+            for value in values {
+                let delta = value - base;
+                words[(delta / 64) as usize] |= 1 << (delta % 64);
+            }
+
+            return Self {
+                base,
+                max,
+                len: values.len(),
+                encoding: Encoding::Bitmap,
+                words,
+            };
+        }
+
+        let mut words = vec![0; packed_words];
+        if width > 0 {
+            for (i, value) in values.iter().enumerate() {
+                let delta = value - base;
+                let bit = i * width as usize;
+                let (word, offset) = (bit / 64, bit % 64);
+                words[word] |= delta << offset;
+                if offset + width as usize > 64 {
+                    words[word + 1] |= delta >> (64 - offset);
+                }
+            }
+        }
+
+        Self {
+            base,
+            max,
+            len: values.len(),
+            encoding: Encoding::Packed { width },
+            words,
+        }
+    }
+    fn decode(&self) -> Vec<u64> {
+        match self.encoding {
+            Encoding::Packed { .. } => (0..self.len).map(|i| self.get(i)).collect(),
+            Encoding::Bitmap => {
+                let mut values = Vec::with_capacity(self.len);
+                for (i, &word) in self.words.iter().enumerate() {
+                    let mut word = word;
+                    while word != 0 {
+                        values.push(self.base + i as u64 * 64 + word.trailing_zeros() as u64);
+                        word &= word - 1;
+                    }
+                }
+
+                values
+            }
+        }
+    }
+    fn min(&self) -> Option<u64> {
+        (self.len > 0).then_some(self.base)
+    }
+    fn max(&self) -> Option<u64> {
+        (self.len > 0).then_some(self.max)
+    }
+    fn get(&self, position: usize) -> u64 {
+        match self.encoding {
+            Encoding::Packed { width: 0 } => self.base,
+            Encoding::Packed { width } => {
+                let bit = position * width as usize;
+                let (word, offset) = (bit / 64, bit % 64);
+                let mut delta = self.words[word] >> offset;
+                if offset + width as usize > 64 {
+                    delta |= self.words[word + 1] << (64 - offset);
+                }
+                if width < 64 {
+                    delta &= (1 << width) - 1;
+                }
+
+                self.base + delta
+            }
+            Encoding::Bitmap => {
+                let mut remaining = position;
+                for (i, &word) in self.words.iter().enumerate() {
+                    let ones = word.count_ones() as usize;
+                    if remaining < ones {
+                        let mut word = word;
+                        (0..remaining).for_each(|_| word &= word - 1);
+
+                        return self.base + i as u64 * 64 + word.trailing_zeros() as u64;
+                    }
+                    remaining -= ones;
+                }
+
+                unreachable!("position is out of bounds")
+            }
+        }
+    }
+    // Number of values that are less than `value`
+    fn rank(&self, value: u64) -> usize {
+        if self.len == 0 || value <= self.base {
+            return 0;
+        }
+        if value > self.max {
+            return self.len;
+        }
+
+        match self.encoding {
+            Encoding::Packed { .. } => {
+                let (mut low, mut high) = (0, self.len);
+                while low < high {
+                    let mid = low + (high - low) / 2;
+                    if self.get(mid) < value {
+                        low = mid + 1;
+                    } else {
+                        high = mid;
+                    }
+                }
+
+                low
+            }
+            Encoding::Bitmap => {
+                let delta = value - self.base;
+                let (word, offset) = ((delta / 64) as usize, delta % 64);
+                let below: usize = self.words[..word]
+                    .iter()
+                    .map(|word| word.count_ones() as usize)
+                    .sum();
+
+                below + (self.words[word] & ((1 << offset) - 1)).count_ones() as usize
+            }
+        }
+    }
+    fn contains(&self, value: u64) -> bool {
+        if self.len == 0 || value < self.base || value > self.max {
+            return false;
+        }
+
+        match self.encoding {
+            Encoding::Packed { .. } => {
+                let position = self.rank(value);
+                position < self.len && self.get(position) == value
+            }
+            Encoding::Bitmap => {
+                let delta = value - self.base;
+                self.words[(delta / 64) as usize] & (1 << (delta % 64)) != 0
+            }
+        }
+    }
+    // Bits of a bitmap for the 64 values starting at `value`, which must be within its span
+    fn bits_from(&self, value: u64) -> u64 {
+        let delta = value - self.base;
+        let (word, offset) = ((delta / 64) as usize, delta % 64);
+        let mut bits = self.words[word] >> offset;
+        if offset > 0 {
+            if let Some(next) = self.words.get(word + 1) {
+                bits |= next << (64 - offset);
+            }
+        }
+
+        bits
+    }
+    fn insert(&mut self, value: u64) -> bool {
+        if self.encoding == Encoding::Bitmap && self.base <= value && value <= self.max {
+            let delta = value - self.base;
+            let (word, bit) = ((delta / 64) as usize, 1 << (delta % 64));
+            if self.words[word] & bit != 0 {
+                return false;
+            }
+
+            self.words[word] |= bit;
+            self.len += 1;
+            return true;
+        }
+
+        let mut values = self.decode();
+        match values.binary_search(&value) {
+            Ok(_) => false,
+            Err(position) => {
+                values.insert(position, value);
+                *self = Self::encode(&values);
+                true
+            }
+        }
+    }
+    fn remove(&mut self, value: u64) -> bool {
+        if !self.contains(value) {
+            return false;
+        }
+
+        // Clearing a bit in the middle of a bitmap keeps both its bounds and its encoding valid
+        if self.encoding == Encoding::Bitmap && self.base < value && value < self.max {
+            let delta = value - self.base;
+            self.words[(delta / 64) as usize] &= !(1 << (delta % 64));
+            self.len -= 1;
+            return true;
+        }
+
+        let mut values = self.decode();
+        values.retain(|item| *item != value);
+        *self = Self::encode(&values);
+
+        true
+    }
+}
+
+impl LayoutNode for CompactNode {
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn split_off(&mut self, at: usize) -> Self {
+        let mut values = self.decode();
+        let other = values.split_off(at);
+        *self = Self::encode(&values);
+
+        Self::encode(&other)
+    }
+}
+
+// Collects sorted values into nodes, taking whole nodes over as they are whenever possible
+struct CompactBuilder {
+    inner: Vec<CompactNode>,
+    pending: Vec<u64>,
+    node_capacity: usize,
+}
+
+impl CompactBuilder {
+    fn new(node_capacity: usize) -> Self {
+        Self {
+            inner: vec![],
+            pending: Vec::with_capacity(node_capacity),
+            node_capacity,
+        }
+    }
+    fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            self.inner.push(CompactNode::encode(&self.pending));
+            self.pending.clear();
+        }
+    }
+    fn push(&mut self, value: u64) {
+        self.pending.push(value);
+        if self.pending.len() >= self.node_capacity {
+            self.flush();
+        }
+    }
+    fn push_node(&mut self, node: &CompactNode) {
+        self.flush();
+        self.inner.push(node.clone());
+    }
+    fn build(mut self) -> CompactU64Set {
+        self.flush();
+        if self.inner.is_empty() {
+            self.inner.push(CompactNode::empty());
+        }
+
+        CompactU64Set {
+            layout: Layout::from_nodes(self.inner),
+            node_capacity: self.node_capacity,
+            split_policy: SplitPolicy::default(),
+        }
+    }
+}
+
+// Walks over the nodes of a set, decoding one only once its values have to be looked at one by
+// one
+struct NodeCursor<'a> {
+    nodes: &'a [CompactNode],
+    decoded: Option<(Vec<u64>, usize)>,
+}
+
+impl<'a> NodeCursor<'a> {
+    fn new(nodes: &'a [CompactNode]) -> Self {
+        Self {
+            nodes: match nodes.first() {
+                Some(node) if node.len == 0 => &[],
+                _ => nodes,
+            },
+            decoded: None,
+        }
+    }
+    fn bounds(&self) -> Option<(u64, u64)> {
+        let node = self.nodes.first()?;
+        match &self.decoded {
+            Some((values, position)) => Some((values[*position], node.max)),
+            None => Some((node.base, node.max)),
+        }
+    }
+    fn is_whole(&self) -> bool {
+        self.decoded.is_none()
+    }
+    fn skip_node(&mut self) {
+        self.nodes = &self.nodes[1..];
+        self.decoded = None;
+    }
+    fn peek(&mut self) -> Option<u64> {
+        let node = self.nodes.first()?;
+        let (values, position) = self.decoded.get_or_insert_with(|| (node.decode(), 0));
+
+        Some(values[*position])
+    }
+    fn advance(&mut self) {
+        if let Some((values, position)) = &mut self.decoded {
+            *position += 1;
+            if *position == values.len() {
+                self.skip_node();
+            }
+        }
+    }
+    // Moves to the first value of the current node that is not less than `value`, which has to
+    // exist
+    fn seek(&mut self, value: u64) {
+        let node = &self.nodes[0];
+        self.decoded = Some((node.decode(), node.rank(value)));
+    }
+    // Hands the rest of the current node over to the builder
+    fn drain_node(&mut self, builder: &mut CompactBuilder) {
+        match self.decoded.take() {
+            Some((values, position)) => values[position..]
+                .iter()
+                .for_each(|value| builder.push(*value)),
+            None => builder.push_node(&self.nodes[0]),
+        }
+        self.skip_node();
+    }
+}
+
+/// An ordered set of `u64` based on a two-level B-Tree, whose nodes are compressed.
+///
+/// A [`BTreeSet<u64>`](crate::BTreeSet) spends eight bytes on every element. Each node of this set
+/// rather stores its smallest element, and either the differences of the others to it packed
+/// with as many bits as the largest one needs, or a bitmap of the span of the node when that is
+/// smaller, which is the case for dense identifiers. The API mirrors the one of
+/// [`BTreeSet`](crate::BTreeSet), including positional access and ranks, except that elements are
+/// returned by value, and [`union`](CompactU64Set::union) and
+/// [`intersection`](CompactU64Set::intersection) build a new set, copying or skipping whole nodes
+/// that do not overlap the other set.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::compressed::CompactU64Set;
+///
+/// let mut ids = CompactU64Set::from_iter(1000..3000);
+/// ids.insert(10_000);
+/// ids.remove(1500);
+///
+/// assert!(ids.contains(2999));
+/// assert_eq!(ids.rank(2000), 999);
+/// assert_eq!(ids.get_index(1998), Some(2999));
+/// assert!(ids.range(2998..).eq([2998, 2999, 10_000]));
+///
+/// let evens = CompactU64Set::from_iter((0..4000).step_by(2));
+/// assert_eq!(ids.intersection(&evens).len(), 999);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct CompactU64Set {
+    layout: Layout<CompactNode>,
+    node_capacity: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    split_policy: SplitPolicy,
+}
+
+impl Default for CompactU64Set {
+    fn default() -> Self {
+        Self {
+            layout: Layout::new(CompactNode::empty()),
+            node_capacity: DEFAULT_INNER_SIZE,
+            split_policy: SplitPolicy::default(),
+        }
+    }
+}
+
+impl CompactU64Set {
+    /// Makes a new, empty `CompactU64Set` with maximum node size 1024.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `CompactU64Set` with the given maximum node size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            node_capacity: maximum_node_size,
+            ..Default::default()
+        }
+    }
+    /// Clears the set, removing all elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::from_iter([1, 2]);
+    /// set.clear();
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.layout = Layout::new(CompactNode::empty());
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    /// use indexset::SplitPolicy;
+    ///
+    /// let mut set = CompactU64Set::new();
+    /// set.set_split_policy(SplitPolicy::RightBiased);
+    /// assert!(matches!(set.split_policy(), SplitPolicy::RightBiased));
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.split_policy = split_policy;
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.split_policy
+    }
+    /// Returns the number of elements in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::new();
+    /// assert_eq!(set.len(), 0);
+    /// set.insert(1);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.layout.len()
+    }
+    /// Returns `true` if the set contains no elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::new();
+    /// assert!(set.is_empty());
+    /// set.insert(1);
+    /// assert!(!set.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn locate_node(&self, value: u64) -> usize {
+        self.layout
+            .locate_node(|node| node.max().is_some_and(|max| max < value))
+    }
+    /// Returns `true` if the set contains the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let set = CompactU64Set::from_iter([1, 2, 3]);
+    /// assert!(set.contains(1));
+    /// assert!(!set.contains(4));
+    /// ```
+    pub fn contains(&self, value: u64) -> bool {
+        self.layout.nodes()[self.locate_node(value)].contains(value)
+    }
+    /// Returns the i-th element of the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let set = CompactU64Set::from_iter([30, 10, 20]);
+    /// assert_eq!(set.get_index(0), Some(10));
+    /// assert_eq!(set.get_index(2), Some(30));
+    /// assert_eq!(set.get_index(3), None);
+    /// ```
+    pub fn get_index(&self, idx: usize) -> Option<u64> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let (node_idx, position_within_node) = self.layout.locate_ith(idx);
+
+        Some(self.layout.nodes()[node_idx].get(position_within_node))
+    }
+    /// Returns the position in which the given value would fall in the already-existing sorted
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let set = CompactU64Set::from_iter([1, 2, 4]);
+    /// assert_eq!(set.rank(1), 0);
+    /// assert_eq!(set.rank(3), 2);
+    /// assert_eq!(set.rank(5), 3);
+    /// ```
+    pub fn rank(&self, value: u64) -> usize {
+        let node_idx = self.locate_node(value);
+
+        self.layout.offset(node_idx) + self.layout.nodes()[node_idx].rank(value)
+    }
+    /// Returns the first element of the set, which is the minimum one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::new();
+    /// assert_eq!(set.first(), None);
+    /// set.extend([2, 1]);
+    /// assert_eq!(set.first(), Some(1));
+    /// ```
+    pub fn first(&self) -> Option<u64> {
+        self.layout.nodes()[0].min()
+    }
+    /// Returns the last element of the set, which is the maximum one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::new();
+    /// assert_eq!(set.last(), None);
+    /// set.extend([2, 1]);
+    /// assert_eq!(set.last(), Some(2));
+    /// ```
+    pub fn last(&self) -> Option<u64> {
+        self.layout.nodes().last()?.max()
+    }
+    /// Adds a value to the set.
+    ///
+    /// Returns whether the value was newly inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::new();
+    /// assert_eq!(set.insert(2), true);
+    /// assert_eq!(set.insert(2), false);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn insert(&mut self, value: u64) -> bool {
+        let node_idx = self.locate_node(value);
+        let node = &self.layout.nodes()[node_idx];
+        if node.contains(value) {
+            return false;
+        }
+
+        let position = node.rank(value);
+        self.layout.insert(
+            node_idx,
+            position,
+            self.node_capacity,
+            self.split_policy,
+            |node, _| node.insert(value),
+        );
+
+        true
+    }
+    /// Removes a value from the set. Returns whether the value was present in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let mut set = CompactU64Set::from_iter([2]);
+    /// assert_eq!(set.remove(2), true);
+    /// assert_eq!(set.remove(2), false);
+    /// ```
+    pub fn remove(&mut self, value: u64) -> bool {
+        let node_idx = self.locate_node(value);
+        if !self.layout.nodes()[node_idx].contains(value) {
+            return false;
+        }
+
+        self.layout.remove_with(node_idx, |node| node.remove(value))
+    }
+    fn spine(&self, start: usize, end: usize) -> Spine<Nodes<'_, CompactNode, Decoded>> {
+        self.layout.spine(
+            start,
+            end,
+            |node| node.decode().into_iter(),
+            |node, range| {
+                let mut values = node.decode();
+                values.truncate(range.end);
+                values.drain(..range.start);
+
+                values.into_iter()
+            },
+        )
+    }
+    /// Gets an iterator that visits the elements of the set in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let set = CompactU64Set::from_iter([3, 1, 2]);
+    /// let mut iter = set.iter();
+    /// assert_eq!(iter.next(), Some(1));
+    /// assert_eq!(iter.next_back(), Some(3));
+    /// assert_eq!(iter.next(), Some(2));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> CompactIter<'_> {
+        CompactIter {
+            spine: self.spine(0, self.len()),
+        }
+    }
+    /// Constructs a double-ended iterator over a sub-range of elements in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let set = CompactU64Set::from_iter([3, 5, 8]);
+    /// assert!(set.range(4..).eq([5, 8]));
+    /// assert!(set.range(..=5).rev().eq([5, 3]));
+    /// ```
+    pub fn range<R>(&self, range: R) -> CompactRange<'_>
+    where
+        R: RangeBounds<u64>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.rank(*start),
+            Bound::Excluded(start) => match start.checked_add(1) {
+                Some(start) => self.rank(start),
+                None => self.len(),
+            },
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => match end.checked_add(1) {
+                Some(end) => self.rank(end),
+                None => self.len(),
+            },
+            Bound::Excluded(end) => self.rank(*end),
+            Bound::Unbounded => self.len(),
+        };
+
+        CompactRange {
+            iter: CompactIter {
+                spine: self.spine(start, end),
+            },
+        }
+    }
+    /// Returns a new set with the elements that are in `self` or in `other`.
+    ///
+    /// Nodes of either set that do not overlap the other one are copied over without being
+    /// decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let a = CompactU64Set::from_iter([1, 2, 3]);
+    /// let b = CompactU64Set::from_iter([3, 4]);
+    /// assert!(a.union(&b).iter().eq([1, 2, 3, 4]));
+    /// ```
+    pub fn union(&self, other: &Self) -> Self {
+        let mut builder = CompactBuilder::new(self.node_capacity);
+        let mut left = NodeCursor::new(self.layout.nodes());
+        let mut right = NodeCursor::new(other.layout.nodes());
+        loop {
+            match (left.bounds(), right.bounds()) {
+                (None, None) => break,
+                (Some(_), None) => left.drain_node(&mut builder),
+                (None, Some(_)) => right.drain_node(&mut builder),
+                (Some((_, left_max)), Some((right_min, _))) if left_max < right_min => {
+                    left.drain_node(&mut builder)
+                }
+                (Some((left_min, _)), Some((_, right_max))) if right_max < left_min => {
+                    right.drain_node(&mut builder)
+                }
+                _ => {
+                    let (left_value, right_value) = (left.peek().unwrap(), right.peek().unwrap());
+                    builder.push(left_value.min(right_value));
+                    if left_value <= right_value {
+                        left.advance();
+                    }
+                    if right_value <= left_value {
+                        right.advance();
+                    }
+                }
+            }
+        }
+
+        builder.build()
+    }
+    /// Returns a new set with the elements that are both in `self` and in `other`.
+    ///
+    /// Nodes of either set that do not overlap the other one are skipped without being decoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::compressed::CompactU64Set;
+    ///
+    /// let a = CompactU64Set::from_iter([1, 2, 3]);
+    /// let b = CompactU64Set::from_iter([2, 3, 4]);
+    /// assert!(a.intersection(&b).iter().eq([2, 3]));
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut builder = CompactBuilder::new(self.node_capacity);
+        let mut left = NodeCursor::new(self.layout.nodes());
+        let mut right = NodeCursor::new(other.layout.nodes());
+        while let (Some((left_min, left_max)), Some((right_min, right_max))) =
+            (left.bounds(), right.bounds())
+        {
+            if left_max < right_min {
+                left.skip_node();
+            } else if right_max < left_min {
+                right.skip_node();
+            } else if left.is_whole()
+                && right.is_whole()
+                && left.nodes[0].encoding == Encoding::Bitmap
+                && right.nodes[0].encoding == Encoding::Bitmap
+            {
+                // Overlapping bitmaps are intersected 64 values at a time
+                let (low, high) = (left_min.max(right_min), left_max.min(right_max));
+                let mut value = low;
+                loop {
+                    let mut word = left.nodes[0].bits_from(value) & right.nodes[0].bits_from(value);
+                    if high - value < 63 {
+                        word &= (1 << (high - value + 1)) - 1;
+                    }
+                    while word != 0 {
+                        builder.push(value + word.trailing_zeros() as u64);
+                        word &= word - 1;
+                    }
+                    if high - value < 64 {
+                        break;
+                    }
+                    value += 64;
+                }
+
+                for (cursor, max) in [(&mut left, left_max), (&mut right, right_max)] {
+                    if max == high {
+                        cursor.skip_node();
+                    } else {
+                        cursor.seek(high + 1);
+                    }
+                }
+            } else {
+                let (left_value, right_value) = (left.peek().unwrap(), right.peek().unwrap());
+                if left_value == right_value {
+                    builder.push(left_value);
+                }
+                if left_value <= right_value {
+                    left.advance();
+                }
+                if right_value <= left_value {
+                    right.advance();
+                }
+            }
+        }
+
+        builder.build()
+    }
+}
+
+impl FromIterator<u64> for CompactU64Set {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let mut set = CompactU64Set::new();
+        set.extend(iter);
+
+        set
+    }
+}
+
+impl<const N: usize> From<[u64; N]> for CompactU64Set {
+    fn from(value: [u64; N]) -> Self {
+        CompactU64Set::from_iter(value)
+    }
+}
+
+impl Extend<u64> for CompactU64Set {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|value| {
+            self.insert(value);
+        });
+    }
+}
+
+impl<'a> IntoIterator for &'a CompactU64Set {
+    type Item = u64;
+
+    type IntoIter = CompactIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+type Decoded = std::vec::IntoIter<u64>;
+
+/// An iterator over the elements of a `CompactU64Set`.
+///
+/// This `struct` is created by the [`iter`] method on [`CompactU64Set`].
+/// See its documentation for more.
+///
+/// [`iter`]: CompactU64Set::iter
+pub struct CompactIter<'a> {
+    spine: Spine<Nodes<'a, CompactNode, Decoded>>,
+}
+
+impl<'a> Iterator for CompactIter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.spine.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spine.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for CompactIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.spine.next_back()
+    }
+}
+
+impl<'a> ExactSizeIterator for CompactIter<'a> {}
+
+impl<'a> FusedIterator for CompactIter<'a> {}
+
+/// An iterator over a sub-range of elements in a `CompactU64Set`.
+///
+/// This `struct` is created by the [`range`] method on [`CompactU64Set`].
+/// See its documentation for more.
+///
+/// [`range`]: CompactU64Set::range
+pub struct CompactRange<'a> {
+    iter: CompactIter<'a>,
+}
+
+impl<'a> Iterator for CompactRange<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for CompactRange<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl<'a> ExactSizeIterator for CompactRange<'a> {}
+
+impl<'a> FusedIterator for CompactRange<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompactU64Set, Encoding, StringSet};
+    use crate::core::layout::LayoutNode;
+    use crate::SplitPolicy;
+    use rand::{Rng, SeedableRng};
+    use std::ops::Bound;
+
+    fn url(rng: &mut impl Rng) -> String {
+        let host = ["example.com", "example.org", "ex.ample.net"][rng.gen_range(0..3)];
+        let section = ["articles", "authors", "arts", "été"][rng.gen_range(0..4)];
+
+        format!("https://{host}/{section}/{}", rng.gen_range(0..400))
+    }
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([40; 32]);
+        let mut set = StringSet::with_maximum_node_size(16);
+        let mut model = std::collections::BTreeSet::new();
+        for _ in 0..20_000 {
+            let value = url(&mut rng);
+            match rng.gen_range(0..10) {
+                0..=5 => assert_eq!(set.insert(&value), model.insert(value)),
+                6..=7 => assert_eq!(set.take(&value), model.take(&value)),
+                8 => assert_eq!(set.pop_first(), model.pop_first()),
+                _ => assert_eq!(set.pop_last(), model.pop_last()),
+            }
+            assert_eq!(set.len(), model.len());
+        }
+
+        assert!(set.iter().eq(model.iter().map(String::as_str)));
+        assert!(set.iter().rev().eq(model.iter().rev().map(String::as_str)));
+        for (idx, value) in model.iter().enumerate() {
+            assert_eq!(set.get_index(idx).as_deref(), Some(value.as_str()));
+            assert_eq!(set.rank(value), idx);
+            assert_eq!(set.get(value).as_deref(), Some(value.as_str()));
+        }
+        for _ in 0..2_000 {
+            let (a, b) = (url(&mut rng), url(&mut rng));
+            let (start, end) = if a <= b { (a, b) } else { (b, a) };
+            assert_eq!(set.contains(&start), model.contains(&start));
+            assert_eq!(set.rank(&start), model.range(..start.clone()).count());
+            assert!(set
+                .range(start.as_str()..end.as_str())
+                .eq(model.range(start.clone()..end.clone()).map(String::as_str)));
+            assert!(set
+                .range(start.as_str()..=end.as_str())
+                .rev()
+                .eq(model.range(start..=end).rev().map(String::as_str)));
+        }
+        assert!(set.into_iter().eq(model));
+    }
+
+    #[test]
+    fn test_prefix_is_shared_within_nodes() {
+        let set = StringSet::from_iter((0..10_000).map(|i| format!("https://example.com/{i:05}")));
+
+        assert!(set
+            .layout
+            .nodes()
+            .iter()
+            .all(|node| node.prefix.starts_with("https://example.com/")));
+        assert!(set
+            .layout
+            .nodes()
+            .iter()
+            .all(|node| node.buffer.len() < 5 * node.len()));
+        assert!(set
+            .iter()
+            .map(|value| value.into_owned())
+            .eq((0..10_000).map(|i| format!("https://example.com/{i:05}"))));
+    }
+
+    // Mostly dense runs of identifiers, with a few far away ones
+    fn id(rng: &mut impl Rng) -> u64 {
+        match rng.gen_range(0..10) {
+            0 => rng.gen(),
+            1..=3 => rng.gen_range(0..100_000),
+            _ => rng.gen_range(5_000..8_000),
+        }
+    }
+
+    #[test]
+    fn test_compact_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([41; 32]);
+        let mut set = CompactU64Set::with_maximum_node_size(64);
+        let mut model = std::collections::BTreeSet::new();
+        for _ in 0..30_000 {
+            let value = id(&mut rng);
+            if rng.gen_bool(0.7) {
+                assert_eq!(set.insert(value), model.insert(value));
+            } else {
+                assert_eq!(set.remove(value), model.remove(&value));
+            }
+            assert_eq!(set.len(), model.len());
+        }
+
+        assert!(set.iter().eq(model.iter().copied()));
+        assert!(set.iter().rev().eq(model.iter().rev().copied()));
+        assert_eq!(set.first(), model.first().copied());
+        assert_eq!(set.last(), model.last().copied());
+        for (idx, value) in model.iter().enumerate() {
+            assert_eq!(set.get_index(idx), Some(*value));
+            assert_eq!(set.rank(*value), idx);
+        }
+        for _ in 0..2_000 {
+            let (a, b) = (id(&mut rng), id(&mut rng));
+            let (start, end) = (a.min(b), a.max(b));
+            assert_eq!(set.contains(start), model.contains(&start));
+            assert_eq!(set.rank(start), model.range(..start).count());
+            assert!(set.range(start..end).eq(model.range(start..end).copied()));
+            assert!(set
+                .range((Bound::Excluded(start), Bound::Included(end)))
+                .rev()
+                .eq(model
+                    .range((Bound::Excluded(start), Bound::Included(end)))
+                    .rev()
+                    .copied()));
+        }
+        assert!(set.range(u64::MAX..).eq(model.range(u64::MAX..).copied()));
+    }
+
+    #[test]
+    fn test_compact_union_and_intersection_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([42; 32]);
+        for _ in 0..20 {
+            let mut sets = [
+                CompactU64Set::with_maximum_node_size(128),
+                CompactU64Set::new(),
+            ];
+            let mut models = [
+                std::collections::BTreeSet::new(),
+                std::collections::BTreeSet::new(),
+            ];
+            for (set, model) in sets.iter_mut().zip(models.iter_mut()) {
+                let density = rng.gen_range(0.05..1.0);
+                let start = rng.gen_range(0..5_000);
+                for value in start..start + rng.gen_range(0..20_000) {
+                    if rng.gen_bool(density) {
+                        set.insert(value);
+                        model.insert(value);
+                    }
+                }
+                for _ in 0..rng.gen_range(0..50) {
+                    let value = id(&mut rng);
+                    set.insert(value);
+                    model.insert(value);
+                }
+            }
+
+            let union = sets[0].union(&sets[1]);
+            assert!(union.iter().eq(models[0].union(&models[1]).copied()));
+            assert!(union
+                .iter()
+                .enumerate()
+                .all(|(idx, value)| union.get_index(idx) == Some(value)));
+            let intersection = sets[0].intersection(&sets[1]);
+            assert!(intersection
+                .iter()
+                .eq(models[0].intersection(&models[1]).copied()));
+            assert_eq!(
+                intersection.len(),
+                models[0].intersection(&models[1]).count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_dense_nodes_are_bitmaps() {
+        let set = CompactU64Set::from_iter(1_000_000..1_100_000);
+
+        assert!(set
+            .layout
+            .nodes()
+            .iter()
+            .all(|node| node.encoding == Encoding::Bitmap));
+        let words: usize = set.layout.nodes().iter().map(|node| node.words.len()).sum();
+        assert!(words * 64 < 2 * 100_000);
+        assert!(set.iter().eq(1_000_000..1_100_000));
+    }
+
+    #[test]
+    fn test_right_biased_splits_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([50; 32]);
+        let mut strings = StringSet::with_maximum_node_size(8);
+        strings.set_split_policy(SplitPolicy::RightBiased);
+        let mut compact = CompactU64Set::with_maximum_node_size(8);
+        compact.set_split_policy(SplitPolicy::RightBiased);
+        for value in 0..1000u64 {
+            strings.insert(&format!("{value:04}"));
+            compact.insert(value * 3);
+        }
+        assert!(strings.layout.nodes()[..strings.layout.nodes().len() - 1]
+            .iter()
+            .all(|node| node.len() == 8));
+        assert!(compact.layout.nodes()[..compact.layout.nodes().len() - 1]
+            .iter()
+            .all(|node| node.len == 8));
+
+        let mut string_model: std::collections::BTreeSet<String> =
+            strings.iter().map(String::from).collect();
+        let mut compact_model: std::collections::BTreeSet<u64> = compact.iter().collect();
+        for _ in 0..2000 {
+            let value = rng.gen_range(0..4000u64);
+            let string = format!("{value:04}");
+            assert_eq!(strings.insert(&string), string_model.insert(string));
+            assert_eq!(compact.insert(value), compact_model.insert(value));
+        }
+        assert!(strings.iter().eq(string_model.iter().map(String::as_str)));
+        assert!(compact.iter().eq(compact_model.iter().copied()));
+        assert!(compact
+            .layout
+            .nodes()
+            .iter()
+            .all(|node| (1..=8).contains(&node.len)));
+    }
+}
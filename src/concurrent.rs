@@ -5,3 +5,6 @@ pub(crate) mod operation;
 
 #[cfg(feature = "multimap")]
 pub mod multimap;
+
+#[cfg(feature = "multimap")]
+pub mod table;
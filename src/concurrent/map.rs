@@ -1,7 +1,19 @@
-use std::{borrow::Borrow, iter::FusedIterator, ops::RangeBounds};
-
-use crate::{cdc::change::ChangeEvent, core::pair::Pair};
-
+use std::{
+    borrow::Borrow,
+    collections::TryReserveError,
+    iter::FusedIterator,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::{
+    cdc::change::ChangeEvent,
+    core::node::SplitPolicy,
+    core::pair::Pair,
+    prefix::Prefix,
+    tuple::{PrefixLookup, TuplePrefix},
+};
+
+use super::r#ref::Ref;
 use super::set::BTreeSet;
 
 #[derive(Debug)]
@@ -111,6 +123,47 @@ where
 {
 }
 
+/// A cursor over a [`BTreeMap`], pointing either at an entry or at the "ghost" non-element
+/// that sits between the last entry and the first one.
+///
+/// Like [`super::set::Cursor`], it holds a copy of the entry it points at, so the map can keep
+/// being modified while it is in use.
+pub struct Cursor<'a, K, V>
+where
+    K: Send + Ord + Clone + 'static,
+    V: Send + Clone + 'static,
+{
+    inner: super::set::Cursor<'a, Pair<K, V>>,
+}
+
+impl<'a, K, V> Cursor<'a, K, V>
+where
+    K: Send + Ord + Clone + 'static,
+    V: Send + Clone + 'static,
+{
+    pub fn move_next(&mut self) {
+        self.inner.move_next()
+    }
+    pub fn move_prev(&mut self) {
+        self.inner.move_prev()
+    }
+    pub fn key(&self) -> Option<&K> {
+        self.inner.item().map(|entry| &entry.key)
+    }
+    pub fn value(&self) -> Option<&V> {
+        self.inner.item().map(|entry| &entry.value)
+    }
+    pub fn key_value(&self) -> Option<(&K, &V)> {
+        self.inner.item().map(|entry| (&entry.key, &entry.value))
+    }
+    pub fn peek_next(&self) -> Option<Ref<Pair<K, V>>> {
+        self.inner.peek_next()
+    }
+    pub fn peek_prev(&self) -> Option<Ref<Pair<K, V>>> {
+        self.inner.peek_prev()
+    }
+}
+
 impl<K: Send + Ord + Clone + 'static, V: Send + Clone + 'static> BTreeMap<K, V> {
     /// Makes a new, empty, persistent `BTreeMap`.
     ///
@@ -145,6 +198,29 @@ impl<K: Send + Ord + Clone + 'static, V: Send + Clone + 'static> BTreeMap<K, V>
             set: BTreeSet::with_maximum_node_size(node_capacity),
         }
     }
+    /// Makes a new, empty `BTreeMap` with room for at least `capacity` entries, allocating the
+    /// storage of its nodes upfront. See [`BTreeSet::with_capacity`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let map: BTreeMap<i32, i32> = BTreeMap::with_capacity(10_000);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            set: BTreeSet::with_capacity(capacity),
+        }
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.set.set_split_policy(split_policy)
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.set.split_policy()
+    }
     /// Returns `true` if the map contains a value for the specified key.
     ///
     /// The key may be any borrowed form of the map's key type, but the ordering
@@ -224,6 +300,53 @@ impl<K: Send + Ord + Clone + 'static, V: Send + Clone + 'static> BTreeMap<K, V>
             .0
             .and_then(|pair| Some(pair.value))
     }
+    /// Inserts a key-value pair into the map, like [`insert`](BTreeMap::insert), but reports a
+    /// failure to allocate node storage instead of aborting, in which case the map is left as
+    /// it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let map = BTreeMap::new();
+    /// assert_eq!(map.try_insert(37, "a"), Ok(None));
+    /// assert_eq!(map.try_insert(37, "b"), Ok(Some("a")));
+    /// ```
+    pub fn try_insert(&self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        let new_entry = Pair { key, value };
+
+        Ok(self.set.try_put_cdc(new_entry)?.0.map(|pair| pair.value))
+    }
+    /// Inserts every key-value pair of the iterator into the map through
+    /// [`try_insert`](BTreeMap::try_insert), stopping at the first allocation failure.
+    ///
+    /// If an error is returned, the pairs inserted so far are taken out again and the values they
+    /// replaced put back, leaving the map as it was but for what other threads did in the
+    /// meantime, and they are dropped along with the rest of the iterator.
+    pub fn try_extend<I>(&self, iter: I) -> Result<(), TryReserveError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        self.set
+            .try_extend(iter.into_iter().map(|(key, value)| Pair { key, value }))
+    }
+    /// Tries to reserve room for at least `additional` more entries, setting aside the storage of
+    /// the nodes that splits will need. See [`BTreeSet::try_reserve`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let map: BTreeMap<u64, u64> = BTreeMap::new();
+    /// map.try_reserve(10_000).expect("out of memory");
+    /// map.try_extend((0..10_000).map(|key| (key, key))).expect("out of memory");
+    /// assert_eq!(map.len(), 10_000);
+    /// ```
+    pub fn try_reserve(&self, additional: usize) -> Result<(), TryReserveError> {
+        self.set.try_reserve(additional)
+    }
     pub fn insert_cdc(&self, key: K, value: V) -> (Option<V>, Vec<ChangeEvent<Pair<K, V>>>) {
         let new_entry = Pair { key, value };
 
@@ -285,6 +408,146 @@ impl<K: Send + Ord + Clone + 'static, V: Send + Clone + 'static> BTreeMap<K, V>
     pub fn len(&self) -> usize {
         self.set.len()
     }
+    /// Returns a [`Cursor`] pointing at the first entry whose key is above the given bound.
+    ///
+    /// If no such entry exists then a cursor pointing at the "ghost" non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the first entry of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    /// use std::ops::Bound;
+    ///
+    /// let map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// map.insert(4, "d");
+    /// let mut cursor = map.lower_bound(Bound::Excluded(&1));
+    /// assert_eq!(cursor.key_value(), Some((&2, &"b")));
+    /// cursor.move_next();
+    /// assert_eq!(cursor.key(), Some(&4));
+    /// ```
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+    where
+        Pair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            inner: self.set.lower_bound(bound),
+        }
+    }
+    /// Returns a [`Cursor`] pointing at the last entry whose key is below the given bound.
+    ///
+    /// If no such entry exists then a cursor pointing at the "ghost" non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the last entry of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    /// use std::ops::Bound;
+    ///
+    /// let map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// map.insert(4, "d");
+    /// let mut cursor = map.upper_bound(Bound::Included(&3));
+    /// assert_eq!(cursor.key(), Some(&2));
+    /// cursor.move_prev();
+    /// assert_eq!(cursor.value(), Some(&"a"));
+    /// ```
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+    where
+        Pair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            inner: self.set.upper_bound(bound),
+        }
+    }
+    /// Returns the entry with the least key greater than or equal to the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    /// assert_eq!(map.ceiling(&2).map(|entry| entry.get().value), Some("c"));
+    /// assert!(map.ceiling(&4).is_none());
+    /// ```
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<Ref<Pair<K, V>>>
+    where
+        Pair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.ceiling(key)
+    }
+    /// Returns the entry with the greatest key less than or equal to the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    /// assert_eq!(map.floor(&2).map(|entry| entry.get().value), Some("a"));
+    /// assert!(map.floor(&0).is_none());
+    /// ```
+    pub fn floor<Q>(&self, key: &Q) -> Option<Ref<Pair<K, V>>>
+    where
+        Pair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.floor(key)
+    }
+    /// Returns the entry with the least key strictly greater than the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    /// assert_eq!(map.successor(&1).map(|entry| entry.get().value), Some("c"));
+    /// assert!(map.successor(&3).is_none());
+    /// ```
+    pub fn successor<Q>(&self, key: &Q) -> Option<Ref<Pair<K, V>>>
+    where
+        Pair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.successor(key)
+    }
+    /// Returns the entry with the greatest key strictly less than the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    /// assert_eq!(map.predecessor(&3).map(|entry| entry.get().value), Some("a"));
+    /// assert!(map.predecessor(&1).is_none());
+    /// ```
+    pub fn predecessor<Q>(&self, key: &Q) -> Option<Ref<Pair<K, V>>>
+    where
+        Pair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.predecessor(key)
+    }
     /// Gets an iterator over the entries of the map, sorted by key.
     ///
     /// # Examples
@@ -350,6 +613,56 @@ impl<K: Send + Ord + Clone + 'static, V: Send + Clone + 'static> BTreeMap<K, V>
             inner: super::set::BTreeSet::range(&self.set, range),
         }
     }
+    /// Constructs a double-ended iterator over the entries of the map whose keys start with the
+    /// given prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let map = BTreeMap::new();
+    /// map.insert("user:1".to_string(), "alice");
+    /// map.insert("user:2".to_string(), "bob");
+    /// map.insert("group:1".to_string(), "admins");
+    ///
+    /// let users: Vec<_> = map.prefix_range("user:").map(|(_, &name)| name).collect();
+    /// assert_eq!(users, ["alice", "bob"]);
+    /// ```
+    pub fn prefix_range<Q>(&self, prefix: &Q) -> Range<'_, K, V>
+    where
+        Pair<K, V>: Borrow<Q>,
+        Q: Prefix + ?Sized,
+    {
+        Range {
+            inner: self.set.prefix_range(prefix),
+        }
+    }
+    /// Constructs a double-ended iterator over the entries of the map whose keys' leading fields
+    /// are equal to the given tuple prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::map::BTreeMap;
+    ///
+    /// let events = BTreeMap::new();
+    /// events.insert(("acme", 2), "login");
+    /// events.insert(("acme", 1), "signup");
+    /// events.insert(("initech", 1), "login");
+    ///
+    /// let acme: Vec<_> = events.range_prefix(&("acme",)).map(|(_, &event)| event).collect();
+    /// assert_eq!(acme, ["signup", "login"]);
+    /// ```
+    pub fn range_prefix<P>(&self, prefix: &P) -> Range<'_, K, V>
+    where
+        K: Borrow<dyn PrefixLookup<K, P>> + TuplePrefix<P>,
+        P: Ord + Clone + 'static,
+    {
+        Range {
+            inner: self.set.range_prefix_by(prefix),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -568,4 +881,126 @@ mod tests {
             .collect::<_>();
         assert_eq!(mock_state.nodes, expected_state);
     }
+
+    #[cfg(feature = "cdc")]
+    #[test]
+    fn test_cdc_right_biased_splits() {
+        let mut map = BTreeMap::with_maximum_node_size(8);
+        map.set_split_policy(crate::SplitPolicy::RightBiased);
+        let mut mock_state = PersistedBTreeMap::default();
+        let expected_state = |map: &BTreeMap<i32, i32>| -> std::collections::BTreeMap<_, _> {
+            map.set
+                .index
+                .iter()
+                .map(|e| (e.key().clone().key, e.value().lock_arc().clone()))
+                .collect()
+        };
+
+        for i in 0..201 {
+            let (_, events) = map.insert_cdc(i, i);
+            for event in events {
+                mock_state.persist(&event);
+            }
+        }
+        let nodes = expected_state(&map);
+        assert_eq!(mock_state.nodes, nodes);
+        assert!(nodes.range(..200).all(|(_, node)| node.len() == 8));
+        assert_eq!(nodes[&200].len(), 1);
+
+        // Keys landing inside full nodes still split them evenly
+        for i in (0..200).rev().step_by(7).map(|i| i * 2 + 1) {
+            let (_, events) = map.insert_cdc(i, i);
+            for event in events {
+                mock_state.persist(&event);
+            }
+        }
+        assert_eq!(mock_state.nodes, expected_state(&map));
+    }
+
+    #[test]
+    fn test_try_extend_leaves_the_map_under_allocation_failures() {
+        use rand::{Rng, SeedableRng};
+
+        // Nodes of 256 pairs of `u64` take 4KiB, while the skip list only makes small allocations
+        let map = BTreeMap::with_maximum_node_size(256);
+        for key in 0..3000u64 {
+            map.insert(key * 2, 0u64);
+        }
+        let entries = |map: &BTreeMap<u64, u64>| -> Vec<(u64, u64)> {
+            map.iter().map(|(key, value)| (*key, *value)).collect()
+        };
+        let before = entries(&map);
+        let mut rng = rand::rngs::StdRng::from_seed([61; 32]);
+        let pairs: Vec<_> = (0..1000)
+            .map(|value| (rng.gen_range(0..2000), value + 1))
+            .collect();
+
+        for budget in 0..4 {
+            let input = pairs.clone();
+            let extended =
+                crate::tests::with_allocation_budget_from(4096, budget, || map.try_extend(input));
+            assert!(extended.is_err());
+            assert_eq!(entries(&map), before);
+            assert_eq!(map.len(), before.len());
+        }
+
+        map.try_extend(pairs.iter().copied()).unwrap();
+        let mut expected: std::collections::BTreeMap<_, _> = before.into_iter().collect();
+        expected.extend(pairs);
+        assert_eq!(entries(&map), expected.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_bounds_against_model() {
+        use std::ops::Bound;
+
+        let map = BTreeMap::with_maximum_node_size(3);
+        let model: std::collections::BTreeMap<i32, i32> =
+            (0..60).map(|i| (i * i % 97 * 2, i)).collect();
+        for (key, value) in model.iter() {
+            map.insert(*key, *value);
+        }
+        assert!(map.set.index.len() > 10);
+
+        let entry = |pair: Option<(&i32, &i32)>| pair.map(|(key, value)| (*key, *value));
+        for key in -2..200 {
+            let ceiling = entry(model.range(key..).next());
+            let floor = entry(model.range(..=key).next_back());
+            let successor = entry(model.range(key + 1..).next());
+            let predecessor = entry(model.range(..key).next_back());
+            let get = |pair: &Pair<i32, i32>| (pair.key, pair.value);
+            assert_eq!(map.ceiling(&key).map(|item| get(item.get())), ceiling);
+            assert_eq!(map.floor(&key).map(|item| get(item.get())), floor);
+            assert_eq!(
+                entry(map.lower_bound(Bound::Included(&key)).key_value()),
+                ceiling
+            );
+            assert_eq!(
+                entry(map.lower_bound(Bound::Excluded(&key)).key_value()),
+                successor
+            );
+            assert_eq!(
+                entry(map.upper_bound(Bound::Included(&key)).key_value()),
+                floor
+            );
+            assert_eq!(
+                entry(map.upper_bound(Bound::Excluded(&key)).key_value()),
+                predecessor
+            );
+        }
+
+        // Cursors cross node boundaries both ways
+        let mut cursor = map.lower_bound::<i32>(Bound::Unbounded);
+        for pair in model.iter() {
+            assert_eq!(cursor.key_value(), Some(pair));
+            cursor.move_next();
+        }
+        assert_eq!(cursor.key(), None);
+        let mut cursor = map.upper_bound::<i32>(Bound::Unbounded);
+        for pair in model.iter().rev() {
+            assert_eq!(cursor.key_value(), Some(pair));
+            cursor.move_prev();
+        }
+        assert_eq!(cursor.key(), None);
+    }
 }
@@ -1,7 +1,19 @@
-use std::{borrow::Borrow, iter::FusedIterator, ops::RangeBounds};
-
-use crate::{cdc::change::ChangeEvent, core::multipair::MultiPair};
-
+use std::{
+    borrow::Borrow,
+    collections::TryReserveError,
+    iter::FusedIterator,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::{
+    cdc::change::ChangeEvent,
+    core::multipair::MultiPair,
+    core::node::SplitPolicy,
+    prefix::Prefix,
+    tuple::{PrefixLookup, TuplePrefix},
+};
+
+use super::r#ref::Ref;
 use super::set::BTreeSet;
 
 #[derive(Debug)]
@@ -104,6 +116,39 @@ impl<'a, K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static>
 impl<'a, K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static> FusedIterator for Range<'a, K, V> {
 }
 
+/// A cursor over a [`BTreeMultiMap`], pointing either at an entry or at the "ghost"
+/// non-element that sits between the last entry and the first one.
+///
+/// Entries sharing a key are visited one at a time, and like [`super::set::Cursor`], it holds a
+/// copy of the entry it points at, so the map can keep being modified while it is in use.
+pub struct Cursor<'a, K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static> {
+    inner: super::set::Cursor<'a, MultiPair<K, V>>,
+}
+
+impl<'a, K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static> Cursor<'a, K, V> {
+    pub fn move_next(&mut self) {
+        self.inner.move_next()
+    }
+    pub fn move_prev(&mut self) {
+        self.inner.move_prev()
+    }
+    pub fn key(&self) -> Option<&K> {
+        self.inner.item().map(|entry| &entry.key)
+    }
+    pub fn value(&self) -> Option<&V> {
+        self.inner.item().map(|entry| &entry.value)
+    }
+    pub fn key_value(&self) -> Option<(&K, &V)> {
+        self.inner.item().map(|entry| (&entry.key, &entry.value))
+    }
+    pub fn peek_next(&self) -> Option<Ref<MultiPair<K, V>>> {
+        self.inner.peek_next()
+    }
+    pub fn peek_prev(&self) -> Option<Ref<MultiPair<K, V>>> {
+        self.inner.peek_prev()
+    }
+}
+
 impl<K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static> BTreeMultiMap<K, V> {
     /// Makes a new, empty, persistent `BTreeMultiMap`.
     ///
@@ -138,6 +183,29 @@ impl<K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static> BTr
             set: BTreeSet::with_maximum_node_size(node_capacity),
         }
     }
+    /// Makes a new, empty `BTreeMultiMap` with room for at least `capacity` entries, allocating the
+    /// storage of its nodes upfront. See [`BTreeSet::with_capacity`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let map: BTreeMultiMap<i32, i32> = BTreeMultiMap::with_capacity(10_000);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            set: BTreeSet::with_capacity(capacity),
+        }
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.set.set_split_policy(split_policy)
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.set.split_policy()
+    }
     /// Returns `true` if the map contains at least one occurance of the specified key.
     ///
     /// The key may be any borrowed form of the map's key type, but the ordering
@@ -234,6 +302,56 @@ impl<K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static> BTr
             .0
             .and_then(|pair| Some(pair.value))
     }
+    /// Inserts a key-value pair into the multi map, like [`insert`](BTreeMultiMap::insert), but reports a
+    /// failure to allocate node storage instead of aborting, in which case the multi map is left as
+    /// it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// assert_eq!(map.try_insert(37, "a"), Ok(None));
+    /// assert_eq!(map.try_insert(37, "b"), Ok(None));
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn try_insert(&self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        let new_entry = MultiPair::new(key, value);
+
+        Ok(self.set.try_put_cdc(new_entry)?.0.map(|pair| pair.value))
+    }
+    /// Inserts every key-value pair of the iterator into the multi map through
+    /// [`try_insert`](BTreeMultiMap::try_insert), stopping at the first allocation failure.
+    ///
+    /// If an error is returned, the pairs inserted so far are taken out again and the ones they
+    /// replaced put back, leaving the multi map as it was but for what other threads did in the
+    /// meantime, and they are dropped along with the rest of the iterator.
+    pub fn try_extend<I>(&self, iter: I) -> Result<(), TryReserveError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        self.set.try_extend(
+            iter.into_iter()
+                .map(|(key, value)| MultiPair::new(key, value)),
+        )
+    }
+    /// Tries to reserve room for at least `additional` more entries, setting aside the storage of
+    /// the nodes that splits will need. See [`BTreeSet::try_reserve`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let multimap: BTreeMultiMap<u64, u64> = BTreeMultiMap::new();
+    /// multimap.try_reserve(10_000).expect("out of memory");
+    /// multimap.try_extend((0..10_000).map(|key| (key, key))).expect("out of memory");
+    /// assert_eq!(multimap.len(), 10_000);
+    /// ```
+    pub fn try_reserve(&self, additional: usize) -> Result<(), TryReserveError> {
+        self.set.try_reserve(additional)
+    }
     pub fn insert_cdc(&self, key: K, value: V) -> (Option<V>, Vec<ChangeEvent<MultiPair<K, V>>>) {
         let new_entry = MultiPair::new(key, value);
 
@@ -329,6 +447,148 @@ impl<K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static> BTr
     pub fn len(&self) -> usize {
         self.set.len()
     }
+    /// Returns a [`Cursor`] pointing at the first entry whose key is above the given bound.
+    ///
+    /// If no such entry exists then a cursor pointing at the "ghost" non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the first entry of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    /// use std::ops::Bound;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// map.insert(2, "c");
+    /// map.insert(3, "d");
+    /// let mut cursor = map.lower_bound(Bound::Included(&2));
+    /// assert_eq!(cursor.key(), Some(&2));
+    /// cursor.move_next();
+    /// assert_eq!(cursor.key(), Some(&2));
+    /// cursor.move_next();
+    /// assert_eq!(cursor.key_value(), Some((&3, &"d")));
+    /// ```
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+    where
+        MultiPair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            inner: self.set.lower_bound(bound),
+        }
+    }
+    /// Returns a [`Cursor`] pointing at the last entry whose key is below the given bound.
+    ///
+    /// If no such entry exists then a cursor pointing at the "ghost" non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the last entry of the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    /// use std::ops::Bound;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// map.insert(3, "d");
+    /// let mut cursor = map.upper_bound(Bound::Excluded(&3));
+    /// assert_eq!(cursor.key_value(), Some((&2, &"b")));
+    /// cursor.move_prev();
+    /// cursor.move_prev();
+    /// assert_eq!(cursor.key(), None);
+    /// ```
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, K, V>
+    where
+        MultiPair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            inner: self.set.upper_bound(bound),
+        }
+    }
+    /// Returns the first entry with the least key greater than or equal to the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    /// assert_eq!(map.ceiling(&2).map(|entry| entry.get().key), Some(3));
+    /// ```
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<Ref<MultiPair<K, V>>>
+    where
+        MultiPair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.ceiling(key)
+    }
+    /// Returns the last entry with the greatest key less than or equal to the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "c");
+    /// assert_eq!(map.floor(&2).map(|entry| entry.get().key), Some(1));
+    /// ```
+    pub fn floor<Q>(&self, key: &Q) -> Option<Ref<MultiPair<K, V>>>
+    where
+        MultiPair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.floor(key)
+    }
+    /// Returns the first entry with the least key strictly greater than the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(1, "b");
+    /// map.insert(3, "c");
+    /// assert_eq!(map.successor(&1).map(|entry| entry.get().key), Some(3));
+    /// ```
+    pub fn successor<Q>(&self, key: &Q) -> Option<Ref<MultiPair<K, V>>>
+    where
+        MultiPair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.successor(key)
+    }
+    /// Returns the last entry with the greatest key strictly less than the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// map.insert(1, "a");
+    /// map.insert(3, "b");
+    /// map.insert(3, "c");
+    /// assert_eq!(map.predecessor(&3).map(|entry| entry.get().key), Some(1));
+    /// ```
+    pub fn predecessor<Q>(&self, key: &Q) -> Option<Ref<MultiPair<K, V>>>
+    where
+        MultiPair<K, V>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.predecessor(key)
+    }
     /// Gets an iterator over the entries of the map, sorted by key.
     ///
     /// # Examples
@@ -403,6 +663,58 @@ impl<K: Send + Ord + Clone + 'static, V: Send + Clone + PartialEq + 'static> BTr
 
         self._range((adjusted_start_bound, adjusted_end_bound))
     }
+    /// Constructs a double-ended iterator over all key value pairs whose keys start with the
+    /// given prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// map.insert(b"k1".to_vec(), "a");
+    /// map.insert(b"k1".to_vec(), "b");
+    /// map.insert(b"k2".to_vec(), "c");
+    /// map.insert(b"x".to_vec(), "d");
+    /// assert_eq!(map.prefix_range(&b"k"[..]).count(), 3);
+    /// ```
+    pub fn prefix_range<Q>(&self, prefix: &Q) -> Range<'_, K, V>
+    where
+        MultiPair<K, V>: Borrow<Q>,
+        Q: Prefix + ?Sized,
+    {
+        Range {
+            inner: RawRange {
+                inner: self.set.prefix_range(prefix),
+            },
+        }
+    }
+    /// Constructs a double-ended iterator over all key value pairs whose keys' leading fields are
+    /// equal to the given tuple prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::multimap::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::new();
+    /// map.insert((1, 'a'), "x");
+    /// map.insert((1, 'a'), "y");
+    /// map.insert((1, 'b'), "z");
+    /// map.insert((2, 'a'), "w");
+    /// assert_eq!(map.range_prefix(&(1,)).count(), 3);
+    /// ```
+    pub fn range_prefix<P>(&self, prefix: &P) -> Range<'_, K, V>
+    where
+        K: Borrow<dyn PrefixLookup<K, P>> + TuplePrefix<P>,
+        P: Ord + Clone + 'static,
+    {
+        Range {
+            inner: RawRange {
+                inner: self.set.range_prefix_by(prefix),
+            },
+        }
+    }
 }
 
 #[cfg(test)]
@@ -552,4 +864,120 @@ mod tests {
             (&4, &"f"),
         ].into_iter().collect::<BTreeSet<_>>());
     }
+
+    #[test]
+    fn test_try_extend_leaves_the_multimap_under_allocation_failures() {
+        use rand::{Rng, SeedableRng};
+
+        // Nodes of 256 pairs of `u64` and their discriminators take 6KiB, while the skip list
+        // only makes small allocations
+        let map = BTreeMultiMap::with_maximum_node_size(256);
+        for value in 0..3000u64 {
+            map.insert(value % 500, value);
+        }
+        let entries = |map: &BTreeMultiMap<u64, u64>| -> Vec<(u64, u64)> {
+            let mut entries: Vec<_> = map.iter().map(|(key, value)| (*key, *value)).collect();
+            entries.sort();
+            entries
+        };
+        let before = entries(&map);
+        let mut rng = rand::rngs::StdRng::from_seed([62; 32]);
+        // Values from 3000 on are new, so every pair is added next to the entries of its key
+        let pairs: Vec<_> = (3000..4000u64)
+            .map(|value| (rng.gen_range(0..200), value))
+            .collect();
+
+        for budget in 0..4 {
+            let input = pairs.clone();
+            let extended =
+                crate::tests::with_allocation_budget_from(4096, budget, || map.try_extend(input));
+            assert!(extended.is_err());
+            assert_eq!(entries(&map), before);
+            assert_eq!(map.len(), before.len());
+        }
+
+        map.try_extend(pairs.iter().copied()).unwrap();
+        let mut expected = before;
+        expected.extend(pairs);
+        expected.sort();
+        assert_eq!(entries(&map), expected);
+    }
+
+    #[test]
+    fn test_bounds_against_model() {
+        use std::ops::Bound;
+
+        let map = BTreeMultiMap::with_maximum_node_size(3);
+        for key in (0..40).step_by(2) {
+            map.insert(key, key * 100);
+        }
+        // A run of duplicates that spans several nodes
+        for value in 1..=8 {
+            map.insert(20, value);
+        }
+        let run = map
+            .set
+            .index
+            .iter()
+            .filter(|entry| entry.value().lock().iter().any(|pair| pair.key == 20))
+            .count();
+        assert!(run > 1);
+
+        // Duplicates are ordered among themselves by the multimap, so the model is laid out in
+        // its order, once checked against the inserted entries
+        let model: Vec<(i32, i32)> = map.iter().map(|(key, value)| (*key, *value)).collect();
+        let mut expected: Vec<_> = (0..40).step_by(2).map(|key| (key, key * 100)).collect();
+        expected.extend((1..=8).map(|value| (20, value)));
+        expected.sort();
+        let mut sorted = model.clone();
+        sorted.sort();
+        assert_eq!(sorted, expected);
+        assert!(model.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+        let entry = |pair: Option<(&i32, &i32)>| pair.map(|(key, value)| (*key, *value));
+        for key in -2..42 {
+            let ceiling = model.iter().find(|(k, _)| *k >= key).copied();
+            let floor = model.iter().rev().find(|(k, _)| *k <= key).copied();
+            let successor = model.iter().find(|(k, _)| *k > key).copied();
+            let predecessor = model.iter().rev().find(|(k, _)| *k < key).copied();
+            let get = |pair: &super::MultiPair<i32, i32>| (pair.key, pair.value);
+            assert_eq!(map.ceiling(&key).map(|item| get(item.get())), ceiling);
+            assert_eq!(map.floor(&key).map(|item| get(item.get())), floor);
+            assert_eq!(
+                entry(map.lower_bound(Bound::Included(&key)).key_value()),
+                ceiling
+            );
+            assert_eq!(
+                entry(map.lower_bound(Bound::Excluded(&key)).key_value()),
+                successor
+            );
+            assert_eq!(
+                entry(map.upper_bound(Bound::Included(&key)).key_value()),
+                floor
+            );
+            assert_eq!(
+                entry(map.upper_bound(Bound::Excluded(&key)).key_value()),
+                predecessor
+            );
+        }
+
+        // Walking the run from either end goes through every duplicate across the nodes
+        let run: Vec<_> = model
+            .iter()
+            .filter(|(key, _)| *key == 20)
+            .copied()
+            .collect();
+        let mut cursor = map.lower_bound(Bound::Included(&20));
+        for pair in run.iter() {
+            assert_eq!(entry(cursor.key_value()), Some(*pair));
+            cursor.move_next();
+        }
+        assert_eq!(entry(cursor.key_value()), Some((22, 2200)));
+        let mut cursor = map.upper_bound(Bound::Included(&20));
+        for pair in run.iter().rev() {
+            assert_eq!(entry(cursor.key_value()), Some(*pair));
+            cursor.move_prev();
+        }
+        assert_eq!(entry(cursor.key_value()), Some((18, 1800)));
+    }
 }
@@ -4,15 +4,17 @@ use crossbeam_skiplist::SkipMap;
 use parking_lot::Mutex;
 
 use crate::cdc::change::ChangeEvent;
-use crate::core::node::NodeLike;
+use crate::core::node::{NodeLike, SplitPolicy};
 
 pub type Node<T> = Arc<Mutex<Vec<T>>>;
 
 type OldVersion<T> = Node<T>;
 type CurrentVersion<T> = Node<T>;
 
+type SpareNode<T> = Vec<T>;
+
 pub enum Operation<T: Send> {
-    Split(OldVersion<T>, T, T),
+    Split(OldVersion<T>, T, T, SpareNode<T>, SplitPolicy),
     UpdateMax(CurrentVersion<T>, T),
     MakeUnreachable(CurrentVersion<T>, T),
 }
@@ -20,13 +22,37 @@ pub enum Operation<T: Send> {
 impl<T: Ord + Send + Clone + 'static> Operation<T> {
     pub fn commit(self, index: &SkipMap<T, Node<T>>) -> Result<(Option<T>, Vec<ChangeEvent<T>>), ()> {
         match self {
-            Operation::Split(old_node, old_max, value) => {
+            Operation::Split(old_node, old_max, value, mut new_vec, policy) => {
                 let mut guard = old_node.lock_arc();
                 if let Some(entry) = index.get(&old_max) {
                     if Arc::ptr_eq(entry.value(), &old_node) {
                         let mut cdc = vec![];
+                        let position = guard.lower_bound(&value);
+                        // A value past every element was only routed to this node because it
+                        // was the last one. A split that leaves it untouched may have added a
+                        // node after it since, in which case the value may belong there.
+                        if position == guard.len() && entry.next().is_some() {
+                            return Err(());
+                        }
+                        let split_index = policy.split_point(guard.len(), position);
+                        if split_index == guard.len() {
+                            // Nothing moves, and the value starts a node of its own past the
+                            // full one
+                            #[cfg(feature = "cdc")]
+                            {
+                                let node_insertion = ChangeEvent::CreateNode {
+                                    max_value: value.clone(),
+                                };
+                                cdc.push(node_insertion);
+                            }
+                            new_vec.push(value.clone());
+                            index.insert(value, Arc::new(Mutex::new(new_vec)));
+
+                            return Ok((None, cdc));
+                        }
+
                         entry.remove();
-                        let mut new_vec = guard.halve();
+                        guard.split_off_into(split_index, &mut new_vec);
 
                         #[cfg(feature = "cdc")]
                         {
@@ -40,7 +66,7 @@ impl<T: Ord + Send + Clone + 'static> Operation<T> {
                         let mut old_value: Option<T> = None;
                         let mut insert_attempted = false;
                         if let Some(max) = guard.last().cloned() {
-                            if max > value {
+                            if position < split_index {
                                 let (inserted, idx) = NodeLike::insert(&mut *guard, value.clone());
                                 insert_attempted = true;
                                 if !inserted {
@@ -1,6 +1,7 @@
+use std::collections::TryReserveError;
 use std::fmt::Debug;
 use std::iter::FusedIterator;
-use std::ops::RangeBounds;
+use std::ops::{Bound, RangeBounds};
 use std::{borrow::Borrow, sync::Arc};
 
 use crossbeam_skiplist::SkipMap;
@@ -11,6 +12,8 @@ use crate::cdc::change::ChangeEvent;
 use crate::core::constants::DEFAULT_INNER_SIZE;
 use crate::core::node::*;
 use crate::concurrent::operation::*;
+use crate::prefix::{prefix_bounds, Prefix};
+use crate::tuple::{Bounded, PrefixLookup, TuplePrefix};
 
 use super::r#ref::Ref;
 
@@ -77,6 +80,9 @@ where
     pub(crate) index: SkipMap<T, Node<T>>,
     index_lock: ShardedLock<()>,
     node_capacity: usize,
+    // Empty node storage set aside by `with_capacity`, taken by new nodes before allocating
+    spare: Mutex<Vec<Vec<T>>>,
+    split_policy: SplitPolicy,
 }
 impl<T: Ord + Clone + 'static> Default for BTreeSet<T> {
     fn default() -> Self {
@@ -86,10 +92,23 @@ impl<T: Ord + Clone + 'static> Default for BTreeSet<T> {
             index,
             index_lock: ShardedLock::new(()),
             node_capacity: DEFAULT_INNER_SIZE,
+            spare: Mutex::new(Vec::new()),
+            split_policy: SplitPolicy::default(),
         }
     }
 }
 
+// Grows the storage of a node, reporting a failure instead of aborting when `fallible` is set
+fn reserve<T>(node: &mut Vec<T>, additional: usize, fallible: bool) -> Result<(), TryReserveError> {
+    if fallible {
+        return node.try_reserve_exact(additional);
+    }
+
+    node.reserve_exact(additional);
+
+    Ok(())
+}
+
 impl<T: Ord + Clone + Send> BTreeSet<T> {
     pub fn new() -> Self {
         Self::default()
@@ -108,9 +127,126 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
             index: SkipMap::new(),
             index_lock: ShardedLock::new(()),
             node_capacity,
+            spare: Mutex::new(Vec::new()),
+            split_policy: SplitPolicy::default(),
+        }
+    }
+    /// Makes a new, empty `BTreeSet` with room for at least `capacity` elements.
+    ///
+    /// The storage of the nodes that loading that many elements will need is allocated upfront,
+    /// so that splits take it instead of allocating as they go. As many nodes are set aside as
+    /// even splits need: switching to [`SplitPolicy::RightBiased`] with
+    /// [`set_split_policy`](Self::set_split_policy) lets go of the ones it will not need.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::with_capacity(10_000);
+    /// for value in 0..10_000 {
+    ///     set.insert(value);
+    /// }
+    /// assert_eq!(set.len(), 10_000);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let new = Self::new();
+        match new.reserve_spare(capacity, false) {
+            Ok(()) => new,
+            Err(_) => unreachable!("infallible reservations abort on allocation failures"),
         }
     }
+    // Sets aside the storage of the nodes that `additional` more elements may need. Allocation
+    // failures are only reported when `fallible` is set, keeping the nodes set aside so far.
+    fn reserve_spare(&self, additional: usize, fallible: bool) -> Result<(), TryReserveError> {
+        // The first node is created along with the first element, and each split after it adds
+        // a node
+        let nodes = usize::from(self.index.is_empty())
+            + additional.div_ceil(self.split_policy.node_fill(self.node_capacity));
+        let mut spare = self.spare.lock();
+        let missing = nodes.saturating_sub(spare.len());
+        if fallible {
+            spare.try_reserve(missing)?;
+        } else {
+            spare.reserve(missing);
+        }
+        for _ in 0..missing {
+            let mut node = Vec::new();
+            reserve(&mut node, self.node_capacity, fallible)?;
+            spare.push(node);
+        }
+
+        Ok(())
+    }
+    /// Tries to reserve room for at least `additional` more elements, like
+    /// [`with_capacity`](BTreeSet::with_capacity) does: the storage of the nodes that splits will
+    /// need is allocated and set aside.
+    ///
+    /// If an error is returned the nodes already set aside are kept, and the set is otherwise
+    /// left as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set: BTreeSet<u64> = BTreeSet::new();
+    /// set.try_reserve(10_000).expect("out of memory");
+    /// set.try_extend(0..10_000).expect("out of memory");
+    /// assert_eq!(set.len(), 10_000);
+    /// ```
+    pub fn try_reserve(&self, additional: usize) -> Result<(), TryReserveError> {
+        self.reserve_spare(additional, true)
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    /// use indexset::SplitPolicy;
+    ///
+    /// let mut set = BTreeSet::with_maximum_node_size(64);
+    /// set.set_split_policy(SplitPolicy::RightBiased);
+    /// for id in 0..1000 {
+    ///     set.insert(id);
+    /// }
+    /// assert_eq!(set.len(), 1000);
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        // Nodes set aside for splits that fill them less than the new policy does are let go
+        let spare = self.spare.get_mut();
+        let first = usize::from(self.index.is_empty()).min(spare.len());
+        let reserved = (spare.len() - first) * self.split_policy.node_fill(self.node_capacity);
+        spare.truncate(first + reserved.div_ceil(split_policy.node_fill(self.node_capacity)));
+        self.split_policy = split_policy;
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.split_policy
+    }
+    fn take_spare(&self) -> Vec<T> {
+        self.spare.lock().pop().unwrap_or_default()
+    }
     pub(crate) fn put_cdc(&self, value: T) -> (Option<T>, Vec<ChangeEvent<T>>) {
+        match self.put(value, false) {
+            Ok(value_cdc) => value_cdc,
+            Err(_) => unreachable!("infallible insertions abort on allocation failures"),
+        }
+    }
+    pub(crate) fn try_put_cdc(
+        &self,
+        value: T,
+    ) -> Result<(Option<T>, Vec<ChangeEvent<T>>), TryReserveError> {
+        self.put(value, true)
+    }
+    // Node storage is reserved ahead of every insertion, and only reports allocation failures,
+    // leaving the set untouched, when `fallible` is set
+    fn put(
+        &self,
+        value: T,
+        fallible: bool,
+    ) -> Result<(Option<T>, Vec<ChangeEvent<T>>), TryReserveError> {
         loop {
             let mut cdc = vec![];
             let _global_guard = self.index_lock.read();
@@ -121,7 +257,8 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
                     if let Some(last) = self.index.back() {
                         last
                     } else {
-                        let mut first_vec = Vec::with_capacity(self.node_capacity);
+                        let mut first_vec = self.take_spare();
+                        reserve(&mut first_vec, self.node_capacity, fallible)?;
 
                         first_vec.push(value.clone());
 
@@ -139,7 +276,7 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
 
                             self.index.insert(value, first_node);
 
-                            return (None, cdc);
+                            return Ok((None, cdc));
                         }
 
                         continue;
@@ -150,6 +287,7 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
             let mut node_guard = target_node_entry.value().lock_arc();
             let mut operation = None;
             if node_guard.len() < self.node_capacity {
+                reserve(&mut *node_guard, 1, fallible)?;
                 let old_max = node_guard.last().cloned();
                 let (inserted, idx) = NodeLike::insert(&mut *node_guard, value.clone());
                 if inserted {
@@ -166,7 +304,7 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
                             cdc.push(node_element_insertion);
                         }
 
-                        return (None, cdc);
+                        return Ok((None, cdc));
                     }
 
                     if old_max.is_some() {
@@ -196,13 +334,18 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
                         cdc.push(node_element_insertion);
                     }
 
-                    return (NodeLike::replace(&mut *node_guard, idx, value.clone()), cdc);
+                    return Ok((NodeLike::replace(&mut *node_guard, idx, value.clone()), cdc));
                 }
             } else {
+                // The upper half goes into a node allocated upfront, with room for the value
+                let mut spare = self.take_spare();
+                reserve(&mut spare, self.node_capacity, fallible)?;
                 operation = Some(Operation::Split(
                     target_node_entry.value().clone(),
                     target_node_entry.key().clone(),
                     value.clone(),
+                    spare,
+                    self.split_policy,
                 ))
             }
 
@@ -211,7 +354,7 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
             let _global_guard = self.index_lock.write();
 
             if let Ok(value_cdc) = operation.unwrap().commit(&self.index) {
-                return value_cdc;
+                return Ok(value_cdc);
             }
             drop(_global_guard);
 
@@ -246,6 +389,88 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
 
         false
     }
+    /// Adds a value to the set, like [`insert`](BTreeSet::insert), but reports a failure to
+    /// allocate node storage instead of aborting, in which case the set is left as it was.
+    ///
+    /// The entries of the skip list indexing the nodes are still allocated infallibly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::new();
+    ///
+    /// assert_eq!(set.try_insert(2), Ok(true));
+    /// assert_eq!(set.try_insert(2), Ok(false));
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn try_insert(&self, value: T) -> Result<bool, TryReserveError> {
+        Ok(self.try_put_cdc(value)?.0.is_none())
+    }
+    /// Adds every value of the iterator to the set through
+    /// [`try_insert`](BTreeSet::try_insert), stopping at the first allocation failure.
+    ///
+    /// If an error is returned, the values inserted so far are taken out again and the ones they
+    /// replaced put back, leaving the set as it was but for what other threads did in the
+    /// meantime, and they are dropped along with the rest of the iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::new();
+    /// set.try_extend(0..10).unwrap();
+    ///
+    /// assert_eq!(set.len(), 10);
+    /// ```
+    pub fn try_extend<I>(&self, iter: I) -> Result<(), TryReserveError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        // Every new value is kept along with the one it replaced, if any, for the insertions to be
+        // undone from the last one
+        let mut changes = Vec::new();
+        for value in iter {
+            let put = changes
+                .try_reserve(1)
+                .and_then(|_| self.try_put_cdc(value.clone()));
+            match put {
+                Ok((replaced, _)) => changes.push((value, replaced)),
+                Err(error) => {
+                    for (value, replaced) in changes.into_iter().rev() {
+                        match replaced {
+                            Some(replaced) => self.restore(replaced),
+                            None => {
+                                self.remove(&value);
+                            }
+                        }
+                    }
+
+                    return Err(error);
+                }
+            }
+        }
+
+        Ok(())
+    }
+    // Puts back a value that an insertion replaced, in place, which takes no allocation unless
+    // another thread took the equal value out in the meantime
+    fn restore(&self, value: T) {
+        {
+            let _global_guard = self.index_lock.read();
+            if let Some(entry) = self.index.lower_bound(std::ops::Bound::Included(&value)) {
+                let mut node_guard = entry.value().lock_arc();
+                if let Ok(idx) = node_guard.binary_search(&value) {
+                    node_guard[idx] = value;
+                    return;
+                }
+            }
+        }
+
+        self.put_cdc(value);
+    }
     pub fn remove_cdc<Q>(&self, value: &Q) -> (Option<T>, Vec<ChangeEvent<T>>)
     where
         T: Borrow<Q>,
@@ -400,6 +625,202 @@ impl<T: Ord + Clone + Send> BTreeSet<T> {
             .map(|node| node.value().lock().len())
             .sum()
     }
+    /// Returns a [`Cursor`] pointing at the first element that is above the given bound.
+    ///
+    /// If no such element exists then a cursor pointing at the "ghost" non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the first element of the
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    /// use std::ops::Bound;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// let mut cursor = set.lower_bound(Bound::Excluded(&2));
+    /// assert_eq!(cursor.item(), Some(&3));
+    /// cursor.move_next();
+    /// assert_eq!(cursor.item(), Some(&5));
+    /// cursor.move_next();
+    /// assert_eq!(cursor.item(), None);
+    /// ```
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            btree: self,
+            current: self.first_above(bound).map(|item| item.get().clone()),
+        }
+    }
+    /// Returns a [`Cursor`] pointing at the last element that is below the given bound.
+    ///
+    /// If no such element exists then a cursor pointing at the "ghost" non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the last element of the
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    /// use std::ops::Bound;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// let mut cursor = set.upper_bound(Bound::Included(&4));
+    /// assert_eq!(cursor.item(), Some(&3));
+    /// cursor.move_prev();
+    /// assert_eq!(cursor.item(), Some(&2));
+    /// assert_eq!(cursor.peek_prev().map(|item| *item.get()), Some(1));
+    /// ```
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            btree: self,
+            current: self.last_below(bound).map(|item| item.get().clone()),
+        }
+    }
+    /// Returns the least element in the set that is greater than or equal to the value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.ceiling(&4).map(|item| *item.get()), Some(5));
+    /// assert!(set.ceiling(&6).is_none());
+    /// ```
+    pub fn ceiling<Q>(&self, value: &Q) -> Option<Ref<T>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.first_above(Bound::Included(value))
+    }
+    /// Returns the greatest element in the set that is less than or equal to the value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.floor(&4).map(|item| *item.get()), Some(3));
+    /// assert!(set.floor(&0).is_none());
+    /// ```
+    pub fn floor<Q>(&self, value: &Q) -> Option<Ref<T>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.last_below(Bound::Included(value))
+    }
+    /// Returns the least element in the set that is strictly greater than the value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.successor(&3).map(|item| *item.get()), Some(5));
+    /// assert!(set.successor(&5).is_none());
+    /// ```
+    pub fn successor<Q>(&self, value: &Q) -> Option<Ref<T>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.first_above(Bound::Excluded(value))
+    }
+    /// Returns the greatest element in the set that is strictly less than the value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.predecessor(&5).map(|item| *item.get()), Some(3));
+    /// assert!(set.predecessor(&1).is_none());
+    /// ```
+    pub fn predecessor<Q>(&self, value: &Q) -> Option<Ref<T>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.last_below(Bound::Excluded(value))
+    }
+    // Nodes are keyed by their maximum, so the first node whose maximum is above the bound holds
+    // the least element above it, unless a concurrent removal left it without one, in which case
+    // it is looked up in the following nodes
+    fn first_above<Q>(&self, bound: Bound<&Q>) -> Option<Ref<T>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let _global_guard = self.index_lock.read();
+        let mut entry = self.index.lower_bound(bound);
+        while let Some(current_entry) = entry {
+            let node_guard = current_entry.value().lock_arc();
+            let position = node_guard.partition_point(|item| match bound {
+                Bound::Included(start) => item.borrow() < start,
+                Bound::Excluded(start) => item.borrow() <= start,
+                Bound::Unbounded => false,
+            });
+            if position < node_guard.len() {
+                return Some(Ref {
+                    node_guard,
+                    position,
+                });
+            }
+
+            entry = current_entry.next();
+        }
+
+        None
+    }
+    // Likewise, the first node whose maximum lies past the bound is the last one that may hold
+    // elements below it, as equal elements can run on into it from the nodes before, which are
+    // looked up if it does not
+    fn last_below<Q>(&self, bound: Bound<&Q>) -> Option<Ref<T>>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let _global_guard = self.index_lock.read();
+        let mut entry = match bound {
+            Bound::Included(end) => self.index.lower_bound(Bound::Excluded(end)),
+            Bound::Excluded(end) => self.index.lower_bound(Bound::Included(end)),
+            Bound::Unbounded => None,
+        }
+        .or_else(|| self.index.back());
+        while let Some(current_entry) = entry {
+            let node_guard = current_entry.value().lock_arc();
+            let position = node_guard.partition_point(|item| match bound {
+                Bound::Included(end) => item.borrow() <= end,
+                Bound::Excluded(end) => item.borrow() < end,
+                Bound::Unbounded => true,
+            });
+            if position > 0 {
+                return Some(Ref {
+                    node_guard,
+                    position: position - 1,
+                });
+            }
+
+            entry = current_entry.prev();
+        }
+
+        None
+    }
 }
 
 impl<T> FromIterator<T> for BTreeSet<T>
@@ -451,7 +872,20 @@ where
     T: Ord + Clone + Send + 'static,
 {
     pub fn new(btree: &'a BTreeSet<T>) -> Self {
-        let current_front_entry = btree.index.front();
+        Self::between(btree, btree.index.front(), btree.index.back())
+    }
+    fn between(
+        btree: &'a BTreeSet<T>,
+        current_front_entry: Option<crossbeam_skiplist::map::Entry<'a, T, Node<T>>>,
+        current_back_entry: Option<crossbeam_skiplist::map::Entry<'a, T, Node<T>>>,
+    ) -> Self {
+        // A node that both ends start from is only ever iterated through the front, otherwise
+        // moving backwards from it would step outside of the nodes in between
+        let current_back_entry = current_back_entry.filter(|back_entry| {
+            !current_front_entry
+                .as_ref()
+                .is_some_and(|front_entry| Arc::ptr_eq(back_entry.value(), front_entry.value()))
+        });
         let (current_front_entry_guard, current_front_entry_iter) =
             if let Some(current_entry) = current_front_entry.clone() {
                 let guard = current_entry.value().lock_arc();
@@ -462,7 +896,6 @@ where
                 (None, None)
             };
 
-        let current_back_entry = btree.index.back();
         let (current_back_entry_guard, current_back_entry_iter) =
             if let Some(current_entry) = current_back_entry.clone() {
                 let mut guard = None;
@@ -659,11 +1092,54 @@ where
     }
 }
 
+/// A cursor over a [`BTreeSet`], pointing either at an element or at the "ghost" non-element
+/// that sits between the last element and the first one.
+///
+/// The cursor holds a copy of the element it points at rather than a lock on its node, and
+/// looks its neighbours up again on every move, so the set can keep being modified while it is
+/// in use.
+pub struct Cursor<'a, T>
+where
+    T: Ord + Clone + Send + 'static,
+{
+    btree: &'a BTreeSet<T>,
+    current: Option<T>,
+}
+
+impl<'a, T> Cursor<'a, T>
+where
+    T: Ord + Clone + Send + 'static,
+{
+    pub fn move_next(&mut self) {
+        self.current = self.peek_next().map(|item| item.get().clone());
+    }
+    pub fn move_prev(&mut self) {
+        self.current = self.peek_prev().map(|item| item.get().clone());
+    }
+    pub fn item(&self) -> Option<&T> {
+        self.current.as_ref()
+    }
+    pub fn peek_next(&self) -> Option<Ref<T>> {
+        match self.current.as_ref() {
+            Some(current) => self.btree.first_above(Bound::Excluded(current)),
+            None => self.btree.first_above::<T>(Bound::Unbounded),
+        }
+    }
+    pub fn peek_prev(&self) -> Option<Ref<T>> {
+        match self.current.as_ref() {
+            Some(current) => self.btree.last_below(Bound::Excluded(current)),
+            None => self.btree.last_below::<T>(Bound::Unbounded),
+        }
+    }
+}
+
 pub struct Range<'a, T>
 where
     T: Ord + Clone + Send + 'static,
 {
     iter: Iter<'a, T>,
+    // The first and last elements within the range, if there are any
+    bounds: Option<(T, T)>,
 }
 
 impl<'a, T> Range<'a, T>
@@ -679,90 +1155,59 @@ where
         let _global_guard = btree.index_lock.read();
 
         let start_bound = range.start_bound();
-        let current_front_entry = btree
-            .index
-            .lower_bound(start_bound);
-
-        let mut last_front = None;
-        let (current_front_entry_guard, mut current_front_entry_iter) = if let Some(current_entry) =
-            current_front_entry.clone()
-        {
-            let guard = current_entry.value().lock_arc();
-            let mut iter: std::slice::Iter<'_, T> = unsafe { std::mem::transmute(guard.iter()) };
-            let forward_nth = guard.rank(start_bound, true);
-            if let Some(forward_nth) = forward_nth {
-                last_front = iter.nth(forward_nth).cloned();
-            }
-
-            (Some(guard), Some(iter))
-        } else {
-            (None, None)
-        };
-
         let end_bound = range.end_bound();
-        let mut current_back_entry = btree
-            .index
-            .upper_bound(end_bound)
-            .and_then(|e| e.next())
-            .or_else(|| btree.index.front());
-
-        if current_back_entry.is_none() {
-            match end_bound {
-                std::ops::Bound::Unbounded => {
-                    current_back_entry = btree.index.back();
-                },
-                _ => {}
-            }
-        }
-
-        let mut last_back = None;
-        let (current_back_entry_guard, current_back_entry_iter) =
-            if let Some(current_entry) = current_back_entry.clone() {
-                let mut guard = None;
-                let mut iter = None;
+        let is_after_start = |item: &T| match start_bound {
+            std::ops::Bound::Included(start) => item.borrow() >= start,
+            std::ops::Bound::Excluded(start) => item.borrow() > start,
+            std::ops::Bound::Unbounded => true,
+        };
+        let is_before_end = |item: &T| match end_bound {
+            std::ops::Bound::Included(end) => item.borrow() <= end,
+            std::ops::Bound::Excluded(end) => item.borrow() < end,
+            std::ops::Bound::Unbounded => true,
+        };
 
-                if let Some(front_entry) = current_front_entry.as_ref() {
-                    if !Arc::ptr_eq(current_entry.value(), front_entry.value()) {
-                        let new_guard = current_entry.value().lock_arc();
-                        let mut iter_local: std::slice::Iter<'_, T> = unsafe { std::mem::transmute(new_guard.iter()) };
-                        let backward_nth = new_guard.rank(end_bound, false);
-                        if let Some(backward_nth) = backward_nth {
-                            last_back = iter_local.nth_back(backward_nth).cloned();
-                        }
+        // Nodes are keyed by their maximum, so the first node whose maximum is after the start
+        // holds the first element of the range
+        let current_front_entry = btree.index.lower_bound(start_bound);
+        let first = current_front_entry.as_ref().and_then(|entry| {
+            let guard = entry.value().lock();
+            let position = guard.partition_point(|item| !is_after_start(item));
 
-                        iter = Some(iter_local);
-                        guard = Some(new_guard);
-                    } else {
-                        if let Some(backward_nth) = current_front_entry_guard
-                            .as_ref()
-                            .and_then(|g| Some(g.rank(end_bound, false)))
-                        {
-                            if let Some(backward_nth) = backward_nth {
-                                last_back = current_front_entry_iter.as_mut().and_then(|i| {
-                                    i.nth_back(backward_nth).cloned()
-                                });
-                            }
-                        }
-                    }
-                }
+            guard.get(position).cloned()
+        });
 
-                (guard, iter)
-            } else {
-                (None, None)
-            };
+        // Likewise, the first node whose maximum is not before the end holds the last element,
+        // unless all of its elements are past the end, in which case it is the maximum of the
+        // node that precedes it
+        let mut current_back_entry = match end_bound {
+            std::ops::Bound::Included(end) | std::ops::Bound::Excluded(end) => btree
+                .index
+                .lower_bound(std::ops::Bound::Included(end))
+                .or_else(|| btree.index.back()),
+            std::ops::Bound::Unbounded => btree.index.back(),
+        };
+        let mut last = current_back_entry.as_ref().and_then(|entry| {
+            let guard = entry.value().lock();
+            let position = guard.partition_point(|item| is_before_end(item));
 
+            position.checked_sub(1).and_then(|idx| guard.get(idx).cloned())
+        });
+        if last.is_none() {
+            current_back_entry = current_back_entry.and_then(|entry| entry.prev());
+            last = current_back_entry
+                .as_ref()
+                .and_then(|entry| entry.value().lock().last().cloned());
+        }
 
-        Self {
-            iter: Iter {
-                _btree: btree,
-                current_front_entry,
-                current_front_entry_guard,
-                current_front_entry_iter,
-                current_back_entry,
-                current_back_entry_guard,
-                current_back_entry_iter,
-                last_front,
-                last_back,
+        match (first, last) {
+            (Some(first), Some(last)) if first <= last => Self {
+                iter: Iter::between(btree, current_front_entry, current_back_entry),
+                bounds: Some((first, last)),
+            },
+            _ => Self {
+                iter: Iter::between(btree, None, None),
+                bounds: None,
             },
         }
     }
@@ -775,7 +1220,20 @@ where
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next()
+        loop {
+            let (first, last) = self.bounds.as_ref()?;
+            let next_value = self.iter.next()?;
+            if next_value < first {
+                continue;
+            }
+            if next_value > last {
+                self.bounds = None;
+
+                return None;
+            }
+
+            return Some(next_value);
+        }
     }
 }
 
@@ -784,7 +1242,20 @@ where
     T: Ord + Clone + Send + 'static,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.iter.next_back()
+        loop {
+            let (first, last) = self.bounds.as_ref()?;
+            let next_value = self.iter.next_back()?;
+            if next_value > last {
+                continue;
+            }
+            if next_value < first {
+                self.bounds = None;
+
+                return None;
+            }
+
+            return Some(next_value);
+        }
     }
 }
 
@@ -860,6 +1331,66 @@ where
     {
         Range::new(self, range)
     }
+    /// Constructs a double-ended iterator over the elements of the set that start with the given
+    /// prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::new();
+    /// set.insert("apple");
+    /// set.insert("apricot");
+    /// set.insert("banana");
+    /// assert!(set.prefix_range("ap").eq(&["apple", "apricot"]));
+    /// assert_eq!(set.prefix_range("c").next(), None);
+    /// ```
+    pub fn prefix_range<Q>(&'a self, prefix: &Q) -> Range<'a, T>
+    where
+        T: Borrow<Q>,
+        Q: Prefix + ?Sized,
+    {
+        let successor = prefix.prefix_successor();
+
+        Range::new(self, prefix_bounds(prefix, &successor))
+    }
+    /// Constructs a double-ended iterator over the elements of the set whose leading fields are
+    /// equal to the given tuple prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::set::BTreeSet;
+    ///
+    /// let set = BTreeSet::new();
+    /// set.insert((1, 10));
+    /// set.insert((2, 10));
+    /// set.insert((2, 20));
+    /// set.insert((3, 10));
+    /// assert!(set.range_prefix(&(2,)).eq(&[(2, 10), (2, 20)]));
+    /// ```
+    pub fn range_prefix<P>(&'a self, prefix: &P) -> Range<'a, T>
+    where
+        T: Borrow<dyn PrefixLookup<T, P>> + TuplePrefix<P>,
+        P: Ord + Clone + 'static,
+    {
+        self.range_prefix_by::<T, P>(prefix)
+    }
+    // Shared with the maps, whose elements borrow as the lookup form of their keys
+    pub(crate) fn range_prefix_by<K, P>(&'a self, prefix: &P) -> Range<'a, T>
+    where
+        T: Borrow<dyn PrefixLookup<K, P>>,
+        K: TuplePrefix<P>,
+        P: Ord + Clone + 'static,
+    {
+        let start = Bounded::Infimum(prefix.clone());
+        let end = Bounded::Supremum(prefix.clone());
+        let start: &dyn PrefixLookup<K, P> = &start;
+        let end: &dyn PrefixLookup<K, P> = &end;
+
+        Range::new(self, (Bound::Excluded(start), Bound::Excluded(end)))
+    }
 }
 
 impl<T> BTreeSet<T>
@@ -999,7 +1530,7 @@ mod tests {
     use crate::concurrent::set::{BTreeSet, DEFAULT_INNER_SIZE};
     use rand::Rng;
     use std::collections::HashSet;
-    use std::ops::Bound::Included;
+    use std::ops::Bound::{self, Included};
     use std::sync::{Arc, Mutex};
     use std::thread;
 
@@ -1417,4 +1948,189 @@ mod tests {
             assert!(!set.contains(&i), "Element {} should not be in the set", i);
         }
     }
+
+    #[test]
+    fn test_range_across_nodes() {
+        for node_capacity in [3, 4, 8] {
+            let set = BTreeSet::with_maximum_node_size(node_capacity);
+            for i in (0..40).step_by(2) {
+                set.insert(i);
+            }
+
+            for start in -1..42 {
+                for end in start..42 {
+                    let expected: Vec<i32> = (start..end).filter(|i| i % 2 == 0 && *i < 40).collect();
+                    assert_eq!(set.range(start..end).copied().collect::<Vec<_>>(), expected);
+
+                    let expected: Vec<i32> = (start..=end).filter(|i| i % 2 == 0 && *i < 40).rev().collect();
+                    assert_eq!(set.range(start..=end).rev().copied().collect::<Vec<_>>(), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefix_range() {
+        let set = BTreeSet::with_maximum_node_size(3);
+        let mut model = std::collections::BTreeSet::new();
+        for a in [0x00u8, 0x01, 0xff] {
+            for b in [0x00u8, 0x7f, 0xff] {
+                model.insert(vec![a, b]);
+                model.insert(vec![a]);
+            }
+        }
+        for key in model.iter().rev() {
+            set.insert(key.clone());
+        }
+
+        for prefix in [vec![], vec![0x00], vec![0x01, 0x7f], vec![0xff], vec![0xff, 0xff], vec![0x02]] {
+            let expected: Vec<&Vec<u8>> = model.iter().filter(|k| k.starts_with(&prefix)).collect();
+            assert!(set.prefix_range(&prefix[..]).eq(expected.iter().copied()));
+            assert!(set.prefix_range(&prefix[..]).rev().eq(expected.iter().rev().copied()));
+        }
+    }
+
+    #[test]
+    fn test_range_prefix() {
+        let set = BTreeSet::with_maximum_node_size(3);
+        let mut model = std::collections::BTreeSet::new();
+        for tenant in 0..5 {
+            for timestamp in 0..(tenant * 2) {
+                model.insert((tenant, timestamp, tenant * timestamp));
+            }
+        }
+        for key in model.iter() {
+            set.insert(*key);
+        }
+
+        for tenant in -1..6 {
+            let expected: Vec<_> = model.iter().filter(|key| key.0 == tenant).collect();
+            assert!(set.range_prefix(&(tenant,)).eq(expected.iter().copied()));
+            assert!(set.range_prefix(&(tenant,)).rev().eq(expected.iter().rev().copied()));
+            assert_eq!(set.range_prefix(&(tenant, 1)).count(), usize::from((1..5).contains(&tenant)));
+        }
+    }
+
+    #[test]
+    fn test_bounds_against_model() {
+        let set = BTreeSet::with_maximum_node_size(3);
+        let model: std::collections::BTreeSet<i32> = (0..60).map(|i| i * i % 97 * 2).collect();
+        for value in model.iter() {
+            set.insert(*value);
+        }
+
+        for value in -2..200 {
+            let ceiling = model.range(value..).next().copied();
+            let floor = model.range(..=value).next_back().copied();
+            let successor = model.range(value + 1..).next().copied();
+            let predecessor = model.range(..value).next_back().copied();
+            assert_eq!(set.ceiling(&value).map(|item| *item.get()), ceiling);
+            assert_eq!(set.floor(&value).map(|item| *item.get()), floor);
+            assert_eq!(set.successor(&value).map(|item| *item.get()), successor);
+            assert_eq!(set.predecessor(&value).map(|item| *item.get()), predecessor);
+            assert_eq!(set.lower_bound(Bound::Excluded(&value)).item().copied(), successor);
+            assert_eq!(set.upper_bound(Bound::Included(&value)).item().copied(), floor);
+        }
+
+        let mut cursor = set.lower_bound::<i32>(Bound::Unbounded);
+        for value in model.iter() {
+            assert_eq!(cursor.item(), Some(value));
+            cursor.move_next();
+        }
+        assert_eq!(cursor.item(), None);
+        cursor.move_prev();
+        assert_eq!(cursor.item(), model.last());
+    }
+
+    #[test]
+    fn test_try_insert_fills_preallocated_nodes() {
+        let set = BTreeSet::with_maximum_node_size(4);
+        let model: std::collections::BTreeSet<i32> = (0..300).map(|i| i * 37 % 301).collect();
+        set.try_extend(model.iter().copied()).unwrap();
+        assert_eq!(set.try_insert(37), Ok(false));
+
+        assert!(set.iter().eq(model.iter()));
+        for entry in set.index.iter() {
+            let node = entry.value().lock();
+            assert!(node.len() <= 4 && node.capacity() == 4);
+            assert_eq!(node.last(), Some(entry.key()));
+        }
+    }
+
+    #[test]
+    fn test_with_capacity_takes_nodes_from_the_pool() {
+        let set = BTreeSet::with_capacity(10_000);
+        let spare = set.spare.lock().len();
+        for value in (0..10_000).map(|i| i * 7919 % 10_007) {
+            set.insert(value);
+        }
+
+        assert_eq!(set.len(), 10_000);
+        assert_eq!(set.spare.lock().len(), spare - set.index.len());
+        for entry in set.index.iter() {
+            assert_eq!(entry.value().lock().capacity(), DEFAULT_INNER_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_try_reserve_tops_up_the_pool() {
+        let set = BTreeSet::with_maximum_node_size(8);
+        set.try_reserve(100).unwrap();
+        let spare = set.spare.lock().len();
+        assert_eq!(spare, 1 + 100 / 4);
+        set.try_reserve(100).unwrap();
+        assert_eq!(set.spare.lock().len(), spare);
+        assert!(set.try_reserve(usize::MAX).is_err());
+
+        set.try_extend(0..100).unwrap();
+        assert_eq!(set.spare.lock().len(), spare - set.index.len());
+        for entry in set.index.iter() {
+            assert_eq!(entry.value().lock().capacity(), 8);
+        }
+        set.try_reserve(100).unwrap();
+        assert_eq!(set.spare.lock().len(), 100 / 4);
+    }
+
+    #[test]
+    fn test_reservations_follow_split_policy() {
+        let mut set = BTreeSet::with_maximum_node_size(8);
+        set.try_reserve(100).unwrap();
+        set.set_split_policy(crate::SplitPolicy::RightBiased);
+        assert_eq!(set.spare.lock().len(), 1 + 100_usize.div_ceil(8));
+        set.try_reserve(100).unwrap();
+        assert_eq!(set.spare.lock().len(), 1 + 100_usize.div_ceil(8));
+
+        set.try_extend(0..100).unwrap();
+        assert_eq!(set.spare.lock().len(), 1);
+        assert!(set.iter().copied().eq(0..100));
+    }
+
+    #[test]
+    fn test_stale_right_biased_split_is_retried() {
+        use crate::concurrent::operation::Operation;
+
+        let mut set = BTreeSet::with_maximum_node_size(2);
+        set.set_split_policy(crate::SplitPolicy::RightBiased);
+        set.insert(100);
+        set.insert(110);
+        // Planned while the full node is the last one, as an insert of 150 would
+        let old_node = set.index.back().unwrap().value().clone();
+        let split = Operation::Split(
+            old_node,
+            110,
+            150,
+            Vec::with_capacity(2),
+            crate::SplitPolicy::RightBiased,
+        );
+
+        // Meanwhile another thread adds a node after it without changing it
+        set.insert(120);
+        set.insert(200);
+        assert!(split.commit(&set.index).is_err());
+
+        set.insert(150);
+        assert!(set.iter().eq([100, 110, 120, 150, 200].iter()));
+        let maxima: Vec<i32> = set.index.iter().map(|entry| *entry.key()).collect();
+        assert!(maxima.windows(2).all(|pair| pair[0] < pair[1]));
+    }
 }
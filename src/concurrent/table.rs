@@ -0,0 +1,713 @@
+use std::any::Any;
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
+
+use parking_lot::Mutex;
+
+use super::map::BTreeMap;
+use super::multimap::BTreeMultiMap;
+
+/// The reasons for which a write to an [`IndexedTable`] can be rejected.
+///
+/// Whenever an error is returned, the table is left exactly as it was before the write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableError {
+    /// A row with the same primary key is already in the table.
+    DuplicatePrimaryKey,
+    /// No row with the given primary key is in the table.
+    NotFound,
+    /// Another row already holds the same key in the unique secondary index at the given
+    /// position, in the order in which the indexes were added.
+    UniqueViolation { index: usize },
+}
+
+impl Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::DuplicatePrimaryKey => write!(f, "duplicate primary key"),
+            TableError::NotFound => write!(f, "no row with the given primary key"),
+            TableError::UniqueViolation { index } => {
+                write!(f, "unique constraint of secondary index {index} violated")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TableError {}
+
+/// A typed handle to a secondary index of an [`IndexedTable`], returned when the index is added.
+pub struct IndexId<K> {
+    position: usize,
+    key: PhantomData<fn() -> K>,
+}
+
+impl<K> Clone for IndexId<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for IndexId<K> {}
+
+impl<K> fmt::Debug for IndexId<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexId")
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+impl<K> IndexId<K> {
+    /// Returns the position of the index, in the order in which the indexes were added.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+trait SecondaryIndex<PK, Row>: Send + Sync {
+    // Returns `false`, leaving the index untouched, if the row would violate a unique constraint
+    fn insert(&self, row: &Row, primary_key: &PK) -> bool;
+    fn remove(&self, row: &Row, primary_key: &PK);
+    fn as_any(&self) -> &dyn Any;
+}
+
+enum KeyIndexKind<K, PK>
+where
+    K: Send + Ord + Clone + 'static,
+    PK: Send + Ord + Clone + 'static,
+{
+    Unique(BTreeMap<K, PK>),
+    NonUnique(BTreeMultiMap<K, PK>),
+}
+
+struct KeyIndex<K, PK, Row>
+where
+    K: Send + Ord + Clone + 'static,
+    PK: Send + Ord + Clone + 'static,
+{
+    extractor: Box<dyn Fn(&Row) -> K + Send + Sync>,
+    kind: KeyIndexKind<K, PK>,
+}
+
+impl<K, PK, Row> KeyIndex<K, PK, Row>
+where
+    K: Send + Sync + Ord + Clone + 'static,
+    PK: Send + Sync + Ord + Clone + 'static,
+{
+    fn get(&self, key: &K) -> Vec<PK> {
+        match &self.kind {
+            KeyIndexKind::Unique(map) => map
+                .get(key)
+                .map(|entry| vec![entry.get().value.clone()])
+                .unwrap_or_default(),
+            KeyIndexKind::NonUnique(multimap) => multimap
+                .get(key)
+                .map(|(_, primary_key)| primary_key.clone())
+                .collect(),
+        }
+    }
+    fn range<R>(&self, range: R) -> Vec<(K, PK)>
+    where
+        R: RangeBounds<K>,
+    {
+        match &self.kind {
+            KeyIndexKind::Unique(map) => map
+                .range::<K, R>(range)
+                .map(|(key, primary_key)| (key.clone(), primary_key.clone()))
+                .collect(),
+            KeyIndexKind::NonUnique(multimap) => multimap
+                .range(range)
+                .map(|(key, primary_key)| (key.clone(), primary_key.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl<K, PK, Row> SecondaryIndex<PK, Row> for KeyIndex<K, PK, Row>
+where
+    K: Send + Sync + Ord + Clone + 'static,
+    PK: Send + Sync + Ord + Clone + 'static,
+    Row: 'static,
+{
+    fn insert(&self, row: &Row, primary_key: &PK) -> bool {
+        let key = (self.extractor)(row);
+        match &self.kind {
+            KeyIndexKind::Unique(map) => {
+                if map.contains_key(&key) {
+                    return false;
+                }
+                map.insert(key, primary_key.clone());
+            }
+            KeyIndexKind::NonUnique(multimap) => {
+                multimap.insert(key, primary_key.clone());
+            }
+        }
+
+        true
+    }
+    fn remove(&self, row: &Row, primary_key: &PK) {
+        let key = (self.extractor)(row);
+        match &self.kind {
+            KeyIndexKind::Unique(map) => {
+                map.remove(&key);
+            }
+            KeyIndexKind::NonUnique(multimap) => {
+                multimap.remove(&key, primary_key);
+            }
+        }
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// An in-memory table of rows, stored in a concurrent [`BTreeMap`] by primary key, and kept in
+/// sync with any number of unique or non-unique secondary indexes.
+///
+/// Keys are computed from rows through extractors given when the table and its indexes are made.
+/// Unique secondary indexes are backed by a concurrent [`BTreeMap`] and non-unique ones by a
+/// [`BTreeMultiMap`], both mapping secondary keys to primary keys.
+///
+/// Writes are serialized with each other, and either update every index or, if a constraint
+/// is violated, roll back every change they made. Reads never block, so they may observe a write
+/// that is still in progress; a row is only visible through its primary key once every secondary
+/// index has been updated on insertion. Rows found through a secondary index are checked against
+/// the key they were found under, so a lookup may miss a row that is being updated, but never
+/// returns one whose key does not match.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::concurrent::table::{IndexedTable, TableError};
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct User {
+///     id: u32,
+///     email: String,
+///     age: u8,
+/// }
+///
+/// let mut users = IndexedTable::new(|user: &User| user.id);
+/// let by_email = users.add_unique_index(|user: &User| user.email.clone()).unwrap();
+/// let by_age = users.add_index(|user: &User| user.age);
+///
+/// let alice = User { id: 1, email: "alice@example.com".into(), age: 30 };
+/// let bob = User { id: 2, email: "bob@example.com".into(), age: 25 };
+/// users.insert(alice.clone()).unwrap();
+/// users.insert(bob.clone()).unwrap();
+///
+/// let impostor = User { id: 3, email: "alice@example.com".into(), age: 40 };
+/// assert_eq!(
+///     users.insert(impostor),
+///     Err(TableError::UniqueViolation { index: by_email.position() })
+/// );
+/// assert_eq!(users.len(), 2);
+///
+/// assert_eq!(users.get_by(by_email, &"bob@example.com".to_string()), vec![bob.clone()]);
+/// assert_eq!(users.range_by(by_age, 26..), vec![alice]);
+/// ```
+pub struct IndexedTable<PK, Row>
+where
+    PK: Send + Sync + Ord + Clone + 'static,
+    Row: Send + Sync + Clone + 'static,
+{
+    rows: BTreeMap<PK, Row>,
+    primary_key: Box<dyn Fn(&Row) -> PK + Send + Sync>,
+    indexes: Vec<Box<dyn SecondaryIndex<PK, Row>>>,
+    write_lock: Mutex<()>,
+}
+
+impl<PK, Row> IndexedTable<PK, Row>
+where
+    PK: Send + Sync + Ord + Clone + 'static,
+    Row: Send + Sync + Clone + 'static,
+{
+    /// Makes a new, empty `IndexedTable` whose rows are identified by the given primary key
+    /// extractor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::IndexedTable;
+    ///
+    /// let table = IndexedTable::new(|row: &(u32, String)| row.0);
+    /// assert!(table.is_empty());
+    /// ```
+    pub fn new<F>(primary_key: F) -> Self
+    where
+        F: Fn(&Row) -> PK + Send + Sync + 'static,
+    {
+        Self {
+            rows: BTreeMap::new(),
+            primary_key: Box::new(primary_key),
+            indexes: vec![],
+            write_lock: Mutex::new(()),
+        }
+    }
+    fn add_key_index<K>(&mut self, index: KeyIndex<K, PK, Row>) -> Result<IndexId<K>, TableError>
+    where
+        K: Send + Sync + Ord + Clone + 'static,
+    {
+        let position = self.indexes.len();
+        for (primary_key, row) in self.rows.iter() {
+            if !index.insert(row, primary_key) {
+                return Err(TableError::UniqueViolation { index: position });
+            }
+        }
+        self.indexes.push(Box::new(index));
+
+        Ok(IndexId {
+            position,
+            key: PhantomData,
+        })
+    }
+    /// Adds a unique secondary index over the keys computed by the extractor, indexing every row
+    /// that is already in the table.
+    ///
+    /// If two rows already share a key, the index is not added and an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::{IndexedTable, TableError};
+    ///
+    /// let mut table = IndexedTable::new(|row: &(u32, char)| row.0);
+    /// table.insert((1, 'a')).unwrap();
+    /// table.insert((2, 'a')).unwrap();
+    /// assert_eq!(
+    ///     table.add_unique_index(|row: &(u32, char)| row.1).unwrap_err(),
+    ///     TableError::UniqueViolation { index: 0 }
+    /// );
+    /// ```
+    pub fn add_unique_index<K, F>(&mut self, extractor: F) -> Result<IndexId<K>, TableError>
+    where
+        K: Send + Sync + Ord + Clone + 'static,
+        F: Fn(&Row) -> K + Send + Sync + 'static,
+    {
+        self.add_key_index(KeyIndex {
+            extractor: Box::new(extractor),
+            kind: KeyIndexKind::Unique(BTreeMap::new()),
+        })
+    }
+    /// Adds a non-unique secondary index over the keys computed by the extractor, indexing every
+    /// row that is already in the table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::IndexedTable;
+    ///
+    /// let mut table = IndexedTable::new(|row: &(u32, char)| row.0);
+    /// table.insert((1, 'a')).unwrap();
+    /// table.insert((2, 'a')).unwrap();
+    /// let by_letter = table.add_index(|row: &(u32, char)| row.1);
+    /// assert_eq!(table.get_by(by_letter, &'a').len(), 2);
+    /// ```
+    pub fn add_index<K, F>(&mut self, extractor: F) -> IndexId<K>
+    where
+        K: Send + Sync + Ord + Clone + 'static,
+        F: Fn(&Row) -> K + Send + Sync + 'static,
+    {
+        self.add_key_index(KeyIndex {
+            extractor: Box::new(extractor),
+            kind: KeyIndexKind::NonUnique(BTreeMultiMap::new()),
+        })
+        .expect("non-unique indexes can not be violated")
+    }
+    fn key_index<K>(&self, index: IndexId<K>) -> &KeyIndex<K, PK, Row>
+    where
+        K: Send + Sync + Ord + Clone + 'static,
+    {
+        self.indexes[index.position]
+            .as_any()
+            .downcast_ref()
+            .expect("the index id does not belong to this table")
+    }
+    // Inserts the row into every secondary index, removing it again from those that were already
+    // updated if a unique constraint is violated
+    fn index_row(&self, row: &Row, primary_key: &PK) -> Result<(), TableError> {
+        for (position, index) in self.indexes.iter().enumerate() {
+            if !index.insert(row, primary_key) {
+                self.indexes[..position]
+                    .iter()
+                    .for_each(|index| index.remove(row, primary_key));
+
+                return Err(TableError::UniqueViolation { index: position });
+            }
+        }
+
+        Ok(())
+    }
+    /// Returns the number of rows in the table.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+    /// Returns `true` if the table contains no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Inserts a new row into the table and every one of its indexes.
+    ///
+    /// Fails if a row with the same primary key is already present, or if the row violates a
+    /// unique secondary index, in which case the table is left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::{IndexedTable, TableError};
+    ///
+    /// let table = IndexedTable::new(|row: &(u32, char)| row.0);
+    /// assert_eq!(table.insert((1, 'a')), Ok(()));
+    /// assert_eq!(table.insert((1, 'b')), Err(TableError::DuplicatePrimaryKey));
+    /// ```
+    pub fn insert(&self, row: Row) -> Result<(), TableError> {
+        let _guard = self.write_lock.lock();
+        let primary_key = (self.primary_key)(&row);
+        if self.rows.contains_key(&primary_key) {
+            return Err(TableError::DuplicatePrimaryKey);
+        }
+
+        self.index_row(&row, &primary_key)?;
+        self.rows.insert(primary_key, row);
+
+        Ok(())
+    }
+    /// Replaces the row that has the same primary key as the given one, updating every index,
+    /// and returns the previous row.
+    ///
+    /// Fails if no such row is present, or if the new row violates a unique secondary index, in
+    /// which case the table is left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::{IndexedTable, TableError};
+    ///
+    /// let mut table = IndexedTable::new(|row: &(u32, char)| row.0);
+    /// let by_letter = table.add_unique_index(|row: &(u32, char)| row.1).unwrap();
+    /// table.insert((1, 'a')).unwrap();
+    /// table.insert((2, 'b')).unwrap();
+    ///
+    /// assert_eq!(table.update((1, 'c')), Ok((1, 'a')));
+    /// assert_eq!(table.update((1, 'b')), Err(TableError::UniqueViolation { index: 0 }));
+    /// assert_eq!(table.update((3, 'd')), Err(TableError::NotFound));
+    /// assert_eq!(table.get_by(by_letter, &'c'), vec![(1, 'c')]);
+    /// ```
+    pub fn update(&self, row: Row) -> Result<Row, TableError> {
+        let _guard = self.write_lock.lock();
+        let primary_key = (self.primary_key)(&row);
+        let old_row = match self.rows.get(&primary_key) {
+            Some(entry) => entry.get().value.clone(),
+            None => return Err(TableError::NotFound),
+        };
+
+        for (position, index) in self.indexes.iter().enumerate() {
+            index.remove(&old_row, &primary_key);
+            if !index.insert(&row, &primary_key) {
+                index.insert(&old_row, &primary_key);
+                self.indexes[..position].iter().for_each(|index| {
+                    index.remove(&row, &primary_key);
+                    index.insert(&old_row, &primary_key);
+                });
+
+                return Err(TableError::UniqueViolation { index: position });
+            }
+        }
+        self.rows.insert(primary_key, row);
+
+        Ok(old_row)
+    }
+    /// Removes the row with the given primary key from the table and every one of its indexes,
+    /// and returns it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::IndexedTable;
+    ///
+    /// let mut table = IndexedTable::new(|row: &(u32, char)| row.0);
+    /// let by_letter = table.add_index(|row: &(u32, char)| row.1);
+    /// table.insert((1, 'a')).unwrap();
+    ///
+    /// assert_eq!(table.delete(&1), Some((1, 'a')));
+    /// assert_eq!(table.delete(&1), None);
+    /// assert!(table.get_by(by_letter, &'a').is_empty());
+    /// ```
+    pub fn delete(&self, primary_key: &PK) -> Option<Row> {
+        let _guard = self.write_lock.lock();
+        let (primary_key, row) = self.rows.remove(primary_key)?;
+        self.indexes
+            .iter()
+            .for_each(|index| index.remove(&row, &primary_key));
+
+        Some(row)
+    }
+    /// Returns a copy of the row with the given primary key, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::IndexedTable;
+    ///
+    /// let table = IndexedTable::new(|row: &(u32, char)| row.0);
+    /// table.insert((1, 'a')).unwrap();
+    /// assert_eq!(table.get(&1), Some((1, 'a')));
+    /// assert_eq!(table.get(&2), None);
+    /// ```
+    pub fn get(&self, primary_key: &PK) -> Option<Row> {
+        self.rows
+            .get(primary_key)
+            .map(|entry| entry.get().value.clone())
+    }
+    /// Returns copies of the rows whose primary key falls within the range, sorted by primary key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::IndexedTable;
+    ///
+    /// let table = IndexedTable::new(|row: &(u32, char)| row.0);
+    /// table.insert((1, 'a')).unwrap();
+    /// table.insert((2, 'b')).unwrap();
+    /// table.insert((3, 'c')).unwrap();
+    /// assert_eq!(table.range(2..), vec![(2, 'b'), (3, 'c')]);
+    /// ```
+    pub fn range<R>(&self, range: R) -> Vec<Row>
+    where
+        R: RangeBounds<PK>,
+    {
+        self.rows
+            .range::<PK, R>(range)
+            .map(|(_, row)| row.clone())
+            .collect()
+    }
+    /// Returns copies of the rows that have the given key in a secondary index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index was not added to this table.
+    pub fn get_by<K>(&self, index: IndexId<K>, key: &K) -> Vec<Row>
+    where
+        K: Send + Sync + Ord + Clone + 'static,
+    {
+        let index = self.key_index(index);
+        index
+            .get(key)
+            .iter()
+            .filter_map(|primary_key| self.get(primary_key))
+            .filter(|row| (index.extractor)(row) == *key)
+            .collect()
+    }
+    /// Returns copies of the rows whose key in a secondary index falls within the range, sorted
+    /// by that key. Rows that share a key in a non-unique index are returned in no particular
+    /// order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index was not added to this table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::concurrent::table::IndexedTable;
+    ///
+    /// let mut table = IndexedTable::new(|row: &(u32, char)| row.0);
+    /// let by_letter = table.add_index(|row: &(u32, char)| row.1);
+    /// table.insert((1, 'c')).unwrap();
+    /// table.insert((2, 'b')).unwrap();
+    /// table.insert((3, 'a')).unwrap();
+    /// assert_eq!(table.range_by(by_letter, 'a'..'c'), vec![(3, 'a'), (2, 'b')]);
+    /// ```
+    pub fn range_by<K, R>(&self, index: IndexId<K>, range: R) -> Vec<Row>
+    where
+        K: Send + Sync + Ord + Clone + 'static,
+        R: RangeBounds<K>,
+    {
+        let index = self.key_index(index);
+        index
+            .range(range)
+            .iter()
+            .filter_map(|(key, primary_key)| {
+                self.get(primary_key)
+                    .filter(|row| (index.extractor)(row) == *key)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexedTable, TableError};
+    use rand::{Rng, SeedableRng};
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Account {
+        id: u32,
+        handle: u32,
+        region: u8,
+    }
+
+    #[test]
+    fn test_rollback_on_unique_violation() {
+        let mut table = IndexedTable::new(|account: &Account| account.id);
+        let by_region = table.add_index(|account: &Account| account.region);
+        let by_handle = table
+            .add_unique_index(|account: &Account| account.handle)
+            .unwrap();
+
+        for id in 0..50 {
+            table
+                .insert(Account {
+                    id,
+                    handle: id * 10,
+                    region: (id % 5) as u8,
+                })
+                .unwrap();
+        }
+
+        let clashing = Account {
+            id: 100,
+            handle: 30,
+            region: 9,
+        };
+        assert_eq!(
+            table.insert(clashing),
+            Err(TableError::UniqueViolation { index: 1 })
+        );
+        assert!(table.get_by(by_region, &9).is_empty());
+
+        let moved = Account {
+            id: 4,
+            handle: 20,
+            region: 9,
+        };
+        assert_eq!(
+            table.update(moved),
+            Err(TableError::UniqueViolation { index: 1 })
+        );
+        assert_eq!(table.get_by(by_region, &4).len(), 10);
+        assert!(table.get_by(by_region, &9).is_empty());
+        assert_eq!(table.get_by(by_handle, &40)[0].id, 4);
+
+        assert_eq!(table.len(), 50);
+        assert_eq!(table.range_by(by_handle, 100..200).len(), 10);
+        assert_eq!(table.range_by(by_region, 1..=2).len(), 20);
+        assert_eq!(table.range(10..20).len(), 10);
+    }
+
+    #[test]
+    fn test_indexes_match_rows() {
+        let mut rng = rand::rngs::StdRng::from_seed([31; 32]);
+        let mut table = IndexedTable::new(|account: &Account| account.id);
+        let by_region = table.add_index(|account: &Account| account.region);
+        let by_handle = table
+            .add_unique_index(|account: &Account| account.handle)
+            .unwrap();
+
+        for _ in 0..5_000 {
+            let account = Account {
+                id: rng.gen_range(0..200),
+                handle: rng.gen_range(0..400),
+                region: rng.gen_range(0..8),
+            };
+            match rng.gen_range(0..3) {
+                0 => {
+                    let _ = table.insert(account);
+                }
+                1 => {
+                    let _ = table.update(account);
+                }
+                _ => {
+                    table.delete(&account.id);
+                }
+            }
+        }
+
+        let rows = table.range(..);
+        assert_eq!(rows.len(), table.len());
+        for row in &rows {
+            assert_eq!(table.get_by(by_handle, &row.handle), vec![row.clone()]);
+            assert!(table.get_by(by_region, &row.region).contains(row));
+        }
+        assert_eq!(table.range_by(by_handle, ..).len(), rows.len());
+        assert_eq!(table.range_by(by_region, ..).len(), rows.len());
+    }
+
+    #[test]
+    fn test_concurrent_writers() {
+        let mut table = IndexedTable::new(|account: &Account| account.id);
+        let by_handle = table
+            .add_unique_index(|account: &Account| account.handle)
+            .unwrap();
+        let table = Arc::new(table);
+
+        let handles: Vec<_> = (0..4)
+            .map(|thread| {
+                let table = table.clone();
+                std::thread::spawn(move || {
+                    (0..250)
+                        .filter(|id| {
+                            table
+                                .insert(Account {
+                                    id: thread * 1000 + id,
+                                    handle: *id,
+                                    region: 0,
+                                })
+                                .is_ok()
+                        })
+                        .count()
+                })
+            })
+            .collect();
+        let inserted: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+        assert_eq!(inserted, 250);
+        assert_eq!(table.len(), 250);
+        assert_eq!(table.range_by(by_handle, ..).len(), 250);
+    }
+
+    #[test]
+    fn test_readers_only_see_matching_keys_during_updates() {
+        let mut table = IndexedTable::new(|account: &Account| account.id);
+        let by_region = table.add_index(|account: &Account| account.region);
+        for id in 0..64 {
+            table
+                .insert(Account {
+                    id,
+                    handle: id,
+                    region: 0,
+                })
+                .unwrap();
+        }
+        let table = Arc::new(table);
+
+        let writer = {
+            let table = table.clone();
+            std::thread::spawn(move || {
+                for round in 0..200 {
+                    for id in 0..64 {
+                        table
+                            .update(Account {
+                                id,
+                                handle: id,
+                                region: (round + id as usize) as u8 % 2,
+                            })
+                            .unwrap();
+                    }
+                }
+            })
+        };
+        while !writer.is_finished() {
+            assert!(table
+                .get_by(by_region, &1)
+                .iter()
+                .all(|row| row.region == 1));
+            let rows = table.range_by(by_region, 0..=1);
+            assert!(rows.len() <= 64);
+            assert!(rows.windows(2).all(|pair| pair[0].region <= pair[1].region));
+        }
+        writer.join().unwrap();
+    }
+}
@@ -1,6 +1,9 @@
 pub mod constants;
+pub mod layout;
+pub mod lengths;
 pub mod node;
 pub mod pair;
+pub mod sequenced;
 
 #[cfg(feature = "multimap")]
 pub mod multipair;
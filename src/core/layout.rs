@@ -0,0 +1,598 @@
+use std::collections::TryReserveError;
+use std::iter::Map;
+use std::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::lengths::{FenwickLengths, NodeLengths};
+use crate::core::node::SplitPolicy;
+
+/// A node of a [`Layout`] that can tell its length and give its upper part away by itself.
+///
+/// This is only a shorthand for the collections whose nodes are plain storage: the layout itself
+/// only reaches into its nodes through the closures it is handed, which lets a
+/// [`BTreeSet`](crate::BTreeSet) go through the same layout with nodes that implement
+/// [`NodeLike`](crate::core::node::NodeLike) instead.
+pub(crate) trait LayoutNode: Sized {
+    fn len(&self) -> usize;
+    fn split_off(&mut self, at: usize) -> Self;
+}
+
+impl<T> LayoutNode for Vec<T> {
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+    fn split_off(&mut self, at: usize) -> Self {
+        Vec::split_off(self, at)
+    }
+}
+
+/// Sorted nodes laid out one after the other, under an index of their lengths.
+///
+/// This is the bottom of every two-level tree of the crate, which only differ in what their
+/// nodes hold. The layout always has at least one node, and only the sole node of an empty
+/// layout is ever empty. Node lengths are read from the index, so the layout never asks its
+/// nodes for them once they are in.
+///
+/// Elements may also weigh more than one, as the copies of an element of a
+/// [`BTreeBag`](crate::bag::BTreeBag) do, in which case they go through the weighted methods and
+/// every length and position of the layout counts weight rather than elements.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Layout<N, L = FenwickLengths> {
+    nodes: Vec<N>,
+    index: L,
+    len: usize,
+}
+
+pub type Nodes<'a, N, I> = Map<std::slice::Iter<'a, N>, fn(&'a N) -> I>;
+pub type NodesMut<'a, N, I> = Map<std::slice::IterMut<'a, N>, fn(&'a mut N) -> I>;
+pub type IntoNodes<N, I> = Map<std::vec::IntoIter<N>, fn(N) -> I>;
+
+impl<N, L: NodeLengths> Layout<N, L> {
+    pub fn new(empty: N) -> Self {
+        Self {
+            nodes: vec![empty],
+            index: L::from_iter([0]),
+            len: 0,
+        }
+    }
+    /// Like [`new`](Self::new), but reports an allocation failure instead of aborting.
+    pub fn try_new(empty: N) -> Result<Self, TryReserveError> {
+        let mut nodes = Vec::new();
+        nodes.try_reserve_exact(1)?;
+        nodes.push(empty);
+
+        Ok(Self {
+            nodes,
+            index: L::try_from_iter([0])?,
+            len: 0,
+        })
+    }
+    /// Lays out the given nodes, which have to be sorted and non-empty unless there is only one,
+    /// reading their lengths through `len`.
+    pub fn from_nodes_by<F>(nodes: Vec<N>, len: F) -> Self
+    where
+        F: Fn(&N) -> usize,
+    {
+        let index = L::from_iter(nodes.iter().map(&len));
+
+        Self {
+            len: index.prefix_sum(index.len()),
+            index,
+            nodes,
+        }
+    }
+    /// Allocates a layout for `len` elements, `fill` to a node but for the last one, making each
+    /// node through `node` with room for its elements, and reports an allocation failure instead
+    /// of aborting. The nodes come out empty, and have to be filled up through
+    /// [`nodes_mut`](Self::nodes_mut) before anything else is asked of the layout.
+    pub fn try_with_len<F>(len: usize, fill: usize, mut node: F) -> Result<Self, TryReserveError>
+    where
+        F: FnMut(usize) -> Result<N, TryReserveError>,
+    {
+        let lengths = (0..len.div_ceil(fill)).map(|node_idx| fill.min(len - node_idx * fill));
+        let mut nodes = Vec::new();
+        nodes.try_reserve_exact(lengths.len())?;
+        for node_len in lengths.clone() {
+            nodes.push(node(node_len)?);
+        }
+
+        Ok(Self {
+            nodes,
+            index: L::try_from_iter(lengths)?,
+            len,
+        })
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn nodes(&self) -> &[N] {
+        &self.nodes
+    }
+    // Changing the length of a node through this would leave the index behind
+    pub fn nodes_mut(&mut self) -> &mut [N] {
+        &mut self.nodes
+    }
+    /// Returns the number of elements in the given node.
+    pub fn node_len(&self, node_idx: usize) -> usize {
+        self.index.get(node_idx)
+    }
+    /// Returns the number of elements in the nodes before `node_idx`.
+    pub fn offset(&self, node_idx: usize) -> usize {
+        self.index.prefix_sum(node_idx)
+    }
+    /// Returns the first node for which `before` does not hold, or the last one if it holds for
+    /// all of them. `before` has to hold for a prefix of the nodes and for nothing after it.
+    pub fn locate_node<P>(&self, before: P) -> usize
+    where
+        P: FnMut(&N) -> bool,
+    {
+        self.nodes.partition_point(before).min(self.nodes.len() - 1)
+    }
+    /// Returns the node and position of the `idx`-th element, or right after the last element
+    /// when `idx` is the length of the layout.
+    pub fn locate_ith(&self, idx: usize) -> (usize, usize) {
+        if idx >= self.len {
+            let node_idx = self.nodes.len() - 1;
+            return (node_idx, self.node_len(node_idx));
+        }
+
+        // The node holding the element is the first one whose elements reach past it
+        let node_idx = self.index.index_of(idx + 1);
+
+        (node_idx, idx - self.offset(node_idx))
+    }
+    /// Makes room for a single element at `position` of the given node, splitting the node
+    /// through `split` first if it is `full`. Returns the node and position the element goes to.
+    pub fn make_room<S>(
+        &mut self,
+        node_idx: usize,
+        position: usize,
+        split_policy: SplitPolicy,
+        full: bool,
+        split: S,
+    ) -> (usize, usize)
+    where
+        S: FnOnce(&mut N, usize) -> N,
+    {
+        let node_len = self.node_len(node_idx);
+
+        self.make_weighted_room(
+            node_idx,
+            position,
+            node_len,
+            split_policy,
+            full,
+            |node, at| (split(node, at), node_len - at),
+        )
+    }
+    /// Like [`make_room`](Self::make_room), for a node holding `node_len` elements whatever they
+    /// weigh, `split` returning the weight that went to the new node along with it.
+    pub fn make_weighted_room<S>(
+        &mut self,
+        node_idx: usize,
+        position: usize,
+        node_len: usize,
+        split_policy: SplitPolicy,
+        full: bool,
+        split: S,
+    ) -> (usize, usize)
+    where
+        S: FnOnce(&mut N, usize) -> (N, usize),
+    {
+        if !full {
+            return (node_idx, position);
+        }
+
+        let at = split_policy.split_point(node_len, position);
+        self.split_node_weighted_by(node_idx, |node| split(node, at));
+        if position >= at {
+            return (node_idx + 1, position - at);
+        }
+
+        (node_idx, position)
+    }
+    /// Lets `insert` add a single element to the given node.
+    pub fn insert_with<R, F>(&mut self, node_idx: usize, insert: F) -> R
+    where
+        F: FnOnce(&mut N) -> R,
+    {
+        self.insert_weighted_with(node_idx, 1, insert)
+    }
+    /// Lets `insert` add `weight` to the given node, be it through a new element or an element
+    /// that is already there.
+    pub fn insert_weighted_with<R, F>(&mut self, node_idx: usize, weight: usize, insert: F) -> R
+    where
+        F: FnOnce(&mut N) -> R,
+    {
+        let result = insert(&mut self.nodes[node_idx]);
+        self.index.add_at(node_idx, weight);
+        self.len += weight;
+
+        result
+    }
+    /// Lets `insert` add at most a single element to the given node, returning whether it did.
+    pub fn insert_if<F>(&mut self, node_idx: usize, insert: F) -> bool
+    where
+        F: FnOnce(&mut N) -> bool,
+    {
+        let inserted = insert(&mut self.nodes[node_idx]);
+        if inserted {
+            self.index.add_at(node_idx, 1);
+            self.len += 1;
+        }
+
+        inserted
+    }
+    /// Lets `remove` take a single element out of the given node, which is then dropped if it
+    /// ends up empty, unless it is the only one.
+    pub fn remove_with<R, F>(&mut self, node_idx: usize, remove: F) -> R
+    where
+        F: FnOnce(&mut N) -> R,
+    {
+        self.remove_weighted_with(node_idx, 1, remove)
+    }
+    /// Lets `remove` take `weight` out of the given node, which is then dropped if none of its
+    /// weight is left, unless it is the only one.
+    pub fn remove_weighted_with<R, F>(&mut self, node_idx: usize, weight: usize, remove: F) -> R
+    where
+        F: FnOnce(&mut N) -> R,
+    {
+        let result = remove(&mut self.nodes[node_idx]);
+        if self.node_len(node_idx) == weight && self.nodes.len() > 1 {
+            self.nodes.remove(node_idx);
+            self.index.remove(node_idx);
+        } else {
+            self.index.sub_at(node_idx, weight);
+        }
+        self.len -= weight;
+
+        result
+    }
+    /// Splits the given node in two through `split`, which has to move its elements from `at` on
+    /// to the node it returns, laid out right after it. Only the node that the insertion of an
+    /// element will go to may be left empty.
+    pub fn split_node_by<S>(&mut self, node_idx: usize, at: usize, split: S)
+    where
+        S: FnOnce(&mut N, usize) -> N,
+    {
+        let moved = self.node_len(node_idx) - at;
+        self.split_node_weighted_by(node_idx, |node| (split(node, at), moved));
+    }
+    /// Splits the given node in two through `split`, which has to move its upper elements to the
+    /// node it returns, laid out right after it, along with the weight they took with them.
+    pub fn split_node_weighted_by<S>(&mut self, node_idx: usize, split: S)
+    where
+        S: FnOnce(&mut N) -> (N, usize),
+    {
+        let (new_node, moved) = split(&mut self.nodes[node_idx]);
+        self.index.sub_at(node_idx, moved);
+        self.index.insert(node_idx + 1, moved);
+        self.nodes.insert(node_idx + 1, new_node);
+    }
+    /// Splits the layout in two at the `idx`-th element, splitting the node holding it through
+    /// `split` if it does not start there. Returns the layout of the elements from `idx` on,
+    /// which starts from `empty` if there are none.
+    pub fn split_off_by<S>(&mut self, idx: usize, empty: N, split: S) -> Self
+    where
+        S: FnOnce(&mut N, usize) -> N,
+    {
+        if idx >= self.len {
+            return Self::new(empty);
+        }
+
+        let (mut node_idx, position) = self.locate_ith(idx);
+        if position > 0 {
+            self.split_node_by(node_idx, position, split);
+            node_idx += 1;
+        }
+
+        let nodes = self.nodes.split_off(node_idx);
+        let index = self.index.split_off(node_idx);
+        let other = Self {
+            nodes,
+            index,
+            len: self.len - idx,
+        };
+        self.len = idx;
+        if self.nodes.is_empty() {
+            self.nodes.push(empty);
+            self.index.insert(0, 0);
+        }
+
+        other
+    }
+    /// Makes room for `additional` more nodes, and sets aside what indexing the next one takes,
+    /// reporting an allocation failure instead of aborting.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.nodes.try_reserve(additional)?;
+        self.index.try_reserve()
+    }
+    /// Makes room for `additional` more nodes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+    }
+    /// Moves the nodes of `other`, whose elements all go after the ones of this layout, to its
+    /// end, leaving `other` with `empty` as its sole node. The last node of this layout takes
+    /// the elements of the first node of `other` through `merge` if `fits` says it has room for
+    /// them.
+    ///
+    /// If an allocation fails, both layouts are left as they were.
+    pub fn try_append<F, M>(
+        &mut self,
+        other: &mut Self,
+        empty: N,
+        fits: F,
+        merge: M,
+    ) -> Result<(), TryReserveError>
+    where
+        F: FnOnce(&mut N, usize) -> bool,
+        M: FnOnce(&mut N, &mut N),
+    {
+        self.nodes.try_reserve(other.nodes.len())?;
+        let emptied = Self::try_new(empty)?;
+        let kept = if self.len == 0 { 0 } else { self.nodes.len() };
+        let first = other.node_len(0);
+        let merged = kept > 0 && fits(&mut self.nodes[kept - 1], first);
+        let lengths = self.index.iter().take(kept).chain(other.index.iter());
+        let index =
+            if merged {
+                L::try_from_iter(lengths.enumerate().filter_map(
+                    |(node_idx, len)| match node_idx {
+                        _ if node_idx + 1 == kept => Some(len + first),
+                        _ if node_idx == kept => None,
+                        _ => Some(len),
+                    },
+                ))?
+            } else {
+                L::try_from_iter(lengths)?
+            };
+
+        let mut other = std::mem::replace(other, emptied);
+        self.nodes.truncate(kept);
+        let mut nodes = other.nodes.drain(..);
+        if merged {
+            merge(self.nodes.last_mut().unwrap(), &mut nodes.next().unwrap());
+        }
+        self.nodes.extend(nodes);
+        self.index = index;
+        self.len += other.len;
+
+        Ok(())
+    }
+    /// Returns the nodes holding the positions from `start` to `end`, the latter being excluded,
+    /// along with where they start in the first node and end in the last one.
+    pub fn bounds(&self, start: usize, end: usize) -> (Range<usize>, usize, usize) {
+        if start >= end {
+            return (0..0, 0, 0);
+        }
+
+        let (front_node_idx, front_start) = self.locate_ith(start);
+        let (back_node_idx, back_end_idx) = self.locate_ith(end - 1);
+
+        (
+            front_node_idx..back_node_idx + 1,
+            front_start,
+            back_end_idx + 1,
+        )
+    }
+    /// Walks over the positions from `start` to `end`, the latter being excluded, going through
+    /// `whole` for the nodes that are covered entirely and through `part` for the ones at both
+    /// ends.
+    pub fn spine<'a, I, F>(
+        &'a self,
+        start: usize,
+        end: usize,
+        whole: fn(&'a N) -> I,
+        part: F,
+    ) -> Spine<Nodes<'a, N, I>>
+    where
+        I: DoubleEndedIterator,
+        F: Fn(&'a N, Range<usize>) -> I,
+    {
+        let (nodes, front_start, back_end) = self.bounds(start, end);
+        if nodes.is_empty() {
+            return Spine::new(self.nodes[..0].iter().map(whole), 0);
+        }
+
+        let front_end = self.node_len(nodes.start);
+        let (front, rest) = self.nodes[nodes].split_first().unwrap();
+        if rest.is_empty() {
+            return Spine {
+                front: Some(part(front, front_start..back_end)),
+                ..Spine::new(rest.iter().map(whole), end - start)
+            };
+        }
+
+        let (back, middle) = rest.split_last().unwrap();
+        Spine {
+            front: Some(part(front, front_start..front_end)),
+            back: Some(part(back, 0..back_end)),
+            ..Spine::new(middle.iter().map(whole), end - start)
+        }
+    }
+    /// Walks mutably over the positions from `start` to `end` like [`spine`](Self::spine) does.
+    pub fn spine_mut<'a, I, F>(
+        &'a mut self,
+        start: usize,
+        end: usize,
+        whole: fn(&'a mut N) -> I,
+        part: F,
+    ) -> Spine<NodesMut<'a, N, I>>
+    where
+        I: DoubleEndedIterator,
+        F: Fn(&'a mut N, Range<usize>) -> I,
+    {
+        let (nodes, front_start, back_end) = self.bounds(start, end);
+        if nodes.is_empty() {
+            return Spine::new(self.nodes[..0].iter_mut().map(whole), 0);
+        }
+
+        let front_end = self.node_len(nodes.start);
+        let (front, rest) = self.nodes[nodes].split_first_mut().unwrap();
+        if rest.is_empty() {
+            return Spine {
+                front: Some(part(front, front_start..back_end)),
+                ..Spine::new(rest.iter_mut().map(whole), end - start)
+            };
+        }
+
+        let (back, middle) = rest.split_last_mut().unwrap();
+        Spine {
+            front: Some(part(front, front_start..front_end)),
+            back: Some(part(back, 0..back_end)),
+            ..Spine::new(middle.iter_mut().map(whole), end - start)
+        }
+    }
+    pub fn into_nodes(self) -> Vec<N> {
+        self.nodes
+    }
+    /// Walks over every element, taking the nodes apart through `whole`.
+    pub fn into_spine<I>(self, whole: fn(N) -> I) -> Spine<IntoNodes<N, I>>
+    where
+        I: DoubleEndedIterator,
+    {
+        let len = self.len;
+
+        Spine::new(self.nodes.into_iter().map(whole), len)
+    }
+}
+
+impl<N: LayoutNode, L: NodeLengths> Layout<N, L> {
+    pub fn from_nodes(nodes: Vec<N>) -> Self {
+        Self::from_nodes_by(nodes, N::len)
+    }
+    /// Lets `insert` add a single element at `position` of the given node, splitting the node
+    /// first if it already holds `capacity` elements.
+    pub fn insert<R, F>(
+        &mut self,
+        node_idx: usize,
+        position: usize,
+        capacity: usize,
+        split_policy: SplitPolicy,
+        insert: F,
+    ) -> R
+    where
+        F: FnOnce(&mut N, usize) -> R,
+    {
+        let node_len = self.node_len(node_idx);
+        let full = node_len >= capacity && node_len > 1;
+        let (node_idx, position) =
+            self.make_room(node_idx, position, split_policy, full, N::split_off);
+
+        self.insert_with(node_idx, |node| insert(node, position))
+    }
+    /// Splits the given node in two, its elements from `at` on moving to a new node right after
+    /// it. `at` has to be within the node, neither part being empty.
+    pub fn split_node(&mut self, node_idx: usize, at: usize) {
+        self.split_node_by(node_idx, at, N::split_off);
+    }
+}
+
+// A layout is a node in its own right, which lets layouts be stacked
+impl<N: LayoutNode, L: NodeLengths> LayoutNode for Layout<N, L> {
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn split_off(&mut self, at: usize) -> Self {
+        let (mut node_idx, position) = self.locate_ith(at);
+        if position > 0 {
+            self.split_node(node_idx, position);
+            node_idx += 1;
+        }
+
+        let nodes = self.nodes.split_off(node_idx);
+        let index = self.index.split_off(node_idx);
+        let other = Self {
+            nodes,
+            len: self.len - at,
+            index,
+        };
+        self.len = at;
+
+        other
+    }
+}
+
+/// Walks over the elements of whole nodes, with partially consumed nodes at both ends.
+pub struct Spine<N>
+where
+    N: DoubleEndedIterator,
+    N::Item: DoubleEndedIterator,
+{
+    front: Option<N::Item>,
+    nodes: N,
+    back: Option<N::Item>,
+    len: usize,
+}
+
+impl<N> Spine<N>
+where
+    N: DoubleEndedIterator,
+    N::Item: DoubleEndedIterator,
+{
+    fn new(nodes: N, len: usize) -> Self {
+        Self {
+            front: None,
+            nodes,
+            back: None,
+            len,
+        }
+    }
+}
+
+impl<N> Iterator for Spine<N>
+where
+    N: DoubleEndedIterator,
+    N::Item: DoubleEndedIterator,
+{
+    type Item = <N::Item as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.front.as_mut().and_then(Iterator::next) {
+                self.len -= 1;
+                return Some(item);
+            }
+            match self.nodes.next() {
+                Some(entries) => self.front = Some(entries),
+                None => {
+                    let item = self.back.as_mut().and_then(Iterator::next)?;
+                    self.len -= 1;
+                    return Some(item);
+                }
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<N> DoubleEndedIterator for Spine<N>
+where
+    N: DoubleEndedIterator,
+    N::Item: DoubleEndedIterator,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.back.as_mut().and_then(DoubleEndedIterator::next_back) {
+                self.len -= 1;
+                return Some(item);
+            }
+            match self.nodes.next_back() {
+                Some(entries) => self.back = Some(entries),
+                None => {
+                    let item = self
+                        .front
+                        .as_mut()
+                        .and_then(DoubleEndedIterator::next_back)?;
+                    self.len -= 1;
+                    return Some(item);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,765 @@
+use std::collections::TryReserveError;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An index over the lengths of a run of nodes, telling which node a position falls within and
+/// how many elements come before a node.
+pub trait NodeLengths: FromIterator<usize> {
+    /// Like [`from_iter`](FromIterator::from_iter), but reports an allocation failure instead of
+    /// aborting.
+    fn try_from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Result<Self, TryReserveError>
+    where
+        Self: Sized;
+    /// Returns how many lengths there are.
+    fn len(&self) -> usize;
+    /// Returns the length at `idx`.
+    fn get(&self, idx: usize) -> usize;
+    /// Returns the sum of the first `idx` lengths.
+    fn prefix_sum(&self, idx: usize) -> usize;
+    /// Returns how many of the prefix sums that end with a length fall short of `sum`, which is
+    /// the slot that position `sum` falls within.
+    fn index_of(&self, sum: usize) -> usize;
+    /// Adds `diff` to the length at `idx`.
+    fn add_at(&mut self, idx: usize, diff: usize);
+    /// Subtracts `diff` from the length at `idx`.
+    fn sub_at(&mut self, idx: usize, diff: usize);
+    /// Inserts a length at `idx`, shifting the ones after it.
+    fn insert(&mut self, idx: usize, len: usize);
+    /// Removes the length at `idx`, shifting the ones after it, and returns it.
+    fn remove(&mut self, idx: usize) -> usize;
+    /// Keeps the first `at` lengths, returning the others.
+    fn split_off(&mut self, at: usize) -> Self
+    where
+        Self: Sized;
+    /// Sets aside what inserting a single length takes, so that it does not allocate, reporting
+    /// an allocation failure instead of aborting. Removing lengths never allocates.
+    fn try_reserve(&mut self) -> Result<(), TryReserveError>;
+    /// Returns every length, in order, without allocating.
+    fn iter(&self) -> impl Iterator<Item = usize> + '_;
+}
+
+/// The lengths of a run of nodes, next to a Fenwick tree over them.
+///
+/// Prefix sums and updates of a length take logarithmic time, and reading a length constant
+/// time, but inserting or removing a length rebuilds the tree. This suits layouts whose nodes are
+/// few enough for the rebuild to be cheaper than walking down a deeper structure on every query.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "Vec<usize>", into = "Vec<usize>")
+)]
+#[derive(Debug, Clone)]
+pub struct FenwickLengths {
+    lengths: Vec<usize>,
+    // Entry `idx` holds the sum of the lengths from `idx & (idx + 1)` to `idx`
+    tree: Vec<usize>,
+}
+
+impl FenwickLengths {
+    // Builds the tree in linear time within the storage it already has, which removals never
+    // outgrow
+    fn rebuild(&mut self) {
+        self.tree.clear();
+        self.tree.extend_from_slice(&self.lengths);
+        for idx in 0..self.tree.len() {
+            let parent = idx | (idx + 1);
+            if parent < self.tree.len() {
+                self.tree[parent] += self.tree[idx];
+            }
+        }
+    }
+}
+
+impl NodeLengths for FenwickLengths {
+    fn try_from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Result<Self, TryReserveError> {
+        let iter = iter.into_iter();
+        let mut lengths = Vec::new();
+        lengths.try_reserve_exact(iter.size_hint().0)?;
+        for len in iter {
+            lengths.try_reserve(1)?;
+            lengths.push(len);
+        }
+        let mut tree = Vec::new();
+        tree.try_reserve_exact(lengths.len())?;
+        let mut fenwick = Self { lengths, tree };
+        fenwick.rebuild();
+
+        Ok(fenwick)
+    }
+    fn len(&self) -> usize {
+        self.lengths.len()
+    }
+    fn get(&self, idx: usize) -> usize {
+        self.lengths[idx]
+    }
+    fn prefix_sum(&self, mut idx: usize) -> usize {
+        let mut sum = 0;
+        while idx > 0 {
+            sum += self.tree[idx - 1];
+            idx &= idx - 1;
+        }
+
+        sum
+    }
+    fn index_of(&self, mut sum: usize) -> usize {
+        // Walks down the implicit tree, skipping every range whose lengths add up to less
+        let mut idx = 0;
+        let mut step = (self.tree.len() + 1).next_power_of_two() / 2;
+        while step > 0 {
+            if idx + step <= self.tree.len() && self.tree[idx + step - 1] < sum {
+                idx += step;
+                sum -= self.tree[idx - 1];
+            }
+            step /= 2;
+        }
+
+        idx
+    }
+    fn add_at(&mut self, mut idx: usize, diff: usize) {
+        self.lengths[idx] += diff;
+        while idx < self.tree.len() {
+            self.tree[idx] += diff;
+            idx |= idx + 1;
+        }
+    }
+    fn sub_at(&mut self, mut idx: usize, diff: usize) {
+        self.lengths[idx] -= diff;
+        while idx < self.tree.len() {
+            self.tree[idx] -= diff;
+            idx |= idx + 1;
+        }
+    }
+    fn insert(&mut self, idx: usize, len: usize) {
+        self.lengths.insert(idx, len);
+        self.rebuild();
+    }
+    fn remove(&mut self, idx: usize) -> usize {
+        let len = self.lengths.remove(idx);
+        self.rebuild();
+
+        len
+    }
+    fn split_off(&mut self, at: usize) -> Self {
+        let other = Self::from(self.lengths.split_off(at));
+        self.rebuild();
+
+        other
+    }
+    fn try_reserve(&mut self) -> Result<(), TryReserveError> {
+        self.lengths.try_reserve(1)?;
+        self.tree.try_reserve(1)
+    }
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lengths.iter().copied()
+    }
+}
+
+impl FromIterator<usize> for FenwickLengths {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        Self::from(Vec::from_iter(iter))
+    }
+}
+
+impl From<Vec<usize>> for FenwickLengths {
+    fn from(lengths: Vec<usize>) -> Self {
+        let mut fenwick = Self {
+            tree: Vec::with_capacity(lengths.len()),
+            lengths,
+        };
+        fenwick.rebuild();
+
+        fenwick
+    }
+}
+
+impl From<FenwickLengths> for Vec<usize> {
+    fn from(lengths: FenwickLengths) -> Self {
+        lengths.lengths
+    }
+}
+
+// Widest a node of the tree gets before it is split, and narrowest it gets before it takes
+// entries from a sibling or is merged with it. Every node is allocated with room for one entry
+// past the widest, so that it never reallocates
+const MAX_FANOUT: usize = 32;
+const MIN_FANOUT: usize = MAX_FANOUT / 4;
+
+/// The lengths of a run of nodes, kept in a counted B+tree so that prefix sums, as well as
+/// inserting and removing lengths anywhere, take logarithmic time.
+///
+/// Queries walk down a few levels of nodes, which makes them slower than the ones of
+/// [`FenwickLengths`], so this is only worth it for layouts with so many nodes that rebuilding a
+/// Fenwick tree on every split would dominate.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "Vec<usize>", into = "Vec<usize>")
+)]
+#[derive(Debug, Clone)]
+pub struct Lengths {
+    root: Node,
+    spare: Spare,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf(Vec<usize>),
+    Inner(Vec<Child>),
+}
+
+// A subtree, along with how many lengths it holds and what they add up to
+#[derive(Debug, Clone)]
+struct Child {
+    slots: usize,
+    sum: usize,
+    node: Node,
+}
+
+// Node storage set aside by `try_reserve`, which splits take before allocating
+#[derive(Debug, Clone, Default)]
+struct Spare {
+    leaves: Vec<Vec<usize>>,
+    inners: Vec<Vec<Child>>,
+}
+
+impl Spare {
+    fn leaf(&mut self) -> Vec<usize> {
+        self.leaves
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(MAX_FANOUT + 1))
+    }
+    fn inner(&mut self) -> Vec<Child> {
+        self.inners
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(MAX_FANOUT + 1))
+    }
+}
+
+impl Child {
+    fn new(node: Node) -> Self {
+        let (slots, sum) = node.measure();
+
+        Self { slots, sum, node }
+    }
+}
+
+impl Node {
+    fn measure(&self) -> (usize, usize) {
+        match self {
+            Node::Leaf(lengths) => (lengths.len(), lengths.iter().sum()),
+            Node::Inner(children) => children.iter().fold((0, 0), |(slots, sum), child| {
+                (slots + child.slots, sum + child.sum)
+            }),
+        }
+    }
+    fn fanout(&self) -> usize {
+        match self {
+            Node::Leaf(lengths) => lengths.len(),
+            Node::Inner(children) => children.len(),
+        }
+    }
+    fn split_off(&mut self, at: usize, spare: &mut Spare) -> Self {
+        match self {
+            Node::Leaf(lengths) => {
+                let mut upper = spare.leaf();
+                upper.extend(lengths.drain(at..));
+                Node::Leaf(upper)
+            }
+            Node::Inner(children) => {
+                let mut upper = spare.inner();
+                upper.extend(children.drain(at..));
+                Node::Inner(upper)
+            }
+        }
+    }
+    // Returns the upper half of the node when it grows too wide
+    fn insert(&mut self, idx: usize, len: usize, spare: &mut Spare) -> Option<Child> {
+        match self {
+            Node::Leaf(lengths) => lengths.insert(idx, len),
+            Node::Inner(children) => {
+                let (child_idx, idx) = locate_slot(children, idx, true);
+                let child = &mut children[child_idx];
+                child.slots += 1;
+                child.sum += len;
+                if let Some(upper) = child.node.insert(idx, len, spare) {
+                    child.slots -= upper.slots;
+                    child.sum -= upper.sum;
+                    children.insert(child_idx + 1, upper);
+                }
+            }
+        }
+        if self.fanout() <= MAX_FANOUT {
+            return None;
+        }
+
+        let at = self.fanout() / 2;
+
+        Some(Child::new(self.split_off(at, spare)))
+    }
+    // Returns the length that was removed. The node may be left narrower than it should be, for
+    // its parent to even it out with a sibling
+    fn remove(&mut self, idx: usize) -> usize {
+        let children = match self {
+            Node::Leaf(lengths) => return lengths.remove(idx),
+            Node::Inner(children) => children,
+        };
+
+        let (child_idx, idx) = locate_slot(children, idx, false);
+        let child = &mut children[child_idx];
+        let len = child.node.remove(idx);
+        child.slots -= 1;
+        child.sum -= len;
+        if child.node.fanout() < MIN_FANOUT && children.len() > 1 {
+            let left_idx = child_idx.min(children.len() - 2);
+            let (left, right) = children.split_at_mut(left_idx + 1);
+            let (left, right) = (&mut left[left_idx].node, &mut right[0].node);
+            if left.fanout() + right.fanout() <= MAX_FANOUT {
+                match (left, right) {
+                    (Node::Leaf(left), Node::Leaf(right)) => left.append(right),
+                    (Node::Inner(left), Node::Inner(right)) => left.append(right),
+                    _ => unreachable!("all leaves are at the same depth"),
+                }
+                children.remove(left_idx + 1);
+            } else {
+                match (left, right) {
+                    (Node::Leaf(left), Node::Leaf(right)) => even_out(left, right),
+                    (Node::Inner(left), Node::Inner(right)) => even_out(left, right),
+                    _ => unreachable!("all leaves are at the same depth"),
+                }
+                let (slots, sum) = children[left_idx + 1].node.measure();
+                children[left_idx + 1].slots = slots;
+                children[left_idx + 1].sum = sum;
+            }
+            let (slots, sum) = children[left_idx].node.measure();
+            children[left_idx].slots = slots;
+            children[left_idx].sum = sum;
+        }
+
+        len
+    }
+    fn depth(&self) -> usize {
+        match self {
+            Node::Leaf(_) => 0,
+            Node::Inner(children) => 1 + children[0].node.depth(),
+        }
+    }
+}
+
+// Moves entries from the wider of two siblings to the narrower one, until both are as wide
+fn even_out<E>(left: &mut Vec<E>, right: &mut Vec<E>) {
+    let half = (left.len() + right.len()) / 2;
+    if left.len() > half {
+        right.splice(0..0, left.drain(half..));
+    } else {
+        let moved = right.len() - half;
+        left.extend(right.drain(..moved));
+    }
+}
+
+// Returns the child holding slot `idx` and where the slot is within it. Past the last slot is
+// within the last child when `inserting`
+fn locate_slot(children: &[Child], mut idx: usize, inserting: bool) -> (usize, usize) {
+    for (child_idx, child) in children.iter().enumerate() {
+        if idx < child.slots || (inserting && idx == child.slots) {
+            return (child_idx, idx);
+        }
+        idx -= child.slots;
+    }
+
+    unreachable!("slot out of bounds")
+}
+
+impl Lengths {
+    // Walks down to the length at `idx`, letting `update` change the sums along the way
+    fn get_mut<F>(&mut self, mut idx: usize, update: F) -> &mut usize
+    where
+        F: Fn(&mut usize),
+    {
+        let mut node = &mut self.root;
+        loop {
+            match node {
+                Node::Leaf(lengths) => return &mut lengths[idx],
+                Node::Inner(children) => {
+                    let (child_idx, child_position) = locate_slot(children, idx, false);
+                    let child = &mut children[child_idx];
+                    update(&mut child.sum);
+                    idx = child_position;
+                    node = &mut child.node;
+                }
+            }
+        }
+    }
+    // Returns the lengths from `idx` to the end of the leaf holding it
+    fn leaf_from(&self, mut idx: usize) -> &[usize] {
+        let mut node = &self.root;
+        loop {
+            match node {
+                Node::Leaf(lengths) => return &lengths[idx..],
+                Node::Inner(children) => {
+                    let (child_idx, child_position) = locate_slot(children, idx, false);
+                    idx = child_position;
+                    node = &children[child_idx].node;
+                }
+            }
+        }
+    }
+    // Builds the tree level by level. Allocation failures are only reported when `fallible` is
+    // set
+    fn build<I>(iter: I, fallible: bool) -> Result<Self, TryReserveError>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        // Nodes are built halfway between their narrowest and widest, so that they take a few
+        // insertions or removals before being split or merged
+        const FANOUT: usize = (MIN_FANOUT + MAX_FANOUT) / 2;
+
+        fn allocate<E>(fallible: bool) -> Result<Vec<E>, TryReserveError> {
+            if !fallible {
+                return Ok(Vec::with_capacity(MAX_FANOUT + 1));
+            }
+
+            let mut node = Vec::new();
+            node.try_reserve_exact(MAX_FANOUT + 1)?;
+
+            Ok(node)
+        }
+
+        let mut lengths = iter.into_iter().peekable();
+        let mut level: Vec<Child> = Vec::new();
+        while lengths.peek().is_some() || level.is_empty() {
+            let mut leaf = allocate(fallible)?;
+            leaf.extend(lengths.by_ref().take(FANOUT));
+            if fallible {
+                level.try_reserve(1)?;
+            }
+            level.push(Child::new(Node::Leaf(leaf)));
+        }
+        while level.len() > 1 {
+            let mut children = level.into_iter().peekable();
+            let mut parents: Vec<Child> = Vec::new();
+            while children.peek().is_some() {
+                let mut inner = allocate(fallible)?;
+                inner.extend(children.by_ref().take(FANOUT));
+                if fallible {
+                    parents.try_reserve(1)?;
+                }
+                parents.push(Child::new(Node::Inner(inner)));
+            }
+            level = parents;
+        }
+
+        Ok(Self {
+            root: level.pop().unwrap().node,
+            spare: Spare::default(),
+        })
+    }
+}
+
+impl NodeLengths for Lengths {
+    fn try_from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Result<Self, TryReserveError> {
+        Self::build(iter, true)
+    }
+    fn len(&self) -> usize {
+        self.root.measure().0
+    }
+    fn get(&self, mut idx: usize) -> usize {
+        let mut node = &self.root;
+        loop {
+            match node {
+                Node::Leaf(lengths) => return lengths[idx],
+                Node::Inner(children) => {
+                    let (child_idx, child_position) = locate_slot(children, idx, false);
+                    idx = child_position;
+                    node = &children[child_idx].node;
+                }
+            }
+        }
+    }
+    fn prefix_sum(&self, mut idx: usize) -> usize {
+        let mut sum = 0;
+        let mut node = &self.root;
+        loop {
+            match node {
+                Node::Leaf(lengths) => return sum + lengths[..idx].iter().sum::<usize>(),
+                Node::Inner(children) => {
+                    let Some(child) = children.iter().find(|child| {
+                        if idx < child.slots {
+                            return true;
+                        }
+                        idx -= child.slots;
+                        sum += child.sum;
+                        false
+                    }) else {
+                        assert_eq!(idx, 0, "prefix past the last length");
+                        return sum;
+                    };
+                    node = &child.node;
+                }
+            }
+        }
+    }
+    fn index_of(&self, mut sum: usize) -> usize {
+        let mut idx = 0;
+        let mut node = &self.root;
+        loop {
+            match node {
+                Node::Leaf(lengths) => {
+                    return idx
+                        + lengths
+                            .iter()
+                            .take_while(|len| {
+                                let short = **len < sum;
+                                if short {
+                                    sum -= **len;
+                                }
+                                short
+                            })
+                            .count();
+                }
+                Node::Inner(children) => {
+                    let Some(child) = children.iter().find(|child| {
+                        if child.sum < sum {
+                            sum -= child.sum;
+                            idx += child.slots;
+                            return false;
+                        }
+                        true
+                    }) else {
+                        return idx;
+                    };
+                    node = &child.node;
+                }
+            }
+        }
+    }
+    fn add_at(&mut self, idx: usize, diff: usize) {
+        *self.get_mut(idx, |sum| *sum += diff) += diff;
+    }
+    fn sub_at(&mut self, idx: usize, diff: usize) {
+        *self.get_mut(idx, |sum| *sum -= diff) -= diff;
+    }
+    fn insert(&mut self, idx: usize, len: usize) {
+        if let Some(upper) = self.root.insert(idx, len, &mut self.spare) {
+            let lower = std::mem::replace(&mut self.root, Node::Leaf(Vec::new()));
+            let mut children = self.spare.inner();
+            children.extend([Child::new(lower), upper]);
+            self.root = Node::Inner(children);
+        }
+    }
+    fn try_reserve(&mut self) -> Result<(), TryReserveError> {
+        // A leaf split may spread up to every inner node, and then to a new root
+        let inners = self.root.depth() + 1;
+        while self.spare.leaves.is_empty() {
+            let mut leaf = Vec::new();
+            leaf.try_reserve_exact(MAX_FANOUT + 1)?;
+            self.spare.leaves.try_reserve(1)?;
+            self.spare.leaves.push(leaf);
+        }
+        while self.spare.inners.len() < inners {
+            let mut inner = Vec::new();
+            inner.try_reserve_exact(MAX_FANOUT + 1)?;
+            self.spare.inners.try_reserve(1)?;
+            self.spare.inners.push(inner);
+        }
+
+        Ok(())
+    }
+    fn remove(&mut self, idx: usize) -> usize {
+        let len = self.root.remove(idx);
+        if let Node::Inner(children) = &mut self.root {
+            if children.len() == 1 {
+                self.root = children.pop().unwrap().node;
+            }
+        }
+
+        len
+    }
+    fn split_off(&mut self, at: usize) -> Self {
+        let mut lower = Self::from_iter(self.iter().take(at));
+        let upper = Self::from_iter(self.iter().skip(at));
+        lower.spare = std::mem::take(&mut self.spare);
+        *self = lower;
+
+        upper
+    }
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        // Lengths are read a leaf at a time
+        let len = self.len();
+        let mut next = 0;
+        let mut leaf: &[usize] = &[];
+        std::iter::from_fn(move || {
+            if leaf.is_empty() && next < len {
+                leaf = self.leaf_from(next);
+            }
+            let (first, rest) = leaf.split_first()?;
+            leaf = rest;
+            next += 1;
+
+            Some(*first)
+        })
+    }
+}
+
+impl FromIterator<usize> for Lengths {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        match Self::build(iter, false) {
+            Ok(lengths) => lengths,
+            Err(_) => unreachable!("infallible reservations abort on allocation failures"),
+        }
+    }
+}
+
+impl From<Vec<usize>> for Lengths {
+    fn from(lengths: Vec<usize>) -> Self {
+        Self::from_iter(lengths)
+    }
+}
+
+impl From<Lengths> for Vec<usize> {
+    fn from(lengths: Lengths) -> Self {
+        lengths.iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ftree::FenwickTree;
+    use rand::{Rng, SeedableRng};
+
+    fn assert_matches<L: NodeLengths>(lengths: &L, model: &[usize]) {
+        let fenwick = FenwickTree::from_iter(model.iter().copied());
+        assert_eq!(lengths.len(), model.len());
+        assert!(lengths.iter().eq(model.iter().copied()));
+        for (idx, len) in model.iter().enumerate() {
+            assert_eq!(lengths.get(idx), *len);
+        }
+        for idx in 0..=model.len() {
+            assert_eq!(lengths.prefix_sum(idx), fenwick.prefix_sum(idx, 0));
+        }
+        let total: usize = model.iter().sum();
+        for sum in 0..=total + 1 {
+            assert_eq!(lengths.index_of(sum), fenwick.index_of(sum));
+        }
+    }
+
+    fn check_against_model<L: NodeLengths>() {
+        let mut rng = rand::rngs::StdRng::from_seed([53; 32]);
+        let mut model: Vec<usize> = (0..100).map(|_| rng.gen_range(1..8)).collect();
+        let mut lengths = L::from_iter(model.iter().copied());
+        assert_matches(&lengths, &model);
+
+        for round in 0..4000 {
+            match rng.gen_range(0..4) {
+                0 | 1 if model.len() < 3000 || round % 2 == 0 => {
+                    let idx = rng.gen_range(0..=model.len());
+                    let len = rng.gen_range(1..8);
+                    model.insert(idx, len);
+                    lengths.insert(idx, len);
+                }
+                2 if !model.is_empty() => {
+                    let idx = rng.gen_range(0..model.len());
+                    assert_eq!(lengths.remove(idx), model.remove(idx));
+                }
+                3 if round % 500 == 0 => {
+                    let at = model.len() / 2;
+                    let upper = lengths.split_off(at);
+                    assert_matches(&upper, &model.split_off(at));
+                }
+                _ if !model.is_empty() => {
+                    let idx = rng.gen_range(0..model.len());
+                    if rng.gen_bool(0.5) {
+                        model[idx] += 2;
+                        lengths.add_at(idx, 2);
+                    } else if model[idx] > 1 {
+                        model[idx] -= 1;
+                        lengths.sub_at(idx, 1);
+                    }
+                }
+                _ => {}
+            }
+            if round % 100 == 0 {
+                assert_matches(&lengths, &model);
+            }
+        }
+        assert_matches(&lengths, &model);
+
+        while !model.is_empty() {
+            let idx = rng.gen_range(0..model.len());
+            assert_eq!(lengths.remove(idx), model.remove(idx));
+        }
+        assert_matches(&lengths, &model);
+    }
+
+    #[test]
+    fn test_against_model() {
+        check_against_model::<Lengths>();
+        check_against_model::<FenwickLengths>();
+    }
+
+    #[test]
+    fn test_from_iter_builds_balanced_levels() {
+        for n in [0, 1, MAX_FANOUT, MAX_FANOUT + 1, 5000] {
+            let model: Vec<usize> = (0..n).map(|idx| idx % 5 + 1).collect();
+            let mut lengths = Lengths::from_iter(model.iter().copied());
+            assert_matches(&lengths, &model);
+
+            lengths.insert(n / 2, 0);
+            let mut model = model;
+            model.insert(n / 2, 0);
+            assert_matches(&lengths, &model);
+        }
+    }
+
+    fn count_nodes(node: &Node) -> usize {
+        match node {
+            Node::Leaf(_) => 1,
+            Node::Inner(children) => {
+                1 + children
+                    .iter()
+                    .map(|child| count_nodes(&child.node))
+                    .sum::<usize>()
+            }
+        }
+    }
+
+    fn assert_capacities(node: &Node) {
+        match node {
+            Node::Leaf(lengths) => assert_eq!(lengths.capacity(), MAX_FANOUT + 1),
+            Node::Inner(children) => {
+                assert_eq!(children.capacity(), MAX_FANOUT + 1);
+                children
+                    .iter()
+                    .for_each(|child| assert_capacities(&child.node));
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserved_insertions_do_not_allocate() {
+        let mut rng = rand::rngs::StdRng::from_seed([59; 32]);
+        let mut model = Vec::new();
+        let mut lengths = Lengths::from_iter(model.iter().copied());
+        for _ in 0..5000 {
+            lengths.try_reserve().unwrap();
+            let spare = lengths.spare.leaves.len() + lengths.spare.inners.len();
+            let nodes = count_nodes(&lengths.root);
+            let idx = rng.gen_range(0..=model.len());
+            model.insert(idx, 1);
+            lengths.insert(idx, 1);
+            // Every node the insertion split into came out of the reservation
+            let taken = spare - lengths.spare.leaves.len() - lengths.spare.inners.len();
+            assert_eq!(count_nodes(&lengths.root) - nodes, taken);
+        }
+        assert_matches(&lengths, &model);
+        assert_capacities(&lengths.root);
+
+        while model.len() > 10 {
+            let idx = rng.gen_range(0..model.len());
+            assert_eq!(lengths.remove(idx), model.remove(idx));
+        }
+        assert_matches(&lengths, &model);
+        assert_capacities(&lengths.root);
+    }
+}
@@ -3,6 +3,7 @@ use std::{borrow::Borrow, mem::MaybeUninit};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
+use crate::tuple::PrefixLookup;
 use fastrand;
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Hash)]
@@ -66,6 +67,28 @@ impl<K: Ord, V: PartialEq> Borrow<K> for MultiPair<K, V> {
     }
 }
 
+impl<K: Ord + Borrow<str>, V: PartialEq> Borrow<str> for MultiPair<K, V> {
+    fn borrow(&self) -> &str {
+        self.key.borrow()
+    }
+}
+
+impl<K: Ord + Borrow<[u8]>, V: PartialEq> Borrow<[u8]> for MultiPair<K, V> {
+    fn borrow(&self) -> &[u8] {
+        self.key.borrow()
+    }
+}
+
+impl<K, V, P> Borrow<dyn PrefixLookup<K, P>> for MultiPair<K, V>
+where
+    K: Ord + Borrow<dyn PrefixLookup<K, P>>,
+    V: PartialEq,
+{
+    fn borrow(&self) -> &(dyn PrefixLookup<K, P> + 'static) {
+        self.key.borrow()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1,37 +1,248 @@
 use core::borrow::Borrow;
 use core::cmp::Ordering;
+use std::collections::TryReserveError;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The storage of a single node of a [`BTreeSet`](crate::BTreeSet).
+///
+/// A node holds a sorted run of distinct elements, and the tree only ever reaches into its nodes
+/// through this trait. `Vec<T>` is the default node type, and other layouts, such as inline
+/// fixed-capacity arrays or nodes carrying extra per-node metadata, can be plugged into a tree by
+/// implementing it and passing the type as the second parameter of [`BTreeSet`](crate::BTreeSet).
+///
+/// Nodes hand out references to their elements, so they have to hold them as they are. Layouts
+/// that rebuild elements on the fly, such as prefix-compressed strings or bit-packed integers,
+/// cannot implement this trait. The collections of [`compressed`](crate::compressed) hold such
+/// nodes, and only differ from a tree over this trait in how they reach into them: the way nodes
+/// are laid out, split and indexed is the same for both.
+///
+/// Positions are always relative to the node, and implementations may assume that the tree never
+/// inserts an element that would break the ordering of the node, nor asks for a position that is
+/// out of bounds, unless stated otherwise.
 pub trait NodeLike<T: Ord> {
-    #[allow(dead_code)]
+    /// Iterator over the elements of the node, in ascending order.
+    type Iter<'a>: DoubleEndedIterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a;
+    /// Iterator moving every element out of the node, in ascending order.
+    type Drain<'a>: DoubleEndedIterator<Item = T>
+    where
+        Self: 'a;
+
+    /// Makes a new, empty node sized after the maximum node size of the tree.
+    fn with_capacity(capacity: usize) -> Self
+    where
+        Self: Sized;
+    /// Returns a reference to the element at the given position, if any.
     fn get_ith(&self, index: usize) -> Option<&T>;
-    #[allow(dead_code)]
-    fn halve(&mut self) -> Self;
-    #[allow(dead_code)]
+    /// Returns a mutable reference to the element at the given position, if any.
+    fn get_ith_mut(&mut self, index: usize) -> Option<&mut T>;
+    /// Splits the node in two, keeping the lower half and returning the upper one.
+    fn halve(&mut self) -> Self
+    where
+        Self: Sized,
+    {
+        self.split_off(self.len() / 2)
+    }
+    /// Splits the node at the given position, returning every element from `at` onwards.
+    fn split_off(&mut self, at: usize) -> Self
+    where
+        Self: Sized;
+    /// Like [`split_off`](NodeLike::split_off), but moves the elements into `other`, an empty node
+    /// set aside beforehand, so that splitting does not have to allocate a new one.
+    fn split_off_into(&mut self, at: usize, other: &mut Self)
+    where
+        Self: Sized,
+    {
+        *other = self.split_off(at);
+    }
+    /// Like [`split_off`](NodeLike::split_off), but reports an allocation failure instead of
+    /// aborting, in which case the node is left untouched.
+    ///
+    /// Merging the returned elements back into the node must not allocate, since the tree does so
+    /// to undo a split when a later step of a fallible insertion fails.
+    fn try_split_off(&mut self, at: usize) -> Result<Self, TryReserveError>
+    where
+        Self: Sized,
+    {
+        Ok(self.split_off(at))
+    }
+    /// Moves every element of `other`, which must all be greater than the ones of the node, to
+    /// its end, leaving `other` empty.
+    fn merge(&mut self, other: &mut Self);
+    /// Removes every element of the node, returning them in ascending order.
+    fn drain(&mut self) -> Self::Drain<'_>;
+    /// Returns an iterator over the elements of the node.
+    fn iter(&self) -> Self::Iter<'_>;
+    /// Returns the number of elements in the node.
     fn len(&self) -> usize;
-    #[allow(dead_code)]
+    /// Returns `true` if the node holds no elements.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns `true` if the node must be split before taking another element, given the maximum
+    /// node size that the tree was made with. Nodes with a fixed capacity may ignore it.
+    fn is_full(&self, capacity: usize) -> bool;
+    /// Tries to reserve room for at least `additional` more elements, so that inserting them does
+    /// not allocate. Nodes with a fixed capacity can rely on the default, which does nothing.
+    fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let _ = additional;
+
+        Ok(())
+    }
+    /// Inserts a value unless an equal one is already present. Returns whether the value was
+    /// inserted, along with its position.
     fn insert(&mut self, value: T) -> (bool, usize);
-    #[allow(dead_code)]
+    /// Inserts a value at the given position, which the tree has already checked is where it
+    /// belongs and does not hold an equal element. The default searches for the position again.
+    fn insert_at(&mut self, position: usize, value: T) {
+        let _ = position;
+        self.insert(value);
+    }
+    /// Removes and returns the element at the given position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn remove_ith(&mut self, index: usize) -> T;
+    /// Returns the number of leading elements for which the predicate holds, assuming that it
+    /// holds for a prefix of the node and for nothing after it.
+    fn partition_point<P>(&self, pred: P) -> usize
+    where
+        P: FnMut(&T) -> bool;
+    /// Returns the number of elements that are less than the value, which is where it would be
+    /// inserted.
+    fn lower_bound<Q: Ord + ?Sized>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+    {
+        self.partition_point(|item| item.borrow() < value)
+    }
+    /// Returns `true` if the node contains an element equal to the value.
     fn contains<Q: Ord + ?Sized>(&self, value: &Q) -> bool
     where
         T: Borrow<Q>;
-    #[allow(dead_code)]
+    /// Returns the position of the element equal to the value, if any.
     fn try_select<Q: Ord + ?Sized>(&self, value: &Q) -> Option<usize>
     where
         T: Borrow<Q>;
-    #[allow(dead_code)]
+    /// Returns how many elements lie beyond the bound, counting from the start of the node or
+    /// from its end, if any.
     fn rank<Q: Ord + ?Sized>(&self, bound: std::ops::Bound<&Q>, from_start: bool) -> Option<usize>
     where
         T: Borrow<Q>;
-    #[allow(dead_code)]
+    /// Removes the element equal to the value, if any, returning it along with its position.
     fn delete<Q: Ord + ?Sized>(&mut self, value: &Q) -> Option<(T, usize)>
     where
         T: Borrow<Q>;
-    #[allow(dead_code)]
+    /// Replaces the element at the given position, returning the previous one, if any.
     fn replace(&mut self, idx: usize, value: T) -> Option<T>;
-    #[allow(dead_code)]
+    /// Returns a reference to the greatest element of the node, if any.
     fn max(&self) -> Option<&T>;
 }
 
+/// Where a tree splits a full node that an element has to go into.
+///
+/// Even splits suit keys arriving in any order. When keys mostly arrive in ascending order, such
+/// as timestamps or autoincremented ids, even splits leave every node behind the tail half full,
+/// and [`SplitPolicy::RightBiased`] keeps them full instead.
+///
+/// Trees keep their policy through serialization, except for custom ones, whose functions cannot
+/// be serialized and which come back as even splits.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::{BTreeSet, SplitPolicy};
+///
+/// let mut set = BTreeSet::new();
+/// set.set_split_policy(SplitPolicy::RightBiased);
+/// for timestamp in 0..10_000 {
+///     set.insert(timestamp);
+/// }
+///
+/// // Every node but the last one is full
+/// let chunks: Vec<_> = set.chunks().map(|chunk| chunk.len()).collect();
+/// assert!(chunks[..chunks.len() - 1].iter().all(|len| *len == chunks[0]));
+/// ```
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "SerializedSplitPolicy", into = "SerializedSplitPolicy")
+)]
+#[derive(Debug, Clone, Copy, Default)]
+pub enum SplitPolicy {
+    /// Splits full nodes in two halves.
+    #[default]
+    Even,
+    /// Leaves a full node as it is when the element goes past its greatest one, starting a new
+    /// node with the element alone, and splits it in two halves otherwise.
+    RightBiased,
+    /// Asks a function, given the length of the full node and the position that the element
+    /// takes in it, how many elements stay in the node.
+    Custom(fn(usize, usize) -> usize),
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "SplitPolicy")]
+enum SerializedSplitPolicy {
+    Even,
+    RightBiased,
+}
+
+#[cfg(feature = "serde")]
+impl From<SplitPolicy> for SerializedSplitPolicy {
+    fn from(split_policy: SplitPolicy) -> Self {
+        match split_policy {
+            SplitPolicy::RightBiased => SerializedSplitPolicy::RightBiased,
+            SplitPolicy::Even | SplitPolicy::Custom(_) => SerializedSplitPolicy::Even,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<SerializedSplitPolicy> for SplitPolicy {
+    fn from(split_policy: SerializedSplitPolicy) -> Self {
+        match split_policy {
+            SerializedSplitPolicy::Even => SplitPolicy::Even,
+            SerializedSplitPolicy::RightBiased => SplitPolicy::RightBiased,
+        }
+    }
+}
+
+impl SplitPolicy {
+    /// Returns how many elements of a full node of length `len` stay in it when an element is
+    /// inserted at `position`, the rest going into a new node after it.
+    ///
+    /// The result is kept within bounds that leave neither node empty once the element is in:
+    /// between `1` and `len - 1`, or up to `len` when the element goes past every other one.
+    pub fn split_point(&self, len: usize, position: usize) -> usize {
+        let at = match *self {
+            SplitPolicy::Even => len / 2,
+            SplitPolicy::RightBiased if position >= len => len,
+            SplitPolicy::RightBiased => len / 2,
+            SplitPolicy::Custom(policy) => policy(len, position),
+        };
+        let upper_bound = if position >= len { len } else { len - 1 };
+
+        at.clamp(1, upper_bound.max(1))
+    }
+    // Elements that a node made by a split can be counted on to end up with, which is what
+    // reservations are sized after: right-biased splits fill their nodes up when keys come in
+    // ascending order, and custom policies are taken to split evenly
+    pub(crate) fn node_fill(&self, node_capacity: usize) -> usize {
+        match *self {
+            SplitPolicy::RightBiased => node_capacity,
+            SplitPolicy::Even | SplitPolicy::Custom(_) => node_capacity / 2,
+        }
+        .max(1)
+    }
+}
+
 #[inline]
 fn search<Q, T: Ord>(haystack: &[T], needle: &Q) -> Result<usize, usize>
 where
@@ -122,19 +333,73 @@ where
 }
 
 impl<T: Ord> NodeLike<T> for Vec<T> {
+    type Iter<'a>
+        = std::slice::Iter<'a, T>
+    where
+        T: 'a;
+    type Drain<'a>
+        = std::vec::Drain<'a, T>
+    where
+        T: 'a;
+
+    #[inline]
+    fn with_capacity(capacity: usize) -> Self {
+        Vec::with_capacity(capacity)
+    }
     #[inline]
     fn get_ith(&self, index: usize) -> Option<&T> {
         self.get(index)
     }
     #[inline]
+    fn get_ith_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.get_mut(index)
+    }
+    #[inline]
     fn halve(&mut self) -> Self {
-        self.split_off(self.capacity() / 2)
+        Vec::split_off(self, self.capacity() / 2)
+    }
+    #[inline]
+    fn split_off(&mut self, at: usize) -> Self {
+        Vec::split_off(self, at)
+    }
+    #[inline]
+    fn split_off_into(&mut self, at: usize, other: &mut Self) {
+        other.extend(Vec::drain(self, at..));
+    }
+    #[inline]
+    fn try_split_off(&mut self, at: usize) -> Result<Self, TryReserveError> {
+        let mut upper = Vec::new();
+        upper.try_reserve_exact(self.len() - at)?;
+        upper.extend(Vec::drain(self, at..));
+
+        Ok(upper)
+    }
+    #[inline]
+    fn merge(&mut self, other: &mut Self) {
+        self.append(other)
+    }
+    #[inline]
+    fn drain(&mut self) -> Self::Drain<'_> {
+        Vec::drain(self, ..)
+    }
+    #[inline]
+    fn iter(&self) -> Self::Iter<'_> {
+        self.as_slice().iter()
     }
     #[inline]
     fn len(&self) -> usize {
         self.len()
     }
     #[inline]
+    fn is_full(&self, capacity: usize) -> bool {
+        // A single element is never split, as that would leave one of the halves empty
+        self.len() >= capacity && self.len() > 1
+    }
+    #[inline]
+    fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        Vec::try_reserve(self, additional)
+    }
+    #[inline]
     fn insert(&mut self, value: T) -> (bool, usize) {
         match search(&self, &value) {
             Ok(idx) => (false, idx),
@@ -145,6 +410,21 @@ impl<T: Ord> NodeLike<T> for Vec<T> {
         }
     }
     #[inline]
+    fn insert_at(&mut self, position: usize, value: T) {
+        Vec::insert(self, position, value)
+    }
+    #[inline]
+    fn remove_ith(&mut self, index: usize) -> T {
+        self.remove(index)
+    }
+    #[inline]
+    fn partition_point<P>(&self, pred: P) -> usize
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.as_slice().partition_point(pred)
+    }
+    #[inline]
     fn contains<Q>(&self, value: &Q) -> bool
     where
         T: Borrow<Q> + Ord,
@@ -3,6 +3,11 @@ use core::cmp::Ordering;
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 
+use crate::tuple::PrefixLookup;
+
+/// A key-value pair, as stored in the nodes of a [`BTreeMap`](crate::BTreeMap).
+///
+/// Pairs are compared with one another by key only.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Hash)]
 pub struct Pair<K, V>
@@ -47,3 +52,26 @@ impl<K: Ord, V> Borrow<K> for Pair<K, V> {
         &self.key
     }
 }
+
+// Lets string and byte keyed pairs be looked up by `str` and `[u8]` prefixes
+impl<K: Ord + Borrow<str>, V> Borrow<str> for Pair<K, V> {
+    fn borrow(&self) -> &str {
+        self.key.borrow()
+    }
+}
+
+impl<K: Ord + Borrow<[u8]>, V> Borrow<[u8]> for Pair<K, V> {
+    fn borrow(&self) -> &[u8] {
+        self.key.borrow()
+    }
+}
+
+// Lets tuple keyed pairs be looked up by `tuple::Bounded` prefixes
+impl<K, V, P> Borrow<dyn PrefixLookup<K, P>> for Pair<K, V>
+where
+    K: Ord + Borrow<dyn PrefixLookup<K, P>>,
+{
+    fn borrow(&self) -> &(dyn PrefixLookup<K, P> + 'static) {
+        self.key.borrow()
+    }
+}
@@ -0,0 +1,11 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A value tagged with the order in which it was inserted, so that equal values can coexist in
+/// the same tree while still being ordered by insertion.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Sequenced<T> {
+    pub value: T,
+    pub sequence: u64,
+}
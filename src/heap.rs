@@ -0,0 +1,491 @@
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{BTreeMap, BTreeSet};
+
+/// A double-ended priority queue whose entries are addressed by id, made of a [`BTreeSet`] of
+/// `(priority, id)` pairs and a [`BTreeMap`] from ids to priorities that are kept consistent with
+/// each other.
+///
+/// Each id is queued at most once. Its priority can be changed or the id removed at any time, and
+/// since entries are kept sorted, the one with the `k`-th smallest priority and the rank of an id
+/// are found in logarithmic time. Entries of equal priority are ordered by id.
+///
+/// Since each id and priority is stored on both sides, queueing requires both `Id` and `P` to be
+/// [`Clone`].
+///
+/// # Examples
+///
+/// ```
+/// use indexset::heap::IndexedPriorityQueue;
+///
+/// let mut tasks = IndexedPriorityQueue::new();
+/// tasks.push("compile", 3);
+/// tasks.push("test", 5);
+/// tasks.push("lint", 1);
+///
+/// // Bumping a task reorders it instead of queueing it twice
+/// assert_eq!(tasks.change_priority("test", 0), Some(5));
+/// assert_eq!(tasks.rank_of("test"), Some(0));
+/// assert_eq!(tasks.peek_kth(1), Some((&"lint", &1)));
+///
+/// assert_eq!(tasks.pop_min(), Some(("test", 0)));
+/// assert_eq!(tasks.pop_max(), Some(("compile", 3)));
+/// assert_eq!(tasks.len(), 1);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct IndexedPriorityQueue<Id, P>
+where
+    Id: Ord,
+    P: Ord,
+{
+    queue: BTreeSet<(P, Id)>,
+    priorities: BTreeMap<Id, P>,
+}
+
+impl<Id: Ord, P: Ord> Default for IndexedPriorityQueue<Id, P> {
+    fn default() -> Self {
+        Self {
+            queue: BTreeSet::default(),
+            priorities: BTreeMap::default(),
+        }
+    }
+}
+
+impl<Id: Ord, P: Ord> IndexedPriorityQueue<Id, P> {
+    /// Makes a new, empty `IndexedPriorityQueue` with maximum node size 1024.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let mut queue: IndexedPriorityQueue<u32, u64> = IndexedPriorityQueue::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `IndexedPriorityQueue` whose two sides have the given maximum node size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let mut queue: IndexedPriorityQueue<u32, u64> =
+    ///     IndexedPriorityQueue::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            queue: BTreeSet::with_maximum_node_size(maximum_node_size),
+            priorities: BTreeMap::with_maximum_node_size(maximum_node_size),
+        }
+    }
+    /// Clears the queue, removing all entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let mut queue = IndexedPriorityQueue::new();
+    /// queue.push(1, 10);
+    /// queue.clear();
+    /// assert!(queue.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.priorities.clear();
+    }
+    /// Returns the number of entries in the queue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let queue = IndexedPriorityQueue::from_iter([(1, 10), (2, 20)]);
+    /// assert_eq!(queue.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+    /// Returns `true` if the queue contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+    /// Returns `true` if the id is queued.
+    ///
+    /// The id may be any borrowed form of the id type, but the ordering on the borrowed form
+    /// *must* match the ordering on the id type.
+    pub fn contains<Q>(&self, id: &Q) -> bool
+    where
+        Id: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.priorities.contains_key(id)
+    }
+    /// Returns the priority of the id, if it is queued.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let queue = IndexedPriorityQueue::from_iter([(1, 10)]);
+    /// assert_eq!(queue.priority(&1), Some(&10));
+    /// assert_eq!(queue.priority(&2), None);
+    /// ```
+    pub fn priority<Q>(&self, id: &Q) -> Option<&P>
+    where
+        Id: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.priorities.get(id)
+    }
+    /// Returns the entry with the smallest priority, if any.
+    pub fn peek_min(&self) -> Option<(&Id, &P)> {
+        self.queue.first().map(|(priority, id)| (id, priority))
+    }
+    /// Returns the entry with the greatest priority, if any.
+    pub fn peek_max(&self) -> Option<(&Id, &P)> {
+        self.queue.last().map(|(priority, id)| (id, priority))
+    }
+    /// Returns the entry with the `k`-th smallest priority, counting from zero, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let queue = IndexedPriorityQueue::from_iter([("a", 30), ("b", 10), ("c", 20)]);
+    /// assert_eq!(queue.peek_kth(0), Some((&"b", &10)));
+    /// assert_eq!(queue.peek_kth(2), Some((&"a", &30)));
+    /// assert_eq!(queue.peek_kth(3), None);
+    /// ```
+    pub fn peek_kth(&self, k: usize) -> Option<(&Id, &P)> {
+        self.queue.get_index(k).map(|(priority, id)| (id, priority))
+    }
+    /// Removes the entry with the smallest priority and returns it, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let mut queue = IndexedPriorityQueue::from_iter([("a", 2), ("b", 1)]);
+    /// assert_eq!(queue.pop_min(), Some(("b", 1)));
+    /// assert_eq!(queue.pop_min(), Some(("a", 2)));
+    /// assert_eq!(queue.pop_min(), None);
+    /// ```
+    pub fn pop_min(&mut self) -> Option<(Id, P)> {
+        let (priority, id) = self.queue.pop_first()?;
+        self.priorities.remove(&id);
+
+        Some((id, priority))
+    }
+    /// Removes the entry with the greatest priority and returns it, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let mut queue = IndexedPriorityQueue::from_iter([("a", 2), ("b", 1)]);
+    /// assert_eq!(queue.pop_max(), Some(("a", 2)));
+    /// assert_eq!(queue.pop_max(), Some(("b", 1)));
+    /// assert_eq!(queue.pop_max(), None);
+    /// ```
+    pub fn pop_max(&mut self) -> Option<(Id, P)> {
+        let (priority, id) = self.queue.pop_last()?;
+        self.priorities.remove(&id);
+
+        Some((id, priority))
+    }
+    /// Returns an iterator over the entries of the queue, from the smallest priority to the
+    /// greatest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let queue = IndexedPriorityQueue::from_iter([("a", 2), ("b", 1)]);
+    /// assert!(queue.iter().eq([(&"b", &1), (&"a", &2)]));
+    /// ```
+    pub fn iter(&self) -> Iter<'_, Id, P> {
+        Iter {
+            inner: self.queue.iter(),
+        }
+    }
+}
+
+impl<Id: Ord + Clone, P: Ord + Clone> IndexedPriorityQueue<Id, P> {
+    /// Queues the id with the given priority. If the id was already queued, its priority is
+    /// changed instead, and the previous one is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let mut queue = IndexedPriorityQueue::new();
+    /// assert_eq!(queue.push("a", 2), None);
+    /// assert_eq!(queue.push("a", 1), Some(2));
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn push(&mut self, id: Id, priority: P) -> Option<P> {
+        let previous = self.priorities.insert(id.clone(), priority.clone());
+        if let Some(previous) = previous.as_ref() {
+            self.queue.remove(&(previous.clone(), id.clone()));
+        }
+        self.queue.insert((priority, id));
+
+        previous
+    }
+    /// Changes the priority of a queued id, returning the previous one. Returns `None`, leaving
+    /// the queue untouched, if the id is not queued.
+    ///
+    /// The id may be any borrowed form of the id type, but the ordering on the borrowed form
+    /// *must* match the ordering on the id type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let mut queue = IndexedPriorityQueue::from_iter([("a", 2), ("b", 1)]);
+    /// assert_eq!(queue.change_priority("a", 0), Some(2));
+    /// assert_eq!(queue.change_priority("c", 0), None);
+    /// assert_eq!(queue.peek_min(), Some((&"a", &0)));
+    /// ```
+    pub fn change_priority<Q>(&mut self, id: &Q, priority: P) -> Option<P>
+    where
+        Id: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (id, previous) = self.remove(id)?;
+        self.push(id, priority);
+
+        Some(previous)
+    }
+    /// Removes an id from the queue, returning it along with its priority if it was queued.
+    ///
+    /// The id may be any borrowed form of the id type, but the ordering on the borrowed form
+    /// *must* match the ordering on the id type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let mut queue = IndexedPriorityQueue::from_iter([("a", 2), ("b", 1)]);
+    /// assert_eq!(queue.remove("a"), Some(("a", 2)));
+    /// assert_eq!(queue.remove("a"), None);
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn remove<Q>(&mut self, id: &Q) -> Option<(Id, P)>
+    where
+        Id: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (id, priority) = self.priorities.get_key_value(id)?;
+        let entry = (priority.clone(), id.clone());
+        let (priority, id) = self.queue.take(&entry)?;
+        self.priorities.remove::<Id>(&id);
+
+        Some((id, priority))
+    }
+    /// Returns how many entries come before the id, that is its position when ordered by
+    /// priority, if it is queued.
+    ///
+    /// The id may be any borrowed form of the id type, but the ordering on the borrowed form
+    /// *must* match the ordering on the id type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::heap::IndexedPriorityQueue;
+    ///
+    /// let queue = IndexedPriorityQueue::from_iter([("a", 30), ("b", 10), ("c", 20)]);
+    /// assert_eq!(queue.rank_of("a"), Some(2));
+    /// assert_eq!(queue.rank_of("d"), None);
+    /// ```
+    pub fn rank_of<Q>(&self, id: &Q) -> Option<usize>
+    where
+        Id: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (id, priority) = self.priorities.get_key_value(id)?;
+
+        Some(self.queue.rank(&(priority.clone(), id.clone())))
+    }
+}
+
+impl<Id: Ord + Clone, P: Ord + Clone> FromIterator<(Id, P)> for IndexedPriorityQueue<Id, P> {
+    fn from_iter<T: IntoIterator<Item = (Id, P)>>(iter: T) -> Self {
+        let mut queue = Self::new();
+        queue.extend(iter);
+
+        queue
+    }
+}
+
+impl<Id: Ord + Clone, P: Ord + Clone> Extend<(Id, P)> for IndexedPriorityQueue<Id, P> {
+    fn extend<T: IntoIterator<Item = (Id, P)>>(&mut self, iter: T) {
+        for (id, priority) in iter {
+            self.push(id, priority);
+        }
+    }
+}
+
+impl<Id: Ord, P: Ord> IntoIterator for IndexedPriorityQueue<Id, P> {
+    type Item = (Id, P);
+
+    type IntoIter = IntoIter<Id, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.queue.into_iter(),
+        }
+    }
+}
+
+impl<'a, Id: Ord, P: Ord> IntoIterator for &'a IndexedPriorityQueue<Id, P> {
+    type Item = (&'a Id, &'a P);
+
+    type IntoIter = Iter<'a, Id, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of an `IndexedPriorityQueue`, sorted by priority.
+///
+/// This `struct` is created by the [`iter`] method on [`IndexedPriorityQueue`].
+/// See its documentation for more.
+///
+/// [`iter`]: IndexedPriorityQueue::iter
+pub struct Iter<'a, Id, P>
+where
+    Id: Ord,
+    P: Ord,
+{
+    inner: crate::Iter<'a, (P, Id)>,
+}
+
+impl<'a, Id: Ord, P: Ord> Iterator for Iter<'a, Id, P> {
+    type Item = (&'a Id, &'a P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(priority, id)| (id, priority))
+    }
+}
+
+impl<'a, Id: Ord, P: Ord> DoubleEndedIterator for Iter<'a, Id, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(priority, id)| (id, priority))
+    }
+}
+
+impl<'a, Id: Ord, P: Ord> FusedIterator for Iter<'a, Id, P> {}
+
+/// An owning iterator over the entries of an `IndexedPriorityQueue`, sorted by priority.
+///
+/// This `struct` is created by the [`into_iter`] method on [`IndexedPriorityQueue`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+///
+/// [`into_iter`]: IndexedPriorityQueue#method.into_iter
+pub struct IntoIter<Id, P>
+where
+    Id: Ord,
+    P: Ord,
+{
+    inner: crate::IntoIter<(P, Id)>,
+}
+
+impl<Id: Ord, P: Ord> Iterator for IntoIter<Id, P> {
+    type Item = (Id, P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(priority, id)| (id, priority))
+    }
+}
+
+impl<Id: Ord, P: Ord> DoubleEndedIterator for IntoIter<Id, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(priority, id)| (id, priority))
+    }
+}
+
+impl<Id: Ord, P: Ord> FusedIterator for IntoIter<Id, P> {}
+
+#[cfg(test)]
+mod tests {
+    use super::IndexedPriorityQueue;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([52; 32]);
+        let mut queue = IndexedPriorityQueue::with_maximum_node_size(8);
+        let mut model: std::collections::HashMap<u16, u32> = std::collections::HashMap::new();
+        let sorted = |model: &std::collections::HashMap<u16, u32>| {
+            let mut entries: Vec<(u32, u16)> = model.iter().map(|(id, p)| (*p, *id)).collect();
+            entries.sort();
+            entries
+        };
+        for _ in 0..20_000 {
+            let id = rng.gen_range(0..300u16);
+            let priority = rng.gen_range(0..100u32);
+            match rng.gen_range(0..8) {
+                0 | 1 | 2 => assert_eq!(queue.push(id, priority), model.insert(id, priority)),
+                3 => assert_eq!(
+                    queue.change_priority(&id, priority),
+                    model.get_mut(&id).map(|p| std::mem::replace(p, priority))
+                ),
+                4 => assert_eq!(
+                    queue.remove(&id),
+                    model.remove(&id).map(|priority| (id, priority))
+                ),
+                5 => {
+                    let expected = sorted(&model).first().map(|(p, id)| (*id, *p));
+                    assert_eq!(queue.pop_min(), expected);
+                    expected.map(|(id, _)| model.remove(&id));
+                }
+                6 => {
+                    let expected = sorted(&model).last().map(|(p, id)| (*id, *p));
+                    assert_eq!(queue.pop_max(), expected);
+                    expected.map(|(id, _)| model.remove(&id));
+                }
+                _ => {
+                    let entries = sorted(&model);
+                    let rank = entries.iter().position(|(_, queued)| *queued == id);
+                    assert_eq!(queue.rank_of(&id), rank);
+                    let k = rng.gen_range(0..=entries.len());
+                    assert_eq!(queue.peek_kth(k), entries.get(k).map(|(p, id)| (id, p)));
+                }
+            }
+            assert_eq!(queue.len(), model.len());
+        }
+
+        let entries = sorted(&model);
+        assert!(queue
+            .iter()
+            .eq(entries.iter().map(|(priority, id)| (id, priority))));
+        for (id, priority) in model.iter() {
+            assert_eq!(queue.priority(id), Some(priority));
+        }
+        assert!(queue.into_iter().rev().eq(entries
+            .into_iter()
+            .rev()
+            .map(|(priority, id)| (id, priority))));
+    }
+}
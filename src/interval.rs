@@ -0,0 +1,835 @@
+use std::iter::FusedIterator;
+use std::ops::{Bound, Range};
+
+use crate::{BTreeMap, RangeMap};
+
+/// An ordered map from non-overlapping, half-open ranges to values, built on top of
+/// [`BTreeMap`].
+///
+/// Each range is stored once, keyed by its start. Inserting a range overwrites whatever was
+/// previously mapped inside of it, splitting ranges that are only partially covered, and adjacent
+/// ranges that map to equal values are coalesced into a single one.
+///
+/// Since the underlying tree is an order-statistic tree, ranges can also be looked up by their
+/// position with [`IntervalMap::get_index`] and [`IntervalMap::rank`].
+///
+/// # Examples
+///
+/// ```
+/// use indexset::interval::IntervalMap;
+///
+/// let mut tenants = IntervalMap::new();
+/// tenants.insert(0..100, "a");
+/// tenants.insert(100..200, "a");
+/// tenants.insert(50..150, "b");
+///
+/// assert_eq!(tenants.get(&10), Some(&"a"));
+/// assert_eq!(tenants.get(&120), Some(&"b"));
+/// assert_eq!(tenants.get(&200), None);
+///
+/// let ranges: Vec<_> = tenants.iter().collect();
+/// assert_eq!(ranges, [(&0..&50, &"a"), (&50..&150, &"b"), (&150..&200, &"a")]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IntervalMap<K, V>
+where
+    K: Ord,
+{
+    inner: BTreeMap<K, (K, V)>,
+}
+
+impl<K: Ord, V> Default for IntervalMap<K, V> {
+    fn default() -> Self {
+        Self {
+            inner: BTreeMap::default(),
+        }
+    }
+}
+
+impl<K: Ord, V> IntervalMap<K, V> {
+    /// Makes a new, empty `IntervalMap`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map: IntervalMap<u32, &str> = IntervalMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Clears the map, removing all ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..10, "a");
+    /// map.clear();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.inner.clear()
+    }
+    /// Returns the number of disjoint ranges in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..10, "a");
+    /// map.insert(10..20, "a");
+    /// map.insert(30..40, "b");
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    /// Returns `true` if the map contains no ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// assert!(map.is_empty());
+    /// map.insert(0..10, "a");
+    /// assert!(!map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+    /// Returns the range covering the given point alongside its value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..10, "a");
+    /// assert_eq!(map.get_key_value(&5), Some((&0..&10, &"a")));
+    /// assert_eq!(map.get_key_value(&10), None);
+    /// ```
+    pub fn get_key_value(&self, point: &K) -> Option<(Range<&K>, &V)> {
+        let cursor = self.inner.lower_bound(Bound::Included(point));
+        let (start, (end, value)) = match cursor.key_value() {
+            Some(entry) if entry.0 == point => entry,
+            _ => cursor.peek_prev()?,
+        };
+
+        if point < end {
+            return Some((start..end, value));
+        }
+
+        None
+    }
+    /// Returns a reference to the value of the range covering the given point, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..10, "a");
+    /// assert_eq!(map.get(&0), Some(&"a"));
+    /// assert_eq!(map.get(&10), None);
+    /// ```
+    pub fn get(&self, point: &K) -> Option<&V> {
+        self.get_key_value(point).map(|(_, value)| value)
+    }
+    /// Returns `true` if some range in the map covers the given point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..10, "a");
+    /// assert!(map.contains_key(&9));
+    /// assert!(!map.contains_key(&10));
+    /// ```
+    pub fn contains_key(&self, point: &K) -> bool {
+        self.get_key_value(point).is_some()
+    }
+    /// Returns the range in the i-th position of the map alongside its value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(10..20, "b");
+    /// map.insert(0..10, "a");
+    /// assert_eq!(map.get_index(0), Some((&0..&10, &"a")));
+    /// assert_eq!(map.get_index(1), Some((&10..&20, &"b")));
+    /// assert_eq!(map.get_index(2), None);
+    /// ```
+    pub fn get_index(&self, idx: usize) -> Option<(Range<&K>, &V)> {
+        self.inner
+            .get_index(idx)
+            .map(|(start, (end, value))| (start..end, value))
+    }
+    /// Returns the number of ranges that end at or before the given point.
+    ///
+    /// If the point is covered by a range, then that range is the one at the returned position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..10, "a");
+    /// map.insert(20..30, "b");
+    /// assert_eq!(map.rank(&5), 0);
+    /// assert_eq!(map.rank(&10), 1);
+    /// assert_eq!(map.rank(&25), 1);
+    /// assert_eq!(map.rank(&30), 2);
+    /// ```
+    pub fn rank(&self, point: &K) -> usize {
+        let rank = self.inner.rank(point);
+        if rank > 0 {
+            if let Some((_, (end, _))) = self.inner.get_index(rank - 1) {
+                if point < end {
+                    return rank - 1;
+                }
+            }
+        }
+
+        rank
+    }
+    /// Returns `true` if some range in the map intersects the given range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(10..20, "a");
+    /// assert!(map.overlaps(&(15..25)));
+    /// assert!(!map.overlaps(&(20..25)));
+    /// ```
+    pub fn overlaps(&self, range: &Range<K>) -> bool {
+        self.overlapping(range).next().is_some()
+    }
+    /// Gets an iterator over every range that intersects the given range, in ascending order.
+    ///
+    /// Ranges are returned whole, even if they are only partially covered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..10, "a");
+    /// map.insert(10..20, "b");
+    /// map.insert(20..30, "c");
+    ///
+    /// let overlapping: Vec<_> = map.overlapping(&(5..20)).map(|(_, value)| *value).collect();
+    /// assert_eq!(overlapping, ["a", "b"]);
+    /// ```
+    pub fn overlapping(&self, range: &Range<K>) -> Overlapping<'_, K, V> {
+        let start_idx = self.rank(&range.start);
+        let end_idx = self.inner.rank(&range.end);
+
+        if start_idx < end_idx && range.start < range.end {
+            return Overlapping {
+                inner: Some(RangeMap {
+                    inner: self.inner.set.range_idx(start_idx..end_idx),
+                }),
+            };
+        }
+
+        Overlapping { inner: None }
+    }
+    /// Gets an iterator over the ranges of the map and their values, in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(10..20, "b");
+    /// map.insert(0..10, "a");
+    ///
+    /// let (first_range, first_value) = map.iter().next().unwrap();
+    /// assert_eq!((first_range, *first_value), (&0..&10, "a"));
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.inner.iter(),
+        }
+    }
+}
+
+impl<K, V> IntervalMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + PartialEq,
+{
+    /// Maps every point in the given range to the value.
+    ///
+    /// Existing ranges that overlap with the given one are either overwritten, or trimmed in
+    /// case they are only partially covered. If the resulting range touches a neighbouring range
+    /// with an equal value, both are coalesced. Inserting an empty range does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..30, "a");
+    /// map.insert(10..20, "b");
+    /// assert_eq!(map.len(), 3);
+    ///
+    /// map.insert(10..20, "a");
+    /// assert_eq!(map.len(), 1);
+    /// assert_eq!(map.get_index(0), Some((&0..&30, &"a")));
+    /// ```
+    pub fn insert(&mut self, range: Range<K>, value: V) {
+        if range.start >= range.end {
+            return;
+        }
+
+        self.remove(range.clone());
+        let Range {
+            mut start,
+            mut end,
+        } = range;
+
+        let rank = self.inner.rank(&start);
+        if rank > 0 {
+            if let Some((previous_start, (previous_end, previous_value))) =
+                self.inner.get_index(rank - 1)
+            {
+                if previous_end == &start && previous_value == &value {
+                    start = previous_start.clone();
+                    self.inner.pop_index(rank - 1);
+                }
+            }
+        }
+
+        if let Some((next_end, next_value)) = self.inner.get(&end) {
+            if next_value == &value {
+                let next_end = next_end.clone();
+                self.inner.remove(&end);
+                end = next_end;
+            }
+        }
+
+        self.inner.insert(start, (end, value));
+    }
+    /// Unmaps every point in the given range, trimming the ranges that are only partially
+    /// covered by it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::IntervalMap;
+    ///
+    /// let mut map = IntervalMap::new();
+    /// map.insert(0..30, "a");
+    /// map.remove(10..20);
+    ///
+    /// let ranges: Vec<_> = map.iter().collect();
+    /// assert_eq!(ranges, [(&0..&10, &"a"), (&20..&30, &"a")]);
+    /// ```
+    pub fn remove(&mut self, range: Range<K>) {
+        if range.start >= range.end {
+            return;
+        }
+
+        let idx = self.rank(&range.start);
+        let mut remainders = vec![];
+        while let Some((start, _)) = self.inner.get_index(idx) {
+            if start >= &range.end {
+                break;
+            }
+
+            let (start, (end, value)) = self.inner.pop_index(idx);
+            match (start < range.start, end > range.end) {
+                (true, true) => {
+                    remainders.push((start, (range.start.clone(), value.clone())));
+                    remainders.push((range.end.clone(), (end, value)));
+                }
+                (true, false) => remainders.push((start, (range.start.clone(), value))),
+                (false, true) => remainders.push((range.end.clone(), (end, value))),
+                (false, false) => {}
+            }
+        }
+
+        remainders.into_iter().for_each(|(start, end_value)| {
+            self.inner.insert(start, end_value);
+        });
+    }
+}
+
+impl<K, V> FromIterator<(Range<K>, V)> for IntervalMap<K, V>
+where
+    K: Ord + Clone,
+    V: Clone + PartialEq,
+{
+    fn from_iter<T: IntoIterator<Item = (Range<K>, V)>>(iter: T) -> Self {
+        let mut map = IntervalMap::new();
+        iter.into_iter().for_each(|(range, value)| {
+            map.insert(range, value);
+        });
+
+        map
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a IntervalMap<K, V>
+where
+    K: Ord,
+{
+    type Item = (Range<&'a K>, &'a V);
+
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the ranges of an `IntervalMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`IntervalMap`].
+/// See its documentation for more.
+///
+/// [`iter`]: IntervalMap::iter
+pub struct Iter<'a, K, V>
+where
+    K: Ord,
+{
+    inner: crate::IterMap<'a, K, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V>
+where
+    K: Ord,
+{
+    type Item = (Range<&'a K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(start, (end, value))| (start..end, value))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V>
+where
+    K: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|(start, (end, value))| (start..end, value))
+    }
+}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> where K: Ord {}
+
+/// An iterator over the ranges of an `IntervalMap` that intersect a given range.
+///
+/// This `struct` is created by the [`overlapping`] method on [`IntervalMap`].
+/// See its documentation for more.
+///
+/// [`overlapping`]: IntervalMap::overlapping
+pub struct Overlapping<'a, K, V>
+where
+    K: Ord,
+{
+    inner: Option<RangeMap<'a, K, (K, V)>>,
+}
+
+impl<'a, K, V> Iterator for Overlapping<'a, K, V>
+where
+    K: Ord,
+{
+    type Item = (Range<&'a K>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .as_mut()?
+            .next()
+            .map(|(start, (end, value))| (start..end, value))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Overlapping<'a, K, V>
+where
+    K: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .as_mut()?
+            .next_back()
+            .map(|(start, (end, value))| (start..end, value))
+    }
+}
+
+impl<'a, K, V> FusedIterator for Overlapping<'a, K, V> where K: Ord {}
+
+/// An ordered set of non-overlapping, half-open ranges, built on top of [`IntervalMap`].
+///
+/// Overlapping and adjacent ranges are merged on insertion.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::interval::RangeSet;
+///
+/// let mut set = RangeSet::new();
+/// set.insert(0..10);
+/// set.insert(5..20);
+/// set.insert(30..40);
+///
+/// assert!(set.contains(&15));
+/// assert!(!set.contains(&25));
+/// assert_eq!(set.iter().collect::<Vec<_>>(), [&0..&20, &30..&40]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RangeSet<K>
+where
+    K: Ord,
+{
+    map: IntervalMap<K, ()>,
+}
+
+impl<K: Ord> Default for RangeSet<K> {
+    fn default() -> Self {
+        Self {
+            map: IntervalMap::default(),
+        }
+    }
+}
+
+impl<K: Ord> RangeSet<K> {
+    /// Makes a new, empty `RangeSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::interval::RangeSet;
+    ///
+    /// let mut set: RangeSet<u32> = RangeSet::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Clears the set, removing all ranges.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+    /// Returns the number of disjoint ranges in the set.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    /// Returns `true` if the set contains no ranges.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    /// Returns `true` if some range in the set covers the given point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::RangeSet;
+    ///
+    /// let mut set = RangeSet::new();
+    /// set.insert(0..10);
+    /// assert!(set.contains(&0));
+    /// assert!(!set.contains(&10));
+    /// ```
+    pub fn contains(&self, point: &K) -> bool {
+        self.map.contains_key(point)
+    }
+    /// Returns the range covering the given point, if any.
+    pub fn get(&self, point: &K) -> Option<Range<&K>> {
+        self.map.get_key_value(point).map(|(range, _)| range)
+    }
+    /// Returns the range in the i-th position of the set, if any.
+    pub fn get_index(&self, idx: usize) -> Option<Range<&K>> {
+        self.map.get_index(idx).map(|(range, _)| range)
+    }
+    /// Returns the number of ranges that end at or before the given point.
+    ///
+    /// See [`IntervalMap::rank`] for more.
+    pub fn rank(&self, point: &K) -> usize {
+        self.map.rank(point)
+    }
+    /// Returns `true` if some range in the set intersects the given range.
+    pub fn overlaps(&self, range: &Range<K>) -> bool {
+        self.map.overlaps(range)
+    }
+    /// Gets an iterator over every range that intersects the given range, in ascending order.
+    pub fn overlapping(&self, range: &Range<K>) -> OverlappingRanges<'_, K> {
+        OverlappingRanges {
+            inner: self.map.overlapping(range),
+        }
+    }
+    /// Gets an iterator over the ranges of the set, in ascending order.
+    pub fn iter(&self) -> Ranges<'_, K> {
+        Ranges {
+            inner: self.map.iter(),
+        }
+    }
+}
+
+impl<K: Ord + Clone> RangeSet<K> {
+    /// Adds every point in the given range to the set, merging it with the ranges it overlaps
+    /// or touches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::RangeSet;
+    ///
+    /// let mut set = RangeSet::new();
+    /// set.insert(0..10);
+    /// set.insert(10..20);
+    /// assert_eq!(set.get_index(0), Some(&0..&20));
+    /// ```
+    pub fn insert(&mut self, range: Range<K>) {
+        self.map.insert(range, ())
+    }
+    /// Removes every point in the given range from the set, trimming the ranges that are only
+    /// partially covered by it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::interval::RangeSet;
+    ///
+    /// let mut set = RangeSet::new();
+    /// set.insert(0..20);
+    /// set.remove(5..10);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), [&0..&5, &10..&20]);
+    /// ```
+    pub fn remove(&mut self, range: Range<K>) {
+        self.map.remove(range)
+    }
+}
+
+impl<K: Ord + Clone> FromIterator<Range<K>> for RangeSet<K> {
+    fn from_iter<T: IntoIterator<Item = Range<K>>>(iter: T) -> Self {
+        let mut set = RangeSet::new();
+        iter.into_iter().for_each(|range| set.insert(range));
+
+        set
+    }
+}
+
+impl<'a, K> IntoIterator for &'a RangeSet<K>
+where
+    K: Ord,
+{
+    type Item = Range<&'a K>;
+
+    type IntoIter = Ranges<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the ranges of a `RangeSet`.
+///
+/// This `struct` is created by the [`iter`] method on [`RangeSet`].
+/// See its documentation for more.
+///
+/// [`iter`]: RangeSet::iter
+pub struct Ranges<'a, K>
+where
+    K: Ord,
+{
+    inner: Iter<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for Ranges<'a, K>
+where
+    K: Ord,
+{
+    type Item = Range<&'a K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(range, _)| range)
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for Ranges<'a, K>
+where
+    K: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(range, _)| range)
+    }
+}
+
+impl<'a, K> FusedIterator for Ranges<'a, K> where K: Ord {}
+
+/// An iterator over the ranges of a `RangeSet` that intersect a given range.
+///
+/// This `struct` is created by the [`overlapping`] method on [`RangeSet`].
+/// See its documentation for more.
+///
+/// [`overlapping`]: RangeSet::overlapping
+pub struct OverlappingRanges<'a, K>
+where
+    K: Ord,
+{
+    inner: Overlapping<'a, K, ()>,
+}
+
+impl<'a, K> Iterator for OverlappingRanges<'a, K>
+where
+    K: Ord,
+{
+    type Item = Range<&'a K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(range, _)| range)
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for OverlappingRanges<'a, K>
+where
+    K: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(range, _)| range)
+    }
+}
+
+impl<'a, K> FusedIterator for OverlappingRanges<'a, K> where K: Ord {}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntervalMap, RangeSet};
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_insert_splits_and_coalesces() {
+        let mut map = IntervalMap::new();
+        map.insert(0..100, 1);
+        map.insert(200..300, 1);
+        map.insert(50..250, 2);
+
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(&0..&50, &1), (&50..&250, &2), (&250..&300, &1)]
+        );
+
+        map.insert(40..60, 2);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(&0..&40, &1), (&40..&250, &2), (&250..&300, &1)]
+        );
+
+        map.insert(0..40, 2);
+        map.insert(250..300, 2);
+        assert_eq!(map.iter().collect::<Vec<_>>(), [(&0..&300, &2)]);
+
+        map.insert(100..100, 3);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_point_and_overlap_queries() {
+        let map = IntervalMap::from_iter([(10..20, 'a'), (20..30, 'b'), (40..50, 'c')]);
+
+        assert_eq!(map.get(&9), None);
+        assert_eq!(map.get(&10), Some(&'a'));
+        assert_eq!(map.get(&20), Some(&'b'));
+        assert_eq!(map.get(&35), None);
+        assert_eq!(map.get(&49), Some(&'c'));
+        assert_eq!(map.get(&50), None);
+
+        let values = |range| map.overlapping(&range).map(|(_, v)| *v).collect::<String>();
+        assert_eq!(values(0..10), "");
+        assert_eq!(values(0..11), "a");
+        assert_eq!(values(19..21), "ab");
+        assert_eq!(values(30..40), "");
+        assert_eq!(values(29..41), "bc");
+        assert_eq!(values(0..100), "abc");
+        assert_eq!(values(50..100), "");
+        assert_eq!(values(15..15), "");
+        assert_eq!(
+            map.overlapping(&(0..100)).rev().map(|(_, v)| *v).collect::<String>(),
+            "cba"
+        );
+
+        assert_eq!(map.rank(&0), 0);
+        assert_eq!(map.rank(&25), 1);
+        assert_eq!(map.rank(&35), 2);
+        assert_eq!(map.rank(&45), 2);
+        assert_eq!(map.rank(&50), 3);
+        assert_eq!(map.get_index(2), Some((&40..&50, &'c')));
+    }
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([7u8; 32]);
+        let mut map = IntervalMap::new();
+        let mut model = vec![None; 512];
+        for _ in 0..5_000 {
+            let start = rng.gen_range(0..512usize);
+            let end = rng.gen_range(start..=512);
+            if rng.gen_bool(0.75) {
+                let value = rng.gen_range(0..3u8);
+                map.insert(start..end, value);
+                model[start..end].iter_mut().for_each(|v| *v = Some(value));
+            } else {
+                map.remove(start..end);
+                model[start..end].iter_mut().for_each(|v| *v = None);
+            }
+        }
+
+        for (point, expected) in model.iter().enumerate() {
+            assert_eq!(map.get(&point), expected.as_ref(), "mismatch on {point}");
+        }
+
+        let ranges = map.iter().collect::<Vec<_>>();
+        for window in ranges.windows(2) {
+            let ((left, left_value), (right, right_value)) = (&window[0], &window[1]);
+            assert!(left.end <= right.start);
+            assert!(left.end != right.start || left_value != right_value);
+        }
+    }
+
+    #[test]
+    fn test_range_set() {
+        let mut set = RangeSet::from_iter([0..10, 20..30, 5..15, 30..35]);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [&0..&15, &20..&35]);
+
+        set.insert(14..21);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [&0..&35]);
+
+        set.remove(10..12);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [&0..&10, &12..&35]);
+        assert!(set.contains(&9));
+        assert!(!set.contains(&10));
+        assert_eq!(set.get(&20), Some(&12..&35));
+        assert_eq!(set.rank(&11), 1);
+        assert!(set.overlaps(&(11..13)));
+        assert!(!set.overlaps(&(10..12)));
+    }
+}
@@ -6,19 +6,54 @@ pub mod cdc;
 
 mod core;
 
+pub mod bag;
+
+pub mod bimap;
+
+pub mod compressed;
+
+pub mod heap;
+
+pub mod interval;
+
+pub mod prefix;
+
+pub mod search;
+
+pub mod soa;
+
+pub mod stats;
+
+pub mod tiered;
+
+pub mod tuple;
+
 use crate::Entry::{Occupied, Vacant};
 use core::constants::DEFAULT_INNER_SIZE;
-use core::node::*;
-use core::pair::Pair;
+use core::layout::Layout;
+pub use core::node::{NodeLike, SplitPolicy};
+pub use core::pair::Pair;
+use core::sequenced::Sequenced;
+#[cfg(feature = "serde")]
 use ftree::FenwickTree;
+use prefix::Prefix;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use stats::{
+    bucket_count, bucket_of, bucket_positions, distinct_positions, quantile_position, Bucket,
+    RngLike,
+};
 use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::collections::Bound;
+use std::collections::TryReserveError;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::iter::FusedIterator;
+use std::marker::PhantomData;
 use std::mem::swap;
 use std::ops::{Index, RangeBounds};
+use tuple::TuplePrefix;
 
 type Node<T> = Vec<T>;
 
@@ -37,6 +72,10 @@ type Node<T> = Vec<T>;
 /// Iterators returned by [`BTreeSet::iter`] produce their items in order, and take worst-case
 /// logarithmic and amortized constant time per item returned.
 ///
+/// Elements are kept in nodes of type `N`, which defaults to `Vec<T>`. Any type implementing
+/// [`NodeLike`] can be used instead, such as an inline fixed-capacity array; such trees are made
+/// through [`Default`], since [`BTreeSet::new`] only builds `Vec`-backed ones.
+///
 /// [`Cell`]: core::cell::Cell
 /// [`RefCell`]: core::cell::RefCell
 ///
@@ -77,16 +116,18 @@ type Node<T> = Vec<T>;
 ///
 /// let set = BTreeSet::from_iter([1, 2, 3]);
 /// ```
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct BTreeSet<T>
+pub struct BTreeSet<T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    inner: Vec<Node<T>>,
-    index: FenwickTree<usize>,
+    layout: Layout<N>,
     node_capacity: usize,
-    len: usize,
+    // Empty nodes set aside by `with_capacity` and `try_reserve`, which splits take before
+    // allocating new ones
+    spare: Vec<N>,
+    split_policy: SplitPolicy,
+    _marker: PhantomData<T>,
 }
 
 impl<T: Ord> BTreeSet<T> {
@@ -121,11 +162,43 @@ impl<T: Ord> BTreeSet<T> {
     ///
     /// let mut set: BTreeSet<i32> = BTreeSet::with_maximum_node_size(128);
     pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
-        let mut new: Self = Default::default();
-        new.inner = vec![Node::with_capacity(maximum_node_size)];
-        new.node_capacity = maximum_node_size;
+        Self::with_node_capacity(maximum_node_size)
+    }
+    /// Makes a new, empty `BTreeSet` with room for at least `capacity` elements.
+    ///
+    /// The list of nodes is allocated upfront, along with the nodes that splits will need, so
+    /// that loading that many elements does not reallocate the list nor allocate nodes as it goes.
+    /// As many nodes are set aside as even splits need: switching to
+    /// [`SplitPolicy::RightBiased`] with [`set_split_policy`](Self::set_split_policy) lets go of
+    /// the ones it will not need. The nodes set aside are neither cloned nor serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::with_capacity(100_000);
+    /// for value in 0..100_000 {
+    ///     set.insert(value);
+    /// }
+    /// assert_eq!(set.len(), 100_000);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut new = Self::new();
+        match new.reserve_nodes(capacity, false) {
+            Ok(()) => new,
+            Err(_) => unreachable!("infallible reservations abort on allocation failures"),
+        }
+    }
+}
 
-        new
+impl<T: Ord, N: NodeLike<T>> BTreeSet<T, N> {
+    fn with_node_capacity(node_capacity: usize) -> Self {
+        Self {
+            layout: Layout::new(N::with_capacity(node_capacity)),
+            node_capacity,
+            ..Default::default()
+        }
     }
     /// Clears the set, removing all elements.
     ///
@@ -140,32 +213,43 @@ impl<T: Ord> BTreeSet<T> {
     /// assert!(v.is_empty());
     /// ```
     pub fn clear(&mut self) {
-        self.inner = vec![Node::with_capacity(self.node_capacity)];
-        self.index = FenwickTree::from_iter(vec![0]);
-        self.len = 0;
+        self.layout = Layout::new(N::with_capacity(self.node_capacity));
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::{BTreeSet, SplitPolicy};
+    ///
+    /// let mut set: BTreeSet<u64> = BTreeSet::new();
+    /// set.set_split_policy(SplitPolicy::RightBiased);
+    /// assert!(matches!(set.split_policy(), SplitPolicy::RightBiased));
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        // Nodes set aside for splits that fill them less than the new policy does are let go
+        let reserved = self.spare.len() * self.split_policy.node_fill(self.node_capacity);
+        self.spare
+            .truncate(reserved.div_ceil(split_policy.node_fill(self.node_capacity)));
+        self.split_policy = split_policy;
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.split_policy
     }
     fn locate_node<Q>(&self, value: &Q) -> usize
     where
         T: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let mut node_idx = self.inner.partition_point(|node| {
-            if let Some(&max) = node.last().as_ref() {
+        // When the value is greater than every element, it goes to the last node
+        self.layout.locate_node(|node| {
+            if let Some(&max) = node.max().as_ref() {
                 return max.borrow() < value;
             };
 
             false
-        });
-
-        // When value is greater than all elements inside inner[node_idx], then len
-        // of inner[node_idx], which is not a valid place for insertion, is returned. It will
-        // never return less than 0, so it is only necessary to check whether it is out of bounds
-        // from the right
-        if self.inner.get(node_idx).is_none() {
-            node_idx -= 1
-        }
-
-        node_idx
+        })
     }
     fn locate_node_cmp<P, Q>(&self, mut cmp: P) -> usize
     where
@@ -173,19 +257,13 @@ impl<T: Ord> BTreeSet<T> {
         Q: Ord + ?Sized,
         P: FnMut(&Q) -> bool,
     {
-        let mut node_idx = self.inner.partition_point(|node| {
-            if let Some(max) = node.last() {
+        self.layout.locate_node(|node| {
+            if let Some(max) = node.max() {
                 return cmp(max.borrow());
             }
 
             true
-        });
-
-        if self.inner.get(node_idx).is_none() {
-            node_idx -= 1
-        }
-
-        node_idx
+        })
     }
     fn locate_value<Q>(&self, value: &Q) -> (usize, usize)
     where
@@ -193,8 +271,7 @@ impl<T: Ord> BTreeSet<T> {
         Q: Ord + ?Sized,
     {
         let node_idx = self.locate_node(value);
-        let position_within_node =
-            self.inner[node_idx].partition_point(|item| item.borrow() < value);
+        let position_within_node = self.layout.nodes()[node_idx].lower_bound(value);
 
         (node_idx, position_within_node)
     }
@@ -205,27 +282,66 @@ impl<T: Ord> BTreeSet<T> {
         P: FnMut(&Q) -> bool,
     {
         let node_idx = self.locate_node_cmp(&mut cmp);
-        let position_within_node = self.inner[node_idx].partition_point(|item| cmp(item.borrow()));
+        let position_within_node =
+            self.layout.nodes()[node_idx].partition_point(|item| cmp(item.borrow()));
 
         (node_idx, position_within_node)
     }
+    // Positions past the last element land past the last node
     fn locate_ith(&self, idx: usize) -> (usize, usize) {
-        let mut node_index = self.index.index_of(idx);
-        let mut offset = 0;
-
-        if node_index != 0 {
-            offset = self.index.prefix_sum(node_index, 0);
+        if idx >= self.len() {
+            return (self.layout.nodes().len(), idx - self.len());
         }
 
-        let mut position_within_node = idx - offset;
-        if let Some(node) = self.inner.get(node_index) {
-            if position_within_node == node.len() {
-                node_index += 1;
-                position_within_node = 0;
+        self.layout.locate_ith(idx)
+    }
+    // Returns `true` if the node at `node_idx` is the one that a search for the value would land on
+    fn owns<Q>(&self, node_idx: usize, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let Some(node) = self.layout.nodes().get(node_idx) else {
+            return false;
+        };
+
+        (node_idx == 0
+            || self.layout.nodes()[node_idx - 1]
+                .max()
+                .is_some_and(|max| max.borrow() < value))
+            && (node_idx + 1 == self.layout.nodes().len()
+                || node.max().is_some_and(|max| value <= max.borrow()))
+    }
+    // Looks for where the value goes right at or after the hinted position, and then at the start
+    // of the next node, before falling back to searching the whole set
+    fn locate_with_hint<Q>(&self, hint: Hint, value: &Q) -> (usize, usize)
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        for (node_idx, hinted) in [(hint.node_idx, hint.position), (hint.node_idx + 1, 0)] {
+            if !self.owns(node_idx, value) {
+                continue;
             }
+
+            let node = &self.layout.nodes()[node_idx];
+            let fits = |position: usize| {
+                position <= node.len()
+                    && (position == 0
+                        || node
+                            .get_ith(position - 1)
+                            .is_some_and(|item| item.borrow() < value))
+                    && !matches!(node.get_ith(position), Some(item) if item.borrow() < value)
+            };
+            let position = [hinted, hinted + 1]
+                .into_iter()
+                .find(|position| fits(*position))
+                .unwrap_or_else(|| node.lower_bound(value));
+
+            return (node_idx, position);
         }
 
-        (node_index, position_within_node)
+        self.locate_value(value)
     }
     /// Returns a reference to the element in the i-th position of the set, if any.
     ///
@@ -245,16 +361,16 @@ impl<T: Ord> BTreeSet<T> {
     /// ```
     pub fn get_index(&self, idx: usize) -> Option<&T> {
         let (node_idx, position_within_node) = self.locate_ith(idx);
-        if let Some(candidate_node) = self.inner.get(node_idx) {
-            return candidate_node.get(position_within_node);
+        if let Some(candidate_node) = self.layout.nodes().get(node_idx) {
+            return candidate_node.get_ith(position_within_node);
         }
 
         None
     }
     fn get_mut_index(&mut self, index: usize) -> Option<&mut T> {
         let (node_idx, position_within_node) = self.locate_ith(index);
-        if let Some(_) = self.inner.get(node_idx) {
-            return self.inner[node_idx].get_mut(position_within_node);
+        if let Some(_) = self.layout.nodes().get(node_idx) {
+            return self.layout.nodes_mut()[node_idx].get_ith_mut(position_within_node);
         }
 
         None
@@ -281,14 +397,74 @@ impl<T: Ord> BTreeSet<T> {
         Q: Ord + ?Sized,
     {
         let (node_idx, position_within_node) = self.locate_value(value);
-        if let Some(candidate_node) = self.inner.get(node_idx) {
-            return candidate_node.get(position_within_node);
+        if let Some(candidate_node) = self.layout.nodes().get(node_idx) {
+            return candidate_node.get_ith(position_within_node);
         }
 
         None
     }
-    /// Returns a reference to the first element in the set, if any, that is not less than the
-    /// input.
+    /// Returns a [`Cursor`] pointing at the first element that is above the given bound.
+    ///
+    /// If no such element exists then a cursor pointing at the "ghost" non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the first element of the
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    /// use std::ops::Bound;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.lower_bound(Bound::Included(&2)).item(), Some(&2));
+    /// assert_eq!(set.lower_bound(Bound::Excluded(&2)).item(), Some(&3));
+    /// assert_eq!(set.lower_bound(Bound::Included(&4)).item(), Some(&5));
+    /// assert_eq!(set.lower_bound(Bound::Excluded(&5)).item(), None);
+    /// ```
+    pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, T, N>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Cursor {
+            set: self,
+            idx: self.lower_bound_idx(bound),
+        }
+    }
+    /// Returns a [`Cursor`] pointing at the last element that is below the given bound.
+    ///
+    /// If no such element exists then a cursor pointing at the "ghost" non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the last element of the
+    /// set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    /// use std::ops::Bound;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.upper_bound(Bound::Included(&4)).item(), Some(&3));
+    /// assert_eq!(set.upper_bound(Bound::Excluded(&3)).item(), Some(&2));
+    /// assert_eq!(set.upper_bound(Bound::Unbounded).item(), Some(&5));
+    /// assert_eq!(set.upper_bound(Bound::Excluded(&1)).item(), None);
+    /// ```
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> Cursor<'_, T, N>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let idx = match self.upper_bound_idx(bound) {
+            0 => self.len(),
+            end_idx => end_idx - 1,
+        };
+
+        Cursor { set: self, idx }
+    }
+    /// Returns a reference to the least element in the set that is greater than or equal to the
+    /// value, if any.
     ///
     /// The value may be any borrowed form of the set's element type,
     /// but the ordering on the borrowed form *must* match the
@@ -300,20 +476,104 @@ impl<T: Ord> BTreeSet<T> {
     /// use indexset::BTreeSet;
     ///
     /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
-    /// assert_eq!(set.lower_bound(&2), Some(&2));
-    /// assert_eq!(set.lower_bound(&4), Some(&5));
+    /// assert_eq!(set.ceiling(&2), Some(&2));
+    /// assert_eq!(set.ceiling(&4), Some(&5));
+    /// assert_eq!(set.ceiling(&6), None);
     /// ```
-    pub fn lower_bound<Q>(&self, value: &Q) -> Option<&T>
+    pub fn ceiling<Q>(&self, value: &Q) -> Option<&T>
     where
         T: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let (node_idx, position_within_node) = self.locate_value(value);
-        if let Some(candidate_node) = self.inner.get(node_idx) {
-            return candidate_node.get(position_within_node);
+        self.get_index(self.lower_bound_idx(Bound::Included(value)))
+    }
+    /// Returns a reference to the greatest element in the set that is less than or equal to the
+    /// value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.floor(&2), Some(&2));
+    /// assert_eq!(set.floor(&4), Some(&3));
+    /// assert_eq!(set.floor(&0), None);
+    /// ```
+    pub fn floor<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.upper_bound_idx(Bound::Included(value))
+            .checked_sub(1)
+            .and_then(|idx| self.get_index(idx))
+    }
+    /// Returns a reference to the least element in the set that is strictly greater than the
+    /// value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.successor(&2), Some(&3));
+    /// assert_eq!(set.successor(&4), Some(&5));
+    /// assert_eq!(set.successor(&5), None);
+    /// ```
+    pub fn successor<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_index(self.lower_bound_idx(Bound::Excluded(value)))
+    }
+    /// Returns a reference to the greatest element in the set that is strictly less than the
+    /// value, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3, 5]);
+    /// assert_eq!(set.predecessor(&2), Some(&1));
+    /// assert_eq!(set.predecessor(&4), Some(&3));
+    /// assert_eq!(set.predecessor(&1), None);
+    /// ```
+    pub fn predecessor<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.upper_bound_idx(Bound::Excluded(value))
+            .checked_sub(1)
+            .and_then(|idx| self.get_index(idx))
+    }
+    // Position of the first element above the bound, or the length of the set if there is none
+    fn lower_bound_idx<Q>(&self, bound: Bound<&Q>) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match bound {
+            Bound::Included(start) => self.rank_cmp(|item: &Q| item < start),
+            Bound::Excluded(start) => self.rank_cmp(|item: &Q| item <= start),
+            Bound::Unbounded => 0,
+        }
+    }
+    // Position right after the last element below the bound, or zero if there is none
+    fn upper_bound_idx<Q>(&self, bound: Bound<&Q>) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match bound {
+            Bound::Included(end) => self.rank_cmp(|item: &Q| item <= end),
+            Bound::Excluded(end) => self.rank_cmp(|item: &Q| item < end),
+            Bound::Unbounded => self.len(),
         }
-
-        None
     }
     /// Returns the number of elements in the set.
     ///
@@ -328,7 +588,7 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(v.len(), 1);
     /// ```
     pub fn len(&self) -> usize {
-        self.len
+        self.layout.len()
     }
     /// Adds a value to the set.
     ///
@@ -356,33 +616,246 @@ impl<T: Ord> BTreeSet<T> {
     /// ```
     pub fn insert(&mut self, value: T) -> bool {
         let node_idx = self.locate_node(&value);
-        if self.inner[node_idx].len() == DEFAULT_INNER_SIZE {
-            let new_node = self.inner[node_idx].halve();
-            let mut insert_node_idx = node_idx;
-            if value >= new_node[0] {
-                insert_node_idx += 1;
-            }
+        let node = &self.layout.nodes()[node_idx];
+        if !node.is_full(self.node_capacity) {
+            // The node looks for the value and inserts it in a single search
+            return self
+                .layout
+                .insert_if(node_idx, |node| NodeLike::insert(node, value).0);
+        }
+
+        let position = node.lower_bound(&value);
+        if node.get_ith(position) == Some(&value) {
+            return false;
+        }
 
-            self.inner.insert(node_idx + 1, new_node);
-            if NodeLike::insert(&mut self.inner[insert_node_idx], value).0 {
-                // Reconstruct the index after the new node and inner value inserts.
-                self.index = FenwickTree::from_iter(self.inner.iter().map(|node| node.len()));
-                self.len += 1;
+        self.insert_located(node_idx, position, value);
 
-                true
-            } else {
-                // Reconstruct the index after the new node insert even if the new value wasn't added.
-                self.index = FenwickTree::from_iter(self.inner.iter().map(|node| node.len()));
-                false
+        true
+    }
+
+    // Splits a full node at the given position, moving the elements from there onwards into a
+    // node set aside beforehand if any is left
+    fn split_node(spare: &mut Vec<N>, node: &mut N, at: usize) -> N {
+        match spare.pop() {
+            Some(mut new_node) => {
+                node.split_off_into(at, &mut new_node);
+                new_node
             }
-        } else if NodeLike::insert(&mut self.inner[node_idx], value).0 {
-            self.index.add_at(node_idx, 1);
-            self.len += 1;
+            None => node.split_off(at),
+        }
+    }
+    /// Adds a value to the set, starting the search for its position from a hint, and returns
+    /// whether the value was newly inserted along with a hint to where it now is.
+    ///
+    /// The hint is checked against the neighbouring elements, and the whole set is only searched
+    /// when the value does not go right at or after the hinted position. Feeding each returned
+    /// hint into the next insert makes loading mostly sorted values skip nearly every search. Any
+    /// hint is safe to pass, including [`Hint::default`] and stale ones, as long as it comes from
+    /// this set; a wrong one only costs the search it was meant to save.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::{BTreeSet, Hint};
+    ///
+    /// let mut set = BTreeSet::from_iter([0, 100]);
+    /// let mut hint = Hint::default();
+    /// for value in 1..100 {
+    ///     let (inserted, next) = set.insert_with_hint(hint, value);
+    ///     assert!(inserted);
+    ///     hint = next;
+    /// }
+    ///
+    /// assert_eq!(set.insert_with_hint(hint, 99).0, false);
+    /// assert!(set.iter().copied().eq(0..=100));
+    /// ```
+    pub fn insert_with_hint(&mut self, hint: Hint, value: T) -> (bool, Hint) {
+        let (node_idx, position) = self.locate_with_hint(hint, &value);
+        if self.layout.nodes()[node_idx].get_ith(position) == Some(&value) {
+            return (false, Hint { node_idx, position });
+        }
 
-            true
+        (true, self.insert_located(node_idx, position, value))
+    }
+    // Inserts a value at the position found for it, splitting its node first if it is full
+    fn insert_located(&mut self, node_idx: usize, position: usize, value: T) -> Hint {
+        let full = self.layout.nodes()[node_idx].is_full(self.node_capacity);
+        let spare = &mut self.spare;
+        let (node_idx, position) =
+            self.layout
+                .make_room(node_idx, position, self.split_policy, full, |node, at| {
+                    Self::split_node(spare, node, at)
+                });
+        self.layout
+            .insert_with(node_idx, |node| node.insert_at(position, value));
+
+        Hint { node_idx, position }
+    }
+    // Reserves room in the list of nodes, and sets empty nodes aside, for the splits that
+    // `additional` more elements may cause. Allocation failures are only reported, leaving the
+    // set as it was, when `fallible` is set.
+    fn reserve_nodes(&mut self, additional: usize, fallible: bool) -> Result<(), TryReserveError> {
+        let nodes = additional.div_ceil(self.split_policy.node_fill(self.node_capacity));
+        let missing = nodes.saturating_sub(self.spare.len());
+        let node_capacity = self.node_capacity;
+        if !fallible {
+            self.layout.reserve(nodes);
+            self.spare.reserve(missing);
+            self.spare
+                .extend((0..missing).map(|_| N::with_capacity(node_capacity)));
+
+            return Ok(());
+        }
+
+        self.layout.try_reserve(nodes)?;
+        self.spare.try_reserve(missing)?;
+        for _ in 0..missing {
+            let mut node = N::with_capacity(0);
+            node.try_reserve(node_capacity)?;
+            self.spare.push(node);
+        }
+
+        Ok(())
+    }
+    // Splits the node the value belongs to if it is full, and reserves room for one more element
+    // in the node it ends up in. Returns the index of that node, or leaves the set untouched if
+    // an allocation fails.
+    fn try_make_room(&mut self, value: &T) -> Result<usize, TryReserveError> {
+        let node_idx = self.locate_node(value);
+        let node = &mut self.layout.nodes_mut()[node_idx];
+        if !node.is_full(self.node_capacity) {
+            node.try_reserve(1)?;
+
+            return Ok(node_idx);
+        }
+
+        self.layout.try_reserve(1)?;
+        let node = &mut self.layout.nodes_mut()[node_idx];
+        let position = node.lower_bound(value);
+        let at = self.split_policy.split_point(node.len(), position);
+        let pooled = !self.spare.is_empty();
+        let mut new_node = match self.spare.pop() {
+            Some(mut new_node) => {
+                node.split_off_into(at, &mut new_node);
+                new_node
+            }
+            None => node.try_split_off(at)?,
+        };
+        let mut insert_node_idx = node_idx;
+        let reserved = if position >= at {
+            insert_node_idx += 1;
+            new_node.try_reserve(1)
         } else {
-            false
+            node.try_reserve(1)
+        };
+        if let Err(error) = reserved {
+            node.merge(&mut new_node);
+            if pooled {
+                self.spare.push(new_node);
+            }
+
+            return Err(error);
+        }
+
+        // The node is already split, and the layout only has to take the upper part in
+        self.layout.split_node_by(node_idx, at, |_, _| new_node);
+
+        Ok(insert_node_idx)
+    }
+    /// Adds a value to the set, like [`insert`](BTreeSet::insert), but reports an allocation
+    /// failure instead of aborting.
+    ///
+    /// If an error is returned the set is left as it was, and the value is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::new();
+    ///
+    /// assert_eq!(set.try_insert(2), Ok(true));
+    /// assert_eq!(set.try_insert(2), Ok(false));
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn try_insert(&mut self, value: T) -> Result<bool, TryReserveError> {
+        Ok(self.try_insert_ranked(value)?.is_some())
+    }
+    // Adds a value like `try_insert`, returning its position if it was not in the set yet
+    fn try_insert_ranked(&mut self, value: T) -> Result<Option<usize>, TryReserveError> {
+        if self.contains(&value) {
+            return Ok(None);
+        }
+
+        let node_idx = self.try_make_room(&value)?;
+        let (_, position) = self
+            .layout
+            .insert_with(node_idx, |node| NodeLike::insert(node, value));
+
+        Ok(Some(self.layout.offset(node_idx) + position))
+    }
+    /// Adds every value of the iterator to the set through [`try_insert`](BTreeSet::try_insert),
+    /// stopping at the first allocation failure.
+    ///
+    /// If an error is returned, the values inserted so far are taken out again, leaving the set
+    /// as it was, and they are dropped along with the rest of the iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::from_iter([1, 2]);
+    /// set.try_extend([2, 3, 4]).unwrap();
+    ///
+    /// assert!(set.iter().eq([1, 2, 3, 4].iter()));
+    /// ```
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), TryReserveError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        // Positions of the new values, which undoing the insertions from the last one finds them
+        // at again, as removals never allocate
+        let mut inserted = Vec::new();
+        for value in iter {
+            match inserted
+                .try_reserve(1)
+                .and_then(|_| self.try_insert_ranked(value))
+            {
+                Ok(Some(idx)) => inserted.push(idx),
+                Ok(None) => {}
+                Err(error) => {
+                    for idx in inserted.into_iter().rev() {
+                        self.pop_index(idx);
+                    }
+
+                    return Err(error);
+                }
+            }
         }
+
+        Ok(())
+    }
+    /// Tries to reserve room for at least `additional` more elements, like
+    /// [`with_capacity`](BTreeSet::with_capacity) does: the list of nodes is grown, and the nodes
+    /// that splits under the current [`SplitPolicy`] will need are allocated and set aside.
+    ///
+    /// If an error is returned the nodes already set aside are kept, and the set is otherwise
+    /// left as it was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let mut set: BTreeSet<u64> = BTreeSet::new();
+    /// set.try_reserve(100_000).expect("out of memory");
+    /// set.try_extend(0..100_000).expect("out of memory");
+    /// assert_eq!(set.len(), 100_000);
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.reserve_nodes(additional, true)
     }
 
     /// Adds a value to the set, replacing the existing element, if any, that is
@@ -428,8 +901,8 @@ impl<T: Ord> BTreeSet<T> {
         Q: Ord + ?Sized,
     {
         let (node_idx, position_within_node) = self.locate_value(value);
-        if let Some(candidate_node) = self.inner.get(node_idx) {
-            if let Some(candidate_value) = candidate_node.get(position_within_node) {
+        if let Some(candidate_node) = self.layout.nodes().get(node_idx) {
+            if let Some(candidate_value) = candidate_node.get_ith(position_within_node) {
                 return value == candidate_value.borrow();
             }
         }
@@ -444,8 +917,8 @@ impl<T: Ord> BTreeSet<T> {
         R: FnMut(&Q) -> bool,
     {
         let (node_idx, position_within_node) = self.locate_value_cmp(cmp);
-        if let Some(candidate_node) = self.inner.get(node_idx) {
-            if let Some(candidate_value) = candidate_node.get(position_within_node) {
+        if let Some(candidate_node) = self.layout.nodes().get(node_idx) {
+            if let Some(candidate_value) = candidate_node.get_ith(position_within_node) {
                 return cmp2(candidate_value.borrow());
             }
         }
@@ -453,29 +926,8 @@ impl<T: Ord> BTreeSet<T> {
         false
     }
     fn delete_at(&mut self, node_idx: usize, position_within_node: usize) -> T {
-        let removal = self.inner[node_idx].remove(position_within_node);
-
-        let mut decrease_length = false;
-        // check whether the node has to be deleted
-        if self.inner[node_idx].len() == 0 {
-            // delete it as long as it is not the last remaining node
-            if self.inner.len() > 1 {
-                self.inner.remove(node_idx);
-                self.len -= 1;
-                self.index = FenwickTree::from_iter(self.inner.iter().map(|node| node.len()));
-            } else {
-                decrease_length = true;
-            }
-        } else {
-            decrease_length = true;
-        }
-
-        if decrease_length {
-            self.index.sub_at(node_idx, 1);
-            self.len -= 1;
-        }
-
-        removal
+        self.layout
+            .remove_with(node_idx, |node| node.remove_ith(position_within_node))
     }
     fn delete<Q>(&mut self, value: &Q) -> (Option<T>, bool)
     where
@@ -485,8 +937,8 @@ impl<T: Ord> BTreeSet<T> {
         let mut removed = false;
         let mut removal = None;
         let (node_idx, position_within_node) = self.locate_value(value);
-        if let Some(candidate_node) = self.inner.get(node_idx) {
-            if let Some(candidate_value) = candidate_node.get(position_within_node) {
+        if let Some(candidate_node) = self.layout.nodes().get(node_idx) {
+            if let Some(candidate_value) = candidate_node.get_ith(position_within_node) {
                 if value == candidate_value.borrow() {
                     removal = Some(self.delete_at(node_idx, position_within_node));
                     removed = true;
@@ -506,8 +958,8 @@ impl<T: Ord> BTreeSet<T> {
         let mut removed = false;
         let mut removal = None;
         let (node_idx, position_within_node) = self.locate_value_cmp(cmp);
-        if let Some(candidate_node) = self.inner.get(node_idx) {
-            if let Some(candidate_value) = candidate_node.get(position_within_node) {
+        if let Some(candidate_node) = self.layout.nodes().get(node_idx) {
+            if let Some(candidate_value) = candidate_node.get_ith(position_within_node) {
                 if cmp2(candidate_value.borrow()) {
                     removal = Some(self.delete_at(node_idx, position_within_node));
                     removed = true;
@@ -583,8 +1035,8 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(set.first(), Some(&1));
     /// ```
     pub fn first(&self) -> Option<&T> {
-        if let Some(candidate_node) = self.inner.get(0) {
-            return candidate_node.get(0);
+        if let Some(candidate_node) = self.layout.nodes().get(0) {
+            return candidate_node.get_ith(0);
         }
 
         None
@@ -607,10 +1059,8 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(set.last(), Some(&2));
     /// ```
     pub fn last(&self) -> Option<&T> {
-        if let Some(candidate_node) = self.inner.get(self.inner.len() - 1) {
-            if candidate_node.len() > 0 {
-                return candidate_node.get(candidate_node.len() - 1);
-            }
+        if let Some(candidate_node) = self.layout.nodes().get(self.layout.nodes().len() - 1) {
+            return candidate_node.max();
         }
 
         None
@@ -633,8 +1083,8 @@ impl<T: Ord> BTreeSet<T> {
     /// ```
     pub fn pop_first(&mut self) -> Option<T> {
         let (first_node_idx, first_position_within_node) = (0, 0);
-        if let Some(candidate_node) = self.inner.get(first_node_idx) {
-            if candidate_node.get(first_position_within_node).is_some() {
+        if let Some(candidate_node) = self.layout.nodes().get(first_node_idx) {
+            if candidate_node.get_ith(first_position_within_node).is_some() {
                 return Some(self.delete_at(first_node_idx, first_position_within_node));
             }
         }
@@ -678,12 +1128,11 @@ impl<T: Ord> BTreeSet<T> {
     /// assert!(set.is_empty());
     /// ```
     pub fn pop_last(&mut self) -> Option<T> {
-        let last_node_idx = self.inner.len() - 1;
-        let mut last_position_within_node = self.inner[last_node_idx].len();
-        last_position_within_node = last_position_within_node.saturating_sub(1);
+        let last_node_idx = self.layout.nodes().len() - 1;
+        let last_position_within_node = self.layout.node_len(last_node_idx).saturating_sub(1);
 
-        if let Some(candidate_node) = self.inner.get(last_node_idx) {
-            if candidate_node.get(last_position_within_node).is_some() {
+        if let Some(candidate_node) = self.layout.nodes().get(last_node_idx) {
+            if candidate_node.get_ith(last_position_within_node).is_some() {
                 return Some(self.delete_at(last_node_idx, last_position_within_node));
             }
         }
@@ -808,7 +1257,7 @@ impl<T: Ord> BTreeSet<T> {
     /// assert_eq!(set_iter.next(), Some(&3));
     /// assert_eq!(set_iter.next(), None);
     /// ```
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T, N> {
         Iter::new(self)
     }
     /// Visits the elements representing the union,
@@ -829,7 +1278,7 @@ impl<T: Ord> BTreeSet<T> {
     /// let union: Vec<_> = a.union(&b).cloned().collect();
     /// assert_eq!(union, [1, 2]);
     /// ```
-    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T> {
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, T, N> {
         Union {
             merge_iter: MergeIter {
                 start: true,
@@ -860,7 +1309,7 @@ impl<T: Ord> BTreeSet<T> {
     /// let diff: Vec<_> = a.difference(&b).cloned().collect();
     /// assert_eq!(diff, [1]);
     /// ```
-    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, T> {
+    pub fn difference<'a>(&'a self, other: &'a Self) -> Difference<'a, T, N> {
         Difference {
             merge_iter: MergeIter {
                 start: true,
@@ -891,7 +1340,7 @@ impl<T: Ord> BTreeSet<T> {
     /// let sym_diff: Vec<_> = a.symmetric_difference(&b).cloned().collect();
     /// assert_eq!(sym_diff, [1, 3]);
     /// ```
-    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, T> {
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self) -> SymmetricDifference<'a, T, N> {
         SymmetricDifference {
             merge_iter: MergeIter {
                 start: true,
@@ -922,7 +1371,7 @@ impl<T: Ord> BTreeSet<T> {
     /// let intersection: Vec<_> = a.intersection(&b).cloned().collect();
     /// assert_eq!(intersection, [2]);
     /// ```
-    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T> {
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> Intersection<'a, T, N> {
         Intersection {
             merge_iter: MergeIter {
                 start: true,
@@ -955,7 +1404,7 @@ impl<T: Ord> BTreeSet<T> {
         F: FnMut(&Q) -> bool,
     {
         let mut positions_to_delete = vec![];
-        for (node_idx, node) in self.inner.iter().enumerate() {
+        for (node_idx, node) in self.layout.nodes().iter().enumerate() {
             for (position_within_node, item) in node.iter().enumerate() {
                 if !f(item.borrow()) {
                     positions_to_delete.push((node_idx, position_within_node));
@@ -977,26 +1426,8 @@ impl<T: Ord> BTreeSet<T> {
         P: FnMut(&Q) -> bool,
     {
         let (node_idx, position_within_node) = self.locate_value_cmp(cmp);
-        let first_node = self.inner[node_idx].split_off(position_within_node);
-        let mut remaining_nodes = vec![];
-        while self.inner.len() > node_idx + 1 {
-            remaining_nodes.push(self.inner.pop().unwrap());
-        }
-        remaining_nodes.reverse();
-        remaining_nodes.insert(0, first_node);
-        let mut latter_half = BTreeSet::default();
-        latter_half.len = remaining_nodes.iter().map(|node| node.len()).sum();
-        latter_half.inner = remaining_nodes;
-        latter_half.index = FenwickTree::from_iter(latter_half.inner.iter().map(|node| node.len()));
-
-        if self.inner[node_idx].len() == 0 && self.inner.len() > 1 {
-            self.inner.remove(node_idx);
-        }
 
-        self.index = FenwickTree::from_iter(self.inner.iter().map(|node| node.len()));
-        self.len = self.inner.iter().map(|node| node.len()).sum();
-
-        latter_half
+        self.split_off_index(self.layout.offset(node_idx) + position_within_node)
     }
     /// Splits the collection into two at the value. Returns a new collection
     /// with all elements greater than or equal to the value.
@@ -1032,27 +1463,66 @@ impl<T: Ord> BTreeSet<T> {
         T: Borrow<Q>,
         Q: Ord + ?Sized,
     {
-        let (node_idx, position_within_node) = self.locate_value(value);
-        let first_node = self.inner[node_idx].split_off(position_within_node);
-        let mut remaining_nodes = vec![];
-        while self.inner.len() > node_idx + 1 {
-            remaining_nodes.push(self.inner.pop().unwrap());
-        }
-        remaining_nodes.reverse();
-        remaining_nodes.insert(0, first_node);
-        let mut latter_half = BTreeSet::default();
-        latter_half.len = remaining_nodes.iter().map(|node| node.len()).sum();
-        latter_half.inner = remaining_nodes;
-        latter_half.index = FenwickTree::from_iter(latter_half.inner.iter().map(|node| node.len()));
-
-        if self.inner[node_idx].len() == 0 && self.inner.len() > 1 {
-            self.inner.remove(node_idx);
-        }
+        self.split_off_index(self.rank(value))
+    }
+    /// Splits the collection into two at the given position. Returns a new collection
+    /// with all elements whose index is greater than or equal to `idx`.
+    ///
+    /// Only the node containing the `idx`-th element is split, every node after it is moved
+    /// over as-is.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let mut a = BTreeSet::from_iter([1, 2, 3, 17, 41]);
+    ///
+    /// let b = a.split_off_index(2);
+    ///
+    /// assert_eq!(a.len(), 2);
+    /// assert_eq!(b.len(), 3);
+    ///
+    /// assert!(a.contains(&1));
+    /// assert!(a.contains(&2));
+    ///
+    /// assert!(b.contains(&3));
+    /// assert!(b.contains(&17));
+    /// assert!(b.contains(&41));
+    /// ```
+    pub fn split_off_index(&mut self, idx: usize) -> Self {
+        let empty = N::with_capacity(self.node_capacity);
 
-        self.index = FenwickTree::from_iter(self.inner.iter().map(|node| node.len()));
-        self.len = self.inner.iter().map(|node| node.len()).sum();
+        Self {
+            layout: self.layout.split_off_by(idx, empty, N::split_off),
+            node_capacity: self.node_capacity,
+            spare: Vec::new(),
+            split_policy: self.split_policy,
+            _marker: PhantomData,
+        }
+    }
+    /// Consumes the collection and splits it into two at the given position. The first
+    /// collection holds the elements whose index is less than `idx`, and the second one holds
+    /// the remaining ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([5, 4, 3, 2, 1]);
+    ///
+    /// let (top, rest) = set.split_at(2);
+    ///
+    /// assert_eq!(top.iter().cloned().collect::<Vec<_>>(), [1, 2]);
+    /// assert_eq!(rest.iter().cloned().collect::<Vec<_>>(), [3, 4, 5]);
+    /// ```
+    pub fn split_at(mut self, idx: usize) -> (Self, Self) {
+        let latter_half = self.split_off_index(idx);
 
-        latter_half
+        (self, latter_half)
     }
     /// Moves all elements from `other` into `self`, leaving `other` empty.
     ///
@@ -1087,13 +1557,111 @@ impl<T: Ord> BTreeSet<T> {
             self.replace(value);
         }
     }
+    /// Moves all elements from `other` into `self`, like [`append`](BTreeSet::append), but
+    /// reports an allocation failure instead of aborting.
+    ///
+    /// If an error is returned both sets are left as they were.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let mut a = BTreeSet::from_iter([1, 2, 3]);
+    /// let mut b = BTreeSet::from_iter([3, 4, 5]);
+    ///
+    /// a.try_append(&mut b).unwrap();
+    ///
+    /// assert!(a.iter().eq([1, 2, 3, 4, 5].iter()));
+    /// assert!(b.is_empty());
+    /// ```
+    pub fn try_append(&mut self, other: &mut Self) -> Result<(), TryReserveError> {
+        if other.is_empty() {
+            return Ok(());
+        }
+
+        let disjoint = match (self.last(), other.first()) {
+            (Some(last), Some(first)) => last < first,
+            _ => true,
+        };
+
+        if disjoint {
+            let node_capacity = self.node_capacity;
+            let empty = N::with_capacity(0);
+
+            // The nodes that meet are left apart if there is no memory to merge them
+            return self.layout.try_append(
+                &mut other.layout,
+                empty,
+                |last_node, len| {
+                    last_node.len() + len <= node_capacity && last_node.try_reserve(len).is_ok()
+                },
+                |last_node, first_node| last_node.merge(first_node),
+            );
+        }
+
+        // Overlapping sets are merged into nodes allocated upfront, filled as far as splits under
+        // the split policy fill them, so that neither set changes unless every allocation went
+        // through. Values of `other` replace the equal ones of `self`, as in `append`
+        let len = self.union(other).count();
+        let fill = self.split_policy.node_fill(self.node_capacity);
+        let mut merged: Layout<N> = Layout::try_with_len(len, fill, |node_len| {
+            let mut node = N::with_capacity(0);
+            node.try_reserve(node_len)?;
+
+            Ok(node)
+        })?;
+        let emptied = Layout::try_new(N::with_capacity(0))?;
+
+        let mut left = self
+            .layout
+            .nodes_mut()
+            .iter_mut()
+            .flat_map(N::drain)
+            .peekable();
+        let mut right = other
+            .layout
+            .nodes_mut()
+            .iter_mut()
+            .flat_map(N::drain)
+            .peekable();
+        let nodes = merged.nodes_mut();
+        let mut node_idx = 0;
+        loop {
+            let value = match (left.peek(), right.peek()) {
+                (Some(left_value), Some(right_value)) => match left_value.cmp(right_value) {
+                    Ordering::Less => left.next(),
+                    Ordering::Equal => {
+                        left.next();
+                        right.next()
+                    }
+                    Ordering::Greater => right.next(),
+                },
+                (Some(_), None) => left.next(),
+                (None, _) => right.next(),
+            };
+            let Some(value) = value else {
+                break;
+            };
+            if nodes[node_idx].len() == fill {
+                node_idx += 1;
+            }
+            let node = &mut nodes[node_idx];
+            node.insert_at(node.len(), value);
+        }
+        drop((left, right));
+
+        self.layout = merged;
+        other.layout = emptied;
+
+        Ok(())
+    }
     fn resolve_range<R>(&self, range: R) -> ((usize, usize, usize), (usize, usize, usize))
     where
         R: RangeBounds<usize>,
     {
         let mut global_front_idx: usize = 0;
-        let mut global_back_idx: usize =
-            self.index.prefix_sum(self.inner.len(), 0).saturating_sub(1);
+        let mut global_back_idx: usize = self.len().saturating_sub(1);
 
         // Solving global indexes
         let start = range.start_bound();
@@ -1153,7 +1721,7 @@ impl<T: Ord> BTreeSet<T> {
     /// }
     /// assert_eq!(Some(&5), set.range(4..).next());
     /// ```
-    pub fn range<R, Q>(&self, range: R) -> Range<'_, T>
+    pub fn range<R, Q>(&self, range: R) -> Range<'_, T, N>
     where
         Q: Ord + ?Sized,
         T: Borrow<Q>,
@@ -1172,6 +1740,176 @@ impl<T: Ord> BTreeSet<T> {
 
         self.range_idx(start_idx..=end_idx)
     }
+    // Both ends are global positions, the end being excluded
+    fn chunks_idx(&self, start: usize, end: usize) -> Chunks<'_, T, N>
+    where
+        N: AsRef<[T]>,
+    {
+        if start >= end {
+            return Chunks {
+                nodes: self.layout.nodes()[..0].iter(),
+                front_start: 0,
+                back_end: 0,
+                _marker: PhantomData,
+            };
+        }
+
+        let (front_node_idx, front_start) = self.locate_ith(start);
+        let (back_node_idx, back_end_idx) = self.locate_ith(end - 1);
+
+        Chunks {
+            nodes: self.layout.nodes()[front_node_idx..=back_node_idx].iter(),
+            front_start,
+            back_end: back_end_idx + 1,
+            _marker: PhantomData,
+        }
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the set, as contiguous
+    /// slices of elements in ascending order.
+    ///
+    /// Slices can be handed over to code that processes many elements at once, such as vectorized
+    /// kernels or bulk copies, without going through the elements one by one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter(0..10_000u32);
+    ///
+    /// let sum: u32 = set.chunks().map(|chunk| chunk.iter().sum::<u32>()).sum();
+    /// assert_eq!(sum, 49_995_000);
+    /// assert!(set.chunks().len() > 1);
+    /// assert!(set.chunks().flatten().eq(set.iter()));
+    /// ```
+    pub fn chunks(&self) -> Chunks<'_, T, N>
+    where
+        N: AsRef<[T]>,
+    {
+        self.chunks_idx(0, self.len())
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the set that hold elements
+    /// within the range, as contiguous slices trimmed to its bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter(0..10);
+    ///
+    /// assert_eq!(set.range_chunks(3..=7).next(), Some(&[3, 4, 5, 6, 7][..]));
+    /// assert_eq!(set.range_chunks(20..).next(), None);
+    /// ```
+    pub fn range_chunks<Q, R>(&self, range: R) -> Chunks<'_, T, N>
+    where
+        N: AsRef<[T]>,
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start_idx = self.lower_bound_idx(range.start_bound());
+        let end_idx = self.upper_bound_idx(range.end_bound());
+
+        self.chunks_idx(start_idx, end_idx)
+    }
+    /// Constructs a double-ended iterator over the elements of the set that start with the given
+    /// prefix.
+    ///
+    /// Elements starting with a prefix are contiguous in the set, so this only takes two
+    /// logarithmic lookups before iterating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter(["apple", "apricot", "banana", "ap"]);
+    /// assert!(set.prefix_range("ap").eq(&["ap", "apple", "apricot"]));
+    /// assert!(set.prefix_range("apr").rev().eq(&["apricot"]));
+    /// assert_eq!(set.prefix_range("c").next(), None);
+    /// ```
+    pub fn prefix_range<Q>(&self, prefix: &Q) -> Range<'_, T, N>
+    where
+        Q: Prefix + ?Sized,
+        T: Borrow<Q>,
+    {
+        let (start_idx, end_idx) = self.prefix_to_idx(prefix);
+        if start_idx == end_idx {
+            return self.empty_range();
+        }
+
+        self.range_idx(start_idx..end_idx)
+    }
+    /// Returns the number of elements of the set that start with the given prefix, in
+    /// logarithmic time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([b"ab".to_vec(), b"abc".to_vec(), b"b".to_vec()]);
+    /// assert_eq!(set.count_prefix(&b"ab"[..]), 2);
+    /// assert_eq!(set.count_prefix(&b""[..]), 3);
+    /// ```
+    pub fn count_prefix<Q>(&self, prefix: &Q) -> usize
+    where
+        Q: Prefix + ?Sized,
+        T: Borrow<Q>,
+    {
+        let (start_idx, end_idx) = self.prefix_to_idx(prefix);
+
+        end_idx - start_idx
+    }
+    fn prefix_to_idx<Q>(&self, prefix: &Q) -> (usize, usize)
+    where
+        Q: Prefix + ?Sized,
+        T: Borrow<Q>,
+    {
+        let start_idx = self.rank_cmp(|item: &Q| item < prefix);
+        let end_idx = self.rank_cmp(|item: &Q| item < prefix || item.has_prefix(prefix));
+
+        (start_idx, end_idx)
+    }
+    /// Constructs a double-ended iterator over the elements of the set whose leading fields are
+    /// equal to the given tuple prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([(1, 10, 'a'), (2, 10, 'b'), (2, 20, 'c'), (3, 10, 'd')]);
+    /// assert!(set.range_prefix(&(2,)).eq(&[(2, 10, 'b'), (2, 20, 'c')]));
+    /// assert!(set.range_prefix(&(2, 20)).eq(&[(2, 20, 'c')]));
+    /// assert_eq!(set.range_prefix(&(4,)).next(), None);
+    /// ```
+    pub fn range_prefix<P>(&self, prefix: &P) -> Range<'_, T, N>
+    where
+        T: TuplePrefix<P>,
+    {
+        let start_idx = self.rank_cmp(|item: &T| item.cmp_prefix(prefix) == Ordering::Less);
+        let end_idx = self.rank_cmp(|item: &T| item.cmp_prefix(prefix) != Ordering::Greater);
+        if start_idx == end_idx {
+            return self.empty_range();
+        }
+
+        self.range_idx(start_idx..end_idx)
+    }
+    fn empty_range(&self) -> Range<'_, T, N> {
+        Range {
+            spine_iter: Iter {
+                btree: self,
+                current_front_node_idx: 0,
+                current_front_idx: 0,
+                current_back_node_idx: 0,
+                current_back_idx: 0,
+                current_front_iterator: None,
+                current_back_iterator: None,
+            },
+        }
+    }
     /// Returns the position in which the given element would fall in the already-existing sorted
     /// order.
     ///
@@ -1197,10 +1935,232 @@ impl<T: Ord> BTreeSet<T> {
     {
         let (node_idx, position_within_node) = self.locate_value(value);
 
-        let offset = self.index.prefix_sum(node_idx, 0);
+        let offset = self.layout.offset(node_idx);
 
         offset + position_within_node
     }
+    /// Returns the `q`-quantile of the set, that is the smallest element that at least a `q`
+    /// fraction of the elements are less than or equal to, or `None` if the set is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not within `0.0..=1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter(1..=100);
+    /// assert_eq!(set.quantile(0.0), Some(&1));
+    /// assert_eq!(set.quantile(0.5), Some(&50));
+    /// assert_eq!(set.quantile(0.99), Some(&99));
+    /// assert_eq!(set.quantile(1.0), Some(&100));
+    /// ```
+    pub fn quantile(&self, q: f64) -> Option<&T> {
+        self.get_index(quantile_position(self.len(), q))
+    }
+    /// Returns the quantiles of the set for each of the given fractions, in the same order, or
+    /// nothing if the set is empty. See [`quantile`](BTreeSet::quantile) for more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any fraction is not within `0.0..=1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter(1..=1000);
+    /// assert_eq!(set.quantiles(&[0.5, 0.9, 0.99]), [&500, &900, &990]);
+    /// ```
+    pub fn quantiles(&self, qs: &[f64]) -> Vec<&T> {
+        qs.iter().filter_map(|q| self.quantile(*q)).collect()
+    }
+    /// Splits the set into at most `buckets` runs of consecutive elements of the same size, give
+    /// or take one, and returns the bounds and size of each. There are fewer buckets than asked
+    /// for only when the set has fewer elements, so that no bucket is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buckets` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    /// use indexset::stats::Bucket;
+    ///
+    /// let set = BTreeSet::from_iter((0..10).map(|value| value * 10));
+    /// assert_eq!(
+    ///     set.equi_depth_histogram(3),
+    ///     [
+    ///         Bucket { lower: &0, upper: &20, count: 3 },
+    ///         Bucket { lower: &30, upper: &50, count: 3 },
+    ///         Bucket { lower: &60, upper: &90, count: 4 },
+    ///     ]
+    /// );
+    /// ```
+    pub fn equi_depth_histogram(&self, buckets: usize) -> Vec<Bucket<&T>> {
+        let len = self.len();
+        let buckets = bucket_count(len, buckets);
+
+        (0..buckets)
+            .map(|idx| {
+                let positions = bucket_positions(len, buckets, idx);
+                Bucket {
+                    lower: self.get_index(positions.start).unwrap(),
+                    upper: self.get_index(positions.end - 1).unwrap(),
+                    count: positions.len(),
+                }
+            })
+            .collect()
+    }
+    /// Returns the index of the bucket of [`equi_depth_histogram(buckets)`] that the value
+    /// falls into, counting from zero, or `None` if the set is empty. Values outside of the set
+    /// fall into the bucket that would hold them, with values past the greatest element falling
+    /// into the last one.
+    ///
+    /// The value may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// [`equi_depth_histogram(buckets)`]: BTreeSet::equi_depth_histogram
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buckets` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter((0..10).map(|value| value * 10));
+    /// assert_eq!(set.ntile(&20, 3), Some(0));
+    /// assert_eq!(set.ntile(&25, 3), Some(1));
+    /// assert_eq!(set.ntile(&1000, 3), Some(2));
+    /// ```
+    pub fn ntile<Q>(&self, value: &Q, buckets: usize) -> Option<usize>
+    where
+        Q: Ord + ?Sized,
+        T: Borrow<Q>,
+    {
+        let len = self.len();
+        let buckets = bucket_count(len, buckets);
+        if len == 0 {
+            return None;
+        }
+
+        Some(bucket_of(len, buckets, self.rank(value).min(len - 1)))
+    }
+    /// Returns an element of the set picked uniformly at random, or `None` if the set is empty.
+    ///
+    /// See [`RngLike`](stats::RngLike) for how to plug a random number generator in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    /// # use indexset::stats::RngLike;
+    /// # struct XorShift(u64);
+    /// # impl RngLike for XorShift {
+    /// #     fn next_u64(&mut self) -> u64 {
+    /// #         self.0 ^= self.0 << 13;
+    /// #         self.0 ^= self.0 >> 7;
+    /// #         self.0 ^= self.0 << 17;
+    /// #         self.0
+    /// #     }
+    /// # }
+    /// # let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    ///
+    /// let set = BTreeSet::from_iter(0..100);
+    /// assert!(set.sample(&mut rng).is_some_and(|value| *value < 100));
+    /// assert_eq!(BTreeSet::<u32>::new().sample(&mut rng), None);
+    /// ```
+    pub fn sample(&self, rng: &mut impl RngLike) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.get_index(rng.gen_below(self.len() as u64) as usize)
+    }
+    /// Returns an element of the set within the range, picked uniformly at random among those in
+    /// it, or `None` if there are none.
+    ///
+    /// The range bounds may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    /// # use indexset::stats::RngLike;
+    /// # struct XorShift(u64);
+    /// # impl RngLike for XorShift {
+    /// #     fn next_u64(&mut self) -> u64 {
+    /// #         self.0 ^= self.0 << 13;
+    /// #         self.0 ^= self.0 >> 7;
+    /// #         self.0 ^= self.0 << 17;
+    /// #         self.0
+    /// #     }
+    /// # }
+    /// # let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    ///
+    /// let set = BTreeSet::from_iter(0..100);
+    /// let value = set.sample_range(20..30, &mut rng).unwrap();
+    /// assert!((20..30).contains(value));
+    /// assert_eq!(set.sample_range(200.., &mut rng), None);
+    /// ```
+    pub fn sample_range<Q, R>(&self, range: R, rng: &mut impl RngLike) -> Option<&T>
+    where
+        Q: Ord + ?Sized,
+        T: Borrow<Q>,
+        R: RangeBounds<Q>,
+    {
+        let start = self.lower_bound_idx(range.start_bound());
+        let end = self.upper_bound_idx(range.end_bound());
+        if end <= start {
+            return None;
+        }
+
+        self.get_index(start + rng.gen_below((end - start) as u64) as usize)
+    }
+    /// Returns `k` distinct elements of the set picked uniformly at random, in ascending order,
+    /// or every element if the set has no more than `k`.
+    ///
+    /// Only `k` random numbers are drawn, however large the set is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeSet;
+    /// # use indexset::stats::RngLike;
+    /// # struct XorShift(u64);
+    /// # impl RngLike for XorShift {
+    /// #     fn next_u64(&mut self) -> u64 {
+    /// #         self.0 ^= self.0 << 13;
+    /// #         self.0 ^= self.0 >> 7;
+    /// #         self.0 ^= self.0 << 17;
+    /// #         self.0
+    /// #     }
+    /// # }
+    /// # let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+    ///
+    /// let set = BTreeSet::from_iter(0..1000);
+    /// let sample = set.sample_k_distinct(10, &mut rng);
+    /// assert_eq!(sample.len(), 10);
+    /// assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+    /// assert_eq!(set.sample_k_distinct(2000, &mut rng).len(), 1000);
+    /// ```
+    pub fn sample_k_distinct(&self, k: usize, rng: &mut impl RngLike) -> Vec<&T> {
+        distinct_positions(self.len(), k, rng)
+            .into_iter()
+            .filter_map(|position| self.get_index(position))
+            .collect()
+    }
     fn rank_cmp<Q, P>(&self, cmp: P) -> usize
     where
         T: Borrow<Q>,
@@ -1209,11 +2169,11 @@ impl<T: Ord> BTreeSet<T> {
     {
         let (node_idx, position_within_node) = self.locate_value_cmp(cmp);
 
-        let offset = self.index.prefix_sum(node_idx, 0);
+        let offset = self.layout.offset(node_idx);
 
         offset + position_within_node
     }
-    fn range_idx<R>(&self, range: R) -> Range<'_, T>
+    fn range_idx<R>(&self, range: R) -> Range<'_, T, N>
     where
         R: RangeBounds<usize>,
     {
@@ -1222,17 +2182,26 @@ impl<T: Ord> BTreeSet<T> {
             (global_back_idx, back_node_idx, back_start_idx),
         ) = self.resolve_range(range);
 
-        let front_iter = if front_node_idx < self.inner.len() {
-            Some(self.inner[front_node_idx][front_start_idx..].iter())
-        } else {
-            None
-        };
+        // Both ends of the range are reached by skipping over the leading and trailing items of
+        // their nodes, which is cheap for the random-access iterators of most node types
+        let front_iter = self.layout.nodes().get(front_node_idx).map(|node| {
+            let mut iter = node.iter();
+            if front_start_idx > 0 {
+                iter.nth(front_start_idx - 1);
+            }
 
-        let back_iter = if back_node_idx < self.inner.len() {
-            Some(self.inner[back_node_idx][..=back_start_idx].iter())
-        } else {
-            None
-        };
+            iter
+        });
+
+        let back_iter = self.layout.nodes().get(back_node_idx).map(|node| {
+            let mut iter = node.iter();
+            let trailing_items = node.len().saturating_sub(back_start_idx + 1);
+            if trailing_items > 0 {
+                iter.nth_back(trailing_items - 1);
+            }
+
+            iter
+        });
 
         Range {
             spine_iter: Iter {
@@ -1277,61 +2246,210 @@ where
     }
 }
 
-impl<T> Default for BTreeSet<T>
+impl<T, N> Default for BTreeSet<T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     fn default() -> Self {
         let node_capacity = DEFAULT_INNER_SIZE;
 
         Self {
-            inner: vec![Node::with_capacity(node_capacity)],
-            index: FenwickTree::from_iter(vec![0]),
+            layout: Layout::new(N::with_capacity(node_capacity)),
             node_capacity,
-            len: 0,
+            spare: Vec::new(),
+            split_policy: SplitPolicy::default(),
+            _marker: PhantomData,
         }
     }
 }
 
-/// An iterator over the items of a `BTreeSet`.
-///
-/// This `struct` is created by the [`iter`] method on [`BTreeSet`].
+// The nodes set aside for splits are left behind, since a clone starts out with no more room
+// than it needs
+impl<T, N> Clone for BTreeSet<T, N>
+where
+    T: Ord + Clone,
+    N: NodeLike<T> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            layout: self.layout.clone(),
+            node_capacity: self.node_capacity,
+            spare: Vec::new(),
+            split_policy: self.split_policy,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// Sets are written out with the same fields as when they kept a Fenwick tree over their nodes
+// next to them, so that data serialized by either version reads back in the other one. The
+// index is rebuilt from the nodes on the way in
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(rename = "BTreeSet")]
+struct SerializeSet<'a, N> {
+    inner: &'a [N],
+    index: FenwickTree<usize>,
+    node_capacity: usize,
+    len: usize,
+    split_policy: SplitPolicy,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "BTreeSet")]
+struct DeserializeSet<N> {
+    inner: Vec<N>,
+    node_capacity: usize,
+    #[serde(default)]
+    split_policy: SplitPolicy,
+}
+
+#[cfg(feature = "serde")]
+impl<T, N> Serialize for BTreeSet<T, N>
+where
+    T: Ord,
+    N: NodeLike<T> + Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializeSet {
+            inner: self.layout.nodes(),
+            index: FenwickTree::from_iter(self.layout.nodes().iter().map(N::len)),
+            node_capacity: self.node_capacity,
+            len: self.len(),
+            split_policy: self.split_policy,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, N> Deserialize<'de> for BTreeSet<T, N>
+where
+    T: Ord,
+    N: NodeLike<T> + Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut set = DeserializeSet::<N>::deserialize(deserializer)?;
+        if set.inner.is_empty() {
+            set.inner.push(N::with_capacity(set.node_capacity));
+        }
+
+        Ok(Self {
+            layout: Layout::from_nodes_by(set.inner, N::len),
+            node_capacity: set.node_capacity,
+            spare: Vec::new(),
+            split_policy: set.split_policy,
+            _marker: PhantomData,
+        })
+    }
+}
+
+// Sets are compared, hashed and printed through their elements only, so that neither the way
+// they are split into nodes nor their capacity shows
+impl<T, N> PartialEq for BTreeSet<T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T, N> Eq for BTreeSet<T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+}
+
+impl<T, N> PartialOrd for BTreeSet<T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, N> Ord for BTreeSet<T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<T, N> Hash for BTreeSet<T, N>
+where
+    T: Ord + Hash,
+    N: NodeLike<T>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|item| item.hash(state));
+    }
+}
+
+impl<T, N> fmt::Debug for BTreeSet<T, N>
+where
+    T: Ord + fmt::Debug,
+    N: NodeLike<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the items of a `BTreeSet`.
+///
+/// This `struct` is created by the [`iter`] method on [`BTreeSet`].
 /// See its documentation for more.
 ///
 /// [`iter`]: BTreeSet::iter
-pub struct Iter<'a, T>
+pub struct Iter<'a, T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    btree: &'a BTreeSet<T>,
+    btree: &'a BTreeSet<T, N>,
     current_front_node_idx: usize,
     current_front_idx: usize,
     current_back_node_idx: usize,
     current_back_idx: usize,
-    current_front_iterator: Option<std::slice::Iter<'a, T>>,
-    current_back_iterator: Option<std::slice::Iter<'a, T>>,
+    current_front_iterator: Option<N::Iter<'a>>,
+    current_back_iterator: Option<N::Iter<'a>>,
 }
 
-impl<'a, T> Iter<'a, T>
+impl<'a, T, N> Iter<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    pub fn new(btree: &'a BTreeSet<T>) -> Self {
+    pub fn new(btree: &'a BTreeSet<T, N>) -> Self {
         Self {
             btree,
             current_front_node_idx: 0,
             current_front_idx: 0,
-            current_back_node_idx: btree.inner.len() - 1,
+            current_back_node_idx: btree.layout.nodes().len() - 1,
             current_back_idx: btree.len(),
-            current_front_iterator: Some(btree.inner[0].iter()),
-            current_back_iterator: Some(btree.inner[btree.inner.len() - 1].iter()),
+            current_front_iterator: Some(btree.layout.nodes()[0].iter()),
+            current_back_iterator: Some(
+                btree.layout.nodes()[btree.layout.nodes().len() - 1].iter(),
+            ),
         }
     }
 }
 
-impl<'a, T> Iterator for Iter<'a, T>
+impl<'a, T, N> Iterator for Iter<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = &'a T;
 
@@ -1344,20 +2462,21 @@ where
             Some(value)
         } else {
             self.current_front_node_idx += 1;
-            if self.current_front_node_idx >= self.btree.inner.len() {
+            if self.current_front_node_idx >= self.btree.layout.nodes().len() {
                 return None;
             }
             self.current_front_iterator =
-                Some(self.btree.inner[self.current_front_node_idx].iter());
+                Some(self.btree.layout.nodes()[self.current_front_node_idx].iter());
 
             self.next()
         }
     }
 }
 
-impl<'a, T> DoubleEndedIterator for Iter<'a, T>
+impl<'a, T, N> DoubleEndedIterator for Iter<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         if self.current_front_idx == self.current_back_idx {
@@ -1375,22 +2494,29 @@ where
                 return None;
             };
             self.current_back_node_idx -= 1;
-            self.current_back_iterator = Some(self.btree.inner[self.current_back_node_idx].iter());
+            self.current_back_iterator =
+                Some(self.btree.layout.nodes()[self.current_back_node_idx].iter());
 
             self.next_back()
         }
     }
 }
 
-impl<'a, T> FusedIterator for Iter<'a, T> where T: Ord {}
+impl<'a, T, N> FusedIterator for Iter<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+}
 
-impl<'a, T> IntoIterator for &'a BTreeSet<T>
+impl<'a, T, N> IntoIterator for &'a BTreeSet<T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = &'a T;
 
-    type IntoIter = Iter<'a, T>;
+    type IntoIter = Iter<'a, T, N>;
 
     fn into_iter(self) -> Self::IntoIter {
         Iter::new(self)
@@ -1403,16 +2529,18 @@ where
 /// (provided by the [`IntoIterator`] trait). See its documentation for more.
 ///
 /// [`into_iter`]: BTreeSet#method.into_iter
-pub struct IntoIter<T>
+pub struct IntoIter<T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    btree: BTreeSet<T>,
+    btree: BTreeSet<T, N>,
 }
 
-impl<T> Iterator for IntoIter<T>
+impl<T, N> Iterator for IntoIter<T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = T;
 
@@ -1421,24 +2549,31 @@ where
     }
 }
 
-impl<T> DoubleEndedIterator for IntoIter<T>
+impl<T, N> DoubleEndedIterator for IntoIter<T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         self.btree.pop_last()
     }
 }
 
-impl<T> FusedIterator for IntoIter<T> where T: Ord {}
+impl<T, N> FusedIterator for IntoIter<T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+}
 
-impl<T> IntoIterator for BTreeSet<T>
+impl<T, N> IntoIterator for BTreeSet<T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = T;
 
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, N>;
 
     fn into_iter(self) -> Self::IntoIter {
         // This will never panic, since there always is at least one node in the btree
@@ -1446,20 +2581,22 @@ where
     }
 }
 
-struct MergeIter<'a, T>
+struct MergeIter<'a, T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     start: bool,
-    left_iter: Iter<'a, T>,
+    left_iter: Iter<'a, T, N>,
     current_left: Option<&'a T>,
-    right_iter: Iter<'a, T>,
+    right_iter: Iter<'a, T, N>,
     current_right: Option<&'a T>,
 }
 
-impl<'a, T> Iterator for MergeIter<'a, T>
+impl<'a, T, N> Iterator for MergeIter<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = (Option<&'a T>, Option<&'a T>);
     fn next(&mut self) -> Option<Self::Item> {
@@ -1502,16 +2639,18 @@ where
 /// See its documentation for more.
 ///
 /// [`union`]: BTreeSet::union
-pub struct Union<'a, T>
+pub struct Union<'a, T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    merge_iter: MergeIter<'a, T>,
+    merge_iter: MergeIter<'a, T, N>,
 }
 
-impl<'a, T> Iterator for Union<'a, T>
+impl<'a, T, N> Iterator for Union<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = &'a T;
 
@@ -1535,7 +2674,12 @@ where
     }
 }
 
-impl<'a, T> FusedIterator for Union<'a, T> where T: Ord {}
+impl<'a, T, N> FusedIterator for Union<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+}
 
 /// A lazy iterator producing elements in the difference of `BTreeSet`s.
 ///
@@ -1543,16 +2687,18 @@ impl<'a, T> FusedIterator for Union<'a, T> where T: Ord {}
 /// See its documentation for more.
 ///
 /// [`difference`]: BTreeSet::difference
-pub struct Difference<'a, T>
+pub struct Difference<'a, T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    merge_iter: MergeIter<'a, T>,
+    merge_iter: MergeIter<'a, T, N>,
 }
 
-impl<'a, T> Iterator for Difference<'a, T>
+impl<'a, T, N> Iterator for Difference<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = &'a T;
 
@@ -1577,7 +2723,12 @@ where
     }
 }
 
-impl<'a, T> FusedIterator for Difference<'a, T> where T: Ord {}
+impl<'a, T, N> FusedIterator for Difference<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+}
 
 /// A lazy iterator producing elements in the symmetric difference of `BTreeSet`s.
 ///
@@ -1585,16 +2736,18 @@ impl<'a, T> FusedIterator for Difference<'a, T> where T: Ord {}
 /// [`BTreeSet`]. See its documentation for more.
 ///
 /// [`symmetric_difference`]: BTreeSet::symmetric_difference
-pub struct SymmetricDifference<'a, T>
+pub struct SymmetricDifference<'a, T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    merge_iter: MergeIter<'a, T>,
+    merge_iter: MergeIter<'a, T, N>,
 }
 
-impl<'a, T> Iterator for SymmetricDifference<'a, T>
+impl<'a, T, N> Iterator for SymmetricDifference<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = &'a T;
 
@@ -1622,7 +2775,12 @@ where
     }
 }
 
-impl<'a, T> FusedIterator for SymmetricDifference<'a, T> where T: Ord {}
+impl<'a, T, N> FusedIterator for SymmetricDifference<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+}
 
 /// A lazy iterator producing elements in the intersection of `BTreeSet`s.
 ///
@@ -1630,16 +2788,18 @@ impl<'a, T> FusedIterator for SymmetricDifference<'a, T> where T: Ord {}
 /// See its documentation for more.
 ///
 /// [`intersection`]: BTreeSet::intersection
-pub struct Intersection<'a, T>
+pub struct Intersection<'a, T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    merge_iter: MergeIter<'a, T>,
+    merge_iter: MergeIter<'a, T, N>,
 }
 
-impl<'a, T> Iterator for Intersection<'a, T>
+impl<'a, T, N> Iterator for Intersection<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = &'a T;
 
@@ -1663,7 +2823,12 @@ where
     }
 }
 
-impl<'a, T> FusedIterator for Intersection<'a, T> where T: Ord {}
+impl<'a, T, N> FusedIterator for Intersection<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+}
 
 /// An iterator over a sub-range of items in a `BTreeSet`.
 ///
@@ -1671,16 +2836,18 @@ impl<'a, T> FusedIterator for Intersection<'a, T> where T: Ord {}
 /// See its documentation for more.
 ///
 /// [`range`]: BTreeSet::range
-pub struct Range<'a, T>
+pub struct Range<'a, T, N = Node<T>>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
-    spine_iter: Iter<'a, T>,
+    spine_iter: Iter<'a, T, N>,
 }
 
-impl<'a, T> Iterator for Range<'a, T>
+impl<'a, T, N> Iterator for Range<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Item = &'a T;
 
@@ -1689,20 +2856,106 @@ where
     }
 }
 
-impl<'a, T> DoubleEndedIterator for Range<'a, T>
+impl<'a, T, N> DoubleEndedIterator for Range<'a, T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         self.spine_iter.next_back()
     }
 }
 
-impl<'a, T> FusedIterator for Range<'a, T> where T: Ord {}
+impl<'a, T, N> FusedIterator for Range<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+}
+
+/// An iterator over the contents of the nodes of a `BTreeSet`, as slices.
+///
+/// This `struct` is created by the [`chunks`] and [`range_chunks`] methods on [`BTreeSet`].
+/// See their documentation for more.
+///
+/// [`chunks`]: BTreeSet::chunks
+/// [`range_chunks`]: BTreeSet::range_chunks
+pub struct Chunks<'a, T, N = Node<T>>
+where
+    T: Ord,
+    N: NodeLike<T> + AsRef<[T]>,
+{
+    nodes: std::slice::Iter<'a, N>,
+    // Elements of the first node that come before the range
+    front_start: usize,
+    // End of the range within the last node
+    back_end: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T, N> Iterator for Chunks<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T> + AsRef<[T]>,
+{
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next()?.as_ref();
+        let end = match self.nodes.len() {
+            0 => self.back_end,
+            _ => node.len(),
+        };
+        let chunk = &node[self.front_start..end];
+        self.front_start = 0;
+
+        Some(chunk)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl<'a, T, N> DoubleEndedIterator for Chunks<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T> + AsRef<[T]>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next_back()?.as_ref();
+        let start = match self.nodes.len() {
+            0 => self.front_start,
+            _ => 0,
+        };
+        let chunk = &node[start..self.back_end];
+        self.back_end = self
+            .nodes
+            .as_slice()
+            .last()
+            .map_or(0, |node| node.as_ref().len());
+
+        Some(chunk)
+    }
+}
+
+impl<'a, T, N> ExactSizeIterator for Chunks<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T> + AsRef<[T]>,
+{
+}
+
+impl<'a, T, N> FusedIterator for Chunks<'a, T, N>
+where
+    T: Ord,
+    N: NodeLike<T> + AsRef<[T]>,
+{
+}
 
-impl<T> Index<usize> for BTreeSet<T>
+impl<T, N> Index<usize> for BTreeSet<T, N>
 where
     T: Ord,
+    N: NodeLike<T>,
 {
     type Output = T;
 
@@ -1937,7 +3190,7 @@ where
 /// ]);
 /// ```
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone)]
 pub struct BTreeMap<K, V>
 where
     K: Ord,
@@ -2008,6 +3261,28 @@ where
     pub fn append(&mut self, other: &mut Self) {
         self.set.append(&mut other.set)
     }
+    /// Moves all elements from `other` into `self`, like [`append`](BTreeMap::append), but
+    /// reports an allocation failure instead of aborting.
+    ///
+    /// If an error is returned both maps are left as they were.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut a = BTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// let mut b = BTreeMap::from_iter([(2, "c"), (3, "d")]);
+    ///
+    /// a.try_append(&mut b).unwrap();
+    ///
+    /// assert_eq!(a[&2], "c");
+    /// assert_eq!(a.len(), 3);
+    /// assert!(b.is_empty());
+    /// ```
+    pub fn try_append(&mut self, other: &mut Self) -> Result<(), TryReserveError> {
+        self.set.try_append(&mut other.set)
+    }
     /// Clears the map, removing all elements.
     ///
     /// # Examples
@@ -2148,8 +3423,8 @@ where
         let (node_idx, position_within_node) = self
             .set
             .locate_value_cmp(|item: &Pair<K, V>| item.key.borrow() < key);
-        if let Some(candidate_node) = self.set.inner.get(node_idx) {
-            if let Some(candidate_value) = candidate_node.get(position_within_node) {
+        if let Some(candidate_node) = self.set.layout.nodes().get(node_idx) {
+            if let Some(candidate_value) = candidate_node.get_ith(position_within_node) {
                 if candidate_value.key.borrow() == key {
                     return Some((&candidate_value.key, &candidate_value.value));
                 }
@@ -2185,11 +3460,8 @@ where
         let (node_idx, position_within_node) = self
             .set
             .locate_value_cmp(|item: &Pair<K, V>| item.key.borrow() < key);
-        if self.set.inner.get(node_idx).is_some()
-            && self.set.inner[node_idx].get(position_within_node).is_some()
-        {
-            let entry = self.set.inner[node_idx].get_mut(position_within_node)?;
-
+        let entry = self.set.layout.nodes_mut()[node_idx].get_mut(position_within_node)?;
+        if entry.key.borrow() == key {
             return Some(&mut entry.value);
         }
 
@@ -2260,6 +3532,139 @@ where
             None
         }
     }
+    /// Inserts a key-value pair into the map, starting the search for the key from a hint, and
+    /// returns the previous value of the key, if any, along with a hint to where the pair now is.
+    ///
+    /// See [`BTreeSet::insert_with_hint`] for how hints are used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::{BTreeMap, Hint};
+    ///
+    /// let mut map = BTreeMap::new();
+    /// let mut hint = Hint::default();
+    /// for key in 0..100 {
+    ///     hint = map.insert_with_hint(hint, key, key * 2).1;
+    /// }
+    ///
+    /// let cursor = map.lower_bound(std::ops::Bound::Included(&50));
+    /// assert_eq!(map.insert_with_hint(cursor.hint(), 50, 0).0, Some(100));
+    /// assert_eq!(map[&50], 0);
+    /// ```
+    pub fn insert_with_hint(&mut self, hint: Hint, key: K, value: V) -> (Option<V>, Hint) {
+        let (node_idx, position) = self.set.locate_with_hint(hint, &key);
+        if let Some(entry) = self.set.layout.nodes_mut()[node_idx].get_ith_mut(position) {
+            if entry.key == key {
+                let old_value = std::mem::replace(&mut entry.value, value);
+                return (Some(old_value), Hint { node_idx, position });
+            }
+        }
+
+        let pair = Pair { key, value };
+        (None, self.set.insert_located(node_idx, position, pair))
+    }
+    /// Inserts a key-value pair into the map, like [`insert`](BTreeMap::insert), but reports an
+    /// allocation failure instead of aborting.
+    ///
+    /// If an error is returned the map is left as it was, and the pair is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.try_insert(37, "a"), Ok(None));
+    /// assert_eq!(map.try_insert(37, "b"), Ok(Some("a")));
+    /// assert_eq!(map[&37], "b");
+    /// ```
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, TryReserveError> {
+        Ok(self.try_insert_ranked(key, value)?.1)
+    }
+    // Inserts a pair like `try_insert`, also returning the position of the key
+    fn try_insert_ranked(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<(usize, Option<V>), TryReserveError> {
+        let (node_idx, position) = self
+            .set
+            .locate_value_cmp(|item: &Pair<K, V>| item.key < key);
+        if let Some(entry) = self.set.layout.nodes_mut()[node_idx].get_ith_mut(position) {
+            if entry.key == key {
+                let old_value = std::mem::replace(&mut entry.value, value);
+                return Ok((self.set.layout.offset(node_idx) + position, Some(old_value)));
+            }
+        }
+
+        match self.set.try_insert_ranked(Pair { key, value })? {
+            Some(idx) => Ok((idx, None)),
+            None => unreachable!("the key was not in the map"),
+        }
+    }
+    /// Inserts every key-value pair of the iterator into the map through
+    /// [`try_insert`](BTreeMap::try_insert), stopping at the first allocation failure.
+    ///
+    /// If an error is returned, the pairs inserted so far are taken out again and the values they
+    /// replaced put back, leaving the map as it was, and they are dropped along with the rest of
+    /// the iterator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, "a")]);
+    /// map.try_extend([(1, "b"), (2, "c")]).unwrap();
+    ///
+    /// assert_eq!(map[&1], "b");
+    /// assert_eq!(map[&2], "c");
+    /// ```
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), TryReserveError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        // Positions of the keys, along with the values they had, which undoing the insertions
+        // from the last one finds them at again
+        let mut changes = Vec::new();
+        for (key, value) in iter {
+            match changes
+                .try_reserve(1)
+                .and_then(|_| self.try_insert_ranked(key, value))
+            {
+                Ok(change) => changes.push(change),
+                Err(error) => {
+                    for (idx, old_value) in changes.into_iter().rev() {
+                        match old_value {
+                            Some(old_value) => *self.get_mut_index(idx).unwrap() = old_value,
+                            None => {
+                                self.pop_index(idx);
+                            }
+                        }
+                    }
+
+                    return Err(error);
+                }
+            }
+        }
+
+        Ok(())
+    }
+    /// Tries to reserve room in the list of nodes for the ones that `additional` more entries may
+    /// split off into. See [`BTreeSet::try_reserve`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut map: BTreeMap<u64, u64> = BTreeMap::new();
+    /// map.try_reserve(10_000).expect("out of memory");
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.set.try_reserve(additional)
+    }
     /// Creates a consuming iterator visiting all the keys, in sorted order.
     /// The map cannot be used after calling this.
     /// The iterator element type is `K`.
@@ -2368,9 +3773,9 @@ where
     /// }
     /// ```
     pub fn iter_mut(&mut self) -> IterMut<K, V> {
-        let last_node_idx = self.set.inner.len() - 1;
+        let last_node_idx = self.set.layout.nodes().len() - 1;
         let len = self.set.len();
-        let mut inner = self.set.inner.iter_mut();
+        let mut inner = self.set.layout.nodes_mut().iter_mut();
         let front_iter = {
             if let Some(node) = inner.next() {
                 node.iter_mut()
@@ -2493,6 +3898,46 @@ where
             set: BTreeSet::with_maximum_node_size(maximum_node_size),
         }
     }
+    /// Makes a new, empty `BTreeMap` with room for at least `capacity` entries, allocating its
+    /// nodes upfront. See [`BTreeSet::with_capacity`] for more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::with_capacity(10_000);
+    /// for key in 0..10_000 {
+    ///     map.insert(key, key * 2);
+    /// }
+    /// assert_eq!(map.len(), 10_000);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            set: BTreeSet::with_capacity(capacity),
+        }
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::{BTreeMap, SplitPolicy};
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.set_split_policy(SplitPolicy::RightBiased);
+    /// for id in 0..10_000 {
+    ///     map.insert(id, id.to_string());
+    /// }
+    /// assert_eq!(map.len(), 10_000);
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.set.set_split_policy(split_policy)
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.set.split_policy()
+    }
     /// Removes and returns the first element in the map.
     /// The key of this element is the minimum key that was in the map.
     ///
@@ -2606,24 +4051,235 @@ where
             inner: self.set.range_idx(start_idx..=end_idx),
         }
     }
-    fn range_to_idx<Q, R>(&self, range: R) -> (usize, usize)
+    /// Gets a double-ended iterator over the contents of the nodes of the map, as contiguous
+    /// slices of entries in ascending order of keys.
+    ///
+    /// Entries are laid out as [`Pair`]s, with keys and values side by side, and their values can
+    /// be changed through [`chunks_mut`](Self::chunks_mut). See
+    /// [`soa::BTreeMap`](crate::soa::BTreeMap) for a map that hands out separate slices of keys
+    /// and values, including mutable ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, 10), (2, 20), (3, 30)]);
+    ///
+    /// let chunk = map.chunks().next().unwrap();
+    /// assert_eq!(chunk.len(), 3);
+    /// assert_eq!((chunk[0].key, chunk[0].value), (1, 10));
+    /// ```
+    pub fn chunks(&self) -> Chunks<'_, Pair<K, V>> {
+        self.set.chunks()
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the map that hold keys
+    /// within the range, as contiguous slices of entries trimmed to its bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, 10), (2, 20), (3, 30)]);
+    ///
+    /// let values: Vec<_> = map.range_chunks(2..).flatten().map(|pair| pair.value).collect();
+    /// assert_eq!(values, [20, 30]);
+    /// ```
+    pub fn range_chunks<Q, R>(&self, range: R) -> Chunks<'_, Pair<K, V>>
     where
         Q: Ord + ?Sized,
         K: Borrow<Q>,
         R: RangeBounds<Q>,
     {
-        let start_idx = match range.start_bound() {
-            Bound::Included(bound) => self
-                .set
-                .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < bound),
-            Bound::Excluded(bound) => {
-                self.set
-                    .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < bound)
-                    + 1
-            }
-            Bound::Unbounded => 0,
-        };
-        let end_idx = match range.end_bound() {
+        let (start_idx, end_idx) = self.key_range_to_idx(range);
+
+        self.set.chunks_idx(start_idx, end_idx)
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the map, as chunks of
+    /// entries in ascending order of keys whose values can be changed in place.
+    ///
+    /// Keys cannot be changed through a [`ChunkMut`], as that could break the order of the map,
+    /// which is why it is not a mutable slice of [`Pair`]s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, 1.0), (2, 2.0), (3, 3.0)]);
+    /// for chunk in map.chunks_mut() {
+    ///     chunk.into_iter().for_each(|(_, value)| *value *= 0.5);
+    /// }
+    /// assert!(map.values().eq(&[0.5, 1.0, 1.5]));
+    /// ```
+    pub fn chunks_mut(&mut self) -> ChunksMut<'_, K, V> {
+        let len = self.len();
+
+        self.chunks_mut_idx(0, len)
+    }
+    // Both ends are global positions, the end being excluded
+    fn chunks_mut_idx(&mut self, start: usize, end: usize) -> ChunksMut<'_, K, V> {
+        let (nodes, front_start, back_end) = self.set.layout.bounds(start, end);
+
+        ChunksMut {
+            nodes: self.set.layout.nodes_mut()[nodes].iter_mut(),
+            front_start,
+            back_end,
+        }
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the map that hold keys
+    /// within the range, as chunks of entries trimmed to its bounds whose values can be changed
+    /// in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, 0), (2, 0), (3, 0)]);
+    /// for mut chunk in map.range_chunks_mut(..3) {
+    ///     assert_eq!(chunk.as_slice()[0].key, 1);
+    ///     chunk.iter_mut().for_each(|(_, value)| *value = 7);
+    /// }
+    /// assert!(map.values().eq(&[7, 7, 0]));
+    /// ```
+    pub fn range_chunks_mut<Q, R>(&mut self, range: R) -> ChunksMut<'_, K, V>
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+        R: RangeBounds<Q>,
+    {
+        let (start_idx, end_idx) = self.key_range_to_idx(range);
+
+        self.chunks_mut_idx(start_idx, end_idx)
+    }
+    /// Constructs a double-ended iterator over the entries of the map whose keys start with the
+    /// given prefix.
+    ///
+    /// Keys starting with a prefix are contiguous in the map, so this only takes two logarithmic
+    /// lookups before iterating.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert("user:1".to_string(), "alice");
+    /// map.insert("user:2".to_string(), "bob");
+    /// map.insert("group:1".to_string(), "admins");
+    ///
+    /// let users: Vec<_> = map.prefix_range("user:").map(|(_, &name)| name).collect();
+    /// assert_eq!(users, ["alice", "bob"]);
+    /// assert_eq!(map.prefix_range("session:").next(), None);
+    /// ```
+    pub fn prefix_range<Q>(&self, prefix: &Q) -> RangeMap<'_, K, V>
+    where
+        Q: Prefix + ?Sized,
+        K: Borrow<Q>,
+    {
+        let (start_idx, end_idx) = self.prefix_to_idx(prefix);
+        if start_idx == end_idx {
+            return RangeMap {
+                inner: self.set.empty_range(),
+            };
+        }
+
+        RangeMap {
+            inner: self.set.range_idx(start_idx..end_idx),
+        }
+    }
+    /// Returns the number of entries of the map whose keys start with the given prefix, in
+    /// logarithmic time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([("ab", 1), ("abc", 2), ("b", 3)]);
+    /// assert_eq!(map.count_prefix("ab"), 2);
+    /// assert_eq!(map.count_prefix("abcd"), 0);
+    /// ```
+    pub fn count_prefix<Q>(&self, prefix: &Q) -> usize
+    where
+        Q: Prefix + ?Sized,
+        K: Borrow<Q>,
+    {
+        let (start_idx, end_idx) = self.prefix_to_idx(prefix);
+
+        end_idx - start_idx
+    }
+    /// Constructs a double-ended iterator over the entries of the map whose keys' leading fields
+    /// are equal to the given tuple prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut events = BTreeMap::new();
+    /// events.insert(("acme", 2, 1), "login");
+    /// events.insert(("acme", 1, 2), "signup");
+    /// events.insert(("initech", 1, 3), "login");
+    ///
+    /// let acme: Vec<_> = events.range_prefix(&("acme",)).map(|(_, &event)| event).collect();
+    /// assert_eq!(acme, ["signup", "login"]);
+    /// ```
+    pub fn range_prefix<P>(&self, prefix: &P) -> RangeMap<'_, K, V>
+    where
+        K: TuplePrefix<P>,
+    {
+        let start_idx = self
+            .set
+            .rank_cmp(|item: &Pair<K, V>| item.key.cmp_prefix(prefix) == Ordering::Less);
+        let end_idx = self
+            .set
+            .rank_cmp(|item: &Pair<K, V>| item.key.cmp_prefix(prefix) != Ordering::Greater);
+        if start_idx == end_idx {
+            return RangeMap {
+                inner: self.set.empty_range(),
+            };
+        }
+
+        RangeMap {
+            inner: self.set.range_idx(start_idx..end_idx),
+        }
+    }
+    fn prefix_to_idx<Q>(&self, prefix: &Q) -> (usize, usize)
+    where
+        Q: Prefix + ?Sized,
+        K: Borrow<Q>,
+    {
+        let start_idx = self
+            .set
+            .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < prefix);
+        let end_idx = self.set.rank_cmp(|item: &Pair<K, V>| {
+            let key = item.key.borrow();
+            key < prefix || key.has_prefix(prefix)
+        });
+
+        (start_idx, end_idx)
+    }
+    fn range_to_idx<Q, R>(&self, range: R) -> (usize, usize)
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+        R: RangeBounds<Q>,
+    {
+        let start_idx = match range.start_bound() {
+            Bound::Included(bound) => self
+                .set
+                .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < bound),
+            Bound::Excluded(bound) => {
+                self.set
+                    .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < bound)
+                    + 1
+            }
+            Bound::Unbounded => 0,
+        };
+        let end_idx = match range.end_bound() {
             Bound::Included(bound) => self
                 .set
                 .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < bound),
@@ -2637,6 +4293,19 @@ where
 
         (start_idx, end_idx)
     }
+    // Resolves the bounds into a half-open range of positions, which is empty whenever no key
+    // falls within them
+    fn key_range_to_idx<Q, R>(&self, range: R) -> (usize, usize)
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+        R: RangeBounds<Q>,
+    {
+        let start_idx = self.lower_bound_idx(range.start_bound());
+        let end_idx = self.upper_bound_idx(range.end_bound());
+
+        (start_idx, end_idx.max(start_idx))
+    }
     /// Constructs a mutable double-ended iterator over a sub-range of elements in the map.
     /// The simplest way is to use the range syntax `min..max`, thus `range(min..max)` will
     /// yield elements from min (inclusive) to max (exclusive).
@@ -2683,9 +4352,9 @@ where
             (global_front_idx, front_node_idx, front_start_idx),
             (global_back_idx, back_node_idx, back_start_idx),
         ) = self.set.resolve_range(range);
-        let end = self.set.inner[back_node_idx].len();
+        let end = self.set.layout.nodes()[back_node_idx].len();
 
-        let mut inner = self.set.inner.iter_mut();
+        let mut inner = self.set.layout.nodes_mut().iter_mut();
 
         let mut front_iter = {
             if let Some(node) = inner.nth(front_node_idx) {
@@ -2784,7 +4453,7 @@ where
     pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
     where
         K: Borrow<Q> + Ord,
-        Q: Ord,
+        Q: Ord + ?Sized,
     {
         let old_entry = self.set.delete_cmp(
             |item: &Pair<K, V>| item.key.borrow() < key,
@@ -2820,7 +4489,7 @@ where
         F: FnMut(&Q, &mut V) -> bool,
     {
         let mut positions_to_delete = vec![];
-        for (node_idx, node) in self.set.inner.iter_mut().enumerate() {
+        for (node_idx, node) in self.set.layout.nodes_mut().iter_mut().enumerate() {
             for (position_within_node, item) in node.iter_mut().enumerate() {
                 if !f(item.key.borrow(), &mut item.value) {
                     positions_to_delete.push((node_idx, position_within_node));
@@ -2876,6 +4545,55 @@ where
                 .split_off_cmp(|item: &Pair<K, V>| item.key.borrow() < key),
         }
     }
+    /// Splits the collection into two at the given position. Returns everything whose index is
+    /// greater than or equal to `idx`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let mut a = BTreeMap::from_iter([(1, "a"), (2, "b"), (3, "c"), (17, "d"), (41, "e")]);
+    ///
+    /// let b = a.split_off_index(2);
+    ///
+    /// assert_eq!(a.len(), 2);
+    /// assert_eq!(b.len(), 3);
+    ///
+    /// assert_eq!(a[&1], "a");
+    /// assert_eq!(a[&2], "b");
+    ///
+    /// assert_eq!(b[&3], "c");
+    /// assert_eq!(b[&17], "d");
+    /// assert_eq!(b[&41], "e");
+    /// ```
+    pub fn split_off_index(&mut self, idx: usize) -> Self {
+        BTreeMap {
+            set: self.set.split_off_index(idx),
+        }
+    }
+    /// Consumes the map and splits it into two at the given position. The first map holds the
+    /// entries whose index is less than `idx`, and the second one holds the remaining ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(3, "c"), (1, "a"), (2, "b")]);
+    ///
+    /// let (top, rest) = map.split_at(1);
+    ///
+    /// assert_eq!(top.keys().cloned().collect::<Vec<_>>(), [1]);
+    /// assert_eq!(rest.keys().cloned().collect::<Vec<_>>(), [2, 3]);
+    /// ```
+    pub fn split_at(mut self, idx: usize) -> (Self, Self) {
+        let latter_half = self.split_off_index(idx);
+
+        (self, latter_half)
+    }
     /// Gets an iterator over the values of the map, in order by key.
     ///
     /// # Examples
@@ -3043,82 +4761,411 @@ where
     /// ```
     pub fn lower_bound<Q>(&self, bound: Bound<&Q>) -> CursorMap<'_, K, V>
     where
-        K: Borrow<Q> + Ord,
-        Q: Ord,
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
     {
-        let start_idx = match bound {
-            Bound::Included(start) => self
-                .set
-                .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < start),
-            Bound::Excluded(start) => {
-                self.set
-                    .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < start)
-                    + 1
-            }
-            Bound::Unbounded => 0,
+        CursorMap {
+            cursor: Cursor {
+                set: &self.set,
+                idx: self.lower_bound_idx(bound),
+            },
+        }
+    }
+    /// Returns a [`Cursor`] pointing at the last element that is below the
+    /// given bound.
+    ///
+    /// If no such element exists then a cursor pointing at the "ghost"
+    /// non-element is returned.
+    ///
+    /// Passing [`Bound::Unbounded`] will return a cursor pointing at the last
+    /// element of the map.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    /// use std::ops::Bound;
+    ///
+    /// let mut a = BTreeMap::new();
+    /// a.insert(1, "a");
+    /// a.insert(2, "b");
+    /// a.insert(3, "c");
+    /// a.insert(4, "d");
+    /// let cursor = a.upper_bound(Bound::Excluded(&3));
+    /// assert_eq!(cursor.key(), Some(&2));
+    /// let cursor = a.upper_bound(Bound::Unbounded);
+    /// assert_eq!(cursor.key(), Some(&4));
+    /// ```
+    pub fn upper_bound<Q>(&self, bound: Bound<&Q>) -> CursorMap<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let idx = match self.upper_bound_idx(bound) {
+            0 => self.len(),
+            end_idx => end_idx - 1,
         };
 
         CursorMap {
             cursor: Cursor {
                 set: &self.set,
-                idx: start_idx,
+                idx,
             },
         }
     }
-    /// Returns the position in which the given element would fall in the already-existing sorted
-    /// order.
+    /// Returns the entry with the least key greater than or equal to the given one, if any.
     ///
-    /// The value may be any borrowed form of the set's element type,
-    /// but the ordering on the borrowed form *must* match the
-    /// ordering on the element type.
+    /// The key may be any borrowed form of the map's key type, but the ordering
+    /// on the borrowed form *must* match the ordering on the key type.
     ///
     /// # Examples
     ///
     /// ```
     /// use indexset::BTreeMap;
     ///
-    /// let set = BTreeMap::from_iter([(1, "a"), (2, "b"), (3, "c")]);
-    /// assert_eq!(set.rank(&1), 0);
-    /// assert_eq!(set.rank(&3), 2);
-    /// assert_eq!(set.rank(&4), 3);
-    /// assert_eq!(set.rank(&100), 3);
+    /// let map = BTreeMap::from_iter([(1, "a"), (3, "c")]);
+    /// assert_eq!(map.ceiling(&2), Some((&3, &"c")));
+    /// assert_eq!(map.ceiling(&3), Some((&3, &"c")));
+    /// assert_eq!(map.ceiling(&4), None);
     /// ```
-    pub fn rank<Q>(&self, value: &Q) -> usize
+    pub fn ceiling<Q>(&self, key: &Q) -> Option<(&K, &V)>
     where
+        K: Borrow<Q>,
         Q: Ord + ?Sized,
+    {
+        self.get_index(self.lower_bound_idx(Bound::Included(key)))
+    }
+    /// Returns the entry with the greatest key less than or equal to the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, "a"), (3, "c")]);
+    /// assert_eq!(map.floor(&2), Some((&1, &"a")));
+    /// assert_eq!(map.floor(&3), Some((&3, &"c")));
+    /// assert_eq!(map.floor(&0), None);
+    /// ```
+    pub fn floor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
         K: Borrow<Q>,
+        Q: Ord + ?Sized,
     {
-        self.set
-            .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < value)
+        self.upper_bound_idx(Bound::Included(key))
+            .checked_sub(1)
+            .and_then(|idx| self.get_index(idx))
     }
-}
-
-impl<K, V, const N: usize> From<[(K, V); N]> for BTreeMap<K, V>
-where
-    K: Ord,
-{
-    fn from(value: [(K, V); N]) -> Self {
-        let mut btree: BTreeMap<K, V> = Default::default();
-
-        value.into_iter().for_each(|(key, value)| {
-            btree.insert(key, value);
-        });
-
-        btree
+    /// Returns the entry with the least key strictly greater than the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, "a"), (3, "c")]);
+    /// assert_eq!(map.successor(&1), Some((&3, &"c")));
+    /// assert_eq!(map.successor(&3), None);
+    /// ```
+    pub fn successor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_index(self.lower_bound_idx(Bound::Excluded(key)))
     }
-}
-
-impl<K, V> IntoIterator for BTreeMap<K, V>
-where
-    K: Ord,
-{
-    type Item = (K, V);
-    type IntoIter = IntoIterMap<K, V>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        IntoIterMap {
-            inner: self.set.into_iter(),
-        }
+    /// Returns the entry with the greatest key strictly less than the given one, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, "a"), (3, "c")]);
+    /// assert_eq!(map.predecessor(&3), Some((&1, &"a")));
+    /// assert_eq!(map.predecessor(&1), None);
+    /// ```
+    pub fn predecessor<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.upper_bound_idx(Bound::Excluded(key))
+            .checked_sub(1)
+            .and_then(|idx| self.get_index(idx))
+    }
+    // Position of the first entry whose key is above the bound, or the length of the map if
+    // there is none
+    fn lower_bound_idx<Q>(&self, bound: Bound<&Q>) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match bound {
+            Bound::Included(start) => self
+                .set
+                .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < start),
+            Bound::Excluded(start) => self
+                .set
+                .rank_cmp(|item: &Pair<K, V>| item.key.borrow() <= start),
+            Bound::Unbounded => 0,
+        }
+    }
+    // Position right after the last entry whose key is below the bound, or zero if there is
+    // none
+    fn upper_bound_idx<Q>(&self, bound: Bound<&Q>) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match bound {
+            Bound::Included(end) => self
+                .set
+                .rank_cmp(|item: &Pair<K, V>| item.key.borrow() <= end),
+            Bound::Excluded(end) => self
+                .set
+                .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < end),
+            Bound::Unbounded => self.len(),
+        }
+    }
+    /// Returns the position in which the given element would fall in the already-existing sorted
+    /// order.
+    ///
+    /// The value may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let set = BTreeMap::from_iter([(1, "a"), (2, "b"), (3, "c")]);
+    /// assert_eq!(set.rank(&1), 0);
+    /// assert_eq!(set.rank(&3), 2);
+    /// assert_eq!(set.rank(&4), 3);
+    /// assert_eq!(set.rank(&100), 3);
+    /// ```
+    pub fn rank<Q>(&self, value: &Q) -> usize
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+    {
+        self.set
+            .rank_cmp(|item: &Pair<K, V>| item.key.borrow() < value)
+    }
+    /// Returns the entry at the `q`-quantile of the keys of the map, or `None` if the map is
+    /// empty. See [`BTreeSet::quantile`] for more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not within `0.0..=1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter((1..=100).map(|key| (key, key * 2)));
+    /// assert_eq!(map.quantile(0.5), Some((&50, &100)));
+    /// ```
+    pub fn quantile(&self, q: f64) -> Option<(&K, &V)> {
+        self.get_index(quantile_position(self.len(), q))
+    }
+    /// Returns the entries at the quantiles of the keys of the map for each of the given
+    /// fractions, in the same order, or nothing if the map is empty. See
+    /// [`BTreeSet::quantile`] for more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any fraction is not within `0.0..=1.0`.
+    pub fn quantiles(&self, qs: &[f64]) -> Vec<(&K, &V)> {
+        qs.iter().filter_map(|q| self.quantile(*q)).collect()
+    }
+    /// Splits the keys of the map into at most `buckets` runs of the same size, give or take one,
+    /// and returns the bounds and size of each. See [`BTreeSet::equi_depth_histogram`] for more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buckets` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    /// use indexset::stats::Bucket;
+    ///
+    /// let map = BTreeMap::from_iter([("a", 1), ("b", 2), ("c", 3)]);
+    /// assert_eq!(
+    ///     map.equi_depth_histogram(2),
+    ///     [
+    ///         Bucket { lower: &"a", upper: &"a", count: 1 },
+    ///         Bucket { lower: &"b", upper: &"c", count: 2 },
+    ///     ]
+    /// );
+    /// ```
+    pub fn equi_depth_histogram(&self, buckets: usize) -> Vec<Bucket<&K>> {
+        self.set
+            .equi_depth_histogram(buckets)
+            .into_iter()
+            .map(|bucket| Bucket {
+                lower: &bucket.lower.key,
+                upper: &bucket.upper.key,
+                count: bucket.count,
+            })
+            .collect()
+    }
+    /// Returns the index of the bucket of [`equi_depth_histogram(buckets)`] that the key falls
+    /// into, counting from zero, or `None` if the map is empty. See [`BTreeSet::ntile`] for more.
+    ///
+    /// [`equi_depth_histogram(buckets)`]: BTreeMap::equi_depth_histogram
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buckets` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([("a", 1), ("b", 2), ("c", 3)]);
+    /// assert_eq!(map.ntile("a", 2), Some(0));
+    /// assert_eq!(map.ntile("bb", 2), Some(1));
+    /// ```
+    pub fn ntile<Q>(&self, key: &Q, buckets: usize) -> Option<usize>
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+    {
+        let len = self.len();
+        let buckets = bucket_count(len, buckets);
+        if len == 0 {
+            return None;
+        }
+
+        Some(bucket_of(len, buckets, self.rank(key).min(len - 1)))
+    }
+    /// Returns an entry of the map picked uniformly at random, or `None` if the map is empty.
+    /// See [`BTreeSet::sample`] for more.
+    pub fn sample(&self, rng: &mut impl RngLike) -> Option<(&K, &V)> {
+        self.set.sample(rng).map(|entry| (&entry.key, &entry.value))
+    }
+    /// Returns an entry of the map whose key is within the range, picked uniformly at random
+    /// among those in it, or `None` if there are none. See [`BTreeSet::sample_range`] for more.
+    pub fn sample_range<Q, R>(&self, range: R, rng: &mut impl RngLike) -> Option<(&K, &V)>
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+        R: RangeBounds<Q>,
+    {
+        let start = self.lower_bound_idx(range.start_bound());
+        let end = self.upper_bound_idx(range.end_bound());
+        if end <= start {
+            return None;
+        }
+
+        self.get_index(start + rng.gen_below((end - start) as u64) as usize)
+    }
+    /// Returns `k` distinct entries of the map picked uniformly at random, in ascending order of
+    /// keys, or every entry if the map has no more than `k`. See [`BTreeSet::sample_k_distinct`]
+    /// for more.
+    pub fn sample_k_distinct(&self, k: usize, rng: &mut impl RngLike) -> Vec<(&K, &V)> {
+        self.set
+            .sample_k_distinct(k, rng)
+            .into_iter()
+            .map(|entry| (&entry.key, &entry.value))
+            .collect()
+    }
+}
+
+// Entries compare by key alone, so maps go through keys and values together instead
+impl<K, V> PartialEq for BTreeMap<K, V>
+where
+    K: Ord,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<K, V> Eq for BTreeMap<K, V>
+where
+    K: Ord,
+    V: Eq,
+{
+}
+
+impl<K, V> PartialOrd for BTreeMap<K, V>
+where
+    K: Ord,
+    V: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other.iter())
+    }
+}
+
+impl<K, V> Ord for BTreeMap<K, V>
+where
+    K: Ord,
+    V: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+impl<K, V> Hash for BTreeMap<K, V>
+where
+    K: Ord + Hash,
+    V: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        self.iter().for_each(|entry| entry.hash(state));
+    }
+}
+
+impl<K, V> fmt::Debug for BTreeMap<K, V>
+where
+    K: Ord + fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, const N: usize> From<[(K, V); N]> for BTreeMap<K, V>
+where
+    K: Ord,
+{
+    fn from(value: [(K, V); N]) -> Self {
+        let mut btree: BTreeMap<K, V> = Default::default();
+
+        value.into_iter().for_each(|(key, value)| {
+            btree.insert(key, value);
+        });
+
+        btree
+    }
+}
+
+impl<K, V> IntoIterator for BTreeMap<K, V>
+where
+    K: Ord,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIterMap<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterMap {
+            inner: self.set.into_iter(),
+        }
     }
 }
 
@@ -3582,103 +5629,308 @@ where
 
 impl<'a, K, V> FusedIterator for ValuesMut<'a, K, V> where K: Ord {}
 
-/// A mutable iterator over a sub-range of entries in a `BTreeMap`.
+/// An iterator over the contents of the nodes of a `BTreeMap`, as chunks of entries with mutable
+/// values.
 ///
-/// This `struct` is created by the [`range_mut`] method on [`BTreeMap`]. See its
-/// documentation for more.
+/// This `struct` is created by the [`chunks_mut`] and [`range_chunks_mut`] methods on
+/// [`BTreeMap`]. See their documentation for more.
 ///
-/// [`range_mut`]: BTreeMap::range_mut
-pub struct RangeMut<'a, K: 'a, V: 'a>
+/// [`chunks_mut`]: BTreeMap::chunks_mut
+/// [`range_chunks_mut`]: BTreeMap::range_chunks_mut
+pub struct ChunksMut<'a, K, V>
 where
     K: Ord,
 {
-    inner: IterMut<'a, K, V>,
+    nodes: std::slice::IterMut<'a, Node<Pair<K, V>>>,
+    // Entries of the first node that come before the range
+    front_start: usize,
+    // End of the range within the last node
+    back_end: usize,
 }
 
-impl<'a, K, V> Iterator for RangeMut<'a, K, V>
+impl<'a, K, V> Iterator for ChunksMut<'a, K, V>
 where
     K: Ord,
 {
-    type Item = (&'a K, &'a mut V);
+    type Item = ChunkMut<'a, K, V>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next()
+        let node = self.nodes.next()?;
+        let end = match self.nodes.len() {
+            0 => self.back_end,
+            _ => node.len(),
+        };
+        let chunk = ChunkMut {
+            entries: &mut node[self.front_start..end],
+        };
+        self.front_start = 0;
+
+        Some(chunk)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
     }
 }
 
-impl<'a, K, V> DoubleEndedIterator for RangeMut<'a, K, V>
+impl<'a, K, V> DoubleEndedIterator for ChunksMut<'a, K, V>
 where
     K: Ord,
 {
     fn next_back(&mut self) -> Option<Self::Item> {
-        self.inner.next_back()
+        let node = self.nodes.next_back()?;
+        let start = match self.nodes.len() {
+            0 => self.front_start,
+            _ => 0,
+        };
+        let chunk = ChunkMut {
+            entries: &mut node[start..self.back_end],
+        };
+        self.back_end = self.nodes.as_slice().last().map_or(0, Vec::len);
+
+        Some(chunk)
     }
 }
 
-impl<'a, K, V> FusedIterator for RangeMut<'a, K, V> where K: Ord {}
+impl<'a, K, V> ExactSizeIterator for ChunksMut<'a, K, V> where K: Ord {}
 
-impl<K, Q, V> Index<&Q> for BTreeMap<K, V>
-where
-    K: Borrow<Q> + Ord,
+impl<'a, K, V> FusedIterator for ChunksMut<'a, K, V> where K: Ord {}
 
-    Q: Ord + ?Sized,
+/// Contiguous entries of a `BTreeMap`, in ascending order of keys, whose values can be changed
+/// but whose keys cannot.
+///
+/// This `struct` is yielded by [`ChunksMut`]. See [`BTreeMap::chunks_mut`] for more.
+pub struct ChunkMut<'a, K, V>
+where
+    K: Ord,
 {
-    type Output = V;
-
-    fn index(&self, index: &Q) -> &Self::Output {
-        self.get(index).unwrap()
-    }
+    entries: &'a mut [Pair<K, V>],
 }
 
-pub struct Cursor<'a, T>
+impl<'a, K, V> ChunkMut<'a, K, V>
 where
-    T: Ord,
+    K: Ord,
 {
-    set: &'a BTreeSet<T>,
-    idx: usize,
-}
-
-impl<'a, T: Ord> Cursor<'a, T> {
-    pub fn move_next(&mut self) {
-        if self.idx == self.set.len() {
-            self.idx = 0
-        } else {
-            self.idx += 1;
-        }
-    }
-    pub fn move_index(&mut self, index: usize) {
-        self.idx = index
+    /// Returns the number of entries in the chunk.
+    pub fn len(&self) -> usize {
+        self.entries.len()
     }
-    pub fn move_prev(&mut self) {
-        if self.idx == 0 {
-            self.idx = self.set.len()
-        } else {
-            self.idx -= 1;
+    /// Returns `true` if the chunk holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Returns the entries of the chunk as a slice.
+    pub fn as_slice(&self) -> &[Pair<K, V>] {
+        self.entries
+    }
+    /// Returns the key of the `idx`-th entry of the chunk along with a mutable reference to its
+    /// value, if any.
+    pub fn get_mut(&mut self, idx: usize) -> Option<(&K, &mut V)> {
+        self.entries
+            .get_mut(idx)
+            .map(|entry| (&entry.key, &mut entry.value))
+    }
+    /// Gets an iterator over the entries of the chunk, with mutable references to the values.
+    pub fn iter_mut(&mut self) -> ChunkIterMut<'_, K, V> {
+        ChunkIterMut {
+            inner: self.entries.iter_mut(),
         }
     }
-    pub fn item(&self) -> Option<&'a T> {
-        self.set.get_index(self.idx)
-    }
-    pub fn peek_next(&self) -> Option<&'a T> {
-        if self.idx == self.set.len() {
-            return self.set.first();
-        }
+}
 
-        self.set.get_index(self.idx + 1)
-    }
-    pub fn peek_index(&self, index: usize) -> Option<&'a T> {
-        self.set.get_index(index)
-    }
-    pub fn peek_prev(&self) -> Option<&'a T> {
-        if self.idx == 0 {
-            return None;
-        }
+impl<'a, K, V> IntoIterator for ChunkMut<'a, K, V>
+where
+    K: Ord,
+{
+    type Item = (&'a K, &'a mut V);
 
-        self.set.get_index(self.idx - 1)
+    type IntoIter = ChunkIterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ChunkIterMut {
+            inner: self.entries.iter_mut(),
+        }
     }
 }
 
-pub struct CursorMap<'a, K, V>
+impl<'a, 'b, K, V> IntoIterator for &'b mut ChunkMut<'a, K, V>
+where
+    K: Ord,
+{
+    type Item = (&'b K, &'b mut V);
+
+    type IntoIter = ChunkIterMut<'b, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// A mutable iterator over the entries of a [`ChunkMut`].
+///
+/// This `struct` is created by the [`iter_mut`] method on [`ChunkMut`]. See its documentation
+/// for more.
+///
+/// [`iter_mut`]: ChunkMut::iter_mut
+pub struct ChunkIterMut<'a, K, V>
+where
+    K: Ord,
+{
+    inner: std::slice::IterMut<'a, Pair<K, V>>,
+}
+
+impl<'a, K, V> Iterator for ChunkIterMut<'a, K, V>
+where
+    K: Ord,
+{
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|entry| (&entry.key, &mut entry.value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for ChunkIterMut<'a, K, V>
+where
+    K: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| (&entry.key, &mut entry.value))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for ChunkIterMut<'a, K, V> where K: Ord {}
+
+impl<'a, K, V> FusedIterator for ChunkIterMut<'a, K, V> where K: Ord {}
+
+/// A mutable iterator over a sub-range of entries in a `BTreeMap`.
+///
+/// This `struct` is created by the [`range_mut`] method on [`BTreeMap`]. See its
+/// documentation for more.
+///
+/// [`range_mut`]: BTreeMap::range_mut
+pub struct RangeMut<'a, K: 'a, V: 'a>
+where
+    K: Ord,
+{
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for RangeMut<'a, K, V>
+where
+    K: Ord,
+{
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for RangeMut<'a, K, V>
+where
+    K: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<'a, K, V> FusedIterator for RangeMut<'a, K, V> where K: Ord {}
+
+impl<K, Q, V> Index<&Q> for BTreeMap<K, V>
+where
+    K: Borrow<Q> + Ord,
+
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, index: &Q) -> &Self::Output {
+        self.get(index).unwrap()
+    }
+}
+
+/// A position within a [`BTreeSet`] or [`BTreeMap`], telling
+/// [`BTreeSet::insert_with_hint`] and [`BTreeMap::insert_with_hint`] where to look first.
+///
+/// Hints come from previous hinted inserts or from a cursor, and go stale as the tree changes,
+/// which only makes the insert fall back to a full search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Hint {
+    node_idx: usize,
+    position: usize,
+}
+
+pub struct Cursor<'a, T, N = Node<T>>
+where
+    T: Ord,
+    N: NodeLike<T>,
+{
+    set: &'a BTreeSet<T, N>,
+    idx: usize,
+}
+
+impl<'a, T: Ord, N: NodeLike<T>> Cursor<'a, T, N> {
+    pub fn move_next(&mut self) {
+        if self.idx == self.set.len() {
+            self.idx = 0
+        } else {
+            self.idx += 1;
+        }
+    }
+    pub fn move_index(&mut self, index: usize) {
+        self.idx = index
+    }
+    pub fn move_prev(&mut self) {
+        if self.idx == 0 {
+            self.idx = self.set.len()
+        } else {
+            self.idx -= 1;
+        }
+    }
+    pub fn item(&self) -> Option<&'a T> {
+        self.set.get_index(self.idx)
+    }
+    pub fn peek_next(&self) -> Option<&'a T> {
+        if self.idx == self.set.len() {
+            return self.set.first();
+        }
+
+        self.set.get_index(self.idx + 1)
+    }
+    pub fn peek_index(&self, index: usize) -> Option<&'a T> {
+        self.set.get_index(index)
+    }
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        if self.idx == 0 {
+            return None;
+        }
+
+        self.set.get_index(self.idx - 1)
+    }
+    /// Returns a hint to the position of the cursor, for [`BTreeSet::insert_with_hint`].
+    pub fn hint(&self) -> Hint {
+        if self.idx >= self.set.len() {
+            let node_idx = self.set.layout.nodes().len() - 1;
+            return Hint {
+                node_idx,
+                position: self.set.layout.nodes()[node_idx].len(),
+            };
+        }
+
+        let (node_idx, position) = self.set.locate_ith(self.idx);
+        Hint { node_idx, position }
+    }
+}
+
+pub struct CursorMap<'a, K, V>
 where
     K: 'a + Ord,
     V: 'a,
@@ -3686,66 +5938,1100 @@ where
     cursor: Cursor<'a, Pair<K, V>>,
 }
 
-impl<'a, K: Ord, V> CursorMap<'a, K, V> {
-    pub fn move_next(&mut self) {
-        self.cursor.move_next()
+impl<'a, K: Ord, V> CursorMap<'a, K, V> {
+    pub fn move_next(&mut self) {
+        self.cursor.move_next()
+    }
+    pub fn move_index(&mut self, index: usize) {
+        self.cursor.move_index(index)
+    }
+    pub fn move_prev(&mut self) {
+        self.cursor.move_prev()
+    }
+    pub fn key(&self) -> Option<&'a K> {
+        if let Some(entry) = self.cursor.item() {
+            return Some(&entry.key);
+        }
+
+        None
+    }
+    pub fn value(&self) -> Option<&'a V> {
+        if let Some(entry) = self.cursor.item() {
+            return Some(&entry.value);
+        }
+
+        None
+    }
+    pub fn key_value(&self) -> Option<(&'a K, &'a V)> {
+        if let Some(entry) = self.cursor.item() {
+            return Some((&entry.key, &entry.value));
+        }
+
+        None
+    }
+    pub fn peek_next(&self) -> Option<(&'a K, &'a V)> {
+        if let Some(entry) = self.cursor.peek_next() {
+            return Some((&entry.key, &entry.value));
+        }
+
+        None
+    }
+    pub fn peek_index(&self, index: usize) -> Option<(&'a K, &'a V)> {
+        if let Some(entry) = self.cursor.peek_index(index) {
+            return Some((&entry.key, &entry.value));
+        }
+
+        None
+    }
+    pub fn peek_prev(&self) -> Option<(&'a K, &'a V)> {
+        if let Some(entry) = self.cursor.peek_prev() {
+            return Some((&entry.key, &entry.value));
+        }
+
+        None
+    }
+    /// Returns a hint to the position of the cursor, for [`BTreeMap::insert_with_hint`].
+    pub fn hint(&self) -> Hint {
+        self.cursor.hint()
+    }
+}
+
+/// An ordered multiset based on a two-level B-Tree.
+///
+/// Unlike [`BTreeSet`], equal elements can be inserted more than once. Every copy is kept, and
+/// equal elements are ordered by insertion, so the first one inserted is always the first one
+/// returned. Position-based lookups, such as [`BTreeMultiSet::get_index`] and
+/// [`BTreeMultiSet::rank`], take every copy into account.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::BTreeMultiSet;
+///
+/// let mut latencies = BTreeMultiSet::new();
+/// latencies.insert(20);
+/// latencies.insert(10);
+/// latencies.insert(20);
+///
+/// assert_eq!(latencies.len(), 3);
+/// assert_eq!(latencies.count(&20), 2);
+/// assert_eq!(latencies.get_index(1), Some(&20));
+/// assert_eq!(latencies.rank(&20), 1);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+pub struct BTreeMultiSet<T>
+where
+    T: Ord,
+{
+    set: BTreeSet<Sequenced<T>>,
+    sequence: u64,
+}
+
+impl<T: Ord> Default for BTreeMultiSet<T> {
+    fn default() -> Self {
+        Self {
+            set: BTreeSet::default(),
+            sequence: 0,
+        }
+    }
+}
+
+impl<T: Ord> BTreeMultiSet<T> {
+    /// Makes a new, empty `BTreeMultiSet` with maximum node size 1024.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let mut set: BTreeMultiSet<i32> = BTreeMultiSet::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `BTreeMultiSet` with the given maximum node size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let mut set: BTreeMultiSet<i32> = BTreeMultiSet::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            set: BTreeSet::with_maximum_node_size(maximum_node_size),
+            sequence: 0,
+        }
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.set.set_split_policy(split_policy)
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.set.split_policy()
+    }
+    /// Clears the multiset, removing all elements.
+    pub fn clear(&mut self) {
+        self.set.clear();
+        self.sequence = 0;
+    }
+    /// Returns the number of elements in the multiset, counting every copy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let set = BTreeMultiSet::from_iter([1, 1, 2]);
+    /// assert_eq!(set.len(), 3);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+    /// Returns `true` if the multiset contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+    /// Adds a value to the multiset, after every element equal to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let mut set = BTreeMultiSet::new();
+    /// set.insert(2);
+    /// set.insert(2);
+    /// assert_eq!(set.len(), 2);
+    /// ```
+    pub fn insert(&mut self, value: T) {
+        let sequence = self.sequence;
+        self.sequence += 1;
+
+        self.set.insert(Sequenced { value, sequence });
+    }
+    fn equal_range_idx<Q>(&self, value: &Q) -> (usize, usize)
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        (
+            self.set
+                .rank_cmp(|item: &Sequenced<T>| item.value.borrow() < value),
+            self.set
+                .rank_cmp(|item: &Sequenced<T>| item.value.borrow() <= value),
+        )
+    }
+    /// Returns the number of elements equal to the value.
+    ///
+    /// The value may be any borrowed form of the multiset's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let set = BTreeMultiSet::from_iter([1, 2, 2, 3]);
+    /// assert_eq!(set.count(&2), 2);
+    /// assert_eq!(set.count(&4), 0);
+    /// ```
+    pub fn count<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (start_idx, end_idx) = self.equal_range_idx(value);
+
+        end_idx - start_idx
+    }
+    /// Returns `true` if the multiset contains at least one element equal to the value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let set = BTreeMultiSet::from_iter([1, 2, 2, 3]);
+    /// assert!(set.contains(&2));
+    /// assert!(!set.contains(&4));
+    /// ```
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.count(value) > 0
+    }
+    /// Returns a reference to the element in the i-th position of the multiset, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let set = BTreeMultiSet::from_iter([3, 1, 3]);
+    /// assert_eq!(set.get_index(0), Some(&1));
+    /// assert_eq!(set.get_index(2), Some(&3));
+    /// assert_eq!(set.get_index(3), None);
+    /// ```
+    pub fn get_index(&self, idx: usize) -> Option<&T> {
+        self.set.get_index(idx).map(|item| &item.value)
+    }
+    /// Returns the position of the first element that is not less than the value, that is, the
+    /// number of elements strictly less than it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let set = BTreeMultiSet::from_iter([1, 1, 2, 3]);
+    /// assert_eq!(set.rank(&1), 0);
+    /// assert_eq!(set.rank(&2), 2);
+    /// assert_eq!(set.rank(&4), 4);
+    /// ```
+    pub fn rank<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set
+            .rank_cmp(|item: &Sequenced<T>| item.value.borrow() < value)
+    }
+    /// Returns a reference to the first element in the multiset, if any.
+    pub fn first(&self) -> Option<&T> {
+        self.set.first().map(|item| &item.value)
+    }
+    /// Returns a reference to the last element in the multiset, if any.
+    pub fn last(&self) -> Option<&T> {
+        self.set.last().map(|item| &item.value)
+    }
+    /// Removes the first element from the multiset and returns it, if any.
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.set.pop_first().map(|item| item.value)
+    }
+    /// Removes the last element from the multiset and returns it, if any.
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.set.pop_last().map(|item| item.value)
+    }
+    /// Removes the earliest inserted element that is equal to the value, and returns it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let mut set = BTreeMultiSet::from_iter([1, 2, 2]);
+    /// assert_eq!(set.remove_one(&2), Some(2));
+    /// assert_eq!(set.count(&2), 1);
+    /// assert_eq!(set.remove_one(&4), None);
+    /// ```
+    pub fn remove_one<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set
+            .delete_cmp(
+                |item: &Sequenced<T>| item.value.borrow() < value,
+                |item| item.value.borrow() == value,
+            )
+            .0
+            .map(|item| item.value)
+    }
+    /// Removes every element that is equal to the value, and returns them in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let mut set = BTreeMultiSet::from_iter([1, 2, 2, 3]);
+    /// assert_eq!(set.remove_all(&2), [2, 2]);
+    /// assert_eq!(set.len(), 2);
+    /// ```
+    pub fn remove_all<Q>(&mut self, value: &Q) -> Vec<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (start_idx, end_idx) = self.equal_range_idx(value);
+
+        (start_idx..end_idx)
+            .map(|_| self.set.pop_index(start_idx).value)
+            .collect()
+    }
+    /// Gets an iterator that visits the elements in the multiset in ascending order, with equal
+    /// elements in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let set = BTreeMultiSet::from_iter([3, 1, 3]);
+    /// assert_eq!(set.iter().collect::<Vec<_>>(), [&1, &3, &3]);
+    /// ```
+    pub fn iter(&self) -> IterMultiSet<'_, T> {
+        IterMultiSet {
+            inner: self.set.iter(),
+        }
+    }
+    /// Constructs a double-ended iterator over a sub-range of elements in the multiset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let set = BTreeMultiSet::from_iter([1, 2, 2, 3, 4]);
+    /// assert_eq!(set.range(2..4).collect::<Vec<_>>(), [&2, &2, &3]);
+    /// assert_eq!(set.range(..1).count(), 0);
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> RangeMultiSet<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start_idx = match range.start_bound() {
+            Bound::Included(bound) => self.rank(bound),
+            Bound::Excluded(bound) => self.equal_range_idx(bound).1,
+            Bound::Unbounded => 0,
+        };
+        let end_idx = match range.end_bound() {
+            Bound::Included(bound) => self.equal_range_idx(bound).1,
+            Bound::Excluded(bound) => self.rank(bound),
+            Bound::Unbounded => self.len(),
+        };
+
+        if start_idx < end_idx {
+            return RangeMultiSet {
+                inner: Some(self.set.range_idx(start_idx..end_idx)),
+            };
+        }
+
+        RangeMultiSet { inner: None }
+    }
+    /// Constructs a double-ended iterator over the elements of the multiset whose leading fields
+    /// are equal to the given tuple prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiSet;
+    ///
+    /// let set = BTreeMultiSet::from_iter([(1, 'a'), (2, 'b'), (2, 'b'), (3, 'c')]);
+    /// assert_eq!(set.range_prefix(&(2,)).count(), 2);
+    /// ```
+    pub fn range_prefix<P>(&self, prefix: &P) -> RangeMultiSet<'_, T>
+    where
+        T: TuplePrefix<P>,
+    {
+        let start_idx = self
+            .set
+            .rank_cmp(|item: &Sequenced<T>| item.value.cmp_prefix(prefix) == Ordering::Less);
+        let end_idx = self
+            .set
+            .rank_cmp(|item: &Sequenced<T>| item.value.cmp_prefix(prefix) != Ordering::Greater);
+
+        if start_idx < end_idx {
+            return RangeMultiSet {
+                inner: Some(self.set.range_idx(start_idx..end_idx)),
+            };
+        }
+
+        RangeMultiSet { inner: None }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BTreeMultiSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut btree = BTreeMultiSet::new();
+        iter.into_iter().for_each(|item| btree.insert(item));
+
+        btree
+    }
+}
+
+impl<T: Ord> IntoIterator for BTreeMultiSet<T> {
+    type Item = T;
+
+    type IntoIter = IntoIterMultiSet<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterMultiSet {
+            inner: self.set.into_iter(),
+        }
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a BTreeMultiSet<T> {
+    type Item = &'a T;
+
+    type IntoIter = IterMultiSet<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> Index<usize> for BTreeMultiSet<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get_index(index).unwrap()
+    }
+}
+
+impl<T> fmt::Debug for BTreeMultiSet<T>
+where
+    T: Ord + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the items of a `BTreeMultiSet`.
+///
+/// This `struct` is created by the [`iter`] method on [`BTreeMultiSet`].
+/// See its documentation for more.
+///
+/// [`iter`]: BTreeMultiSet::iter
+pub struct IterMultiSet<'a, T>
+where
+    T: Ord,
+{
+    inner: Iter<'a, Sequenced<T>>,
+}
+
+impl<'a, T: Ord> Iterator for IterMultiSet<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|item| &item.value)
+    }
+}
+
+impl<'a, T: Ord> DoubleEndedIterator for IterMultiSet<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|item| &item.value)
+    }
+}
+
+impl<'a, T: Ord> FusedIterator for IterMultiSet<'a, T> {}
+
+/// An owning iterator over the items of a `BTreeMultiSet`.
+///
+/// This `struct` is created by the [`into_iter`] method on [`BTreeMultiSet`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+///
+/// [`into_iter`]: BTreeMultiSet#method.into_iter
+pub struct IntoIterMultiSet<T>
+where
+    T: Ord,
+{
+    inner: IntoIter<Sequenced<T>>,
+}
+
+impl<T: Ord> Iterator for IntoIterMultiSet<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|item| item.value)
+    }
+}
+
+impl<T: Ord> DoubleEndedIterator for IntoIterMultiSet<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|item| item.value)
+    }
+}
+
+impl<T: Ord> FusedIterator for IntoIterMultiSet<T> {}
+
+/// An iterator over a sub-range of items in a `BTreeMultiSet`.
+///
+/// This `struct` is created by the [`range`] method on [`BTreeMultiSet`].
+/// See its documentation for more.
+///
+/// [`range`]: BTreeMultiSet::range
+pub struct RangeMultiSet<'a, T>
+where
+    T: Ord,
+{
+    inner: Option<Range<'a, Sequenced<T>>>,
+}
+
+impl<'a, T: Ord> Iterator for RangeMultiSet<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.as_mut()?.next().map(|item| &item.value)
+    }
+}
+
+impl<'a, T: Ord> DoubleEndedIterator for RangeMultiSet<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.as_mut()?.next_back().map(|item| &item.value)
+    }
+}
+
+impl<'a, T: Ord> FusedIterator for RangeMultiSet<'a, T> {}
+
+/// An ordered multimap based on a two-level B-Tree.
+///
+/// Unlike [`BTreeMap`], a key can be inserted more than once, and every key-value pair is kept.
+/// Entries with equal keys are ordered by insertion. Position-based lookups, such as
+/// [`BTreeMultiMap::get_index`] and [`BTreeMultiMap::rank`], take every entry into account.
+///
+/// For a multimap that can be shared between threads, see
+/// `indexset::concurrent::multimap::BTreeMultiMap`.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::BTreeMultiMap;
+///
+/// let mut visits = BTreeMultiMap::new();
+/// visits.insert("bob", 3);
+/// visits.insert("alice", 1);
+/// visits.insert("bob", 2);
+///
+/// assert_eq!(visits.count("bob"), 2);
+/// assert_eq!(visits.get("bob").map(|(_, v)| *v).collect::<Vec<_>>(), [3, 2]);
+/// assert_eq!(visits.get_index(0), Some((&"alice", &1)));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+pub struct BTreeMultiMap<K, V>
+where
+    K: Ord,
+{
+    set: BTreeSet<Pair<Sequenced<K>, V>>,
+    sequence: u64,
+}
+
+impl<K: Ord, V> Default for BTreeMultiMap<K, V> {
+    fn default() -> Self {
+        Self {
+            set: BTreeSet::default(),
+            sequence: 0,
+        }
+    }
+}
+
+impl<K: Ord, V> BTreeMultiMap<K, V> {
+    /// Makes a new, empty `BTreeMultiMap` with maximum node size 1024.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let mut map: BTreeMultiMap<i32, &str> = BTreeMultiMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `BTreeMultiMap` with the given maximum node size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let mut map: BTreeMultiMap<i32, &str> = BTreeMultiMap::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            set: BTreeSet::with_maximum_node_size(maximum_node_size),
+            sequence: 0,
+        }
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.set.set_split_policy(split_policy)
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.set.split_policy()
+    }
+    /// Clears the multimap, removing all entries.
+    pub fn clear(&mut self) {
+        self.set.clear();
+        self.sequence = 0;
+    }
+    /// Returns the number of entries in the multimap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::from_iter([(1, "a"), (1, "b")]);
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+    /// Returns `true` if the multimap contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+    /// Inserts a key-value pair into the multimap, after every entry with an equal key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let mut map = BTreeMultiMap::new();
+    /// map.insert(37, "a");
+    /// map.insert(37, "b");
+    /// assert_eq!(map.count(&37), 2);
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) {
+        let sequence = self.sequence;
+        self.sequence += 1;
+
+        self.set.insert(Pair {
+            key: Sequenced {
+                value: key,
+                sequence,
+            },
+            value,
+        });
+    }
+    fn equal_range_idx<Q>(&self, key: &Q) -> (usize, usize)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        (
+            self.set
+                .rank_cmp(|item: &Pair<Sequenced<K>, V>| item.key.value.borrow() < key),
+            self.set
+                .rank_cmp(|item: &Pair<Sequenced<K>, V>| item.key.value.borrow() <= key),
+        )
+    }
+    fn range_idx(&self, start_idx: usize, end_idx: usize) -> RangeMultiMap<'_, K, V> {
+        if start_idx < end_idx {
+            return RangeMultiMap {
+                inner: Some(self.set.range_idx(start_idx..end_idx)),
+            };
+        }
+
+        RangeMultiMap { inner: None }
+    }
+    /// Returns the number of entries with the given key.
+    ///
+    /// The key may be any borrowed form of the multimap's key type, but the ordering
+    /// on the borrowed form *must* match the ordering on the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::from_iter([(1, "a"), (2, "b"), (2, "c")]);
+    /// assert_eq!(map.count(&2), 2);
+    /// assert_eq!(map.count(&3), 0);
+    /// ```
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (start_idx, end_idx) = self.equal_range_idx(key);
+
+        end_idx - start_idx
+    }
+    /// Returns `true` if the multimap contains at least one entry with the given key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.count(key) > 0
+    }
+    /// Constructs a double-ended iterator over every entry with the given key, in insertion
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::from_iter([(1, "a"), (2, "b"), (1, "c")]);
+    /// assert_eq!(map.get(&1).collect::<Vec<_>>(), [(&1, &"a"), (&1, &"c")]);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> RangeMultiMap<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (start_idx, end_idx) = self.equal_range_idx(key);
+
+        self.range_idx(start_idx, end_idx)
+    }
+    /// Returns the key-value pair in the i-th position of the multimap, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::from_iter([(2, "b"), (1, "a"), (2, "c")]);
+    /// assert_eq!(map.get_index(0), Some((&1, &"a")));
+    /// assert_eq!(map.get_index(2), Some((&2, &"c")));
+    /// assert_eq!(map.get_index(3), None);
+    /// ```
+    pub fn get_index(&self, idx: usize) -> Option<(&K, &V)> {
+        self.set
+            .get_index(idx)
+            .map(|entry| (&entry.key.value, &entry.value))
+    }
+    /// Returns a mutable reference to the value in the i-th position of the multimap, if any.
+    pub fn get_mut_index(&mut self, idx: usize) -> Option<&mut V> {
+        self.set.get_mut_index(idx).map(|entry| &mut entry.value)
+    }
+    /// Returns the position of the first entry whose key is not less than the given key, that
+    /// is, the number of entries with strictly smaller keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::from_iter([(1, "a"), (1, "b"), (2, "c")]);
+    /// assert_eq!(map.rank(&1), 0);
+    /// assert_eq!(map.rank(&2), 2);
+    /// assert_eq!(map.rank(&3), 3);
+    /// ```
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.equal_range_idx(key).0
+    }
+    /// Returns the first key-value pair in the multimap, if any.
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.set
+            .first()
+            .map(|entry| (&entry.key.value, &entry.value))
+    }
+    /// Returns the last key-value pair in the multimap, if any.
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.set
+            .last()
+            .map(|entry| (&entry.key.value, &entry.value))
+    }
+    /// Removes and returns the first entry in the multimap, if any.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.set
+            .pop_first()
+            .map(|entry| (entry.key.value, entry.value))
+    }
+    /// Removes and returns the last entry in the multimap, if any.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.set
+            .pop_last()
+            .map(|entry| (entry.key.value, entry.value))
+    }
+    /// Removes the earliest inserted entry with the given key, and returns it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let mut map = BTreeMultiMap::from_iter([(1, "a"), (1, "b")]);
+    /// assert_eq!(map.remove_one(&1), Some((1, "a")));
+    /// assert_eq!(map.remove_one(&1), Some((1, "b")));
+    /// assert_eq!(map.remove_one(&1), None);
+    /// ```
+    pub fn remove_one<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set
+            .delete_cmp(
+                |item: &Pair<Sequenced<K>, V>| item.key.value.borrow() < key,
+                |item| item.key.value.borrow() == key,
+            )
+            .0
+            .map(|entry| (entry.key.value, entry.value))
+    }
+    /// Removes every entry with the given key, and returns them in insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let mut map = BTreeMultiMap::from_iter([(1, "a"), (2, "b"), (1, "c")]);
+    /// assert_eq!(map.remove_all(&1), [(1, "a"), (1, "c")]);
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn remove_all<Q>(&mut self, key: &Q) -> Vec<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (start_idx, end_idx) = self.equal_range_idx(key);
+
+        (start_idx..end_idx)
+            .map(|_| {
+                let entry = self.set.pop_index(start_idx);
+
+                (entry.key.value, entry.value)
+            })
+            .collect()
     }
-    pub fn move_index(&mut self, index: usize) {
-        self.cursor.move_index(index)
+    /// Gets an iterator over the entries of the multimap, sorted by key, with equal keys in
+    /// insertion order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::from_iter([(2, "b"), (1, "a"), (2, "c")]);
+    /// assert_eq!(
+    ///     map.iter().collect::<Vec<_>>(),
+    ///     [(&1, &"a"), (&2, &"b"), (&2, &"c")]
+    /// );
+    /// ```
+    pub fn iter(&self) -> IterMultiMap<'_, K, V> {
+        IterMultiMap {
+            inner: self.set.iter(),
+        }
     }
-    pub fn move_prev(&mut self) {
-        self.cursor.move_prev()
+    /// Constructs a double-ended iterator over a sub-range of entries in the multimap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let map = BTreeMultiMap::from_iter([(1, "a"), (2, "b"), (2, "c"), (3, "d")]);
+    /// assert_eq!(map.range(2..).map(|(_, v)| *v).collect::<Vec<_>>(), ["b", "c", "d"]);
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> RangeMultiMap<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start_idx = match range.start_bound() {
+            Bound::Included(bound) => self.equal_range_idx(bound).0,
+            Bound::Excluded(bound) => self.equal_range_idx(bound).1,
+            Bound::Unbounded => 0,
+        };
+        let end_idx = match range.end_bound() {
+            Bound::Included(bound) => self.equal_range_idx(bound).1,
+            Bound::Excluded(bound) => self.equal_range_idx(bound).0,
+            Bound::Unbounded => self.len(),
+        };
+
+        self.range_idx(start_idx, end_idx)
     }
-    pub fn key(&self) -> Option<&'a K> {
-        if let Some(entry) = self.cursor.item() {
-            return Some(&entry.key);
-        }
+    /// Constructs a double-ended iterator over the entries of the multimap whose keys' leading
+    /// fields are equal to the given tuple prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::BTreeMultiMap;
+    ///
+    /// let mut map = BTreeMultiMap::new();
+    /// map.insert((1, 'a'), "x");
+    /// map.insert((1, 'b'), "y");
+    /// map.insert((1, 'a'), "z");
+    /// map.insert((2, 'a'), "w");
+    ///
+    /// let values: Vec<_> = map.range_prefix(&(1,)).map(|(_, &value)| value).collect();
+    /// assert_eq!(values, ["x", "z", "y"]);
+    /// ```
+    pub fn range_prefix<P>(&self, prefix: &P) -> RangeMultiMap<'_, K, V>
+    where
+        K: TuplePrefix<P>,
+    {
+        let start_idx = self.set.rank_cmp(|item: &Pair<Sequenced<K>, V>| {
+            item.key.value.cmp_prefix(prefix) == Ordering::Less
+        });
+        let end_idx = self.set.rank_cmp(|item: &Pair<Sequenced<K>, V>| {
+            item.key.value.cmp_prefix(prefix) != Ordering::Greater
+        });
 
-        None
+        self.range_idx(start_idx, end_idx)
     }
-    pub fn value(&self) -> Option<&'a V> {
-        if let Some(entry) = self.cursor.item() {
-            return Some(&entry.value);
-        }
+}
 
-        None
+impl<K: Ord, V> FromIterator<(K, V)> for BTreeMultiMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut btree = BTreeMultiMap::new();
+        iter.into_iter()
+            .for_each(|(key, value)| btree.insert(key, value));
+
+        btree
     }
-    pub fn key_value(&self) -> Option<(&'a K, &'a V)> {
-        if let Some(entry) = self.cursor.item() {
-            return Some((&entry.key, &entry.value));
+}
+
+impl<K: Ord, V> IntoIterator for BTreeMultiMap<K, V> {
+    type Item = (K, V);
+
+    type IntoIter = IntoIterMultiMap<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIterMultiMap {
+            inner: self.set.into_iter(),
         }
+    }
+}
 
-        None
+impl<'a, K: Ord, V> IntoIterator for &'a BTreeMultiMap<K, V> {
+    type Item = (&'a K, &'a V);
+
+    type IntoIter = IterMultiMap<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
-    pub fn peek_next(&self) -> Option<(&'a K, &'a V)> {
-        if let Some(entry) = self.cursor.peek_next() {
-            return Some((&entry.key, &entry.value));
-        }
+}
 
-        None
+impl<K, V> fmt::Debug for BTreeMultiMap<K, V>
+where
+    K: Ord + fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
     }
-    pub fn peek_index(&self, index: usize) -> Option<(&'a K, &'a V)> {
-        if let Some(entry) = self.cursor.peek_index(index) {
-            return Some((&entry.key, &entry.value));
-        }
+}
 
-        None
+/// An iterator over the entries of a `BTreeMultiMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`BTreeMultiMap`].
+/// See its documentation for more.
+///
+/// [`iter`]: BTreeMultiMap::iter
+pub struct IterMultiMap<'a, K, V>
+where
+    K: Ord,
+{
+    inner: Iter<'a, Pair<Sequenced<K>, V>>,
+}
+
+impl<'a, K: Ord, V> Iterator for IterMultiMap<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|entry| (&entry.key.value, &entry.value))
     }
-    pub fn peek_prev(&self) -> Option<(&'a K, &'a V)> {
-        if let Some(entry) = self.cursor.peek_prev() {
-            return Some((&entry.key, &entry.value));
-        }
+}
 
-        None
+impl<'a, K: Ord, V> DoubleEndedIterator for IterMultiMap<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| (&entry.key.value, &entry.value))
+    }
+}
+
+impl<'a, K: Ord, V> FusedIterator for IterMultiMap<'a, K, V> {}
+
+/// An owning iterator over the entries of a `BTreeMultiMap`.
+///
+/// This `struct` is created by the [`into_iter`] method on [`BTreeMultiMap`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+///
+/// [`into_iter`]: BTreeMultiMap#method.into_iter
+pub struct IntoIterMultiMap<K, V>
+where
+    K: Ord,
+{
+    inner: IntoIter<Pair<Sequenced<K>, V>>,
+}
+
+impl<K: Ord, V> Iterator for IntoIterMultiMap<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|entry| (entry.key.value, entry.value))
+    }
+}
+
+impl<K: Ord, V> DoubleEndedIterator for IntoIterMultiMap<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_back()
+            .map(|entry| (entry.key.value, entry.value))
+    }
+}
+
+impl<K: Ord, V> FusedIterator for IntoIterMultiMap<K, V> {}
+
+/// An iterator over a sub-range of entries in a `BTreeMultiMap`.
+///
+/// This `struct` is created by the [`range`] and [`get`] methods on [`BTreeMultiMap`].
+/// See their documentation for more.
+///
+/// [`range`]: BTreeMultiMap::range
+/// [`get`]: BTreeMultiMap::get
+pub struct RangeMultiMap<'a, K, V>
+where
+    K: Ord,
+{
+    inner: Option<Range<'a, Pair<Sequenced<K>, V>>>,
+}
+
+impl<'a, K: Ord, V> Iterator for RangeMultiMap<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .as_mut()?
+            .next()
+            .map(|entry| (&entry.key.value, &entry.value))
+    }
+}
+
+impl<'a, K: Ord, V> DoubleEndedIterator for RangeMultiMap<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner
+            .as_mut()?
+            .next_back()
+            .map(|entry| (&entry.key.value, &entry.value))
     }
 }
 
+impl<'a, K: Ord, V> FusedIterator for RangeMultiMap<'a, K, V> {}
+
 #[cfg(test)]
 mod tests {
     use super::core::constants::*;
     use super::core::node::*;
-    use crate::{BTreeMap, BTreeSet, Node};
+    use crate::{BTreeMap, BTreeMultiMap, BTreeMultiSet, BTreeSet, Hint, Node};
     use rand::{Rng, SeedableRng};
+    use std::collections::Bound;
     use std::collections::Bound::Included;
 
     #[test]
@@ -3801,7 +7087,7 @@ mod tests {
             acc.insert(curr);
             acc
         });
-        assert!(btree.inner.len() > 1);
+        assert!(btree.layout.nodes().len() > 1);
 
         let actual_output: Vec<usize> = btree.into_iter().collect();
 
@@ -3822,7 +7108,7 @@ mod tests {
             acc.insert(curr);
             acc
         });
-        assert!(btree.inner.len() > 1);
+        assert!(btree.layout.nodes().len() > 1);
 
         let actual_output: Vec<usize> = btree.into_iter().collect();
 
@@ -3921,7 +7207,7 @@ mod tests {
     }
 
     #[test]
-    fn test_get_contains_lower_bound() {
+    fn test_get_contains_ceiling() {
         let input: Vec<usize> = (0..(DEFAULT_INNER_SIZE + 1)).into_iter().rev().collect();
         let expected_output: Vec<usize> = (0..(DEFAULT_INNER_SIZE + 1)).collect();
 
@@ -3934,7 +7220,7 @@ mod tests {
             assert_eq!(*btree.get_index(item).unwrap(), item);
             assert_eq!(
                 *btree.get_index(item).unwrap(),
-                *btree.lower_bound(&item).unwrap()
+                *btree.ceiling(&item).unwrap()
             );
             assert!(btree.contains(&item));
         });
@@ -3943,7 +7229,7 @@ mod tests {
     #[test]
     fn test_iter() {
         let btree = BTreeSet::from_iter((0..(DEFAULT_INNER_SIZE * 10)).rev());
-        assert_eq!(btree.inner.len(), 19);
+        assert_eq!(btree.layout.nodes().len(), 19);
         let expected_forward = Vec::from_iter(0..(DEFAULT_INNER_SIZE * 10));
         let actual_forward = Vec::from_iter(btree.iter().cloned());
         assert_eq!(expected_forward, actual_forward);
@@ -3955,7 +7241,7 @@ mod tests {
     #[test]
     fn test_iter_mut() {
         let btree = BTreeMap::from_iter((0..(DEFAULT_INNER_SIZE * 10)).enumerate().rev());
-        assert_eq!(btree.set.inner.len(), 19);
+        assert_eq!(btree.set.layout.nodes().len(), 19);
         let expected_forward = Vec::from_iter((0..(DEFAULT_INNER_SIZE * 10)).enumerate());
         btree
             .clone()
@@ -3981,7 +7267,7 @@ mod tests {
     #[test]
     fn test_into_iter() {
         let btree = BTreeSet::from_iter((0..(DEFAULT_INNER_SIZE * 10)).rev());
-        assert_eq!(btree.inner.len(), 19);
+        assert_eq!(btree.layout.nodes().len(), 19);
         let expected_forward = Vec::from_iter(0..(DEFAULT_INNER_SIZE * 10));
         let actual_forward = Vec::from_iter(btree.clone().into_iter());
         assert_eq!(expected_forward, actual_forward);
@@ -4175,45 +7461,393 @@ mod tests {
     }
 
     #[test]
-    fn test_boolean_set_operations() {
-        let empty_set: BTreeSet<usize> = BTreeSet::new();
-        assert!(empty_set.is_empty());
-        let a = BTreeSet::from_iter((0..(DEFAULT_INNER_SIZE + 1)).into_iter());
-        let b = BTreeSet::from_iter((0..(DEFAULT_INNER_SIZE + 2)).into_iter());
-        let c =
-            BTreeSet::from_iter(((DEFAULT_INNER_SIZE + 2)..(DEFAULT_INNER_SIZE + 4)).into_iter());
+    fn test_boolean_set_operations() {
+        let empty_set: BTreeSet<usize> = BTreeSet::new();
+        assert!(empty_set.is_empty());
+        let a = BTreeSet::from_iter((0..(DEFAULT_INNER_SIZE + 1)).into_iter());
+        let b = BTreeSet::from_iter((0..(DEFAULT_INNER_SIZE + 2)).into_iter());
+        let c =
+            BTreeSet::from_iter(((DEFAULT_INNER_SIZE + 2)..(DEFAULT_INNER_SIZE + 4)).into_iter());
+
+        assert!(a.is_subset(&a));
+        assert!(a.is_superset(&a));
+        assert!(a.is_subset(&b));
+        assert!(!b.is_subset(&a));
+        assert!(b.is_superset(&a));
+        assert!(c.is_disjoint(&a));
+        assert!(c.is_disjoint(&b));
+        assert!(!a.is_disjoint(&b));
+        assert!(!b.is_disjoint(&a));
+    }
+
+    #[test]
+    fn test_split_off() {
+        let btree: BTreeSet<usize> = BTreeSet::from_iter(0..(DEFAULT_INNER_SIZE * 10));
+        for split in vec![
+            1,
+            (DEFAULT_INNER_SIZE * 3) - 6,
+            DEFAULT_INNER_SIZE,
+            DEFAULT_INNER_SIZE + 1,
+            (DEFAULT_INNER_SIZE * 10) - 1,
+        ] {
+            let mut left = btree.clone();
+            let right = left.split_off(&split);
+            assert!(left.is_disjoint(&right));
+            assert!(Vec::from_iter(left.intersection(&right)).is_empty());
+            let expected_left = Vec::from_iter(0..split);
+            let expected_right = Vec::from_iter(split..(DEFAULT_INNER_SIZE * 10));
+
+            assert_eq!(expected_left, Vec::from_iter(left));
+            let actual_right = Vec::from_iter(right);
+            assert_eq!(expected_right, actual_right)
+        }
+    }
+
+    #[test]
+    fn test_split_keeps_configuration() {
+        let mut set = BTreeSet::with_maximum_node_size(4);
+        set.set_split_policy(SplitPolicy::RightBiased);
+        for i in 0..20 {
+            set.insert(i);
+        }
+
+        for (right, nodes) in [
+            (set.clone().split_off_index(10), 8),
+            (set.clone().split_off(&10), 8),
+            (set.clone().split_off_index(20), 5),
+        ] {
+            let mut right = right;
+            assert!(matches!(right.split_policy(), SplitPolicy::RightBiased));
+            for i in 20..40 {
+                right.insert(i);
+            }
+            let chunks: Vec<usize> = right.chunks().map(|chunk| chunk.len()).collect();
+            assert_eq!(chunks.len(), nodes);
+            assert!(chunks[1..].iter().all(|len| *len == 4));
+        }
+
+        let mut map = BTreeMap::with_maximum_node_size(4);
+        map.set_split_policy(SplitPolicy::RightBiased);
+        for i in 0..20 {
+            map.insert(i, i);
+        }
+        let (_, mut right) = map.split_at(10);
+        assert!(matches!(right.split_policy(), SplitPolicy::RightBiased));
+        for i in 20..40 {
+            right.insert(i, i);
+        }
+        let chunks: Vec<usize> = right.chunks().map(|chunk| chunk.len()).collect();
+        assert!(chunks[1..].iter().all(|len| *len == 4));
+    }
+
+    #[test]
+    fn test_split_off_index() {
+        let n = DEFAULT_INNER_SIZE * 10;
+        let btree: BTreeSet<usize> = BTreeSet::from_iter((0..n).rev());
+        for split in vec![
+            0,
+            1,
+            DEFAULT_CUTOFF,
+            (DEFAULT_INNER_SIZE * 3) - 6,
+            DEFAULT_INNER_SIZE,
+            DEFAULT_INNER_SIZE + 1,
+            n - 1,
+            n,
+            n + 1,
+        ] {
+            let mut left = btree.clone();
+            let right = left.split_off_index(split);
+            let expected_left = Vec::from_iter(0..split.min(n));
+            let expected_right = Vec::from_iter(split.min(n)..n);
+
+            assert_eq!(left.len(), expected_left.len());
+            assert_eq!(right.len(), expected_right.len());
+            for (idx, item) in expected_left.iter().enumerate() {
+                assert_eq!(left.get_index(idx), Some(item));
+            }
+            for (idx, item) in expected_right.iter().enumerate() {
+                assert_eq!(right.get_index(idx), Some(item));
+            }
+            assert_eq!(expected_left, Vec::from_iter(left));
+            assert_eq!(expected_right, Vec::from_iter(right));
+        }
+
+        let map = BTreeMap::from_iter((0..n).map(|i| (i, i * 2)));
+        let (left, mut right) = map.split_at(DEFAULT_INNER_SIZE + 1);
+        assert_eq!(left.len(), DEFAULT_INNER_SIZE + 1);
+        assert_eq!(
+            right.get_index(0),
+            Some((&(DEFAULT_INNER_SIZE + 1), &((DEFAULT_INNER_SIZE + 1) * 2)))
+        );
+        right.insert(0, 0);
+        assert_eq!(right.get_index(0), Some((&0, &0)));
+        assert_eq!(right.len(), n - DEFAULT_INNER_SIZE);
+    }
+
+    #[test]
+    fn test_multimap_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([28; 32]);
+        let mut multimap = BTreeMultiMap::with_maximum_node_size(16);
+        let mut model: std::collections::BTreeMap<usize, Vec<usize>> =
+            std::collections::BTreeMap::new();
+        for step in 0..5_000 {
+            let key = rng.gen_range(0..64);
+            match rng.gen_range(0..10) {
+                0 => assert_eq!(
+                    multimap.remove_one(&key),
+                    model.get_mut(&key).and_then(|values| {
+                        (!values.is_empty()).then(|| (key, values.remove(0)))
+                    })
+                ),
+                1 => assert_eq!(
+                    multimap.remove_all(&key),
+                    model
+                        .remove(&key)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|value| (key, value))
+                        .collect::<Vec<_>>()
+                ),
+                _ => {
+                    multimap.insert(key, step);
+                    model.entry(key).or_default().push(step);
+                }
+            }
+        }
+
+        let expected: Vec<(usize, usize)> = model
+            .iter()
+            .flat_map(|(key, values)| values.iter().map(|value| (*key, *value)))
+            .collect();
+        assert_eq!(multimap.len(), expected.len());
+        for (idx, (key, value)) in expected.iter().enumerate() {
+            assert_eq!(multimap.get_index(idx), Some((key, value)));
+        }
+        for key in 0..65 {
+            let values = model.get(&key).cloned().unwrap_or_default();
+            assert_eq!(multimap.count(&key), values.len());
+            assert_eq!(
+                multimap.rank(&key),
+                expected.iter().filter(|(k, _)| *k < key).count()
+            );
+            assert_eq!(
+                multimap.get(&key).map(|(_, v)| *v).collect::<Vec<_>>(),
+                values
+            );
+            assert_eq!(
+                multimap.range(..key).count(),
+                expected.iter().filter(|(k, _)| *k < key).count()
+            );
+            assert_eq!(
+                multimap
+                    .range((Bound::Excluded(key), Bound::Included(key + 10)))
+                    .map(|(k, v)| (*k, *v))
+                    .collect::<Vec<_>>(),
+                expected
+                    .iter()
+                    .filter(|(k, _)| *k > key && *k <= key + 10)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(
+            multimap.into_iter().rev().collect::<Vec<_>>(),
+            expected.into_iter().rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_multiset_spanning_nodes() {
+        let n = DEFAULT_INNER_SIZE * 3;
+        let mut multiset = BTreeMultiSet::new();
+        for item in (0..n).rev() {
+            multiset.insert(item / 4);
+        }
+        assert_eq!(multiset.len(), n);
+        assert_eq!(multiset.count(&0), 4);
+        assert_eq!(multiset.count(&(n / 4)), 0);
+        assert_eq!(multiset.rank(&(DEFAULT_INNER_SIZE / 4)), DEFAULT_INNER_SIZE);
+        assert_eq!(
+            multiset.get_index(DEFAULT_INNER_SIZE + 5),
+            Some(&((DEFAULT_INNER_SIZE + 5) / 4))
+        );
+        assert_eq!(multiset.range(10..12).count(), 8);
+        assert_eq!(multiset.range(12..10).count(), 0);
+        assert_eq!(multiset.remove_all(&(DEFAULT_INNER_SIZE / 4)).len(), 4);
+        assert_eq!(multiset.remove_one(&0), Some(0));
+        assert_eq!(multiset.len(), n - 5);
+        assert!(multiset
+            .iter()
+            .zip(multiset.iter().skip(1))
+            .all(|(a, b)| a <= b));
+        assert_eq!(multiset.pop_first(), Some(0));
+        assert_eq!(multiset.pop_last(), Some((n - 1) / 4));
+
+        let multiset = BTreeMultiSet::from_iter([2, 1, 2]);
+        assert_eq!(format!("{multiset:?}"), "{1, 2, 2}");
+        let multimap = BTreeMultiMap::from_iter([(1, "b"), (0, "a"), (1, "c")]);
+        assert_eq!(format!("{multimap:?}"), r#"{0: "a", 1: "b", 1: "c"}"#);
+    }
+
+    #[test]
+    fn test_prefix_range_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([32; 32]);
+        let alphabet = [0x00u8, 0x01, 0x7f, 0xff];
+        let random_key = |rng: &mut rand::rngs::StdRng| -> Vec<u8> {
+            let len = rng.gen_range(0..4);
+            (0..len)
+                .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                .collect()
+        };
 
-        assert!(a.is_subset(&a));
-        assert!(a.is_superset(&a));
-        assert!(a.is_subset(&b));
-        assert!(!b.is_subset(&a));
-        assert!(b.is_superset(&a));
-        assert!(c.is_disjoint(&a));
-        assert!(c.is_disjoint(&b));
-        assert!(!a.is_disjoint(&b));
-        assert!(!b.is_disjoint(&a));
+        let mut set = BTreeSet::with_maximum_node_size(4);
+        let mut map = BTreeMap::with_maximum_node_size(4);
+        let mut model = std::collections::BTreeSet::new();
+        for _ in 0..200 {
+            let key = random_key(&mut rng);
+            set.insert(key.clone());
+            map.insert(key.clone(), key.len());
+            model.insert(key);
+        }
+        // Prefixes must be looked up across node boundaries
+        assert!(set.chunks().count() > 10);
+        assert!(map.chunks().count() > 10);
+
+        for _ in 0..200 {
+            let prefix = random_key(&mut rng);
+            let expected: Vec<&Vec<u8>> = model.iter().filter(|k| k.starts_with(&prefix)).collect();
+            let prefix = &prefix[..];
+            assert!(set.prefix_range(prefix).eq(expected.iter().copied()));
+            assert!(set
+                .prefix_range(prefix)
+                .rev()
+                .eq(expected.iter().rev().copied()));
+            assert_eq!(set.count_prefix(prefix), expected.len());
+            assert!(map
+                .prefix_range(prefix)
+                .map(|(k, _)| k)
+                .eq(expected.iter().copied()));
+            assert_eq!(map.count_prefix(prefix), expected.len());
+        }
+
+        let words = BTreeSet::from_iter(["a", "a\u{10FFFF}", "a\u{10FFFF}b", "b", "\u{D7FF}x"]);
+        assert!(words
+            .prefix_range("a\u{10FFFF}")
+            .eq(&["a\u{10FFFF}", "a\u{10FFFF}b"]));
+        assert_eq!(words.count_prefix("\u{D7FF}"), 1);
+        assert_eq!(words.count_prefix(""), 5);
     }
 
     #[test]
-    fn test_split_off() {
-        let btree: BTreeSet<usize> = BTreeSet::from_iter(0..(DEFAULT_INNER_SIZE * 10));
-        for split in vec![
-            1,
-            (DEFAULT_INNER_SIZE * 3) - 6,
-            DEFAULT_INNER_SIZE,
-            DEFAULT_INNER_SIZE + 1,
-            (DEFAULT_INNER_SIZE * 10) - 1,
-        ] {
-            let mut left = btree.clone();
-            let right = left.split_off(&split);
-            assert!(left.is_disjoint(&right));
-            assert!(Vec::from_iter(left.intersection(&right)).is_empty());
-            let expected_left = Vec::from_iter(0..split);
-            let expected_right = Vec::from_iter(split..(DEFAULT_INNER_SIZE * 10));
+    fn test_range_prefix_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([33; 32]);
+        let mut set = BTreeSet::with_maximum_node_size(4);
+        let mut map = BTreeMap::with_maximum_node_size(4);
+        let mut multiset = BTreeMultiSet::with_maximum_node_size(4);
+        let mut model = std::collections::BTreeSet::new();
+        let mut inserted = Vec::new();
+        for _ in 0..300 {
+            let key = (
+                rng.gen_range(0..8),
+                rng.gen_range(0..4),
+                rng.gen_range(0..100),
+            );
+            set.insert(key);
+            map.insert(key, key.2);
+            multiset.insert(key);
+            model.insert(key);
+            inserted.push(key);
+        }
+        // Tenants and groups must be found across node boundaries
+        assert!(set.chunks().count() > 20);
+        assert!(map.chunks().count() > 20);
+        assert!(multiset.set.chunks().count() > 20);
+
+        for tenant in -1..9 {
+            let expected: Vec<_> = model.iter().filter(|key| key.0 == tenant).collect();
+            assert!(set.range_prefix(&(tenant,)).eq(expected.iter().copied()));
+            assert!(set
+                .range_prefix(&(tenant,))
+                .rev()
+                .eq(expected.iter().rev().copied()));
+            assert!(map
+                .range_prefix(&(tenant,))
+                .map(|(key, _)| key)
+                .eq(expected.iter().copied()));
+            assert_eq!(
+                multiset.range_prefix(&(tenant,)).count(),
+                inserted.iter().filter(|key| key.0 == tenant).count()
+            );
 
-            assert_eq!(expected_left, Vec::from_iter(left));
-            let actual_right = Vec::from_iter(right);
-            assert_eq!(expected_right, actual_right)
+            for group in 0..4 {
+                let expected = model.iter().filter(|key| key.0 == tenant && key.1 == group);
+                assert!(set.range_prefix(&(tenant, group)).eq(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounds_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([34; 32]);
+        let mut set = BTreeSet::with_maximum_node_size(4);
+        let mut map = BTreeMap::with_maximum_node_size(4);
+        let mut model = std::collections::BTreeSet::new();
+        for _ in 0..100 {
+            let value = rng.gen_range(0..200) * 2;
+            set.insert(value);
+            map.insert(value, -value);
+            model.insert(value);
+        }
+        // Neighbours and cursors must be followed across node boundaries
+        assert!(set.chunks().count() > 20);
+        assert!(map.chunks().count() > 20);
+
+        for value in -2..402 {
+            let ceiling = model.range(value..).next();
+            let successor = model
+                .range((Bound::Excluded(value), Bound::Unbounded))
+                .next();
+            let floor = model.range(..=value).next_back();
+            let predecessor = model.range(..value).next_back();
+            assert_eq!(set.ceiling(&value), ceiling);
+            assert_eq!(set.successor(&value), successor);
+            assert_eq!(set.floor(&value), floor);
+            assert_eq!(set.predecessor(&value), predecessor);
+            assert_eq!(map.ceiling(&value).map(|(key, _)| key), ceiling);
+            assert_eq!(map.successor(&value).map(|(key, _)| key), successor);
+            assert_eq!(map.floor(&value).map(|(key, _)| key), floor);
+            assert_eq!(map.predecessor(&value).map(|(key, _)| key), predecessor);
+
+            assert_eq!(set.lower_bound(Bound::Included(&value)).item(), ceiling);
+            assert_eq!(set.lower_bound(Bound::Excluded(&value)).item(), successor);
+            assert_eq!(set.upper_bound(Bound::Included(&value)).item(), floor);
+            assert_eq!(set.upper_bound(Bound::Excluded(&value)).item(), predecessor);
+            assert_eq!(map.lower_bound(Bound::Included(&value)).key(), ceiling);
+            assert_eq!(map.lower_bound(Bound::Excluded(&value)).key(), successor);
+            assert_eq!(map.upper_bound(Bound::Included(&value)).key(), floor);
+            assert_eq!(map.upper_bound(Bound::Excluded(&value)).key(), predecessor);
+        }
+
+        let mut cursor = set.upper_bound::<i32>(Bound::Unbounded);
+        for value in model.iter().rev() {
+            assert_eq!(cursor.item(), Some(value));
+            cursor.move_prev();
+        }
+        assert_eq!(cursor.item(), None);
+        cursor.move_next();
+        assert_eq!(cursor.item(), model.first());
+
+        for (idx, value) in model.iter().enumerate() {
+            assert_eq!(set.rank(value), idx);
+            let mut cursor = set.lower_bound(Bound::Included(value));
+            cursor.move_next();
+            assert_eq!(cursor.item(), model.iter().nth(idx + 1));
+            let mut cursor = map.upper_bound(Bound::Included(value));
+            cursor.move_prev();
+            assert_eq!(
+                cursor.key(),
+                idx.checked_sub(1).and_then(|idx| model.iter().nth(idx))
+            );
         }
     }
 
@@ -4318,4 +7952,886 @@ mod tests {
             }
         }
     }
+
+    // Keeps its elements in a `Vec`, but splits as soon as it holds four of them
+    #[derive(Debug, Clone)]
+    struct SmallNode<T>(Vec<T>);
+
+    impl<T: Ord> NodeLike<T> for SmallNode<T> {
+        type Iter<'a>
+            = std::slice::Iter<'a, T>
+        where
+            T: 'a;
+        type Drain<'a>
+            = std::vec::Drain<'a, T>
+        where
+            T: 'a;
+
+        fn with_capacity(_: usize) -> Self {
+            SmallNode(Vec::with_capacity(4))
+        }
+        fn get_ith(&self, index: usize) -> Option<&T> {
+            self.0.get(index)
+        }
+        fn get_ith_mut(&mut self, index: usize) -> Option<&mut T> {
+            self.0.get_mut(index)
+        }
+        fn split_off(&mut self, at: usize) -> Self {
+            SmallNode(self.0.split_off(at))
+        }
+        fn merge(&mut self, other: &mut Self) {
+            self.0.append(&mut other.0)
+        }
+        fn drain(&mut self) -> Self::Drain<'_> {
+            self.0.drain(..)
+        }
+        fn iter(&self) -> Self::Iter<'_> {
+            self.0.as_slice().iter()
+        }
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+        fn is_full(&self, _: usize) -> bool {
+            self.0.len() >= 4
+        }
+        fn insert(&mut self, value: T) -> (bool, usize) {
+            NodeLike::insert(&mut self.0, value)
+        }
+        fn remove_ith(&mut self, index: usize) -> T {
+            self.0.remove(index)
+        }
+        fn partition_point<P>(&self, pred: P) -> usize
+        where
+            P: FnMut(&T) -> bool,
+        {
+            self.0.as_slice().partition_point(pred)
+        }
+        fn contains<Q: Ord + ?Sized>(&self, value: &Q) -> bool
+        where
+            T: std::borrow::Borrow<Q>,
+        {
+            NodeLike::contains(&self.0, value)
+        }
+        fn try_select<Q: Ord + ?Sized>(&self, value: &Q) -> Option<usize>
+        where
+            T: std::borrow::Borrow<Q>,
+        {
+            self.0.try_select(value)
+        }
+        fn rank<Q: Ord + ?Sized>(&self, bound: Bound<&Q>, from_start: bool) -> Option<usize>
+        where
+            T: std::borrow::Borrow<Q>,
+        {
+            self.0.rank(bound, from_start)
+        }
+        fn delete<Q: Ord + ?Sized>(&mut self, value: &Q) -> Option<(T, usize)>
+        where
+            T: std::borrow::Borrow<Q>,
+        {
+            self.0.delete(value)
+        }
+        fn replace(&mut self, idx: usize, value: T) -> Option<T> {
+            NodeLike::replace(&mut self.0, idx, value)
+        }
+        fn max(&self) -> Option<&T> {
+            self.0.last()
+        }
+    }
+
+    #[test]
+    fn test_custom_node_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([35; 32]);
+        let mut set: BTreeSet<i32, SmallNode<i32>> = BTreeSet::default();
+        let mut model = std::collections::BTreeSet::new();
+        for _ in 0..2000 {
+            let value = rng.gen_range(0..300);
+            if rng.gen_bool(0.7) {
+                assert_eq!(set.insert(value), model.insert(value));
+            } else {
+                assert_eq!(set.remove(&value), model.remove(&value));
+            }
+            assert_eq!(set.len(), model.len());
+        }
+
+        assert!(set.layout.nodes().len() > 1);
+        assert!(set.iter().eq(model.iter()));
+        assert!(set.iter().rev().eq(model.iter().rev()));
+        for value in 0..300 {
+            assert_eq!(set.contains(&value), model.contains(&value));
+            assert_eq!(set.rank(&value), model.range(..value).count());
+            assert!(set
+                .range(value..value + 17)
+                .eq(model.range(value..value + 17)));
+        }
+        for (idx, value) in model.iter().enumerate() {
+            assert_eq!(set.get_index(idx), Some(value));
+        }
+
+        let mut latter = set.split_off(&150);
+        let model_latter = model.split_off(&150);
+        assert!(set.iter().eq(model.iter()));
+        assert!(latter.iter().eq(model_latter.iter()));
+
+        set.append(&mut latter);
+        model.extend(model_latter);
+        assert!(latter.is_empty());
+        assert!(set.iter().eq(model.iter()));
+        assert_eq!(set.len(), model.len());
+
+        set.retain(|value| value % 3 != 0);
+        model.retain(|value| value % 3 != 0);
+        assert!(set.iter().eq(model.iter()));
+        assert_eq!(set.pop_first(), model.pop_first());
+        assert_eq!(set.pop_last(), model.pop_last());
+        assert!(set.into_iter().eq(model));
+    }
+
+    #[test]
+    fn test_maximum_node_size_is_honoured() {
+        let mut set = BTreeSet::with_maximum_node_size(4);
+        let mut map = BTreeMap::with_maximum_node_size(4);
+        for value in 0..200 {
+            set.insert(value);
+            map.insert(value, value);
+        }
+        assert!(set.chunks().count() >= 50);
+        assert!(set.chunks().all(|chunk| chunk.len() <= 4));
+        assert!(map.chunks().count() >= 50);
+        assert!(map.chunks().all(|chunk| chunk.len() <= 4));
+        assert!(set.iter().copied().eq(0..200));
+
+        let mut set = BTreeSet::with_maximum_node_size(1);
+        for value in (0..10).rev() {
+            set.insert(value);
+        }
+        assert!(set.chunks().all(|chunk| !chunk.is_empty()));
+        assert!(set.iter().copied().eq(0..10));
+    }
+
+    #[test]
+    fn test_append_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([36; 32]);
+        for _ in 0..50 {
+            let mut set = BTreeSet::new();
+            let mut other = BTreeSet::new();
+            let mut model = std::collections::BTreeSet::new();
+            let split = rng.gen_range(0..3000);
+            for _ in 0..rng.gen_range(0..2000) {
+                let value = rng.gen_range(0..split + 1);
+                set.insert(value);
+                model.insert(value);
+            }
+            // Overlaps with `set` half of the time
+            let start = if rng.gen_bool(0.5) {
+                split / 2
+            } else {
+                split + 1
+            };
+            for _ in 0..rng.gen_range(0..2000) {
+                let value = rng.gen_range(start..start + 3000);
+                other.insert(value);
+                model.insert(value);
+            }
+
+            set.append(&mut other);
+            assert!(other.is_empty());
+            assert_eq!(set.len(), model.len());
+            assert!(set.iter().eq(model.iter()));
+            for (idx, value) in model.iter().enumerate() {
+                assert_eq!(set.get_index(idx), Some(value));
+            }
+            set.insert(-1);
+            assert_eq!(set.first(), Some(&-1));
+        }
+    }
+
+    #[test]
+    fn test_range_chunks_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([43; 32]);
+        let mut set = BTreeSet::new();
+        let mut map = BTreeMap::new();
+        let mut model = std::collections::BTreeSet::new();
+        for _ in 0..8000 {
+            let value = rng.gen_range(0..20_000);
+            set.insert(value);
+            map.insert(value, value * 2);
+            model.insert(value);
+        }
+
+        assert!(set.chunks().len() > 4);
+        assert!(set.chunks().flatten().eq(model.iter()));
+        assert!(set
+            .chunks()
+            .rev()
+            .flat_map(|chunk| chunk.iter().rev())
+            .eq(model.iter().rev()));
+        for _ in 0..200 {
+            let (a, b) = (rng.gen_range(-10..20_010), rng.gen_range(-10..20_010));
+            let range = (Bound::Excluded(a.min(b)), Bound::Included(a.max(b)));
+            let expected: Vec<_> = model.range(range).collect();
+
+            let chunks: Vec<_> = set.range_chunks(range).collect();
+            assert_eq!(chunks.len(), set.range_chunks(range).len());
+            assert!(chunks
+                .iter()
+                .all(|chunk| !chunk.is_empty() && chunk.len() <= DEFAULT_INNER_SIZE));
+            assert!(chunks
+                .iter()
+                .copied()
+                .flatten()
+                .eq(expected.iter().copied()));
+            let mut back_chunks: Vec<_> = set.range_chunks(range).rev().collect();
+            back_chunks.reverse();
+            assert_eq!(chunks, back_chunks);
+
+            // Taking chunks from both ends must meet in the middle without overlapping
+            let mut both_ends = set.range_chunks(range);
+            let mut front = vec![];
+            let mut back = vec![];
+            while let Some(chunk) = both_ends.next() {
+                front.push(chunk);
+                if let Some(chunk) = both_ends.next_back() {
+                    back.push(chunk);
+                }
+            }
+            front.extend(back.into_iter().rev());
+            assert_eq!(front, chunks);
+
+            let pairs = map.range_chunks(range).flatten();
+            assert!(pairs
+                .map(|pair| (pair.key, pair.value))
+                .eq(expected.iter().map(|value| (**value, **value * 2))));
+        }
+    }
+
+    #[test]
+    fn test_chunks_mut_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([44; 32]);
+        let mut map = BTreeMap::with_maximum_node_size(8);
+        let mut model = std::collections::BTreeMap::new();
+        for _ in 0..500 {
+            let key = rng.gen_range(0..1000);
+            map.insert(key, 0);
+            model.insert(key, 0);
+        }
+
+        for step in 0..200 {
+            let (a, b) = (rng.gen_range(-10..1010), rng.gen_range(-10..1010));
+            let range = a.min(b)..a.max(b);
+            let mut chunks = map.range_chunks_mut(range.clone());
+            let mut back = chunks.next_back();
+            for mut chunk in chunks.by_ref().chain(back.take()) {
+                assert!(!chunk.is_empty() && chunk.len() <= 8);
+                assert_eq!(chunk.get_mut(chunk.len()), None);
+                chunk
+                    .iter_mut()
+                    .for_each(|(key, value)| *value += key + step);
+            }
+            model
+                .range_mut(range.clone())
+                .for_each(|(key, value)| *value += key + step);
+
+            assert!(map
+                .range_chunks(range.clone())
+                .rev()
+                .flat_map(|chunk| chunk.iter().rev())
+                .map(|pair| (&pair.key, &pair.value))
+                .eq(model.range(range).rev()));
+        }
+        assert!(map.iter().eq(model.iter()));
+        assert_eq!(map.chunks_mut().len(), map.chunks().len());
+        for chunk in map.chunks_mut() {
+            let first = chunk.as_slice()[0].key;
+            for (key, value) in chunk {
+                *value = *key - first;
+            }
+        }
+        assert!(map.chunks().all(|chunk| chunk
+            .iter()
+            .all(|pair| pair.value == pair.key - chunk[0].key)));
+    }
+
+    #[test]
+    fn test_equality_and_hash_ignore_layout() {
+        use std::hash::{BuildHasher, RandomState};
+
+        // Ascending and descending inserts leave the same values split differently across nodes
+        let mut ascending: BTreeSet<i32, SmallNode<i32>> = BTreeSet::default();
+        let mut descending: BTreeSet<i32, SmallNode<i32>> = BTreeSet::default();
+        for value in 0..50 {
+            ascending.insert(value);
+            descending.insert(49 - value);
+        }
+        let layout = |set: &BTreeSet<i32, SmallNode<i32>>| -> Vec<usize> {
+            set.layout.nodes().iter().map(NodeLike::len).collect()
+        };
+        assert_ne!(layout(&ascending), layout(&descending));
+        assert_eq!(ascending, descending);
+        assert_eq!(ascending.cmp(&descending), std::cmp::Ordering::Equal);
+        let state = RandomState::new();
+        assert_eq!(state.hash_one(&ascending), state.hash_one(&descending));
+
+        descending.remove(&49);
+        assert_ne!(ascending, descending);
+        assert!(descending < ascending);
+        let mut shifted = descending.clone();
+        shifted.insert(50);
+        assert!(ascending < shifted);
+
+        let set: BTreeSet<i32> = BTreeSet::from_iter([2, 1]);
+        assert_eq!(format!("{set:?}"), "{1, 2}");
+
+        let map = BTreeMap::from_iter([(1, "a"), (2, "b")]);
+        let other = BTreeMap::from_iter([(1, "a"), (2, "c")]);
+        assert_ne!(map, other);
+        assert!(map < other);
+        assert_eq!(map, BTreeMap::from_iter([(2, "b"), (1, "a")]));
+        assert_eq!(format!("{map:?}"), r#"{1: "a", 2: "b"}"#);
+    }
+
+    // Fails the allocations of the current thread once the budget it was given, if any, runs out.
+    // Allocations smaller than the floor it was given are left out of the budget
+    struct BudgetAllocator;
+
+    thread_local! {
+        static ALLOCATION_BUDGET: std::cell::Cell<Option<usize>> = const { std::cell::Cell::new(None) };
+        static ALLOCATION_FLOOR: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    fn spend_allocation(size: usize) -> bool {
+        if ALLOCATION_FLOOR
+            .try_with(|floor| size < floor.get())
+            .unwrap_or(true)
+        {
+            return true;
+        }
+
+        ALLOCATION_BUDGET
+            .try_with(|budget| match budget.get() {
+                Some(0) => false,
+                Some(left) => {
+                    budget.set(Some(left - 1));
+                    true
+                }
+                None => true,
+            })
+            .unwrap_or(true)
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for BudgetAllocator {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            if !spend_allocation(layout.size()) {
+                return std::ptr::null_mut();
+            }
+            std::alloc::System.alloc(layout)
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            std::alloc::System.dealloc(ptr, layout)
+        }
+        unsafe fn realloc(
+            &self,
+            ptr: *mut u8,
+            layout: std::alloc::Layout,
+            new_size: usize,
+        ) -> *mut u8 {
+            if !spend_allocation(new_size) {
+                return std::ptr::null_mut();
+            }
+            std::alloc::System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: BudgetAllocator = BudgetAllocator;
+
+    fn with_allocation_budget<R>(budget: usize, f: impl FnOnce() -> R) -> R {
+        with_allocation_budget_from(0, budget, f)
+    }
+
+    // Only counts the allocations of at least `floor` bytes, which lets the node storage of the
+    // concurrent trees run out while their skip lists, which allocate infallibly, go on
+    pub(crate) fn with_allocation_budget_from<R>(
+        floor: usize,
+        budget: usize,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        ALLOCATION_FLOOR.with(|cell| cell.set(floor));
+        ALLOCATION_BUDGET.with(|cell| cell.set(Some(budget)));
+        let result = f();
+        ALLOCATION_BUDGET.with(|cell| cell.set(None));
+        ALLOCATION_FLOOR.with(|cell| cell.set(0));
+
+        result
+    }
+
+    #[test]
+    fn test_try_insert_against_model_under_allocation_failures() {
+        let mut rng = rand::rngs::StdRng::from_seed([45; 32]);
+        let mut set = BTreeSet::new();
+        let mut model = std::collections::BTreeSet::new();
+        let mut failures = 0;
+        for _ in 0..60_000 {
+            let value = rng.gen_range(0..1_000_000);
+            let len = set.len();
+            match with_allocation_budget(rng.gen_range(0..4), || set.try_insert(value)) {
+                Ok(inserted) => assert_eq!(inserted, model.insert(value)),
+                Err(_) => {
+                    failures += 1;
+                    assert_eq!(set.len(), len);
+                }
+            }
+        }
+
+        assert!(failures > 0);
+        assert!(set.iter().eq(model.iter()));
+        for (idx, value) in model.iter().enumerate().step_by(97) {
+            assert_eq!(set.get_index(idx), Some(value));
+            assert_eq!(set.rank(value), idx);
+        }
+
+        let mut map = BTreeMap::new();
+        for value in model.iter().take(5000) {
+            while with_allocation_budget(rng.gen_range(0..6), || map.try_insert(*value, 0)).is_err()
+            {
+            }
+        }
+        let first = *model.iter().next().unwrap();
+        assert_eq!(
+            with_allocation_budget(0, || map.try_insert(first, 1)),
+            Ok(Some(0))
+        );
+        assert!(map.keys().eq(model.iter().take(5000)));
+    }
+
+    #[test]
+    fn test_try_append_leaves_both_sets_under_allocation_failures() {
+        let mut rng = rand::rngs::StdRng::from_seed([46; 32]);
+        for round in 0..20 {
+            let spread = if round % 2 == 0 { 0 } else { 100_000 };
+            let mut a = BTreeSet::from_iter((0..3000).map(|_| rng.gen_range(0..100_000)));
+            let mut b = BTreeSet::from_iter((0..3000).map(|_| spread + rng.gen_range(0..100_000)));
+            let (a_before, b_before): (Vec<_>, Vec<_>) =
+                (a.iter().copied().collect(), b.iter().copied().collect());
+            let expected: std::collections::BTreeSet<_> =
+                a.iter().chain(b.iter()).copied().collect();
+
+            let mut failures = 0;
+            while with_allocation_budget(failures, || a.try_append(&mut b)).is_err() {
+                failures += 1;
+                assert!(a.iter().eq(a_before.iter()));
+                assert!(b.iter().eq(b_before.iter()));
+                assert_eq!(a.len(), a_before.len());
+                assert_eq!(b.len(), b_before.len());
+            }
+
+            assert!(failures > 0);
+            assert!(b.is_empty());
+            assert!(a.iter().eq(expected.iter()));
+            for (idx, value) in expected.iter().enumerate().step_by(31) {
+                assert_eq!(a.get_index(idx), Some(value));
+            }
+            b.insert(1);
+            assert!(b.iter().eq([1].iter()));
+        }
+
+        for (a, b) in [(vec![], vec![]), (vec![], vec![2, 1]), (vec![2, 1], vec![])] {
+            let expected: Vec<i32> = (0..=a.len().max(b.len()) as i32).collect();
+            let mut a_set = BTreeSet::from_iter(a.iter().copied());
+            let mut b_set = BTreeSet::from_iter(b.iter().copied());
+            a_set.try_append(&mut b_set).unwrap();
+            a_set.insert(0);
+            b_set.insert(0);
+            assert!(a_set.iter().eq(expected.iter()));
+            assert!(b_set.iter().eq([0].iter()));
+
+            let mut a_map = BTreeMap::from_iter(a.iter().map(|key| (*key, ())));
+            let mut b_map = BTreeMap::from_iter(b.iter().map(|key| (*key, ())));
+            a_map.try_append(&mut b_map).unwrap();
+            a_map.insert(0, ());
+            b_map.insert(0, ());
+            assert!(a_map.keys().eq(expected.iter()));
+            assert_eq!(b_map.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_try_extend_leaves_the_tree_under_allocation_failures() {
+        let mut rng = rand::rngs::StdRng::from_seed([60; 32]);
+        let mut set = BTreeSet::with_maximum_node_size(64);
+        set.set_split_policy(SplitPolicy::Custom(|len, _| len - 1));
+        for _ in 0..3000 {
+            set.insert(rng.gen_range(0..10_000));
+        }
+        let before: Vec<_> = set.iter().copied().collect();
+        let values: Vec<_> = (0..1000).map(|_| rng.gen_range(0..10_000)).collect();
+
+        let mut failures = 0;
+        for budget in 0..40 {
+            let input = values.clone();
+            if with_allocation_budget(budget, || set.try_extend(input)).is_err() {
+                failures += 1;
+                assert!(set.iter().eq(before.iter()));
+                for (idx, value) in before.iter().enumerate().step_by(37) {
+                    assert_eq!(set.get_index(idx), Some(value));
+                    assert_eq!(set.rank(value), idx);
+                }
+            }
+        }
+        assert!(failures > 0);
+        set.try_extend(values.iter().copied()).unwrap();
+        let expected: std::collections::BTreeSet<_> =
+            before.iter().chain(values.iter()).copied().collect();
+        assert!(set.iter().eq(expected.iter()));
+
+        let mut map = BTreeMap::with_maximum_node_size(64);
+        for _ in 0..3000 {
+            map.insert(rng.gen_range(0..10_000), 0);
+        }
+        let before: Vec<_> = map.iter().map(|(key, value)| (*key, *value)).collect();
+        let pairs: Vec<_> = (0..1000)
+            .map(|value| (rng.gen_range(0..10_000), value + 1))
+            .collect();
+
+        let mut failures = 0;
+        for budget in 0..40 {
+            let input = pairs.clone();
+            if with_allocation_budget(budget, || map.try_extend(input)).is_err() {
+                failures += 1;
+                assert!(map
+                    .iter()
+                    .map(|(key, value)| (*key, *value))
+                    .eq(before.iter().copied()));
+                for (idx, (key, _)) in before.iter().enumerate().step_by(37) {
+                    assert_eq!(map.get_index(idx).map(|(key, _)| key), Some(key));
+                }
+            }
+        }
+        assert!(failures > 0);
+        map.try_extend(pairs.iter().copied()).unwrap();
+        let mut expected: std::collections::BTreeMap<_, _> = before.into_iter().collect();
+        expected.extend(pairs);
+        assert!(map.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn test_get_mut_misses_absent_keys() {
+        let mut map = BTreeMap::from_iter([(1, "a"), (3, "c")]);
+        assert_eq!(map.get_mut(&2), None);
+        assert_eq!(map.get_mut(&4), None);
+        assert_eq!(map.try_insert(2, "b"), Ok(None));
+        *map.get_mut(&3).unwrap() = "d";
+        assert!(map.iter().eq([(&1, &"a"), (&2, &"b"), (&3, &"d")]));
+    }
+
+    #[test]
+    fn test_with_capacity_loads_into_preallocated_nodes() {
+        let mut rng = rand::rngs::StdRng::from_seed([47; 32]);
+        let mut shuffled: Vec<u64> = (0..50_000).collect();
+        for idx in (1..shuffled.len()).rev() {
+            shuffled.swap(idx, rng.gen_range(0..=idx));
+        }
+
+        for values in [(0..50_000).collect::<Vec<u64>>(), shuffled] {
+            let mut set = BTreeSet::with_capacity(values.len());
+            let list = set.layout.nodes().as_ptr();
+            let mut buffers: Vec<_> = set.spare.iter().map(|node| node.as_ptr()).collect();
+            buffers.push(set.layout.nodes()[0].as_ptr());
+            for value in values.iter() {
+                set.insert(*value);
+            }
+
+            assert_eq!(set.layout.nodes().as_ptr(), list);
+            assert!(set
+                .layout
+                .nodes()
+                .iter()
+                .all(|node| buffers.iter().any(|buffer| *buffer == node.as_ptr())));
+            assert!(set.iter().copied().eq(0..50_000));
+        }
+
+        let mut set: BTreeSet<u64> = BTreeSet::new();
+        set.try_reserve(5000).unwrap();
+        let spare = set.spare.len();
+        assert!(spare >= 5000 / DEFAULT_CUTOFF);
+        set.try_reserve(5000).unwrap();
+        assert_eq!(set.spare.len(), spare);
+        assert!(set.clone().spare.is_empty());
+    }
+
+    #[test]
+    fn test_reservations_follow_split_policy() {
+        let mut set: BTreeSet<u64> = BTreeSet::with_maximum_node_size(8);
+        set.try_reserve(100).unwrap();
+        assert_eq!(set.spare.len(), 100 / 4);
+        set.set_split_policy(SplitPolicy::RightBiased);
+        assert_eq!(set.spare.len(), 100_usize.div_ceil(8));
+        set.try_reserve(100).unwrap();
+        assert_eq!(set.spare.len(), 100_usize.div_ceil(8));
+
+        let list = set.layout.nodes().as_ptr();
+        set.try_extend(0..100).unwrap();
+        assert_eq!(set.layout.nodes().as_ptr(), list);
+        assert_eq!(set.spare.len(), 1);
+        assert!(set.iter().copied().eq(0..100));
+
+        set.set_split_policy(SplitPolicy::Even);
+        set.try_reserve(100).unwrap();
+        assert_eq!(set.spare.len(), 100 / 4);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_drops_spare_nodes() {
+        let mut set: BTreeSet<u64> = BTreeSet::with_capacity(5000);
+        for value in 0..1000 {
+            set.insert(value);
+        }
+        assert!(!set.spare.is_empty());
+
+        let json = serde_json::to_string(&set).unwrap();
+        let set: BTreeSet<u64> = serde_json::from_str(&json).unwrap();
+        assert!(set.spare.is_empty());
+        assert!(set.iter().copied().eq(0..1000));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_keeps_split_policy() {
+        let mut map: BTreeMap<u64, u64> = BTreeMap::with_maximum_node_size(4);
+        map.set_split_policy(SplitPolicy::RightBiased);
+        map.insert(1, 1);
+
+        let json = serde_json::to_string(&map).unwrap();
+        let mut map: BTreeMap<u64, u64> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(map.split_policy(), SplitPolicy::RightBiased));
+        for i in 2..20 {
+            map.insert(i, i);
+        }
+        let chunks: Vec<usize> = map.chunks().map(|chunk| chunk.len()).collect();
+        assert_eq!(chunks, [4, 4, 4, 4, 3]);
+
+        let mut set: BTreeSet<u64> = BTreeSet::new();
+        set.set_split_policy(SplitPolicy::Custom(|len, _| len - 1));
+        let json = serde_json::to_string(&set).unwrap();
+        let set: BTreeSet<u64> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(set.split_policy(), SplitPolicy::Even));
+
+        // Sets serialized before the policy was, without it, split evenly
+        let json = json.replace(",\"split_policy\":\"Even\"", "");
+        assert!(!json.contains("split_policy"));
+        let set: BTreeSet<u64> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(set.split_policy(), SplitPolicy::Even));
+    }
+
+    #[test]
+    fn test_split_policies_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([48; 32]);
+        let policies = [
+            SplitPolicy::Even,
+            SplitPolicy::RightBiased,
+            SplitPolicy::Custom(|len, _| len - 1),
+            SplitPolicy::Custom(|_, position| position),
+            SplitPolicy::Custom(|_, _| 0),
+        ];
+        for policy in policies {
+            let mut set: BTreeSet<i32, SmallNode<i32>> = BTreeSet::default();
+            set.set_split_policy(policy);
+            let mut map = BTreeMap::with_maximum_node_size(4);
+            map.set_split_policy(policy);
+            let mut model = std::collections::BTreeSet::new();
+            for step in 0..4000 {
+                let value = if step % 2 == 0 {
+                    step
+                } else {
+                    rng.gen_range(0..4000)
+                };
+                assert_eq!(set.insert(value), model.insert(value));
+                map.insert(value, value * 2);
+            }
+
+            assert!(set.iter().eq(model.iter()));
+            assert!(map.keys().eq(model.iter()));
+            assert!(set
+                .layout
+                .nodes()
+                .iter()
+                .all(|node| (1..=4).contains(&node.len())));
+            let map_nodes = &map.set.layout.nodes();
+            assert!(map_nodes.iter().all(|node| (1..=4).contains(&node.len())));
+            assert!(map_nodes.len() >= model.len() / 4);
+            for (idx, value) in model.iter().enumerate() {
+                assert_eq!(set.get_index(idx), Some(value));
+                assert_eq!(set.rank(value), idx);
+                assert_eq!(map.get_index(idx), Some((value, &(value * 2))));
+                assert_eq!(map.rank(value), idx);
+            }
+        }
+
+        // Ascending inserts leave every node but the last one full
+        let mut set: BTreeSet<u64> = BTreeSet::new();
+        set.set_split_policy(SplitPolicy::RightBiased);
+        for value in 0..10_000 {
+            set.insert(value);
+        }
+        let (last, full) = set.layout.nodes().split_last().unwrap();
+        assert!(full.iter().all(|node| node.len() == DEFAULT_INNER_SIZE));
+        assert_eq!(last.len(), 10_000 % DEFAULT_INNER_SIZE);
+        let policy = set.clone().split_policy();
+        assert!(matches!(policy, SplitPolicy::RightBiased));
+    }
+
+    #[test]
+    fn test_insert_with_hint_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([51; 32]);
+        let mut small: BTreeSet<i32, SmallNode<i32>> = BTreeSet::default();
+        let mut set: BTreeSet<i32> = BTreeSet::new();
+        let mut map: BTreeMap<i32, i32> = BTreeMap::new();
+        let mut model = std::collections::BTreeMap::new();
+        let (mut small_hint, mut hint, mut map_hint) = Default::default();
+        for batch in 0..200 {
+            // Runs of ascending values, some of them interleaved with earlier runs
+            let start = rng.gen_range(0..20_000);
+            for value in (start..start + rng.gen_range(1..100)).step_by(rng.gen_range(1..4)) {
+                let (inserted, next) = small.insert_with_hint(small_hint, value);
+                assert_eq!(inserted, !model.contains_key(&value));
+                small_hint = next;
+                let (inserted, next) = set.insert_with_hint(hint, value);
+                assert_eq!(inserted, !model.contains_key(&value));
+                hint = next;
+                let (previous, next) = map.insert_with_hint(map_hint, value, batch);
+                assert_eq!(previous, model.insert(value, batch));
+                map_hint = next;
+            }
+
+            // Stale and unrelated hints
+            let value = rng.gen_range(0..20_000);
+            let stale = small_hint;
+            small.insert_with_hint(stale, value);
+            set.insert_with_hint(small_hint, value);
+            let cursor = map.lower_bound(Bound::Included(&rng.gen_range(0..20_000)));
+            let cursor_hint = cursor.hint();
+            assert_eq!(
+                map.insert_with_hint(cursor_hint, value, -batch).0,
+                model.insert(value, -batch)
+            );
+        }
+
+        assert!(small.iter().eq(model.keys()));
+        assert!(set.iter().eq(model.keys()));
+        assert!(map.iter().eq(model.iter()));
+        for (idx, key) in model.keys().enumerate() {
+            assert_eq!(small.get_index(idx), Some(key));
+            assert_eq!(set.rank(key), idx);
+            assert_eq!(map.get_index(idx).map(|(key, _)| key), Some(key));
+        }
+
+        let mut set: BTreeSet<i32> = BTreeSet::new();
+        let cursor_hint = set.lower_bound(Bound::Unbounded).hint();
+        assert_eq!(set.insert_with_hint(cursor_hint, 1), (true, cursor_hint));
+        let last = set.upper_bound(Bound::Included(&1)).hint();
+        assert_eq!(
+            set.insert_with_hint(last, 2),
+            (
+                true,
+                Hint {
+                    node_idx: 0,
+                    position: 1
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_quantiles_and_histograms_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([54; 32]);
+        for len in [0, 1, 2, 3, 7, 100, 2500] {
+            let mut model: Vec<u32> = (0..len).map(|_| rng.gen_range(0..1_000_000)).collect();
+            model.sort();
+            model.dedup();
+            let mut set: BTreeSet<u32, SmallNode<u32>> = BTreeSet::default();
+            for value in model.iter() {
+                set.insert(*value);
+            }
+            let map = BTreeMap::from_iter(model.iter().map(|key| (*key, *key as u64)));
+
+            let mut qs: Vec<f64> = (0..=20).map(|step| step as f64 / 20.0).collect();
+            qs.extend([0.07, 0.14, 0.28, 1.0 / 3.0, 0.999]);
+            // The smallest value that at least a `q` fraction of all values are at most
+            let expected: Vec<&u32> = qs
+                .iter()
+                .filter_map(|q| {
+                    model.iter().find(|candidate| {
+                        let covered = model.partition_point(|item| item <= candidate);
+                        covered as f64 / model.len() as f64 >= *q
+                    })
+                })
+                .collect();
+            assert_eq!(set.quantiles(&qs), expected);
+            assert!(map
+                .quantiles(&qs)
+                .into_iter()
+                .map(|(key, _)| key)
+                .eq(expected.iter().copied()));
+
+            for buckets in [1, 2, 3, 10, 64, 5000] {
+                let histogram = set.equi_depth_histogram(buckets);
+                assert_eq!(histogram.len(), buckets.min(model.len()));
+                assert_eq!(map.equi_depth_histogram(buckets), histogram);
+                let mut next = 0;
+                for (idx, bucket) in histogram.iter().enumerate() {
+                    assert_eq!(*bucket.lower, model[next]);
+                    next += bucket.count;
+                    assert_eq!(*bucket.upper, model[next - 1]);
+                    assert!(bucket.count.abs_diff(model.len() / histogram.len()) <= 1);
+                    assert_eq!(set.ntile(bucket.lower, buckets), Some(idx));
+                    assert_eq!(map.ntile(bucket.upper, buckets), Some(idx));
+                    let past = (idx + 1).min(histogram.len() - 1);
+                    assert_eq!(set.ntile(&(*bucket.upper + 1), buckets), Some(past));
+                }
+                assert_eq!(next, model.len());
+            }
+        }
+        assert_eq!(BTreeSet::<u32>::new().ntile(&1, 4), None);
+
+        let hundred = BTreeSet::from_iter(0..100);
+        assert_eq!(hundred.quantile(0.07), Some(&6));
+        assert_eq!(hundred.quantile(0.14), Some(&13));
+        assert_eq!(BTreeSet::from_iter(0..25).quantile(0.28), Some(&6));
+    }
+
+    #[test]
+    fn test_sampling_by_rank_is_uniform() {
+        let mut rng = rand::rngs::StdRng::from_seed([57; 32]);
+        let mut set: BTreeSet<u32, SmallNode<u32>> = BTreeSet::default();
+        for value in 0..200 {
+            set.insert(value * 3);
+        }
+        let map = BTreeMap::from_iter((0..200).map(|key| (key * 3, key)));
+
+        let mut counts = vec![0usize; 200];
+        for _ in 0..100_000 {
+            counts[(*set.sample(&mut rng).unwrap() / 3) as usize] += 1;
+            let (key, value) = map.sample_range(30..=60, &mut rng).unwrap();
+            assert!((30..=60).contains(key) && *key == value * 3);
+        }
+        assert!(counts.iter().all(|count| count.abs_diff(500) < 120));
+
+        let mut counts = [0usize; 5];
+        for _ in 0..50_000 {
+            let value = set.sample_range(&10..&25, &mut rng).unwrap();
+            counts[(*value / 3 - 4) as usize] += 1;
+        }
+        assert!(counts.iter().all(|count| count.abs_diff(10_000) < 500));
+        assert_eq!(set.sample_range(&10..&12, &mut rng), None);
+        assert_eq!(set.sample_range(&1000.., &mut rng), None);
+
+        for k in [0, 1, 50, 199, 200, 500] {
+            let sample = set.sample_k_distinct(k, &mut rng);
+            assert_eq!(sample.len(), k.min(200));
+            assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(sample.iter().all(|value| set.contains(*value)));
+            assert_eq!(map.sample_k_distinct(k, &mut rng).len(), k.min(200));
+        }
+        assert_eq!(BTreeMap::<u32, u32>::new().sample(&mut rng), None);
+    }
 }
@@ -0,0 +1,107 @@
+use std::borrow::Borrow;
+#[cfg(feature = "concurrent")]
+use std::ops::Bound;
+
+/// A borrowed key type that can be scanned by prefix, such as [`str`] and `[u8]`.
+///
+/// Every key that starts with a given prefix sorts at or after the prefix itself, and strictly
+/// before the prefix's [successor](Prefix::prefix_successor), so a prefix scan boils down to a
+/// regular range scan between those two bounds.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::prefix::Prefix;
+///
+/// assert_eq!("ab".prefix_successor(), Some("ac".to_string()));
+/// assert_eq!(b"a\xff"[..].prefix_successor(), Some(b"b".to_vec()));
+/// assert_eq!(b"\xff\xff"[..].prefix_successor(), None);
+/// assert!("abc".has_prefix("ab"));
+/// ```
+pub trait Prefix: Ord {
+    /// The owned form of the key, used to hold the upper bound of a prefix scan.
+    type Owned: Borrow<Self>;
+
+    /// Returns `true` if `self` starts with `prefix`.
+    fn has_prefix(&self, prefix: &Self) -> bool;
+
+    /// Returns the smallest key that is greater than every key starting with `self`, or `None`
+    /// if there is no such key and the scan must run until the end of the collection.
+    fn prefix_successor(&self) -> Option<Self::Owned>;
+}
+
+impl Prefix for str {
+    type Owned = String;
+
+    fn has_prefix(&self, prefix: &Self) -> bool {
+        self.starts_with(prefix)
+    }
+
+    fn prefix_successor(&self) -> Option<Self::Owned> {
+        let mut successor = self.to_string();
+        while let Some(last) = successor.pop() {
+            // `char::from_u32` skips over the surrogate range, which holds no valid chars
+            if let Some(next) = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+                successor.push(next);
+                return Some(successor);
+            }
+        }
+
+        None
+    }
+}
+
+impl Prefix for [u8] {
+    type Owned = Vec<u8>;
+
+    fn has_prefix(&self, prefix: &Self) -> bool {
+        self.starts_with(prefix)
+    }
+
+    fn prefix_successor(&self) -> Option<Self::Owned> {
+        let mut successor = self.to_vec();
+        while let Some(last) = successor.pop() {
+            if last < u8::MAX {
+                successor.push(last + 1);
+                return Some(successor);
+            }
+        }
+
+        None
+    }
+}
+
+/// Turns a prefix and its successor into the bounds of the matching range.
+#[cfg(feature = "concurrent")]
+pub(crate) fn prefix_bounds<'a, Q>(
+    prefix: &'a Q,
+    successor: &'a Option<Q::Owned>,
+) -> (Bound<&'a Q>, Bound<&'a Q>)
+where
+    Q: Prefix + ?Sized,
+{
+    let end = match successor {
+        Some(successor) => Bound::Excluded(successor.borrow()),
+        None => Bound::Unbounded,
+    };
+
+    (Bound::Included(prefix), end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Prefix;
+
+    #[test]
+    fn test_prefix_successor() {
+        assert_eq!("".prefix_successor(), None);
+        assert_eq!("a".prefix_successor(), Some("b".to_string()));
+        assert_eq!("a\u{10FFFF}".prefix_successor(), Some("b".to_string()));
+        assert_eq!("\u{D7FF}".prefix_successor(), Some("\u{E000}".to_string()));
+        assert_eq!("\u{10FFFF}".prefix_successor(), None);
+
+        assert_eq!(b""[..].prefix_successor(), None);
+        assert_eq!(b"\x00\xff"[..].prefix_successor(), Some(vec![1]));
+        assert_eq!(b"\xff"[..].prefix_successor(), None);
+    }
+}
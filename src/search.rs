@@ -0,0 +1,367 @@
+use std::borrow::Borrow;
+use std::collections::TryReserveError;
+use std::ops::Bound;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::NodeLike;
+
+/// A key type that picks how a sorted node of its values is searched.
+///
+/// The provided methods go through [`slice::partition_point`], which suits any key. Primitive
+/// integers override them with a lower bound that never branches on a comparison: it halves the
+/// haystack with conditional moves down to a cache line, then counts the smaller elements of that
+/// line with a loop that compilers vectorize. Either way the results are the same as the ones of
+/// [`slice::partition_point`] and [`slice::binary_search`].
+///
+/// The search is only used by the nodes of type [`SearchNode`]. Trees with the default `Vec`
+/// nodes, such as a plain `BTreeSet<u64>`, keep the generic search whatever their key type, since
+/// those nodes take any [`Ord`] key and a search cannot be picked by key type for them.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::search::SearchKey;
+///
+/// let haystack = [1u64, 3, 5, 7];
+/// assert_eq!(u64::lower_bound(&haystack, &5), 2);
+/// assert_eq!(u64::lower_bound(&haystack, &6), 3);
+/// assert_eq!(u64::search(&haystack, &7), Ok(3));
+/// assert_eq!(u64::search(&haystack, &0), Err(0));
+/// ```
+pub trait SearchKey: Ord + Sized {
+    /// Returns the number of elements of the sorted `haystack` that are less than `needle`.
+    fn lower_bound<Q>(haystack: &[Self], needle: &Q) -> usize
+    where
+        Self: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        haystack.partition_point(|item| item.borrow() < needle)
+    }
+    /// Looks `needle` up in the sorted `haystack`, returning either its position or the one at
+    /// which it would be inserted.
+    fn search<Q>(haystack: &[Self], needle: &Q) -> Result<usize, usize>
+    where
+        Self: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let position = Self::lower_bound(haystack, needle);
+        match haystack.get(position) {
+            Some(item) if item.borrow() == needle => Ok(position),
+            _ => Err(position),
+        }
+    }
+}
+
+#[inline]
+fn branchless_lower_bound<T, Q>(haystack: &[T], needle: &Q) -> usize
+where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    // Once the candidates fit in a cache line, comparing against all of them beats halving further
+    let line = (64 / std::mem::size_of::<T>()).max(1);
+    let mut base = 0;
+    let mut size = haystack.len();
+    while size > line {
+        let half = size / 2;
+        // Turning the comparison into an offset keeps it out of the control flow
+        base += usize::from(haystack[base + half].borrow() < needle) * half;
+        size -= half;
+    }
+
+    base + haystack[base..base + size]
+        .iter()
+        .map(|item| usize::from(item.borrow() < needle))
+        .sum::<usize>()
+}
+
+macro_rules! branchless_search_key {
+    ($($t:ty),+) => {
+        $(
+            impl SearchKey for $t {
+                #[inline]
+                fn lower_bound<Q>(haystack: &[Self], needle: &Q) -> usize
+                where
+                    Self: Borrow<Q>,
+                    Q: Ord + ?Sized,
+                {
+                    branchless_lower_bound(haystack, needle)
+                }
+            }
+        )+
+    };
+}
+
+branchless_search_key!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, char);
+
+impl SearchKey for String {}
+
+impl SearchKey for Vec<u8> {}
+
+/// A `Vec`-backed node that looks its elements up through their [`SearchKey`] implementation.
+///
+/// It behaves exactly like the default `Vec` nodes of [`BTreeSet`](crate::BTreeSet), but lookups,
+/// ranks and insertions go through the search picked by the key type, which is faster for
+/// primitive integers. The faster search has to be opted into by naming this node type, as below:
+/// `BTreeSet::new` still makes trees whose nodes search generically.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::search::SearchNode;
+/// use indexset::BTreeSet;
+///
+/// let mut set: BTreeSet<u64, SearchNode<u64>> = BTreeSet::default();
+/// for value in [5, 1, 3] {
+///     set.insert(value);
+/// }
+/// assert!(set.contains(&3));
+/// assert_eq!(set.rank(&4), 2);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SearchNode<T>(Vec<T>);
+
+impl<T> AsRef<[T]> for SearchNode<T> {
+    fn as_ref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T: SearchKey> NodeLike<T> for SearchNode<T> {
+    type Iter<'a>
+        = std::slice::Iter<'a, T>
+    where
+        T: 'a;
+    type Drain<'a>
+        = std::vec::Drain<'a, T>
+    where
+        T: 'a;
+
+    #[inline]
+    fn with_capacity(capacity: usize) -> Self {
+        SearchNode(Vec::with_capacity(capacity))
+    }
+    #[inline]
+    fn get_ith(&self, index: usize) -> Option<&T> {
+        self.0.get(index)
+    }
+    #[inline]
+    fn get_ith_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.0.get_mut(index)
+    }
+    #[inline]
+    fn halve(&mut self) -> Self {
+        SearchNode(NodeLike::halve(&mut self.0))
+    }
+    #[inline]
+    fn split_off(&mut self, at: usize) -> Self {
+        SearchNode(self.0.split_off(at))
+    }
+    #[inline]
+    fn split_off_into(&mut self, at: usize, other: &mut Self) {
+        NodeLike::split_off_into(&mut self.0, at, &mut other.0)
+    }
+    #[inline]
+    fn try_split_off(&mut self, at: usize) -> Result<Self, TryReserveError> {
+        NodeLike::try_split_off(&mut self.0, at).map(SearchNode)
+    }
+    #[inline]
+    fn merge(&mut self, other: &mut Self) {
+        self.0.append(&mut other.0)
+    }
+    #[inline]
+    fn drain(&mut self) -> Self::Drain<'_> {
+        self.0.drain(..)
+    }
+    #[inline]
+    fn iter(&self) -> Self::Iter<'_> {
+        self.0.as_slice().iter()
+    }
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    #[inline]
+    fn is_full(&self, capacity: usize) -> bool {
+        NodeLike::is_full(&self.0, capacity)
+    }
+    #[inline]
+    fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.0.try_reserve(additional)
+    }
+    #[inline]
+    fn insert(&mut self, value: T) -> (bool, usize) {
+        match T::search(&self.0, &value) {
+            Ok(idx) => (false, idx),
+            Err(idx) => {
+                self.0.insert(idx, value);
+                (true, idx)
+            }
+        }
+    }
+    #[inline]
+    fn insert_at(&mut self, position: usize, value: T) {
+        self.0.insert(position, value)
+    }
+    #[inline]
+    fn remove_ith(&mut self, index: usize) -> T {
+        self.0.remove(index)
+    }
+    #[inline]
+    fn partition_point<P>(&self, pred: P) -> usize
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.0.as_slice().partition_point(pred)
+    }
+    #[inline]
+    fn lower_bound<Q: Ord + ?Sized>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+    {
+        T::lower_bound(&self.0, value)
+    }
+    #[inline]
+    fn contains<Q: Ord + ?Sized>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        T::search(&self.0, value).is_ok()
+    }
+    #[inline]
+    fn try_select<Q: Ord + ?Sized>(&self, value: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+    {
+        T::search(&self.0, value).ok()
+    }
+    #[inline]
+    fn rank<Q: Ord + ?Sized>(&self, bound: Bound<&Q>, from_start: bool) -> Option<usize>
+    where
+        T: Borrow<Q>,
+    {
+        let (value, inclusive) = match bound {
+            Bound::Included(value) => (value, true),
+            Bound::Excluded(value) => (value, false),
+            Bound::Unbounded => return None,
+        };
+        let (lower, upper) = match T::search(&self.0, value) {
+            Ok(idx) => (idx, idx + 1),
+            Err(idx) => (idx, idx),
+        };
+        // Elements on the side of the bound that is counted from, all of them but the last one
+        // being skipped
+        let beyond = match (from_start, inclusive) {
+            (true, true) => lower,
+            (true, false) => upper,
+            (false, true) => self.0.len() - upper,
+            (false, false) => self.0.len() - lower,
+        };
+
+        beyond.checked_sub(1)
+    }
+    #[inline]
+    fn delete<Q: Ord + ?Sized>(&mut self, value: &Q) -> Option<(T, usize)>
+    where
+        T: Borrow<Q>,
+    {
+        let idx = T::search(&self.0, value).ok()?;
+
+        Some((self.0.remove(idx), idx))
+    }
+    #[inline]
+    fn replace(&mut self, idx: usize, value: T) -> Option<T> {
+        NodeLike::replace(&mut self.0, idx, value)
+    }
+    #[inline]
+    fn max(&self) -> Option<&T> {
+        self.0.last()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SearchKey, SearchNode};
+    use crate::{BTreeSet, NodeLike};
+    use std::ops::Bound;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_lower_bound_matches_partition_point() {
+        let mut rng = rand::rngs::StdRng::from_seed([38; 32]);
+        for len in (0..70).chain([255, 256, 1023, 1024]) {
+            let mut haystack: Vec<i64> = (0..len).map(|_| rng.gen_range(-500..500)).collect();
+            haystack.sort();
+            haystack.dedup();
+            let bytes: Vec<u8> = (0..=255).step_by(len % 7 + 1).collect();
+
+            for needle in -510..510 {
+                assert_eq!(
+                    i64::lower_bound(&haystack, &needle),
+                    haystack.partition_point(|item| *item < needle)
+                );
+                assert_eq!(
+                    i64::search(&haystack, &needle).is_ok(),
+                    haystack.binary_search(&needle).is_ok()
+                );
+            }
+            for needle in 0..=255 {
+                assert_eq!(
+                    u8::lower_bound(&bytes, &needle),
+                    bytes.partition_point(|item| *item < needle)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_rank_matches_vec_node() {
+        let mut rng = rand::rngs::StdRng::from_seed([37; 32]);
+        for len in (0..40).chain([255, 1024]) {
+            let mut values: Vec<u32> = (0..len).map(|_| rng.gen_range(0..500)).collect();
+            values.sort();
+            values.dedup();
+            let node = SearchNode(values.clone());
+
+            for value in 0..510 {
+                for bound in [
+                    Bound::Included(&value),
+                    Bound::Excluded(&value),
+                    Bound::Unbounded,
+                ] {
+                    for from_start in [true, false] {
+                        assert_eq!(
+                            NodeLike::rank(&node, bound, from_start),
+                            NodeLike::rank(&values, bound, from_start)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_node_against_vec_node() {
+        let mut rng = rand::rngs::StdRng::from_seed([39; 32]);
+        let mut set: BTreeSet<u32, SearchNode<u32>> = BTreeSet::default();
+        let mut reference = BTreeSet::new();
+        for _ in 0..20_000 {
+            let value = rng.gen_range(0..5000);
+            if rng.gen_bool(0.7) {
+                assert_eq!(set.insert(value), reference.insert(value));
+            } else {
+                assert_eq!(set.remove(&value), reference.remove(&value));
+            }
+        }
+
+        assert!(set.iter().eq(reference.iter()));
+        for value in 0..5001 {
+            assert_eq!(set.contains(&value), reference.contains(&value));
+            assert_eq!(set.rank(&value), reference.rank(&value));
+            assert_eq!(set.get(&value), reference.get(&value));
+        }
+    }
+}
@@ -0,0 +1,1588 @@
+use std::borrow::Borrow;
+use std::iter::{FusedIterator, Zip};
+use std::mem::swap;
+use std::ops::{Bound, Index, RangeBounds};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::constants::DEFAULT_INNER_SIZE;
+use crate::core::layout::{IntoNodes, Layout, LayoutNode, Nodes, NodesMut, Spine};
+use crate::core::node::SplitPolicy;
+
+// Keys and values are kept in parallel vectors, so that searching a node only ever touches keys
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+}
+
+type Entries<'a, K, V> = Zip<std::slice::Iter<'a, K>, std::slice::Iter<'a, V>>;
+type EntriesMut<'a, K, V> = Zip<std::slice::Iter<'a, K>, std::slice::IterMut<'a, V>>;
+type IntoEntries<K, V> = Zip<std::vec::IntoIter<K>, std::vec::IntoIter<V>>;
+
+impl<K, V> Node<K, V> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            keys: Vec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+        }
+    }
+    fn insert(&mut self, position: usize, key: K, value: V) {
+        self.keys.insert(position, key);
+        self.values.insert(position, value);
+    }
+    fn remove(&mut self, position: usize) -> (K, V) {
+        (self.keys.remove(position), self.values.remove(position))
+    }
+    fn entries(&self) -> Entries<'_, K, V> {
+        self.keys.iter().zip(self.values.iter())
+    }
+    fn entries_mut(&mut self) -> EntriesMut<'_, K, V> {
+        self.keys.iter().zip(self.values.iter_mut())
+    }
+    fn into_entries(self) -> IntoEntries<K, V> {
+        self.keys.into_iter().zip(self.values)
+    }
+}
+
+impl<K, V> LayoutNode for Node<K, V> {
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+    fn split_off(&mut self, at: usize) -> Self {
+        Self {
+            keys: self.keys.split_off(at),
+            values: self.values.split_off(at),
+        }
+    }
+}
+
+/// An ordered map based on a two-level B-Tree, whose nodes keep keys and values apart.
+///
+/// [`BTreeMap`](crate::BTreeMap) stores every key right next to its value, so looking a key up
+/// drags the values it compares against into the cache as well. This map holds the keys and the
+/// values of each node in two parallel vectors instead, so that searches only scan dense arrays
+/// of keys, which pays off when values are large. The API mirrors the one of
+/// [`BTreeMap`](crate::BTreeMap), including positional access, ranges and the entry API.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::soa::BTreeMap;
+///
+/// let mut documents = BTreeMap::new();
+/// documents.insert("b", [1u8; 256]);
+/// documents.insert("a", [0u8; 256]);
+/// documents.entry("c").or_insert([2u8; 256])[0] = 3;
+///
+/// assert_eq!(documents.get("a").map(|document| document[0]), Some(0));
+/// assert_eq!(documents.get_index(2).map(|(key, _)| *key), Some("c"));
+/// assert!(documents.keys().eq(["a", "b", "c"].iter()));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct BTreeMap<K, V>
+where
+    K: Ord,
+{
+    layout: Layout<Node<K, V>>,
+    node_capacity: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    split_policy: SplitPolicy,
+}
+
+impl<K: Ord, V> Default for BTreeMap<K, V> {
+    fn default() -> Self {
+        Self {
+            layout: Layout::new(Node::with_capacity(DEFAULT_INNER_SIZE)),
+            node_capacity: DEFAULT_INNER_SIZE,
+            split_policy: SplitPolicy::default(),
+        }
+    }
+}
+
+impl<K: Ord, V> BTreeMap<K, V> {
+    /// Makes a new, empty `BTreeMap` with maximum node size 1024.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map: BTreeMap<i32, String> = BTreeMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `BTreeMap` with the given maximum node size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map: BTreeMap<i32, String> = BTreeMap::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            layout: Layout::new(Node::with_capacity(maximum_node_size)),
+            node_capacity: maximum_node_size,
+            ..Default::default()
+        }
+    }
+    /// Clears the map, removing all entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.clear();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.layout = Layout::new(Node::with_capacity(self.node_capacity));
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    /// use indexset::SplitPolicy;
+    ///
+    /// let mut map: BTreeMap<u64, u64> = BTreeMap::new();
+    /// map.set_split_policy(SplitPolicy::RightBiased);
+    /// assert!(matches!(map.split_policy(), SplitPolicy::RightBiased));
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.split_policy = split_policy;
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.split_policy
+    }
+    /// Returns the number of entries in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.len(), 0);
+    /// map.insert(1, "a");
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.layout.len()
+    }
+    /// Returns `true` if the map contains no entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert!(map.is_empty());
+    /// map.insert(1, "a");
+    /// assert!(!map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn locate_node_cmp<P>(&self, mut cmp: P) -> usize
+    where
+        P: FnMut(&K) -> bool,
+    {
+        self.layout
+            .locate_node(|node| node.keys.last().is_some_and(&mut cmp))
+    }
+    // Position of the first key for which `cmp` does not hold, as a node and an offset within it
+    fn locate_cmp<P>(&self, mut cmp: P) -> (usize, usize)
+    where
+        P: FnMut(&K) -> bool,
+    {
+        let node_idx = self.locate_node_cmp(&mut cmp);
+        let position_within_node = self.layout.nodes()[node_idx].keys.partition_point(cmp);
+
+        (node_idx, position_within_node)
+    }
+    fn locate_key<Q>(&self, key: &Q) -> (usize, usize)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.locate_cmp(|item| item.borrow() < key)
+    }
+    fn rank_cmp<P>(&self, cmp: P) -> usize
+    where
+        P: FnMut(&K) -> bool,
+    {
+        let (node_idx, position_within_node) = self.locate_cmp(cmp);
+
+        self.layout.offset(node_idx) + position_within_node
+    }
+    fn find<Q>(&self, key: &Q) -> Option<(usize, usize)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (node_idx, position_within_node) = self.locate_key(key);
+        match self.layout.nodes()[node_idx].keys.get(position_within_node) {
+            Some(candidate) if candidate.borrow() == key => Some((node_idx, position_within_node)),
+            _ => None,
+        }
+    }
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.get(&2), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (node_idx, position_within_node) = self.find(key)?;
+
+        Some(&self.layout.nodes()[node_idx].values[position_within_node])
+    }
+    /// Returns the key-value pair corresponding to the supplied key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get_key_value(&1), Some((&1, &"a")));
+    /// assert_eq!(map.get_key_value(&2), None);
+    /// ```
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (node_idx, position_within_node) = self.find(key)?;
+        let node = &self.layout.nodes()[node_idx];
+
+        Some((
+            &node.keys[position_within_node],
+            &node.values[position_within_node],
+        ))
+    }
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// if let Some(x) = map.get_mut(&1) {
+    ///     *x = "b";
+    /// }
+    /// assert_eq!(map[&1], "b");
+    /// ```
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (node_idx, position_within_node) = self.find(key)?;
+
+        Some(&mut self.layout.nodes_mut()[node_idx].values[position_within_node])
+    }
+    /// Returns `true` if the map contains a value for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.contains_key(&1), true);
+    /// assert_eq!(map.contains_key(&2), false);
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+    /// Returns the i-th key-value pair of the map, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(3, "c"), (1, "a"), (2, "b")]);
+    /// assert_eq!(map.get_index(0), Some((&1, &"a")));
+    /// assert_eq!(map.get_index(2), Some((&3, &"c")));
+    /// assert_eq!(map.get_index(3), None);
+    /// ```
+    pub fn get_index(&self, idx: usize) -> Option<(&K, &V)> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let (node_idx, position_within_node) = self.layout.locate_ith(idx);
+        let node = &self.layout.nodes()[node_idx];
+
+        Some((
+            &node.keys[position_within_node],
+            &node.values[position_within_node],
+        ))
+    }
+    fn get_mut_index(&mut self, idx: usize) -> Option<&mut V> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let (node_idx, position_within_node) = self.layout.locate_ith(idx);
+
+        Some(&mut self.layout.nodes_mut()[node_idx].values[position_within_node])
+    }
+    /// Returns the position in which the given key would fall in the already-existing sorted
+    /// order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, "a"), (2, "b"), (4, "d")]);
+    /// assert_eq!(map.rank(&1), 0);
+    /// assert_eq!(map.rank(&3), 2);
+    /// assert_eq!(map.rank(&5), 3);
+    /// ```
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.rank_cmp(|item| item.borrow() < key)
+    }
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map did not have this key present, `None` is returned. Otherwise, the value is
+    /// updated, and the old value is returned. The key is not updated, though.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.insert(37, "a"), None);
+    ///
+    /// map.insert(37, "b");
+    /// assert_eq!(map.insert(37, "c"), Some("b"));
+    /// assert_eq!(map[&37], "c");
+    /// ```
+    pub fn insert(&mut self, key: K, mut value: V) -> Option<V> {
+        let (node_idx, position_within_node) = self.locate_key(&key);
+        if self.layout.nodes()[node_idx].keys.get(position_within_node) == Some(&key) {
+            swap(
+                &mut self.layout.nodes_mut()[node_idx].values[position_within_node],
+                &mut value,
+            );
+
+            return Some(value);
+        }
+
+        self.layout.insert(
+            node_idx,
+            position_within_node,
+            self.node_capacity,
+            self.split_policy,
+            |node, position| node.insert(position, key, value),
+        );
+
+        None
+    }
+    fn delete_at(&mut self, node_idx: usize, position_within_node: usize) -> (K, V) {
+        self.layout
+            .remove_with(node_idx, |node| node.remove(position_within_node))
+    }
+    /// Removes a key from the map, returning the stored key and value if the key was previously
+    /// in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.remove_entry(&1), Some((1, "a")));
+    /// assert_eq!(map.remove_entry(&1), None);
+    /// ```
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (node_idx, position_within_node) = self.find(key)?;
+
+        Some(self.delete_at(node_idx, position_within_node))
+    }
+    /// Removes a key from the map, returning the value at the key if the key was previously in
+    /// the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.remove(&1), Some("a"));
+    /// assert_eq!(map.remove(&1), None);
+    /// ```
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+    /// Removes the i-th key-value pair from the map and returns it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// assert_eq!(map.pop_index(1), (2, "b"));
+    /// assert_eq!(map.pop_index(0), (1, "a"));
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn pop_index(&mut self, idx: usize) -> (K, V) {
+        assert!(idx < self.len(), "index out of bounds");
+        let (node_idx, position_within_node) = self.layout.locate_ith(idx);
+
+        self.delete_at(node_idx, position_within_node)
+    }
+    /// Returns the first key-value pair in the map, whose key is the minimum one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.first_key_value(), None);
+    /// map.insert(2, "b");
+    /// map.insert(1, "a");
+    /// assert_eq!(map.first_key_value(), Some((&1, &"a")));
+    /// ```
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.get_index(0)
+    }
+    /// Returns the last key-value pair in the map, whose key is the maximum one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.last_key_value(), None);
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// assert_eq!(map.last_key_value(), Some((&2, &"b")));
+    /// ```
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.get_index(self.len().checked_sub(1)?)
+    }
+    /// Removes and returns the first key-value pair in the map, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// assert_eq!(map.pop_first(), Some((1, "a")));
+    /// assert_eq!(map.pop_first(), Some((2, "b")));
+    /// assert_eq!(map.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.delete_at(0, 0))
+    }
+    /// Removes and returns the last key-value pair in the map, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// assert_eq!(map.pop_last(), Some((2, "b")));
+    /// assert_eq!(map.pop_last(), Some((1, "a")));
+    /// assert_eq!(map.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let node_idx = self.layout.nodes().len() - 1;
+        let position_within_node = self.layout.nodes()[node_idx].len() - 1;
+
+        Some(self.delete_at(node_idx, position_within_node))
+    }
+    /// Gets the given key's corresponding entry in the map for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut count: BTreeMap<&str, usize> = BTreeMap::new();
+    ///
+    /// for x in ["a", "b", "a", "c", "a", "b"] {
+    ///     count.entry(x).and_modify(|curr| *curr += 1).or_insert(1);
+    /// }
+    ///
+    /// assert_eq!(count["a"], 3);
+    /// assert_eq!(count["b"], 2);
+    /// assert_eq!(count["c"], 1);
+    /// ```
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let (node_idx, position_within_node) = self.locate_key(&key);
+        let idx = self.layout.offset(node_idx) + position_within_node;
+        if self.layout.nodes()[node_idx].keys.get(position_within_node) == Some(&key) {
+            return Entry::Occupied(OccupiedEntry { map: self, idx });
+        }
+
+        Entry::Vacant(VacantEntry {
+            map: self,
+            key,
+            idx,
+        })
+    }
+    fn spine(&self, start: usize, end: usize) -> Spine<NodeIter<'_, K, V>> {
+        self.layout.spine(start, end, Node::entries, |node, range| {
+            node.keys[range.clone()]
+                .iter()
+                .zip(node.values[range].iter())
+        })
+    }
+    fn spine_mut(&mut self, start: usize, end: usize) -> Spine<NodeIterMut<'_, K, V>> {
+        self.layout
+            .spine_mut(start, end, Node::entries_mut, |node, range| {
+                node.keys[range.clone()]
+                    .iter()
+                    .zip(node.values[range].iter_mut())
+            })
+    }
+    fn range_to_idx<Q, R>(&self, range: R) -> (usize, usize)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.rank_cmp(|item| item.borrow() < start),
+            Bound::Excluded(start) => self.rank_cmp(|item| item.borrow() <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.rank_cmp(|item| item.borrow() <= end),
+            Bound::Excluded(end) => self.rank_cmp(|item| item.borrow() < end),
+            Bound::Unbounded => self.len(),
+        };
+
+        (start, end)
+    }
+    /// Gets an iterator over the entries of the map, sorted by key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(3, "c"), (2, "b"), (1, "a")]);
+    /// let mut iter = map.iter();
+    /// assert_eq!(iter.next(), Some((&1, &"a")));
+    /// assert_eq!(iter.next_back(), Some((&3, &"c")));
+    /// assert_eq!(iter.next(), Some((&2, &"b")));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            spine: self.spine(0, self.len()),
+        }
+    }
+    /// Gets a mutable iterator over the entries of the map, sorted by key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, 1), (2, 2), (3, 3)]);
+    /// for (key, value) in map.iter_mut() {
+    ///     *value += key * 10;
+    /// }
+    /// assert!(map.values().eq([11, 22, 33].iter()));
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        let len = self.len();
+
+        IterMut {
+            spine: self.spine_mut(0, len),
+        }
+    }
+    /// Gets an iterator over the keys of the map, in sorted order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(2, "b"), (1, "a")]);
+    /// assert!(map.keys().eq([1, 2].iter()));
+    /// ```
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { iter: self.iter() }
+    }
+    /// Gets an iterator over the values of the map, in order by key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(2, "b"), (1, "a")]);
+    /// assert!(map.values().eq(["a", "b"].iter()));
+    /// ```
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { iter: self.iter() }
+    }
+    /// Gets a mutable iterator over the values of the map, in order by key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, String::from("a")), (2, String::from("b"))]);
+    /// for value in map.values_mut() {
+    ///     value.push('!');
+    /// }
+    /// assert!(map.values().eq(["a!", "b!"].iter()));
+    /// ```
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut {
+            iter: self.iter_mut(),
+        }
+    }
+    /// Constructs a double-ended iterator over a sub-range of entries in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(3, "a"), (5, "b"), (8, "c")]);
+    /// for (key, value) in map.range(4..) {
+    ///     println!("{key}: {value}");
+    /// }
+    /// assert_eq!(map.range(4..).next(), Some((&5, &"b")));
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let (start, end) = self.range_to_idx(range);
+
+        Range {
+            iter: Iter {
+                spine: self.spine(start, end),
+            },
+        }
+    }
+    /// Constructs a mutable double-ended iterator over a sub-range of entries in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, 0), (2, 0), (3, 0)]);
+    /// for (_, value) in map.range_mut(2..) {
+    ///     *value += 1;
+    /// }
+    /// assert!(map.values().eq([0, 1, 1].iter()));
+    /// ```
+    pub fn range_mut<Q, R>(&mut self, range: R) -> RangeMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let (start, end) = self.range_to_idx(range);
+
+        RangeMut {
+            iter: IterMut {
+                spine: self.spine_mut(start, end),
+            },
+        }
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the map, as pairs of
+    /// contiguous slices of keys and values in ascending order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, 10.0), (2, 20.0), (3, 30.0)]);
+    ///
+    /// let total: f64 = map.chunks().map(|(_, values)| values.iter().sum::<f64>()).sum();
+    /// assert_eq!(total, 60.0);
+    /// assert_eq!(map.chunks().next(), Some((&[1, 2, 3][..], &[10.0, 20.0, 30.0][..])));
+    /// ```
+    pub fn chunks(&self) -> Chunks<'_, K, V> {
+        self.range_chunks_idx(0, self.len())
+    }
+    fn range_chunks_idx(&self, start: usize, end: usize) -> Chunks<'_, K, V> {
+        let (nodes, front_start, back_end) = self.layout.bounds(start, end);
+
+        Chunks {
+            nodes: self.layout.nodes()[nodes].iter(),
+            front_start,
+            back_end,
+        }
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the map that hold keys
+    /// within the range, as pairs of contiguous slices of keys and values trimmed to its bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, 'a'), (2, 'b'), (3, 'c')]);
+    ///
+    /// let (keys, values) = map.range_chunks(2..).next().unwrap();
+    /// assert_eq!((keys, values), (&[2, 3][..], &['b', 'c'][..]));
+    /// ```
+    pub fn range_chunks<Q, R>(&self, range: R) -> Chunks<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let (start, end) = self.range_to_idx(range);
+
+        self.range_chunks_idx(start, end)
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the map, as pairs of
+    /// contiguous slices of keys and mutable values in ascending order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, 1.0), (2, 2.0), (3, 3.0)]);
+    /// for (_, values) in map.chunks_mut() {
+    ///     values.iter_mut().for_each(|value| *value *= 0.5);
+    /// }
+    /// assert!(map.values().eq(&[0.5, 1.0, 1.5]));
+    /// ```
+    pub fn chunks_mut(&mut self) -> ChunksMut<'_, K, V> {
+        let len = self.len();
+
+        self.range_chunks_mut_idx(0, len)
+    }
+    fn range_chunks_mut_idx(&mut self, start: usize, end: usize) -> ChunksMut<'_, K, V> {
+        let (nodes, front_start, back_end) = self.layout.bounds(start, end);
+
+        ChunksMut {
+            nodes: self.layout.nodes_mut()[nodes].iter_mut(),
+            front_start,
+            back_end,
+        }
+    }
+    /// Gets a double-ended iterator over the contents of the nodes of the map that hold keys
+    /// within the range, as pairs of contiguous slices of keys and mutable values trimmed to its
+    /// bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::soa::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, 0), (2, 0), (3, 0)]);
+    /// for (_, values) in map.range_chunks_mut(..3) {
+    ///     values.fill(7);
+    /// }
+    /// assert!(map.values().eq(&[7, 7, 0]));
+    /// ```
+    pub fn range_chunks_mut<Q, R>(&mut self, range: R) -> ChunksMut<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let (start, end) = self.range_to_idx(range);
+
+        self.range_chunks_mut_idx(start, end)
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = BTreeMap::new();
+        iter.into_iter().for_each(|(key, value)| {
+            map.insert(key, value);
+        });
+
+        map
+    }
+}
+
+impl<K: Ord, V, const N: usize> From<[(K, V); N]> for BTreeMap<K, V> {
+    fn from(value: [(K, V); N]) -> Self {
+        BTreeMap::from_iter(value)
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for BTreeMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|(key, value)| {
+            self.insert(key, value);
+        });
+    }
+}
+
+impl<K, Q, V> Index<&Q> for BTreeMap<K, V>
+where
+    K: Borrow<Q> + Ord,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &Self::Output {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+impl<K: Ord, V> IntoIterator for BTreeMap<K, V> {
+    type Item = (K, V);
+
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            spine: self.layout.into_spine(Node::into_entries),
+        }
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a BTreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a mut BTreeMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub enum Entry<'a, K, V>
+where
+    K: 'a + Ord,
+    V: 'a,
+{
+    Vacant(VacantEntry<'a, K, V>),
+    Occupied(OccupiedEntry<'a, K, V>),
+}
+
+pub struct VacantEntry<'a, K, V>
+where
+    K: Ord,
+{
+    map: &'a mut BTreeMap<K, V>,
+    key: K,
+    idx: usize,
+}
+
+pub struct OccupiedEntry<'a, K, V>
+where
+    K: Ord,
+{
+    map: &'a mut BTreeMap<K, V>,
+    idx: usize,
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: 'a + Ord,
+    V: 'a,
+{
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Vacant(entry) => entry.insert(default()),
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+    pub fn or_insert_with_key<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce(&K) -> V,
+    {
+        match self {
+            Entry::Vacant(entry) => {
+                let value = default(entry.key());
+                entry.insert(value)
+            }
+            Entry::Occupied(entry) => entry.into_mut(),
+        }
+    }
+    pub fn key(&self) -> &K {
+        match *self {
+            Entry::Occupied(ref entry) => entry.key(),
+            Entry::Vacant(ref entry) => entry.key(),
+        }
+    }
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Default::default()),
+        }
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V>
+where
+    K: Ord,
+{
+    pub fn key(&self) -> &K {
+        self.map.get_index(self.idx).unwrap().0
+    }
+    pub fn remove_entry(self) -> (K, V) {
+        self.map.pop_index(self.idx)
+    }
+    pub fn get(&self) -> &V {
+        self.map.get_index(self.idx).unwrap().1
+    }
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map.get_mut_index(self.idx).unwrap()
+    }
+    pub fn into_mut(self) -> &'a mut V {
+        self.map.get_mut_index(self.idx).unwrap()
+    }
+    pub fn insert(&mut self, value: V) -> V {
+        let current_value = self.map.get_mut_index(self.idx).unwrap();
+        let mut previous_value = value;
+        swap(&mut previous_value, current_value);
+
+        previous_value
+    }
+    pub fn remove(self) -> V {
+        self.map.pop_index(self.idx).1
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V>
+where
+    K: Ord,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+    pub fn into_key(self) -> K {
+        self.key
+    }
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.insert(self.key, value);
+
+        self.map.get_mut_index(self.idx).unwrap()
+    }
+}
+
+type NodeIter<'a, K, V> = Nodes<'a, Node<K, V>, Entries<'a, K, V>>;
+type NodeIterMut<'a, K, V> = NodesMut<'a, Node<K, V>, EntriesMut<'a, K, V>>;
+type NodeIntoIter<K, V> = IntoNodes<Node<K, V>, IntoEntries<K, V>>;
+
+/// An iterator over the entries of a `BTreeMap`.
+///
+/// This `struct` is created by the [`iter`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`iter`]: BTreeMap::iter
+pub struct Iter<'a, K, V> {
+    spine: Spine<NodeIter<'a, K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.spine.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spine.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.spine.next_back()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+/// A mutable iterator over the entries of a `BTreeMap`.
+///
+/// This `struct` is created by the [`iter_mut`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`iter_mut`]: BTreeMap::iter_mut
+pub struct IterMut<'a, K, V> {
+    spine: Spine<NodeIterMut<'a, K, V>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.spine.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spine.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for IterMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.spine.next_back()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for IterMut<'a, K, V> {}
+
+/// An owning iterator over the entries of a `BTreeMap`.
+///
+/// This `struct` is created by the [`into_iter`] method on [`BTreeMap`]
+/// (provided by the [`IntoIterator`] trait). See its documentation for more.
+///
+/// [`into_iter`]: BTreeMap#method.into_iter
+pub struct IntoIter<K, V> {
+    spine: Spine<NodeIntoIter<K, V>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.spine.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spine.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.spine.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+/// An iterator over the contents of the nodes of a `BTreeMap`, as slices of keys and values.
+///
+/// This `struct` is created by the [`chunks`] and [`range_chunks`] methods on [`BTreeMap`].
+/// See their documentation for more.
+///
+/// [`chunks`]: BTreeMap::chunks
+/// [`range_chunks`]: BTreeMap::range_chunks
+pub struct Chunks<'a, K, V> {
+    nodes: std::slice::Iter<'a, Node<K, V>>,
+    // Entries of the first node that come before the range
+    front_start: usize,
+    // End of the range within the last node
+    back_end: usize,
+}
+
+impl<'a, K, V> Iterator for Chunks<'a, K, V> {
+    type Item = (&'a [K], &'a [V]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next()?;
+        let end = match self.nodes.len() {
+            0 => self.back_end,
+            _ => node.len(),
+        };
+        let chunk = (
+            &node.keys[self.front_start..end],
+            &node.values[self.front_start..end],
+        );
+        self.front_start = 0;
+
+        Some(chunk)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Chunks<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next_back()?;
+        let start = match self.nodes.len() {
+            0 => self.front_start,
+            _ => 0,
+        };
+        let chunk = (
+            &node.keys[start..self.back_end],
+            &node.values[start..self.back_end],
+        );
+        self.back_end = self.nodes.as_slice().last().map_or(0, Node::len);
+
+        Some(chunk)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Chunks<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Chunks<'a, K, V> {}
+
+/// An iterator over the contents of the nodes of a `BTreeMap`, as slices of keys and mutable
+/// values.
+///
+/// This `struct` is created by the [`chunks_mut`] and [`range_chunks_mut`] methods on
+/// [`BTreeMap`]. See their documentation for more.
+///
+/// [`chunks_mut`]: BTreeMap::chunks_mut
+/// [`range_chunks_mut`]: BTreeMap::range_chunks_mut
+pub struct ChunksMut<'a, K, V> {
+    nodes: std::slice::IterMut<'a, Node<K, V>>,
+    // Entries of the first node that come before the range
+    front_start: usize,
+    // End of the range within the last node
+    back_end: usize,
+}
+
+impl<'a, K, V> Iterator for ChunksMut<'a, K, V> {
+    type Item = (&'a [K], &'a mut [V]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next()?;
+        let end = match self.nodes.len() {
+            0 => self.back_end,
+            _ => node.len(),
+        };
+        let chunk = (
+            &node.keys[self.front_start..end],
+            &mut node.values[self.front_start..end],
+        );
+        self.front_start = 0;
+
+        Some(chunk)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for ChunksMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.nodes.next_back()?;
+        let start = match self.nodes.len() {
+            0 => self.front_start,
+            _ => 0,
+        };
+        let chunk = (
+            &node.keys[start..self.back_end],
+            &mut node.values[start..self.back_end],
+        );
+        self.back_end = self.nodes.as_slice().last().map_or(0, Node::len);
+
+        Some(chunk)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for ChunksMut<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for ChunksMut<'a, K, V> {}
+
+/// An iterator over the keys of a `BTreeMap`.
+///
+/// This `struct` is created by the [`keys`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`keys`]: BTreeMap::keys
+pub struct Keys<'a, K, V> {
+    iter: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, _)| key)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Keys<'a, K, V> {}
+
+/// An iterator over the values of a `BTreeMap`.
+///
+/// This `struct` is created by the [`values`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`values`]: BTreeMap::values
+pub struct Values<'a, K, V> {
+    iter: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, value)| value)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+/// A mutable iterator over the values of a `BTreeMap`.
+///
+/// This `struct` is created by the [`values_mut`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`values_mut`]: BTreeMap::values_mut
+pub struct ValuesMut<'a, K, V> {
+    iter: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, value)| value)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for ValuesMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for ValuesMut<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for ValuesMut<'a, K, V> {}
+
+/// An iterator over a sub-range of entries in a `BTreeMap`.
+///
+/// This `struct` is created by the [`range`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`range`]: BTreeMap::range
+pub struct Range<'a, K, V> {
+    iter: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Range<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for Range<'a, K, V> {}
+
+/// A mutable iterator over a sub-range of entries in a `BTreeMap`.
+///
+/// This `struct` is created by the [`range_mut`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`range_mut`]: BTreeMap::range_mut
+pub struct RangeMut<'a, K, V> {
+    iter: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for RangeMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for RangeMut<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for RangeMut<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for RangeMut<'a, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::BTreeMap;
+    use crate::core::layout::LayoutNode;
+    use crate::SplitPolicy;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([37; 32]);
+        let mut map = BTreeMap::with_maximum_node_size(8);
+        let mut model = std::collections::BTreeMap::new();
+        for _ in 0..20_000 {
+            let key = rng.gen_range(0..512);
+            match rng.gen_range(0..4) {
+                0 | 1 => assert_eq!(map.insert(key, key * 2), model.insert(key, key * 2)),
+                2 => assert_eq!(map.remove(&key), model.remove(&key)),
+                _ => {
+                    *map.entry(key).or_insert(0) += 1;
+                    *model.entry(key).or_insert(0) += 1;
+                }
+            }
+            assert_eq!(map.len(), model.len());
+        }
+
+        assert!(map.iter().eq(model.iter()));
+        assert!(map.iter().rev().eq(model.iter().rev()));
+        assert_eq!(map.iter().len(), model.len());
+        for (idx, (key, value)) in model.iter().enumerate() {
+            assert_eq!(map.get_index(idx), Some((key, value)));
+            assert_eq!(map.rank(key), idx);
+            assert_eq!(map.get(key), Some(value));
+        }
+        for start in 0..520 {
+            let end = start + rng.gen_range(0..40);
+            assert!(map.range(start..end).eq(model.range(start..end)));
+            assert!(map
+                .range(start..=end)
+                .rev()
+                .eq(model.range(start..=end).rev()));
+        }
+
+        for (_, value) in map.range_mut(100..200) {
+            *value += 1;
+        }
+        for (_, value) in model.range_mut(100..200) {
+            *value += 1;
+        }
+        for value in map.values_mut().rev().step_by(3) {
+            *value -= 5;
+        }
+        for value in model.values_mut().rev().step_by(3) {
+            *value -= 5;
+        }
+        assert!(map.iter().eq(model.iter()));
+
+        assert_eq!(map.pop_first(), model.pop_first());
+        assert_eq!(map.pop_last(), model.pop_last());
+        assert!(map.into_iter().rev().eq(model.into_iter().rev()));
+    }
+
+    #[test]
+    fn test_mixed_iteration_from_both_ends() {
+        let mut map = BTreeMap::with_maximum_node_size(4);
+        map.extend((0..50).map(|key| (key, key)));
+        let mut range = map.range(3..47);
+        let mut expected = 3..47;
+        for step in 0.. {
+            let (item, expected_item) = if step % 3 == 0 {
+                (range.next_back(), expected.next_back())
+            } else {
+                (range.next(), expected.next())
+            };
+            assert_eq!(item.map(|(key, _)| *key), expected_item);
+            assert_eq!(range.len(), expected.len());
+            if item.is_none() {
+                break;
+            }
+        }
+        assert!(map.range(10..10).next().is_none());
+        assert!(map.range(60..).next().is_none());
+    }
+
+    #[test]
+    fn test_chunks_mut_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([44; 32]);
+        let mut map = BTreeMap::with_maximum_node_size(8);
+        let mut model = std::collections::BTreeMap::new();
+        for _ in 0..500 {
+            let key = rng.gen_range(0..1000);
+            map.insert(key, 0);
+            model.insert(key, 0);
+        }
+
+        for step in 0..200 {
+            let (a, b) = (rng.gen_range(-10..1010), rng.gen_range(-10..1010));
+            let range = a.min(b)..a.max(b);
+            for (keys, values) in map.range_chunks_mut(range.clone()) {
+                assert!(!keys.is_empty() && keys.len() <= 8);
+                keys.iter()
+                    .zip(values.iter_mut())
+                    .for_each(|(key, value)| *value += key + step);
+            }
+            model
+                .range_mut(range.clone())
+                .for_each(|(key, value)| *value += key + step);
+
+            let chunks = map.range_chunks(range.clone());
+            assert!(chunks
+                .rev()
+                .flat_map(|(keys, values)| keys.iter().zip(values).rev())
+                .eq(model.range(range).rev()));
+        }
+        assert!(map
+            .chunks()
+            .flat_map(|(keys, values)| keys.iter().zip(values))
+            .eq(model.iter()));
+        assert_eq!(map.chunks_mut().len(), map.chunks().len());
+    }
+
+    #[test]
+    fn test_right_biased_splits_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([49; 32]);
+        let mut map = BTreeMap::with_maximum_node_size(8);
+        map.set_split_policy(SplitPolicy::RightBiased);
+        for key in 0..1001 {
+            map.insert(key, key);
+        }
+        let (last, full) = map.layout.nodes().split_last().unwrap();
+        assert!(full.iter().all(|node| node.len() == 8));
+        assert_eq!(last.len(), 1);
+
+        let mut model: std::collections::BTreeMap<_, _> =
+            map.iter().map(|(k, v)| (*k, *v)).collect();
+        for _ in 0..2000 {
+            let key = rng.gen_range(-100..1100);
+            assert_eq!(map.insert(key, -key), model.insert(key, -key));
+        }
+        assert!(map.iter().eq(model.iter()));
+        assert!(map
+            .layout
+            .nodes()
+            .iter()
+            .all(|node| (1..=8).contains(&node.len())));
+    }
+}
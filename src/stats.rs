@@ -0,0 +1,472 @@
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::BTreeMultiSet;
+
+// Position of the `q`-quantile among `len` sorted values, using the nearest-rank method: the
+// smallest value that at least a `q` fraction of all values are less than or equal to
+pub(crate) fn quantile_position(len: usize, q: f64) -> usize {
+    assert!(
+        (0.0..=1.0).contains(&q),
+        "quantile must be within 0 and 1, got {q}"
+    );
+
+    // The product may round past a whole number, so the rank it gives is checked against the
+    // fractions of values it and its neighbours cover
+    let total = len as f64;
+    let mut rank = ((q * total).ceil() as usize).min(len);
+    while rank > 0 && (rank - 1) as f64 / total >= q {
+        rank -= 1;
+    }
+    while rank < len && (rank as f64 / total) < q {
+        rank += 1;
+    }
+
+    rank.saturating_sub(1).min(len.saturating_sub(1))
+}
+
+// Positions of the `idx`-th of `buckets` equi-depth buckets over `len` sorted values, the first
+// buckets taking one value less when they cannot all be the same size
+pub(crate) fn bucket_positions(len: usize, buckets: usize, idx: usize) -> std::ops::Range<usize> {
+    idx * len / buckets..(idx + 1) * len / buckets
+}
+
+// Index of the equi-depth bucket that the value at `position` falls into, out of `buckets`
+// buckets over `len` sorted values
+pub(crate) fn bucket_of(len: usize, buckets: usize, position: usize) -> usize {
+    ((position + 1) * buckets - 1) / len
+}
+
+// Number of buckets an equi-depth histogram over `len` values is made of, so that none is empty
+pub(crate) fn bucket_count(len: usize, buckets: usize) -> usize {
+    assert!(buckets > 0, "a histogram needs at least one bucket");
+
+    buckets.min(len)
+}
+
+/// A source of random numbers for the sampling methods of the trees, such as
+/// [`BTreeSet::sample`](crate::BTreeSet::sample), so that the crate does not depend on a
+/// particular random number generator.
+///
+/// Only [`next_u64`](RngLike::next_u64) has to be implemented, usually by forwarding to the
+/// generator at hand.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::BTreeSet;
+/// use indexset::stats::RngLike;
+///
+/// // A xorshift generator, standing in for the one of your choice
+/// struct XorShift(u64);
+///
+/// impl RngLike for XorShift {
+///     fn next_u64(&mut self) -> u64 {
+///         self.0 ^= self.0 << 13;
+///         self.0 ^= self.0 >> 7;
+///         self.0 ^= self.0 << 17;
+///         self.0
+///     }
+/// }
+///
+/// let set = BTreeSet::from_iter(0..100);
+/// let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+/// assert!(set.contains(set.sample(&mut rng).unwrap()));
+/// assert!(rng.gen_below(10) < 10);
+/// ```
+pub trait RngLike {
+    /// Returns the next 64 uniformly random bits.
+    fn next_u64(&mut self) -> u64;
+    /// Returns a uniformly random number below `bound`, without the bias that taking the
+    /// remainder of [`next_u64`](RngLike::next_u64) would have.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero.
+    fn gen_below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "cannot pick a number below zero");
+
+        // Lemire's multiply-and-reject: the high half of the product is uniform once the few
+        // low halves that would make some results more likely than others are rejected
+        let mut product = self.next_u64() as u128 * bound as u128;
+        if (product as u64) < bound {
+            let threshold = bound.wrapping_neg() % bound;
+            while (product as u64) < threshold {
+                product = self.next_u64() as u128 * bound as u128;
+            }
+        }
+
+        (product >> 64) as u64
+    }
+}
+
+impl<R: RngLike + ?Sized> RngLike for &mut R {
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    fn gen_below(&mut self, bound: u64) -> u64 {
+        (**self).gen_below(bound)
+    }
+}
+
+// Picks `k` distinct positions out of `len` uniformly at random, in ascending order, using
+// Floyd's algorithm so that only `k` numbers are drawn whatever `len` is
+pub(crate) fn distinct_positions<R: RngLike + ?Sized>(
+    len: usize,
+    k: usize,
+    rng: &mut R,
+) -> Vec<usize> {
+    let k = k.min(len);
+    let mut positions = crate::BTreeSet::with_capacity(k);
+    for upper in len - k..len {
+        let position = rng.gen_below(upper as u64 + 1) as usize;
+        if !positions.insert(position) {
+            positions.insert(upper);
+        }
+    }
+
+    positions.into_iter().collect()
+}
+
+/// A bucket of an equi-depth histogram, holding a run of consecutive elements of a tree.
+///
+/// This `struct` is returned by [`BTreeSet::equi_depth_histogram`](crate::BTreeSet::equi_depth_histogram)
+/// and [`BTreeMap::equi_depth_histogram`](crate::BTreeMap::equi_depth_histogram).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bucket<T> {
+    /// The smallest element of the bucket.
+    pub lower: T,
+    /// The greatest element of the bucket.
+    pub upper: T,
+    /// The number of elements in the bucket.
+    pub count: usize,
+}
+
+/// Order statistics over a sliding window of values, made of the values in arrival order and a
+/// [`BTreeMultiSet`] of the same values in sorted order.
+///
+/// Values may repeat. Quantiles use the nearest-rank method, so they are always one of the values
+/// in the window, and the median of an even number of values is the lower of the two middle ones.
+/// Pushing, evicting, and looking up a quantile or a rank all take logarithmic time.
+///
+/// Since each value is stored on both sides, `T` has to be [`Clone`].
+///
+/// # Examples
+///
+/// ```
+/// use indexset::stats::RollingQuantiles;
+///
+/// // Latencies of the last 5 requests
+/// let mut latencies = RollingQuantiles::with_window(5);
+/// for latency in [12, 15, 11, 90, 15] {
+///     latencies.push(latency);
+/// }
+/// assert_eq!(latencies.median(), Some(&15));
+/// assert_eq!(latencies.quantile(0.99), Some(&90));
+///
+/// // The oldest latency leaves the window as a new one comes in
+/// assert_eq!(latencies.push(13), Some(12));
+/// assert_eq!(latencies.quantile(0.0), Some(&11));
+/// assert_eq!(latencies.rank_of(&15), 2);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct RollingQuantiles<T>
+where
+    T: Ord,
+{
+    arrivals: VecDeque<T>,
+    sorted: BTreeMultiSet<T>,
+    window: Option<usize>,
+}
+
+impl<T: Ord> Default for RollingQuantiles<T> {
+    fn default() -> Self {
+        Self {
+            arrivals: VecDeque::new(),
+            sorted: BTreeMultiSet::default(),
+            window: None,
+        }
+    }
+}
+
+impl<T: Ord> RollingQuantiles<T> {
+    /// Makes a new, empty `RollingQuantiles` whose window only shrinks through
+    /// [`evict`](RollingQuantiles::evict).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::stats::RollingQuantiles;
+    ///
+    /// let mut latencies: RollingQuantiles<u64> = RollingQuantiles::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `RollingQuantiles` that keeps the given number of most recent values,
+    /// evicting the oldest one whenever a value is pushed into a full window.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::stats::RollingQuantiles;
+    ///
+    /// let mut latencies = RollingQuantiles::with_window(2);
+    /// latencies.push(1);
+    /// latencies.push(2);
+    /// assert_eq!(latencies.push(3), Some(1));
+    /// assert_eq!(latencies.len(), 2);
+    /// ```
+    pub fn with_window(window: usize) -> Self {
+        assert!(window > 0, "window must hold at least one value");
+
+        Self {
+            arrivals: VecDeque::with_capacity(window),
+            window: Some(window),
+            ..Default::default()
+        }
+    }
+    /// Returns the number of values the window keeps, if it is bounded.
+    pub fn window(&self) -> Option<usize> {
+        self.window
+    }
+    /// Clears the window, removing all values.
+    pub fn clear(&mut self) {
+        self.arrivals.clear();
+        self.sorted.clear();
+    }
+    /// Returns the number of values in the window.
+    pub fn len(&self) -> usize {
+        self.arrivals.len()
+    }
+    /// Returns `true` if the window holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.arrivals.is_empty()
+    }
+    /// Removes the oldest value from the window and returns it, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::stats::RollingQuantiles;
+    ///
+    /// let mut latencies = RollingQuantiles::new();
+    /// latencies.push(20);
+    /// latencies.push(10);
+    /// assert_eq!(latencies.evict(), Some(20));
+    /// assert_eq!(latencies.median(), Some(&10));
+    /// ```
+    pub fn evict(&mut self) -> Option<T> {
+        let oldest = self.arrivals.pop_front()?;
+        self.sorted.remove_one(&oldest);
+
+        Some(oldest)
+    }
+    /// Returns the `q`-quantile of the values in the window, that is the smallest value that at
+    /// least a `q` fraction of them are less than or equal to, or `None` if the window is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is not within `0.0..=1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::stats::RollingQuantiles;
+    ///
+    /// let mut latencies = RollingQuantiles::new();
+    /// for latency in 1..=100 {
+    ///     latencies.push(latency);
+    /// }
+    /// assert_eq!(latencies.quantile(0.0), Some(&1));
+    /// assert_eq!(latencies.quantile(0.9), Some(&90));
+    /// assert_eq!(latencies.quantile(1.0), Some(&100));
+    /// ```
+    pub fn quantile(&self, q: f64) -> Option<&T> {
+        let position = quantile_position(self.len(), q);
+
+        self.sorted.get_index(position)
+    }
+    /// Returns the median of the values in the window, the lower of the two middle values when
+    /// there is an even number of them, or `None` if the window is empty.
+    pub fn median(&self) -> Option<&T> {
+        self.quantile(0.5)
+    }
+    /// Returns the number of values in the window that are strictly less than `value`.
+    ///
+    /// The value may be any borrowed form of the element type, but the ordering on the borrowed
+    /// form *must* match the ordering on the element type.
+    pub fn rank_of<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.sorted.rank(value)
+    }
+    /// Returns an iterator over the values in the window, from the oldest to the most recent.
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, T> {
+        self.arrivals.iter()
+    }
+}
+
+impl<T: Ord + Clone> RollingQuantiles<T> {
+    /// Adds a value to the window. If the window is bounded and already full, its oldest value
+    /// is evicted and returned.
+    pub fn push(&mut self, value: T) -> Option<T> {
+        let evicted = match self.window {
+            Some(window) if self.len() >= window => self.evict(),
+            _ => None,
+        };
+        self.sorted.insert(value.clone());
+        self.arrivals.push_back(value);
+
+        evicted
+    }
+}
+
+impl<T: Ord + Clone> Extend<T> for RollingQuantiles<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bucket_count, bucket_of, bucket_positions, distinct_positions, quantile_position, RngLike,
+        RollingQuantiles,
+    };
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_quantile_position() {
+        assert_eq!(quantile_position(0, 0.5), 0);
+        assert_eq!(quantile_position(1, 0.0), 0);
+        assert_eq!(quantile_position(1, 1.0), 0);
+        assert_eq!(quantile_position(4, 0.5), 1);
+        assert_eq!(quantile_position(5, 0.5), 2);
+        assert_eq!(quantile_position(100, 0.99), 98);
+        assert_eq!(quantile_position(100, 1.0), 99);
+        assert_eq!(quantile_position(100, 0.07), 6);
+        assert_eq!(quantile_position(100, 0.14), 13);
+        assert_eq!(quantile_position(25, 0.28), 6);
+        assert_eq!(quantile_position(3, 1.0 / 3.0), 0);
+        for len in 1..300 {
+            for covered in 1..=len {
+                let q = covered as f64 / len as f64;
+                assert_eq!(quantile_position(len, q), covered - 1, "{covered}/{len}");
+            }
+        }
+        assert!(std::panic::catch_unwind(|| quantile_position(10, 1.5)).is_err());
+        assert!(std::panic::catch_unwind(|| quantile_position(10, f64::NAN)).is_err());
+    }
+
+    impl RngLike for rand::rngs::StdRng {
+        fn next_u64(&mut self) -> u64 {
+            rand::RngCore::next_u64(self)
+        }
+    }
+
+    #[test]
+    fn test_gen_below_is_uniform() {
+        let mut rng = rand::rngs::StdRng::from_seed([55; 32]);
+        let mut counts = [0usize; 6];
+        for _ in 0..60_000 {
+            counts[rng.gen_below(6) as usize] += 1;
+        }
+        assert!(counts.iter().all(|count| count.abs_diff(10_000) < 500));
+        assert_eq!(rng.gen_below(1), 0);
+        assert!(rng.gen_below(u64::MAX) < u64::MAX);
+    }
+
+    #[test]
+    fn test_distinct_positions_are_uniform() {
+        let mut rng = rand::rngs::StdRng::from_seed([56; 32]);
+        let mut counts = [0usize; 10];
+        for _ in 0..20_000 {
+            let positions = distinct_positions(10, 3, &mut rng);
+            assert_eq!(positions.len(), 3);
+            assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+            positions.iter().for_each(|position| counts[*position] += 1);
+        }
+        assert!(counts.iter().all(|count| count.abs_diff(6_000) < 400));
+        assert_eq!(distinct_positions(4, 10, &mut rng), [0, 1, 2, 3]);
+        assert!(distinct_positions(0, 3, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_buckets_cover_every_position_once() {
+        for len in 1..60 {
+            for buckets in 1..70 {
+                let buckets = bucket_count(len, buckets);
+                let mut next = 0;
+                for idx in 0..buckets {
+                    let positions = bucket_positions(len, buckets, idx);
+                    assert_eq!(positions.start, next);
+                    assert!(!positions.is_empty());
+                    assert!(
+                        positions.len() >= len / buckets && positions.len() <= len / buckets + 1
+                    );
+                    for position in positions.clone() {
+                        assert_eq!(bucket_of(len, buckets, position), idx);
+                    }
+                    next = positions.end;
+                }
+                assert_eq!(next, len);
+            }
+        }
+        assert_eq!(bucket_count(0, 4), 0);
+        assert!(std::panic::catch_unwind(|| bucket_count(10, 0)).is_err());
+    }
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([53; 32]);
+        let mut rolling = RollingQuantiles::with_window(64);
+        let mut model: std::collections::VecDeque<u32> = std::collections::VecDeque::new();
+        for step in 0..20_000 {
+            let value = rng.gen_range(0..50);
+            if step % 7 == 0 {
+                assert_eq!(rolling.evict(), model.pop_front());
+            } else {
+                let evicted = if model.len() == 64 {
+                    model.pop_front()
+                } else {
+                    None
+                };
+                model.push_back(value);
+                assert_eq!(rolling.push(value), evicted);
+            }
+
+            let mut sorted: Vec<u32> = model.iter().copied().collect();
+            sorted.sort();
+            assert_eq!(rolling.len(), model.len());
+            assert_eq!(rolling.median(), sorted.get((sorted.len().max(1) - 1) / 2));
+            let q = rng.gen_range(0.0..=1.0);
+            let expected = sorted.iter().find(|candidate| {
+                let covered = sorted.iter().filter(|item| item <= candidate).count();
+                covered as f64 / sorted.len() as f64 >= q
+            });
+            assert_eq!(rolling.quantile(q), expected);
+            assert_eq!(
+                rolling.rank_of(&value),
+                sorted.iter().filter(|item| **item < value).count()
+            );
+        }
+
+        assert!(rolling.iter().eq(model.iter()));
+        rolling.clear();
+        assert_eq!(rolling.quantile(0.5), None);
+    }
+}
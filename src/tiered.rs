@@ -0,0 +1,1363 @@
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::ops::{Bound, Range, RangeBounds};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::constants::DEFAULT_INNER_SIZE;
+use crate::core::layout::{Layout, Nodes, Spine};
+use crate::core::lengths::Lengths;
+use crate::core::node::SplitPolicy;
+use crate::core::pair::Pair;
+
+// A run of consecutive nodes, with the index of their lengths
+type Group<T> = Layout<Vec<T>>;
+
+type GroupIter<'a, T> = Spine<Nodes<'a, Vec<T>, std::slice::Iter<'a, T>>>;
+
+fn group_iter<T>(group: &Group<T>, positions: Range<usize>) -> GroupIter<'_, T> {
+    group.spine(
+        positions.start,
+        positions.end,
+        |node| node.iter(),
+        |node, positions| node[positions].iter(),
+    )
+}
+
+fn whole_group_iter<T>(group: &Group<T>) -> GroupIter<'_, T> {
+    group_iter(group, 0..group.len())
+}
+
+/// An ordered set based on a three-level B-Tree, for sets that grow into the billions of
+/// elements.
+///
+/// The nodes of [`BTreeSet`](crate::BTreeSet) sit in a single list, which splits shift and
+/// lookups binary-search through. The list grows with the number of nodes, and shifting it ends
+/// up dominating once there are millions of them. This set adds a middle level instead: nodes are
+/// kept in groups of bounded size, under an index of the length of each group. Splitting a node
+/// only shifts the nodes of its group, and a group is only split once it holds more nodes than
+/// the maximum group size.
+///
+/// The lengths of nodes and groups are indexed by counted B-trees, which take a new node or group
+/// in logarithmic time. What stays linear is shifting lists: a node split moves the nodes after
+/// it within its group, up to the maximum group size, and a group split, which happens at most
+/// once every half a group of node splits, moves the groups after it. Lookups and positional access
+/// are logarithmic.
+///
+/// [`BTreeMap`] is the map counterpart of this set.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::tiered::BTreeSet;
+///
+/// let mut set = BTreeSet::with_maximum_sizes(4, 4);
+/// for value in (0..1000).rev() {
+///     set.insert(value);
+/// }
+///
+/// assert_eq!(set.get_index(500), Some(&500));
+/// assert_eq!(set.rank(&750), 750);
+/// assert!(set.range(10..15).eq([10, 11, 12, 13, 14].iter()));
+/// assert!(set.remove(&500));
+/// assert_eq!(set.get_index(500), Some(&501));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct BTreeSet<T>
+where
+    T: Ord,
+{
+    groups: Layout<Group<T>, Lengths>,
+    node_capacity: usize,
+    group_capacity: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    split_policy: SplitPolicy,
+}
+
+impl<T: Ord> Default for BTreeSet<T> {
+    fn default() -> Self {
+        Self::with_maximum_sizes(DEFAULT_INNER_SIZE, DEFAULT_INNER_SIZE)
+    }
+}
+
+impl<T: Ord> BTreeSet<T> {
+    /// Makes a new, empty `BTreeSet` with maximum node size 1024, and up to 1024 nodes per group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set: BTreeSet<u64> = BTreeSet::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `BTreeSet` with the given maximum node size, and up to 1024 nodes per
+    /// group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set: BTreeSet<u64> = BTreeSet::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self::with_maximum_sizes(maximum_node_size, DEFAULT_INNER_SIZE)
+    }
+    /// Makes a new, empty `BTreeSet` with the given maximum node size and maximum number of
+    /// nodes per group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set: BTreeSet<u64> = BTreeSet::with_maximum_sizes(128, 64);
+    /// ```
+    pub fn with_maximum_sizes(maximum_node_size: usize, maximum_group_size: usize) -> Self {
+        Self {
+            groups: Layout::new(Layout::new(Vec::with_capacity(maximum_node_size))),
+            node_capacity: maximum_node_size,
+            group_capacity: maximum_group_size.max(2),
+            split_policy: SplitPolicy::default(),
+        }
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options. Groups
+    /// are always split evenly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    /// use indexset::SplitPolicy;
+    ///
+    /// let mut set: BTreeSet<u64> = BTreeSet::new();
+    /// set.set_split_policy(SplitPolicy::RightBiased);
+    /// assert!(matches!(set.split_policy(), SplitPolicy::RightBiased));
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.split_policy = split_policy;
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.split_policy
+    }
+    /// Clears the set, removing all elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::new();
+    /// set.insert(1);
+    /// set.clear();
+    /// assert!(set.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.groups = Layout::new(Layout::new(Vec::with_capacity(self.node_capacity)));
+    }
+    /// Returns the number of elements in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::new();
+    /// assert_eq!(set.len(), 0);
+    /// set.insert(1);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+    /// Returns `true` if the set contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn node(&self, group_idx: usize, node_idx: usize) -> &Vec<T> {
+        &self.groups.nodes()[group_idx].nodes()[node_idx]
+    }
+    // Elements have to stay in place through this, so that lengths and order hold
+    fn node_mut(&mut self, group_idx: usize, node_idx: usize) -> &mut Vec<T> {
+        &mut self.groups.nodes_mut()[group_idx].nodes_mut()[node_idx]
+    }
+    // Group, node and position of the first element for which `before` does not hold, assuming
+    // that it holds for a prefix of the set and for nothing after it
+    fn locate_by<P>(&self, mut before: P) -> (usize, usize, usize)
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let group_idx = self.groups.locate_node(|group| {
+            let max = group.nodes().last().and_then(|node| node.last());
+            max.is_some_and(&mut before)
+        });
+        let group = &self.groups.nodes()[group_idx];
+        let node_idx = group.locate_node(|node| node.last().is_some_and(&mut before));
+        let position = group.nodes()[node_idx].partition_point(before);
+
+        (group_idx, node_idx, position)
+    }
+    fn locate<Q>(&self, value: &Q) -> (usize, usize, usize)
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.locate_by(|item| item.borrow() < value)
+    }
+    // Group, node and position of the `idx`-th element
+    fn locate_ith(&self, idx: usize) -> (usize, usize, usize) {
+        let (group_idx, idx_within_group) = self.groups.locate_ith(idx);
+        let (node_idx, position) = self.groups.nodes()[group_idx].locate_ith(idx_within_group);
+
+        (group_idx, node_idx, position)
+    }
+    fn rank_by<P>(&self, before: P) -> usize
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let (group_idx, node_idx, position) = self.locate_by(before);
+
+        self.groups.offset(group_idx) + self.groups.nodes()[group_idx].offset(node_idx) + position
+    }
+    /// Adds a value to the set.
+    ///
+    /// Returns whether the value was newly inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::new();
+    /// assert_eq!(set.insert(2), true);
+    /// assert_eq!(set.insert(2), false);
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    pub fn insert(&mut self, value: T) -> bool {
+        let (group_idx, node_idx, position) = self.locate(&value);
+        if self.node(group_idx, node_idx).get(position) == Some(&value) {
+            return false;
+        }
+
+        self.insert_at(group_idx, node_idx, position, value);
+
+        true
+    }
+    fn insert_at(&mut self, group_idx: usize, node_idx: usize, position: usize, value: T) {
+        let (node_capacity, split_policy) = (self.node_capacity, self.split_policy);
+        self.groups.insert_with(group_idx, |group| {
+            group.insert(
+                node_idx,
+                position,
+                node_capacity,
+                split_policy,
+                |node, position| node.insert(position, value),
+            )
+        });
+        let group = &self.groups.nodes()[group_idx];
+        if group.nodes().len() > self.group_capacity {
+            let at = group.offset(group.nodes().len() / 2);
+            self.groups.split_node(group_idx, at);
+        }
+    }
+    fn remove_at(&mut self, group_idx: usize, node_idx: usize, position: usize) -> T {
+        self.groups.remove_with(group_idx, |group| {
+            group.remove_with(node_idx, |node| node.remove(position))
+        })
+    }
+    /// Removes a value from the set. Returns whether the value was present in the set.
+    ///
+    /// The value may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::from_iter([2]);
+    /// assert_eq!(set.remove(&2), true);
+    /// assert_eq!(set.remove(&2), false);
+    /// ```
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.take(value).is_some()
+    }
+    /// Removes and returns the element in the set, if any, that is equal to the value.
+    ///
+    /// The value may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (group_idx, node_idx, position) = self.locate(value);
+        let item = self.node(group_idx, node_idx).get(position)?;
+        if item.borrow() != value {
+            return None;
+        }
+
+        Some(self.remove_at(group_idx, node_idx, position))
+    }
+    /// Returns a reference to the element in the set, if any, that is equal to the value.
+    ///
+    /// The value may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (group_idx, node_idx, position) = self.locate(value);
+
+        self.node(group_idx, node_idx)
+            .get(position)
+            .filter(|item| (*item).borrow() == value)
+    }
+    /// Returns `true` if the set contains an element equal to the value.
+    ///
+    /// The value may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3]);
+    /// assert_eq!(set.contains(&1), true);
+    /// assert_eq!(set.contains(&4), false);
+    /// ```
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(value).is_some()
+    }
+    /// Returns a reference to the element in the i-th position of the set, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3]);
+    /// assert_eq!(set.get_index(0), Some(&1));
+    /// assert_eq!(set.get_index(2), Some(&3));
+    /// assert_eq!(set.get_index(3), None);
+    /// ```
+    pub fn get_index(&self, idx: usize) -> Option<&T> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let (group_idx, node_idx, position) = self.locate_ith(idx);
+        self.node(group_idx, node_idx).get(position)
+    }
+    /// Returns the number of elements in the set that are less than the value.
+    ///
+    /// The value may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([1, 2, 3]);
+    /// assert_eq!(set.rank(&1), 0);
+    /// assert_eq!(set.rank(&3), 2);
+    /// assert_eq!(set.rank(&100), 3);
+    /// ```
+    pub fn rank<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.rank_by(|item| item.borrow() < value)
+    }
+    /// Returns a reference to the first element in the set, if any. This element is always the
+    /// minimum of all elements in the set.
+    pub fn first(&self) -> Option<&T> {
+        self.node(0, 0).first()
+    }
+    /// Returns a reference to the last element in the set, if any. This element is always the
+    /// maximum of all elements in the set.
+    pub fn last(&self) -> Option<&T> {
+        self.groups.nodes().last()?.nodes().last()?.last()
+    }
+    /// Removes the first element from the set and returns it, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::from_iter([1, 2]);
+    /// assert_eq!(set.pop_first(), Some(1));
+    /// assert_eq!(set.pop_first(), Some(2));
+    /// assert_eq!(set.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.remove_at(0, 0, 0))
+    }
+    /// Removes the last element from the set and returns it, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let mut set = BTreeSet::from_iter([1, 2]);
+    /// assert_eq!(set.pop_last(), Some(2));
+    /// assert_eq!(set.pop_last(), Some(1));
+    /// assert_eq!(set.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let group_idx = self.groups.nodes().len() - 1;
+        let node_idx = self.groups.nodes()[group_idx].nodes().len() - 1;
+        let position = self.node(group_idx, node_idx).len() - 1;
+        Some(self.remove_at(group_idx, node_idx, position))
+    }
+    fn iter_between(&self, start: usize, end: usize) -> Iter<'_, T> {
+        Iter {
+            spine: self.groups.spine(start, end, whole_group_iter, group_iter),
+        }
+    }
+    /// Gets an iterator that visits the elements in the set in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter([3, 1, 2]);
+    /// assert!(set.iter().eq([1, 2, 3].iter()));
+    /// assert!(set.iter().rev().eq([3, 2, 1].iter()));
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.iter_between(0, self.len())
+    }
+    /// Constructs a double-ended iterator over a sub-range of elements in the set.
+    ///
+    /// The range bounds may be any borrowed form of the set's element type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the element type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeSet;
+    ///
+    /// let set = BTreeSet::from_iter(0..100);
+    /// assert!(set.range(10..13).eq([10, 11, 12].iter()));
+    /// assert!(set.range(..=2).rev().eq([2, 1, 0].iter()));
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.rank_by(|item| item.borrow() < start),
+            Bound::Excluded(start) => self.rank_by(|item| item.borrow() <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.rank_by(|item| item.borrow() <= end),
+            Bound::Excluded(end) => self.rank_by(|item| item.borrow() < end),
+            Bound::Unbounded => self.len(),
+        };
+
+        self.iter_between(start, end.max(start))
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BTreeSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = BTreeSet::new();
+        iter.into_iter().for_each(|value| {
+            set.insert(value);
+        });
+
+        set
+    }
+}
+
+impl<T: Ord> Extend<T> for BTreeSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|value| {
+            self.insert(value);
+        });
+    }
+}
+
+impl<'a, T: Ord> IntoIterator for &'a BTreeSet<T> {
+    type Item = &'a T;
+
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of a `BTreeSet`, or of a sub-range of them.
+///
+/// This `struct` is created by the [`iter`] and [`range`] methods on [`BTreeSet`].
+/// See their documentation for more.
+///
+/// [`iter`]: BTreeSet::iter
+/// [`range`]: BTreeSet::range
+pub struct Iter<'a, T>
+where
+    T: Ord,
+{
+    spine: Spine<Nodes<'a, Group<T>, GroupIter<'a, T>>>,
+}
+
+impl<'a, T: Ord> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.spine.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.spine.size_hint()
+    }
+}
+
+impl<'a, T: Ord> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.spine.next_back()
+    }
+}
+
+impl<'a, T: Ord> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T: Ord> FusedIterator for Iter<'a, T> {}
+
+/// An ordered map based on a three-level B-Tree, for maps that grow into the billions of
+/// entries.
+///
+/// This is a [`BTreeSet`] of key-value pairs ordered by key, which keeps its nodes in bounded
+/// groups in the same way. See [`BTreeSet`] for what the middle level brings.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::tiered::BTreeMap;
+///
+/// let mut map = BTreeMap::with_maximum_sizes(4, 4);
+/// for key in (0..1000).rev() {
+///     map.insert(key, key * 2);
+/// }
+///
+/// assert_eq!(map.get(&500), Some(&1000));
+/// assert_eq!(map.get_index(250), Some((&250, &500)));
+/// assert_eq!(map.rank(&750), 750);
+/// assert!(map.range(10..12).eq([(&10, &20), (&11, &22)]));
+/// assert_eq!(map.remove(&500), Some(1000));
+/// assert_eq!(map.get_index(500), Some((&501, &1002)));
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct BTreeMap<K, V>
+where
+    K: Ord,
+{
+    set: BTreeSet<Pair<K, V>>,
+}
+
+impl<K: Ord, V> Default for BTreeMap<K, V> {
+    fn default() -> Self {
+        Self {
+            set: BTreeSet::default(),
+        }
+    }
+}
+
+impl<K: Ord, V> BTreeMap<K, V> {
+    /// Makes a new, empty `BTreeMap` with maximum node size 1024, and up to 1024 nodes per group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map: BTreeMap<u64, &str> = BTreeMap::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes a new, empty `BTreeMap` with the given maximum node size, and up to 1024 nodes per
+    /// group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map: BTreeMap<u64, &str> = BTreeMap::with_maximum_node_size(128);
+    /// ```
+    pub fn with_maximum_node_size(maximum_node_size: usize) -> Self {
+        Self {
+            set: BTreeSet::with_maximum_node_size(maximum_node_size),
+        }
+    }
+    /// Makes a new, empty `BTreeMap` with the given maximum node size and maximum number of
+    /// nodes per group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![allow(unused_mut)]
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map: BTreeMap<u64, &str> = BTreeMap::with_maximum_sizes(128, 64);
+    /// ```
+    pub fn with_maximum_sizes(maximum_node_size: usize, maximum_group_size: usize) -> Self {
+        Self {
+            set: BTreeSet::with_maximum_sizes(maximum_node_size, maximum_group_size),
+        }
+    }
+    /// Sets where full nodes are split from now on. See [`SplitPolicy`] for the options. Groups
+    /// are always split evenly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    /// use indexset::SplitPolicy;
+    ///
+    /// let mut map: BTreeMap<u64, &str> = BTreeMap::new();
+    /// map.set_split_policy(SplitPolicy::RightBiased);
+    /// assert!(matches!(map.split_policy(), SplitPolicy::RightBiased));
+    /// ```
+    pub fn set_split_policy(&mut self, split_policy: SplitPolicy) {
+        self.set.set_split_policy(split_policy);
+    }
+    /// Returns where full nodes are split.
+    pub fn split_policy(&self) -> SplitPolicy {
+        self.set.split_policy()
+    }
+    /// Clears the map, removing all entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// map.clear();
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.set.clear();
+    }
+    /// Returns the number of entries in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.len(), 0);
+    /// map.insert(1, "a");
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+    // Group, node and position of the entry with the given key, if any
+    fn find<Q>(&self, key: &Q) -> Option<(usize, usize, usize)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (group_idx, node_idx, position) = self.set.locate_by(|pair| pair.key.borrow() < key);
+        let pair = self.set.node(group_idx, node_idx).get(position)?;
+        if pair.key.borrow() != key {
+            return None;
+        }
+
+        Some((group_idx, node_idx, position))
+    }
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.get(&2), None);
+    /// ```
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+    /// Returns the key-value pair corresponding to the supplied key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.get_key_value(&1), Some((&1, &"a")));
+    /// assert_eq!(map.get_key_value(&2), None);
+    /// ```
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (group_idx, node_idx, position) = self.find(key)?;
+        let pair = &self.set.node(group_idx, node_idx)[position];
+
+        Some((&pair.key, &pair.value))
+    }
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// if let Some(x) = map.get_mut(&1) {
+    ///     *x = "b";
+    /// }
+    /// assert_eq!(map.get(&1), Some(&"b"));
+    /// ```
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (group_idx, node_idx, position) = self.find(key)?;
+
+        Some(&mut self.set.node_mut(group_idx, node_idx)[position].value)
+    }
+    /// Returns `true` if the map contains a value for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.contains_key(&1), true);
+    /// assert_eq!(map.contains_key(&2), false);
+    /// ```
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+    /// Returns the i-th key-value pair of the map, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(3, "c"), (1, "a"), (2, "b")]);
+    /// assert_eq!(map.get_index(0), Some((&1, &"a")));
+    /// assert_eq!(map.get_index(2), Some((&3, &"c")));
+    /// assert_eq!(map.get_index(3), None);
+    /// ```
+    pub fn get_index(&self, idx: usize) -> Option<(&K, &V)> {
+        self.set.get_index(idx).map(|pair| (&pair.key, &pair.value))
+    }
+    /// Returns the number of keys in the map that are less than the given one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(1, "a"), (2, "b"), (4, "d")]);
+    /// assert_eq!(map.rank(&1), 0);
+    /// assert_eq!(map.rank(&3), 2);
+    /// assert_eq!(map.rank(&5), 3);
+    /// ```
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.set.rank_by(|pair| pair.key.borrow() < key)
+    }
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map did not have this key present, `None` is returned. Otherwise, the value is
+    /// updated, and the old value is returned. The key is not updated, though.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.insert(37, "a"), None);
+    ///
+    /// map.insert(37, "b");
+    /// assert_eq!(map.insert(37, "c"), Some("b"));
+    /// assert_eq!(map.get(&37), Some(&"c"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (group_idx, node_idx, position) = self.set.locate(&key);
+        if let Some(pair) = self
+            .set
+            .node_mut(group_idx, node_idx)
+            .get_mut(position)
+            .filter(|pair| pair.key == key)
+        {
+            return Some(std::mem::replace(&mut pair.value, value));
+        }
+
+        self.set
+            .insert_at(group_idx, node_idx, position, Pair { key, value });
+
+        None
+    }
+    /// Removes a key from the map, returning the stored key and value if the key was previously
+    /// in the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.remove_entry(&1), Some((1, "a")));
+    /// assert_eq!(map.remove_entry(&1), None);
+    /// ```
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (group_idx, node_idx, position) = self.find(key)?;
+        let pair = self.set.remove_at(group_idx, node_idx, position);
+
+        Some((pair.key, pair.value))
+    }
+    /// Removes a key from the map, returning the value at the key if the key was previously in
+    /// the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(1, "a");
+    /// assert_eq!(map.remove(&1), Some("a"));
+    /// assert_eq!(map.remove(&1), None);
+    /// ```
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+    /// Returns the first key-value pair in the map, whose key is the minimum one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.first_key_value(), None);
+    /// map.insert(2, "b");
+    /// map.insert(1, "a");
+    /// assert_eq!(map.first_key_value(), Some((&1, &"a")));
+    /// ```
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.set.first().map(|pair| (&pair.key, &pair.value))
+    }
+    /// Returns the last key-value pair in the map, whose key is the maximum one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// assert_eq!(map.last_key_value(), None);
+    /// map.insert(1, "a");
+    /// map.insert(2, "b");
+    /// assert_eq!(map.last_key_value(), Some((&2, &"b")));
+    /// ```
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.set.last().map(|pair| (&pair.key, &pair.value))
+    }
+    /// Removes and returns the first key-value pair in the map, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// assert_eq!(map.pop_first(), Some((1, "a")));
+    /// assert_eq!(map.pop_first(), Some((2, "b")));
+    /// assert_eq!(map.pop_first(), None);
+    /// ```
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.set.pop_first().map(|pair| (pair.key, pair.value))
+    }
+    /// Removes and returns the last key-value pair in the map, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::from_iter([(1, "a"), (2, "b")]);
+    /// assert_eq!(map.pop_last(), Some((2, "b")));
+    /// assert_eq!(map.pop_last(), Some((1, "a")));
+    /// assert_eq!(map.pop_last(), None);
+    /// ```
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.set.pop_last().map(|pair| (pair.key, pair.value))
+    }
+    /// Gets an iterator over the entries of the map, sorted by key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(3, "c"), (2, "b"), (1, "a")]);
+    /// let mut iter = map.iter();
+    /// assert_eq!(iter.next(), Some((&1, &"a")));
+    /// assert_eq!(iter.next_back(), Some((&3, &"c")));
+    /// assert_eq!(iter.next(), Some((&2, &"b")));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter(&self) -> IterMap<'_, K, V> {
+        IterMap {
+            iter: self.set.iter(),
+        }
+    }
+    /// Gets an iterator over the keys of the map, in sorted order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(2, "b"), (1, "a")]);
+    /// assert!(map.keys().eq([1, 2].iter()));
+    /// ```
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { iter: self.iter() }
+    }
+    /// Gets an iterator over the values of the map, in order by key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(2, "b"), (1, "a")]);
+    /// assert!(map.values().eq(["a", "b"].iter()));
+    /// ```
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { iter: self.iter() }
+    }
+    /// Constructs a double-ended iterator over a sub-range of entries in the map.
+    ///
+    /// The range bounds may be any borrowed form of the map's key type,
+    /// but the ordering on the borrowed form *must* match the
+    /// ordering on the key type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use indexset::tiered::BTreeMap;
+    ///
+    /// let map = BTreeMap::from_iter([(3, "a"), (5, "b"), (8, "c")]);
+    /// assert_eq!(map.range(4..).next(), Some((&5, &"b")));
+    /// assert!(map.range(..=5).rev().eq([(&5, &"b"), (&3, &"a")]));
+    /// ```
+    pub fn range<Q, R>(&self, range: R) -> IterMap<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.set.rank_by(|pair| pair.key.borrow() < start),
+            Bound::Excluded(start) => self.set.rank_by(|pair| pair.key.borrow() <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.set.rank_by(|pair| pair.key.borrow() <= end),
+            Bound::Excluded(end) => self.set.rank_by(|pair| pair.key.borrow() < end),
+            Bound::Unbounded => self.len(),
+        };
+
+        IterMap {
+            iter: self.set.iter_between(start, end.max(start)),
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = BTreeMap::new();
+        iter.into_iter().for_each(|(key, value)| {
+            map.insert(key, value);
+        });
+
+        map
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for BTreeMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|(key, value)| {
+            self.insert(key, value);
+        });
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a BTreeMap<K, V> {
+    type Item = (&'a K, &'a V);
+
+    type IntoIter = IterMap<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a `BTreeMap`, or of a sub-range of them.
+///
+/// This `struct` is created by the [`iter`] and [`range`] methods on [`BTreeMap`].
+/// See their documentation for more.
+///
+/// [`iter`]: BTreeMap::iter
+/// [`range`]: BTreeMap::range
+pub struct IterMap<'a, K, V>
+where
+    K: Ord,
+{
+    iter: Iter<'a, Pair<K, V>>,
+}
+
+impl<'a, K: Ord, V> Iterator for IterMap<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|pair| (&pair.key, &pair.value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: Ord, V> DoubleEndedIterator for IterMap<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|pair| (&pair.key, &pair.value))
+    }
+}
+
+impl<'a, K: Ord, V> ExactSizeIterator for IterMap<'a, K, V> {}
+
+impl<'a, K: Ord, V> FusedIterator for IterMap<'a, K, V> {}
+
+/// An iterator over the keys of a `BTreeMap`.
+///
+/// This `struct` is created by the [`keys`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`keys`]: BTreeMap::keys
+pub struct Keys<'a, K, V>
+where
+    K: Ord,
+{
+    iter: IterMap<'a, K, V>,
+}
+
+impl<'a, K: Ord, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, _)| key)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: Ord, V> DoubleEndedIterator for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K: Ord, V> ExactSizeIterator for Keys<'a, K, V> {}
+
+impl<'a, K: Ord, V> FusedIterator for Keys<'a, K, V> {}
+
+/// An iterator over the values of a `BTreeMap`.
+///
+/// This `struct` is created by the [`values`] method on [`BTreeMap`].
+/// See its documentation for more.
+///
+/// [`values`]: BTreeMap::values
+pub struct Values<'a, K, V>
+where
+    K: Ord,
+{
+    iter: IterMap<'a, K, V>,
+}
+
+impl<'a, K: Ord, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, value)| value)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: Ord, V> DoubleEndedIterator for Values<'a, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K: Ord, V> ExactSizeIterator for Values<'a, K, V> {}
+
+impl<'a, K: Ord, V> FusedIterator for Values<'a, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{BTreeMap, BTreeSet};
+    use crate::SplitPolicy;
+    use rand::{Rng, SeedableRng};
+    use std::ops::Bound;
+
+    // Checks that the lengths kept at every level add up
+    fn assert_consistent<T: Ord + std::fmt::Debug>(set: &BTreeSet<T>) {
+        let mut len = 0;
+        for (group_idx, group) in set.groups.nodes().iter().enumerate() {
+            let group_len: usize = group.nodes().iter().map(Vec::len).sum();
+            assert_eq!(group.len(), group_len);
+            assert_eq!(set.groups.offset(group_idx), len);
+            let mut offset = 0;
+            for (node_idx, node) in group.nodes().iter().enumerate() {
+                assert!(!node.is_empty() || set.is_empty());
+                assert!(node.len() <= set.node_capacity);
+                assert_eq!(group.offset(node_idx), offset);
+                offset += node.len();
+            }
+            assert!(group.nodes().len() <= set.group_capacity);
+            len += group_len;
+        }
+        assert_eq!(set.len(), len);
+        assert!(set.iter().zip(set.iter().skip(1)).all(|(a, b)| a < b));
+    }
+
+    #[test]
+    fn test_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([58; 32]);
+        let mut set = BTreeSet::with_maximum_sizes(4, 4);
+        let mut model = std::collections::BTreeSet::new();
+        for step in 0..30_000 {
+            let value = rng.gen_range(0..3000);
+            match rng.gen_range(0..6) {
+                0 => assert_eq!(set.remove(&value), model.remove(&value)),
+                1 if step % 50 == 0 => {
+                    assert_eq!(set.pop_first(), model.pop_first());
+                    assert_eq!(set.pop_last(), model.pop_last());
+                }
+                _ => assert_eq!(set.insert(value), model.insert(value)),
+            }
+            assert_eq!(set.len(), model.len());
+        }
+
+        assert_consistent(&set);
+        assert!(set.groups.nodes().len() > 4);
+        assert!(set.iter().eq(model.iter()));
+        assert!(set.iter().rev().eq(model.iter().rev()));
+        for (idx, value) in model.iter().enumerate() {
+            assert_eq!(set.get_index(idx), Some(value));
+            assert_eq!(set.rank(value), idx);
+            assert_eq!(set.get(value), Some(value));
+        }
+        assert_eq!(set.get_index(model.len()), None);
+        assert_eq!(set.first(), model.first());
+        assert_eq!(set.last(), model.last());
+        for start in (0..3010).step_by(7) {
+            let end = start + rng.gen_range(0..200);
+            assert!(set.range(start..end).eq(model.range(start..end)));
+            assert!(set
+                .range(start..=end)
+                .rev()
+                .eq(model.range(start..=end).rev()));
+            assert_eq!(set.range(start..end).len(), model.range(start..end).count());
+            let mut both_ends = set.range(start..end);
+            let mut model_ends = model.range(start..end);
+            while let Some(value) = both_ends.next() {
+                assert_eq!(Some(value), model_ends.next());
+                assert_eq!(both_ends.next_back(), model_ends.next_back());
+            }
+            assert_eq!(model_ends.next(), None);
+        }
+
+        while let Some(value) = set.pop_first() {
+            assert_eq!(Some(value), model.pop_first());
+        }
+        assert_consistent(&set);
+        assert_eq!(set.iter().next(), None);
+        assert!(set.insert(1));
+        assert!(set.iter().eq([1].iter()));
+    }
+
+    #[test]
+    fn test_map_against_model() {
+        let mut rng = rand::rngs::StdRng::from_seed([59; 32]);
+        let mut map = BTreeMap::with_maximum_sizes(4, 4);
+        let mut model = std::collections::BTreeMap::new();
+        for step in 0..30_000 {
+            let key = rng.gen_range(0..3000);
+            match rng.gen_range(0..6) {
+                0 => assert_eq!(map.remove(&key), model.remove(&key)),
+                1 => {
+                    if let Some(value) = map.get_mut(&key) {
+                        *value += 1;
+                    }
+                    if let Some(value) = model.get_mut(&key) {
+                        *value += 1;
+                    }
+                }
+                2 if step % 50 == 0 => {
+                    assert_eq!(map.pop_first(), model.pop_first());
+                    assert_eq!(map.pop_last(), model.pop_last());
+                }
+                _ => assert_eq!(map.insert(key, step), model.insert(key, step)),
+            }
+            assert_eq!(map.len(), model.len());
+        }
+
+        assert_consistent(&map.set);
+        assert!(map.iter().eq(model.iter()));
+        assert!(map.iter().rev().eq(model.iter().rev()));
+        assert!(map.keys().eq(model.keys()));
+        assert!(map.values().eq(model.values()));
+        for (idx, (key, value)) in model.iter().enumerate() {
+            assert_eq!(map.get_index(idx), Some((key, value)));
+            assert_eq!(map.rank(key), idx);
+            assert_eq!(map.get_key_value(key), Some((key, value)));
+        }
+        assert_eq!(map.get_index(model.len()), None);
+        assert_eq!(map.first_key_value(), model.first_key_value());
+        assert_eq!(map.last_key_value(), model.last_key_value());
+        for start in (0..3010).step_by(7) {
+            let end = start + rng.gen_range(0..200);
+            assert!(map.range(start..end).eq(model.range(start..end)));
+            assert!(map
+                .range((Bound::Excluded(start), Bound::Included(end)))
+                .rev()
+                .eq(model
+                    .range((Bound::Excluded(start), Bound::Included(end)))
+                    .rev()));
+        }
+
+        let keys: Vec<_> = model.keys().copied().collect();
+        for key in keys {
+            assert_eq!(map.remove_entry(&key), model.remove_entry(&key));
+        }
+        assert!(map.is_empty());
+        assert_consistent(&map.set);
+    }
+
+    #[test]
+    fn test_splits_stay_within_groups() {
+        let mut set = BTreeSet::with_maximum_sizes(8, 16);
+        set.set_split_policy(SplitPolicy::RightBiased);
+        for value in 0..100_000u32 {
+            set.insert(value);
+        }
+
+        assert_consistent(&set);
+        let nodes: usize = set
+            .groups
+            .nodes()
+            .iter()
+            .map(|group| group.nodes().len())
+            .sum();
+        assert_eq!(nodes, 100_000 / 8);
+        assert!(set.groups.nodes().len() <= nodes / 8 + 1);
+        assert!(set.iter().copied().eq(0..100_000));
+
+        let mut set = BTreeSet::with_maximum_sizes(8, 16);
+        for value in (0..20_000u32).map(|i| i.wrapping_mul(2_654_435_761) % 20_011) {
+            set.insert(value);
+        }
+        assert_consistent(&set);
+        for idx in (0..set.len()).step_by(97) {
+            let value = *set.get_index(idx).unwrap();
+            assert_eq!(set.rank(&value), idx);
+        }
+    }
+}
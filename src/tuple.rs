@@ -0,0 +1,214 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+
+/// A tuple key that can be compared against a tuple made of its leading fields.
+///
+/// All keys that share a prefix are contiguous in a tree, which is what lets `range_prefix`
+/// look them up, e.g. every `(tenant_id, timestamp, id)` key of a given `(tenant_id,)`.
+///
+/// It is implemented for tuples of up to six fields, against each of their prefixes, including
+/// the whole tuple itself.
+///
+/// # Examples
+///
+/// ```
+/// use indexset::tuple::TuplePrefix;
+/// use std::cmp::Ordering;
+///
+/// let key = (1, "b", 3.to_string());
+/// assert_eq!(key.cmp_prefix(&(1,)), Ordering::Equal);
+/// assert_eq!(key.cmp_prefix(&(1, "a")), Ordering::Greater);
+/// assert_eq!(key.cmp_prefix(&(2,)), Ordering::Less);
+/// ```
+pub trait TuplePrefix<P>: Ord {
+    /// Compares the leading fields of the key with the prefix.
+    fn cmp_prefix(&self, prefix: &P) -> Ordering;
+}
+
+/// One of the two ends of the keys that start with a prefix.
+///
+/// An `Infimum` sorts before every key starting with the prefix, and a `Supremum` sorts after all
+/// of them, yet neither is ever equal to a key, so they can be used as sentinels without having
+/// to come up with a minimum or maximum value for the remaining fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bounded<P> {
+    Infimum(P),
+    Supremum(P),
+}
+
+impl<P> Bounded<P> {
+    /// Returns the prefix the bound was built from.
+    pub fn prefix(&self) -> &P {
+        match self {
+            Bounded::Infimum(prefix) | Bounded::Supremum(prefix) => prefix,
+        }
+    }
+    fn cmp_key<K>(&self, key: &K) -> Ordering
+    where
+        K: TuplePrefix<P>,
+    {
+        match (key.cmp_prefix(self.prefix()), self) {
+            (Ordering::Equal, Bounded::Infimum(_)) => Ordering::Less,
+            (Ordering::Equal, Bounded::Supremum(_)) => Ordering::Greater,
+            (ordering, _) => ordering.reverse(),
+        }
+    }
+}
+
+impl<P: Ord> PartialOrd for Bounded<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P: Ord> Ord for Bounded<P> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.prefix()
+            .cmp(other.prefix())
+            .then_with(|| match (self, other) {
+                (Bounded::Infimum(_), Bounded::Supremum(_)) => Ordering::Less,
+                (Bounded::Supremum(_), Bounded::Infimum(_)) => Ordering::Greater,
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+/// Either a key or a [`Bounded`] end of its prefixes.
+pub enum KeyOrBound<'a, K, P> {
+    Key(&'a K),
+    Bound(&'a Bounded<P>),
+}
+
+/// A common borrowed form for tuple keys and their [`Bounded`] prefixes.
+///
+/// Tuple keys borrow as `dyn PrefixLookup<K, P>`, which lets trees that look their keys up by
+/// [`Borrow`], such as the concurrent ones, take a [`Bounded`] prefix as a range bound.
+pub trait PrefixLookup<K, P> {
+    fn key_or_bound(&self) -> KeyOrBound<'_, K, P>;
+}
+
+impl<K, P> PrefixLookup<K, P> for Bounded<P> {
+    fn key_or_bound(&self) -> KeyOrBound<'_, K, P> {
+        KeyOrBound::Bound(self)
+    }
+}
+
+impl<K, P> PartialEq for dyn PrefixLookup<K, P> + '_
+where
+    K: TuplePrefix<P>,
+    P: Ord,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K, P> Eq for dyn PrefixLookup<K, P> + '_
+where
+    K: TuplePrefix<P>,
+    P: Ord,
+{
+}
+
+impl<K, P> PartialOrd for dyn PrefixLookup<K, P> + '_
+where
+    K: TuplePrefix<P>,
+    P: Ord,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K, P> Ord for dyn PrefixLookup<K, P> + '_
+where
+    K: TuplePrefix<P>,
+    P: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.key_or_bound(), other.key_or_bound()) {
+            (KeyOrBound::Key(key), KeyOrBound::Key(other_key)) => key.cmp(other_key),
+            (KeyOrBound::Key(key), KeyOrBound::Bound(bound)) => bound.cmp_key(key).reverse(),
+            (KeyOrBound::Bound(bound), KeyOrBound::Key(key)) => bound.cmp_key(key),
+            (KeyOrBound::Bound(bound), KeyOrBound::Bound(other_bound)) => bound.cmp(other_bound),
+        }
+    }
+}
+
+macro_rules! tuple_prefix {
+    (($($key:ident),+) => ($($prefix:ident $idx:tt),+)) => {
+        impl<$($key: Ord),+> TuplePrefix<($($prefix,)+)> for ($($key,)+) {
+            fn cmp_prefix(&self, prefix: &($($prefix,)+)) -> Ordering {
+                $(
+                    match self.$idx.cmp(&prefix.$idx) {
+                        Ordering::Equal => {}
+                        ordering => return ordering,
+                    }
+                )+
+
+                Ordering::Equal
+            }
+        }
+
+        impl<$($key),+> PrefixLookup<($($key,)+), ($($prefix,)+)> for ($($key,)+) {
+            fn key_or_bound(&self) -> KeyOrBound<'_, ($($key,)+), ($($prefix,)+)> {
+                KeyOrBound::Key(self)
+            }
+        }
+
+        impl<$($key: 'static),+> Borrow<dyn PrefixLookup<($($key,)+), ($($prefix,)+)>>
+            for ($($key,)+)
+        {
+            fn borrow(&self) -> &(dyn PrefixLookup<($($key,)+), ($($prefix,)+)> + 'static) {
+                self
+            }
+        }
+    };
+}
+
+tuple_prefix!((A) => (A 0));
+tuple_prefix!((A, B) => (A 0));
+tuple_prefix!((A, B) => (A 0, B 1));
+tuple_prefix!((A, B, C) => (A 0));
+tuple_prefix!((A, B, C) => (A 0, B 1));
+tuple_prefix!((A, B, C) => (A 0, B 1, C 2));
+tuple_prefix!((A, B, C, D) => (A 0));
+tuple_prefix!((A, B, C, D) => (A 0, B 1));
+tuple_prefix!((A, B, C, D) => (A 0, B 1, C 2));
+tuple_prefix!((A, B, C, D) => (A 0, B 1, C 2, D 3));
+tuple_prefix!((A, B, C, D, E) => (A 0));
+tuple_prefix!((A, B, C, D, E) => (A 0, B 1));
+tuple_prefix!((A, B, C, D, E) => (A 0, B 1, C 2));
+tuple_prefix!((A, B, C, D, E) => (A 0, B 1, C 2, D 3));
+tuple_prefix!((A, B, C, D, E) => (A 0, B 1, C 2, D 3, E 4));
+tuple_prefix!((A, B, C, D, E, F) => (A 0));
+tuple_prefix!((A, B, C, D, E, F) => (A 0, B 1));
+tuple_prefix!((A, B, C, D, E, F) => (A 0, B 1, C 2));
+tuple_prefix!((A, B, C, D, E, F) => (A 0, B 1, C 2, D 3));
+tuple_prefix!((A, B, C, D, E, F) => (A 0, B 1, C 2, D 3, E 4));
+tuple_prefix!((A, B, C, D, E, F) => (A 0, B 1, C 2, D 3, E 4, F 5));
+
+#[cfg(test)]
+mod tests {
+    use super::{Bounded, PrefixLookup, TuplePrefix};
+    use std::borrow::Borrow;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn test_bounds_enclose_prefix() {
+        let keys = [(0, 5, 1), (1, 0, 0), (1, 0, 9), (1, 3, 2), (2, 0, 0)];
+        let lower = Bounded::Infimum((1,));
+        let upper = Bounded::Supremum((1,));
+        let lower: &dyn PrefixLookup<(i32, i32, i32), (i32,)> = &lower;
+        let upper: &dyn PrefixLookup<(i32, i32, i32), (i32,)> = &upper;
+
+        for key in keys {
+            let view: &dyn PrefixLookup<(i32, i32, i32), (i32,)> = key.borrow();
+            let within = key.cmp_prefix(&(1,)) == Ordering::Equal;
+            assert_eq!(view > lower && view < upper, within);
+            assert!(view != lower);
+            assert!(view != upper);
+        }
+        assert!(lower < upper);
+    }
+}